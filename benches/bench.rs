@@ -1,7 +1,7 @@
 use criterion::{criterion_group, criterion_main, Benchmark, Criterion, Throughput};
 
 use approx::assert_relative_eq;
-use futures01::future;
+use futures01::{future, Stream};
 use rand::distributions::{Alphanumeric, Uniform};
 use rand::prelude::*;
 use vector::event::Event;
@@ -30,6 +30,7 @@ criterion_group!(
     benchmark_complex,
     bench_elasticsearch_index,
     benchmark_regex,
+    benchmark_transform_batch,
 );
 criterion_main!(
     benches,
@@ -436,6 +437,102 @@ fn benchmark_regex(c: &mut Criterion) {
     );
 }
 
+const TRANSFORM_BATCH_LINES: usize = 100_000;
+
+fn benchmark_transform_batch(c: &mut Criterion) {
+    bench_transform_batch(c, "json_parser", || {
+        let rt = vector::runtime::Runtime::single_threaded().unwrap();
+        let parser = transforms::json_parser::JsonParserConfig::default()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        let events = (0..TRANSFORM_BATCH_LINES)
+            .map(|i| Event::from(&format!(r#"{{"line":{},"message":"hello"}}"#, i)[..]))
+            .collect::<Vec<Event>>();
+
+        (parser, events)
+    });
+
+    bench_transform_batch(c, "regex_parser", || {
+        let rt = vector::runtime::Runtime::single_threaded().unwrap();
+        let parser = transforms::regex_parser::RegexParserConfig {
+            regex: r#"^(?P<addr>\d+\.\d+\.\d+\.\d+) (?P<user>\S+) (?P<auth>\S+) \[(?P<date>[^\]]+)\] "(?P<method>[A-Z]+) (?P<uri>[^"]+) HTTP/\d\.\d" (?P<code>\d+) (?P<size>\d+)"#.into(),
+            field: None,
+            drop_failed: true,
+            ..Default::default()
+        }
+        .build(TransformContext::new_test(rt.executor()))
+        .unwrap();
+
+        let events = http_access_log_lines()
+            .take(TRANSFORM_BATCH_LINES)
+            .map(|line| Event::from(&line[..]))
+            .collect::<Vec<Event>>();
+
+        (parser, events)
+    });
+
+    bench_transform_batch(c, "coercer", || {
+        let rt = vector::runtime::Runtime::single_threaded().unwrap();
+        let parser = toml::from_str::<transforms::coercer::CoercerConfig>(
+            r#"
+            [types]
+            code = "int"
+            size = "int"
+            "#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test(rt.executor()))
+        .unwrap();
+
+        let events = (0..TRANSFORM_BATCH_LINES)
+            .map(|i| {
+                let mut event = Event::from("hello");
+                event.as_mut_log().insert("code", "200");
+                event.as_mut_log().insert("size", i.to_string());
+                event
+            })
+            .collect::<Vec<Event>>();
+
+        (parser, events)
+    });
+}
+
+fn bench_transform_batch<S>(c: &mut Criterion, name: &str, setup: S)
+where
+    S: Fn() -> (Box<dyn transforms::Transform>, Vec<Event>) + Copy + 'static,
+{
+    c.bench(
+        &format!("transform_batch/{}", name),
+        Benchmark::new("per_event", move |b| {
+            b.iter_with_setup(setup, |(mut parser, events)| {
+                let mut output = Vec::with_capacity(1);
+                let mut count = 0;
+                for event in events {
+                    parser.transform_into(&mut output, event);
+                    count += output.drain(..).count();
+                }
+
+                assert_eq!(count, TRANSFORM_BATCH_LINES);
+            });
+        })
+        .with_function("stream", move |b| {
+            b.iter_with_setup(setup, |(parser, events)| {
+                let input = futures01::stream::iter_ok(events);
+                let count = parser
+                    .transform_stream(Box::new(input))
+                    .wait()
+                    .filter_map(Result::ok)
+                    .count();
+
+                assert_eq!(count, TRANSFORM_BATCH_LINES);
+            });
+        })
+        .sample_size(10)
+        .noise_threshold(0.05),
+    );
+}
+
 fn benchmark_complex(c: &mut Criterion) {
     let num_lines: usize = 100_000;
 
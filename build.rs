@@ -1,9 +1,13 @@
 fn main() {
     println!("cargo:rerun-if-changed=proto/event.proto");
+    println!("cargo:rerun-if-changed=proto/prometheus.proto");
     let mut prost_build = prost_build::Config::new();
     prost_build.btree_map(&["."]);
     prost_build
-        .compile_protos(&["proto/event.proto"], &["proto/"])
+        .compile_protos(
+            &["proto/event.proto", "proto/prometheus.proto"],
+            &["proto/"],
+        )
         .unwrap();
     built::write_built_file().unwrap();
 }
@@ -0,0 +1,177 @@
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+use std::{io, usize};
+use tokio_codec::{Decoder, Encoder};
+
+const U32_LENGTH: usize = 4;
+
+/// Varints longer than this can't hold a `u64`.
+const MAX_VARINT_LENGTH: usize = 10;
+
+/// How the length of each frame is written ahead of it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum LengthPrefix {
+    /// A big-endian `u32`, as written by the `vector` sink.
+    U32,
+    /// A protobuf-style base 128 varint, as written by
+    /// `prost::Message::encode_length_delimited`.
+    Varint,
+}
+
+/// Frames prefixed with their length.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct LengthDelimitedCodec {
+    prefix: LengthPrefix,
+    max_length: usize,
+    discarding: usize,
+}
+
+impl LengthDelimitedCodec {
+    /// Returns a `LengthDelimitedCodec` with `u32` length prefixes and
+    /// without a frame length limit.
+    pub fn new() -> Self {
+        LengthDelimitedCodec {
+            prefix: LengthPrefix::U32,
+            max_length: usize::MAX,
+            discarding: 0,
+        }
+    }
+
+    /// Returns a `LengthDelimitedCodec` with `u32` length prefixes and a
+    /// maximum frame length limit.
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        LengthDelimitedCodec {
+            max_length,
+            ..LengthDelimitedCodec::new()
+        }
+    }
+
+    /// Returns a `LengthDelimitedCodec` with the specified length prefix and
+    /// a maximum frame length limit.
+    pub fn new_with_prefix(prefix: LengthPrefix, max_length: usize) -> Self {
+        LengthDelimitedCodec {
+            prefix,
+            ..LengthDelimitedCodec::new_with_max_length(max_length)
+        }
+    }
+
+    /// Returns the maximum frame length when decoding.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Returns the length of the next frame and the size of its prefix, if
+    /// the whole prefix has arrived.
+    fn decode_length(&self, buf: &[u8]) -> Result<Option<(usize, usize)>, io::Error> {
+        match self.prefix {
+            LengthPrefix::U32 => {
+                if buf.len() < U32_LENGTH {
+                    return Ok(None);
+                }
+                let length = BigEndian::read_u32(&buf[..U32_LENGTH]) as usize;
+                Ok(Some((length, U32_LENGTH)))
+            }
+            LengthPrefix::Varint => {
+                let mut length = 0u64;
+                for (index, byte) in buf.iter().take(MAX_VARINT_LENGTH).enumerate() {
+                    length |= u64::from(byte & 0x7f) << (7 * index);
+                    if byte & 0x80 == 0 {
+                        return Ok(Some((length as usize, index + 1)));
+                    }
+                }
+                if buf.len() >= MAX_VARINT_LENGTH {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "varint length prefix is too long",
+                    ))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+impl Default for LengthDelimitedCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for LengthDelimitedCodec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        loop {
+            if self.discarding > 0 {
+                let discard_to = self.discarding.min(buf.len());
+                buf.advance(discard_to);
+                self.discarding -= discard_to;
+                if self.discarding > 0 {
+                    return Ok(None);
+                }
+            }
+
+            let (length, prefix_length) = match self.decode_length(buf)? {
+                Some(length) => length,
+                None => return Ok(None),
+            };
+
+            if length > self.max_length {
+                warn!(
+                    message = "discarding frame larger than max_length",
+                    frame_len = length,
+                    max_len = self.max_length,
+                    rate_limit_secs = 30
+                );
+                buf.advance(prefix_length);
+                self.discarding = length;
+                continue;
+            }
+
+            let frame_end = prefix_length + length;
+            if buf.len() < frame_end {
+                buf.reserve(frame_end - buf.len());
+                return Ok(None);
+            }
+
+            buf.advance(prefix_length);
+            let frame = buf.split_to(length);
+
+            trace!(message = "decoding the frame.", bytes_proccesed = frame_end);
+
+            return Ok(Some(frame.freeze()));
+        }
+    }
+}
+
+impl Encoder for LengthDelimitedCodec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Bytes, buf: &mut BytesMut) -> Result<(), io::Error> {
+        match self.prefix {
+            LengthPrefix::U32 => {
+                if item.len() > u32::max_value() as usize {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "frame is too long for a u32 length",
+                    ));
+                }
+                buf.reserve(U32_LENGTH + item.len());
+                buf.put_u32_be(item.len() as u32);
+            }
+            LengthPrefix::Varint => {
+                buf.reserve(MAX_VARINT_LENGTH + item.len());
+                let mut length = item.len() as u64;
+                while length >= 0x80 {
+                    buf.put_u8((length as u8 & 0x7f) | 0x80);
+                    length >>= 7;
+                }
+                buf.put_u8(length as u8);
+            }
+        }
+        buf.put(item);
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 #[macro_use]
 extern crate tracing;
 
+mod length_delimited;
+mod octet_counting;
+
+pub use length_delimited::{LengthDelimitedCodec, LengthPrefix};
+pub use octet_counting::OctetCountingCodec;
+
 use bytes::{BufMut, Bytes, BytesMut};
 use std::{cmp, io, usize};
 use tokio_codec::{Decoder, Encoder};
@@ -71,6 +77,10 @@ impl Decoder for BytesDelimitedCodec {
 
             if self.is_discarding {
                 self.discard(newline_pos, read_to, buf);
+                if self.is_discarding && buf.is_empty() {
+                    // Wait for more data to find the end of the frame.
+                    return Ok(None);
+                }
             } else {
                 return if let Some(pos) = newline_pos {
                     // We found a correct frame
@@ -0,0 +1,122 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use std::{io, usize};
+use tokio_codec::{Decoder, Encoder};
+
+/// The longest octet count we accept, in digits. Anything longer can't be a
+/// count of a frame we would ever keep.
+const MAX_COUNT_DIGITS: usize = 20;
+
+/// Frames prefixed with their length as ASCII digits and a space, as in
+/// octet-counted syslog over TCP (RFC 6587, section 3.4.1).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct OctetCountingCodec {
+    max_length: usize,
+    discarding: usize,
+}
+
+impl OctetCountingCodec {
+    /// Returns an `OctetCountingCodec` without a frame length limit.
+    pub fn new() -> Self {
+        OctetCountingCodec {
+            max_length: usize::MAX,
+            discarding: 0,
+        }
+    }
+
+    /// Returns an `OctetCountingCodec` with a maximum frame length limit.
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        OctetCountingCodec {
+            max_length,
+            ..OctetCountingCodec::new()
+        }
+    }
+
+    /// Returns the maximum frame length when decoding.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+}
+
+impl Default for OctetCountingCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for OctetCountingCodec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        loop {
+            if self.discarding > 0 {
+                let discard_to = self.discarding.min(buf.len());
+                buf.advance(discard_to);
+                self.discarding -= discard_to;
+                if self.discarding > 0 {
+                    return Ok(None);
+                }
+            }
+
+            let digits = buf.iter().take_while(|b| b.is_ascii_digit()).count();
+            if digits > MAX_COUNT_DIGITS {
+                return Err(invalid_count());
+            } else if digits == buf.len() {
+                // The whole count hasn't arrived yet.
+                return Ok(None);
+            } else if digits == 0 || buf[digits] != b' ' {
+                return Err(invalid_count());
+            }
+
+            let length = std::str::from_utf8(&buf[..digits])
+                .ok()
+                .and_then(|digits| digits.parse::<usize>().ok())
+                .ok_or_else(invalid_count)?;
+
+            if length > self.max_length {
+                warn!(
+                    message = "discarding frame larger than max_length",
+                    frame_len = length,
+                    max_len = self.max_length,
+                    rate_limit_secs = 30
+                );
+                buf.advance(digits + 1);
+                self.discarding = length;
+                continue;
+            }
+
+            let frame_end = digits + 1 + length;
+            if buf.len() < frame_end {
+                buf.reserve(frame_end - buf.len());
+                return Ok(None);
+            }
+
+            buf.advance(digits + 1);
+            let frame = buf.split_to(length);
+
+            trace!(message = "decoding the frame.", bytes_proccesed = frame_end);
+
+            return Ok(Some(frame.freeze()));
+        }
+    }
+}
+
+impl Encoder for OctetCountingCodec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Bytes, buf: &mut BytesMut) -> Result<(), io::Error> {
+        let count = format!("{} ", item.len());
+        buf.reserve(count.len() + item.len());
+        buf.put_slice(count.as_bytes());
+        buf.put(item);
+        Ok(())
+    }
+}
+
+fn invalid_count() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "frame does not start with an octet count",
+    )
+}
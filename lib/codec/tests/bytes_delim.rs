@@ -127,3 +127,18 @@ fn bytes_decode_json_multiline() {
 
     assert_eq!(i, 52);
 }
+
+#[test]
+fn bytes_delim_decode_max_length_across_reads() {
+    let mut codec = BytesDelimitedCodec::new_with_max_length(b'\n', 4);
+    let buf = &mut BytesMut::new();
+
+    buf.extend_from_slice(b"toolong");
+    assert_eq!(None, codec.decode(buf).unwrap());
+    assert_eq!(None, codec.decode(buf).unwrap());
+    assert!(buf.is_empty());
+
+    buf.extend_from_slice(b"er\nok\n");
+    assert_eq!(Some("ok".into()), codec.decode(buf).unwrap());
+    assert_eq!(None, codec.decode(buf).unwrap());
+}
@@ -0,0 +1,70 @@
+use bytes::{BufMut, BytesMut};
+use codec::{LengthDelimitedCodec, LengthPrefix};
+use tokio_codec::{Decoder, Encoder};
+
+#[test]
+fn length_delimited_decode() {
+    let mut codec = LengthDelimitedCodec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"\x00\x00\x00\x03abc\x00\x00\x00\x02d");
+
+    assert_eq!(Some("abc".into()), codec.decode(buf).unwrap());
+    assert_eq!(None, codec.decode(buf).unwrap());
+    buf.put_slice(b"e");
+    assert_eq!(Some("de".into()), codec.decode(buf).unwrap());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn length_delimited_encode() {
+    let mut codec = LengthDelimitedCodec::new();
+
+    let mut buf = BytesMut::new();
+    codec.encode("abc".into(), &mut buf).unwrap();
+
+    assert_eq!(b"\x00\x00\x00\x03abc", &buf[..]);
+}
+
+#[test]
+fn length_delimited_decode_max_length() {
+    let mut codec = LengthDelimitedCodec::new_with_max_length(3);
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"\x00\x00\x00\x04abcd\x00\x00\x00\x01e");
+
+    assert_eq!(Some("e".into()), codec.decode(buf).unwrap());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn length_delimited_varint_decode() {
+    let mut codec = LengthDelimitedCodec::new_with_prefix(LengthPrefix::Varint, usize::max_value());
+    let buf = &mut BytesMut::new();
+    buf.extend_from_slice(b"\x03abc\x82");
+
+    assert_eq!(Some("abc".into()), codec.decode(buf).unwrap());
+    assert_eq!(None, codec.decode(buf).unwrap());
+    buf.extend_from_slice(b"\x01");
+    buf.extend_from_slice(&[b'x'; 130]);
+    assert_eq!(130, codec.decode(buf).unwrap().unwrap().len());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn length_delimited_varint_encode() {
+    let mut codec = LengthDelimitedCodec::new_with_prefix(LengthPrefix::Varint, usize::max_value());
+
+    let mut buf = BytesMut::new();
+    codec.encode(vec![b'x'; 300].into(), &mut buf).unwrap();
+
+    assert_eq!(b"\xac\x02", &buf[..2]);
+    assert_eq!(302, buf.len());
+}
+
+#[test]
+fn length_delimited_varint_too_long() {
+    let mut codec = LengthDelimitedCodec::new_with_prefix(LengthPrefix::Varint, usize::max_value());
+    let buf = &mut BytesMut::new();
+    buf.extend_from_slice(&[0xff; 11]);
+
+    assert!(codec.decode(buf).is_err());
+}
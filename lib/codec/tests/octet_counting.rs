@@ -0,0 +1,56 @@
+use bytes::{BufMut, BytesMut};
+use codec::OctetCountingCodec;
+use tokio_codec::{Decoder, Encoder};
+
+#[test]
+fn octet_counting_decode() {
+    let mut codec = OctetCountingCodec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"7 abc\ndef5 ghi");
+
+    assert_eq!(Some("abc\ndef".into()), codec.decode(buf).unwrap());
+    assert_eq!(None, codec.decode(buf).unwrap());
+    buf.put_slice(b"jk");
+    assert_eq!(Some("ghijk".into()), codec.decode(buf).unwrap());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn octet_counting_encode() {
+    let mut codec = OctetCountingCodec::new();
+
+    let mut buf = BytesMut::new();
+    codec.encode("abc\n".into(), &mut buf).unwrap();
+
+    assert_eq!(b"4 abc\n", &buf[..]);
+}
+
+#[test]
+fn octet_counting_decode_max_length() {
+    let mut codec = OctetCountingCodec::new_with_max_length(3);
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"4 abcd3 efg");
+
+    assert_eq!(Some("efg".into()), codec.decode(buf).unwrap());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn octet_counting_decode_split_discard() {
+    let mut codec = OctetCountingCodec::new_with_max_length(3);
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"6 abc");
+
+    assert_eq!(None, codec.decode(buf).unwrap());
+    buf.put_slice(b"def2 gh");
+    assert_eq!(Some("gh".into()), codec.decode(buf).unwrap());
+}
+
+#[test]
+fn octet_counting_decode_invalid_count() {
+    let mut codec = OctetCountingCodec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"abc\n");
+
+    assert!(codec.decode(buf).is_err());
+}
@@ -0,0 +1,208 @@
+//! Property tests for the framing codecs: anything encoded decodes back to
+//! the same frames however the input is split into reads, and arbitrary
+//! input never makes a codec panic or yield a frame over its limit.
+
+use bytes::{Bytes, BytesMut};
+use codec::{BytesDelimitedCodec, LengthDelimitedCodec, LengthPrefix, OctetCountingCodec};
+use quickcheck::{QuickCheck, TestResult};
+use std::io;
+use tokio_codec::{Decoder, Encoder};
+
+const MAX_LENGTH: usize = 16;
+
+/// Feeds `input` to `codec` in reads sized by `reads`, as a socket would.
+fn decode_in_reads<D>(codec: &mut D, input: &[u8], reads: &[u8]) -> Result<Vec<Bytes>, io::Error>
+where
+    D: Decoder<Item = Bytes, Error = io::Error>,
+{
+    let mut buf = BytesMut::new();
+    let mut frames = Vec::new();
+    let mut rest = input;
+    let mut reads = reads.iter().cycle();
+
+    while !rest.is_empty() {
+        let read = reads
+            .next()
+            .map(|read| 1 + *read as usize)
+            .unwrap_or_else(|| rest.len())
+            .min(rest.len());
+        buf.extend_from_slice(&rest[..read]);
+        rest = &rest[read..];
+        while let Some(frame) = codec.decode(&mut buf)? {
+            frames.push(frame);
+        }
+    }
+    while let Some(frame) = codec.decode_eof(&mut buf)? {
+        frames.push(frame);
+    }
+
+    Ok(frames)
+}
+
+fn roundtrip<C>(mut codec: C, frames: Vec<Vec<u8>>, reads: Vec<u8>) -> TestResult
+where
+    C: Decoder<Item = Bytes, Error = io::Error> + Encoder<Item = Bytes, Error = io::Error> + Clone,
+{
+    let mut input = BytesMut::new();
+    for frame in &frames {
+        codec.encode(frame.clone().into(), &mut input).unwrap();
+    }
+
+    let decoded = decode_in_reads(&mut codec.clone(), &input, &reads).unwrap();
+    let decoded: Vec<Vec<u8>> = decoded.iter().map(|frame| frame.to_vec()).collect();
+    let expected: Vec<Vec<u8>> = frames
+        .into_iter()
+        .filter(|frame| frame.len() <= MAX_LENGTH)
+        .collect();
+
+    TestResult::from_bool(decoded == expected)
+}
+
+fn check(f: fn(Vec<Vec<u8>>, Vec<u8>) -> TestResult) {
+    QuickCheck::new().tests(200).quickcheck(f);
+}
+
+#[test]
+fn octet_counting_roundtrip() {
+    fn inner(frames: Vec<Vec<u8>>, reads: Vec<u8>) -> TestResult {
+        roundtrip(
+            OctetCountingCodec::new_with_max_length(MAX_LENGTH),
+            frames,
+            reads,
+        )
+    }
+    check(inner);
+}
+
+#[test]
+fn length_delimited_u32_roundtrip() {
+    fn inner(frames: Vec<Vec<u8>>, reads: Vec<u8>) -> TestResult {
+        roundtrip(
+            LengthDelimitedCodec::new_with_prefix(LengthPrefix::U32, MAX_LENGTH),
+            frames,
+            reads,
+        )
+    }
+    check(inner);
+}
+
+#[test]
+fn length_delimited_varint_roundtrip() {
+    fn inner(frames: Vec<Vec<u8>>, reads: Vec<u8>) -> TestResult {
+        roundtrip(
+            LengthDelimitedCodec::new_with_prefix(LengthPrefix::Varint, MAX_LENGTH),
+            frames,
+            reads,
+        )
+    }
+    check(inner);
+}
+
+#[test]
+fn bytes_delimited_roundtrip() {
+    fn inner(frames: Vec<Vec<u8>>, reads: Vec<u8>) -> TestResult {
+        // Frames can't contain the delimiter, and decoding only resumes
+        // after an oversized frame on the next read, which may never come.
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                frame
+                    .into_iter()
+                    .filter(|b| *b != b'\n')
+                    .take(MAX_LENGTH)
+                    .collect()
+            })
+            .collect();
+        roundtrip(
+            BytesDelimitedCodec::new_with_max_length(b'\n', MAX_LENGTH),
+            frames,
+            reads,
+        )
+    }
+    check(inner);
+}
+
+fn fuzz<D>(mut codec: D, input: Vec<u8>, reads: Vec<u8>) -> TestResult
+where
+    D: Decoder<Item = Bytes, Error = io::Error>,
+{
+    // Garbage may well be rejected, but must never panic or get through
+    // as an oversized frame.
+    match decode_in_reads(&mut codec, &input, &reads) {
+        Ok(frames) => TestResult::from_bool(frames.iter().all(|frame| frame.len() <= MAX_LENGTH)),
+        Err(_) => TestResult::passed(),
+    }
+}
+
+fn check_fuzz(f: fn(Vec<u8>, Vec<u8>) -> TestResult) {
+    QuickCheck::new().tests(1000).quickcheck(f);
+}
+
+#[test]
+fn octet_counting_fuzz() {
+    fn inner(input: Vec<u8>, reads: Vec<u8>) -> TestResult {
+        fuzz(
+            OctetCountingCodec::new_with_max_length(MAX_LENGTH),
+            input,
+            reads,
+        )
+    }
+    check_fuzz(inner);
+}
+
+#[test]
+fn octet_counting_fuzz_digits() {
+    // Mostly digits and spaces, so most inputs get past the octet count.
+    fn inner(input: Vec<u8>, reads: Vec<u8>) -> TestResult {
+        let input = input
+            .into_iter()
+            .map(|b| match b % 12 {
+                10 => b' ',
+                11 => b,
+                digit => b'0' + digit,
+            })
+            .collect();
+        fuzz(
+            OctetCountingCodec::new_with_max_length(MAX_LENGTH),
+            input,
+            reads,
+        )
+    }
+    check_fuzz(inner);
+}
+
+#[test]
+fn length_delimited_u32_fuzz() {
+    fn inner(input: Vec<u8>, reads: Vec<u8>) -> TestResult {
+        fuzz(
+            LengthDelimitedCodec::new_with_prefix(LengthPrefix::U32, MAX_LENGTH),
+            input,
+            reads,
+        )
+    }
+    check_fuzz(inner);
+}
+
+#[test]
+fn length_delimited_varint_fuzz() {
+    fn inner(input: Vec<u8>, reads: Vec<u8>) -> TestResult {
+        fuzz(
+            LengthDelimitedCodec::new_with_prefix(LengthPrefix::Varint, MAX_LENGTH),
+            input,
+            reads,
+        )
+    }
+    check_fuzz(inner);
+}
+
+#[test]
+fn bytes_delimited_fuzz() {
+    fn inner(input: Vec<u8>, reads: Vec<u8>) -> TestResult {
+        fuzz(
+            BytesDelimitedCodec::new_with_max_length(b'\n', MAX_LENGTH),
+            input,
+            reads,
+        )
+    }
+    check_fuzz(inner);
+}
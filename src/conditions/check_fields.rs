@@ -1,11 +1,13 @@
 use crate::{
-    conditions::{Condition, ConditionConfig, ConditionDescription},
+    conditions::{AnyCondition, Condition, ConditionConfig, ConditionDescription},
     event::Value,
     Event,
 };
 use indexmap::IndexMap;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::net::IpAddr;
 use string_cache::DefaultAtom as Atom;
 
 #[derive(Deserialize, Serialize, Clone, Derivative)]
@@ -20,12 +22,43 @@ pub enum CheckFieldsPredicateArg {
     Float(f64),
     #[derivative(Debug = "transparent")]
     Boolean(bool),
+    #[derivative(Debug = "transparent")]
+    Array(Vec<CheckFieldsPredicateArg>),
+}
+
+impl CheckFieldsPredicateArg {
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            CheckFieldsPredicateArg::Integer(i) => Some(*i as f64),
+            CheckFieldsPredicateArg::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
 }
 
 pub trait CheckFieldsPredicate: std::fmt::Debug + Send + Sync {
     fn check(&self, e: &Event) -> bool;
 }
 
+/// Looks up the value a predicate targets. See `Metric::lookup` for the
+/// targets of metrics.
+fn target_value<'a>(event: &'a Event, target: &Atom) -> Option<Cow<'a, Value>> {
+    match event {
+        Event::Log(log) => log.get(target).map(Cow::Borrowed),
+        Event::Metric(metric) => metric.lookup(target).map(Cow::Owned),
+        Event::Trace(trace) => trace.lookup(target).map(Cow::Owned),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        Value::Bytes(bytes) => std::str::from_utf8(bytes).ok()?.trim().parse().ok(),
+        _ => None,
+    }
+}
+
 //------------------------------------------------------------------------------
 
 #[derive(Debug, Clone)]
@@ -39,42 +72,29 @@ impl EqualsPredicate {
         target: String,
         arg: &CheckFieldsPredicateArg,
     ) -> Result<Box<dyn CheckFieldsPredicate>, String> {
-        Ok(Box::new(Self {
-            target: target.into(),
-            arg: arg.clone(),
-        }))
+        match arg {
+            CheckFieldsPredicateArg::Array(_) => {
+                Err("equals predicate requires a string, number or boolean argument".to_owned())
+            }
+            _ => Ok(Box::new(Self {
+                target: target.into(),
+                arg: arg.clone(),
+            })),
+        }
     }
 }
 
 impl CheckFieldsPredicate for EqualsPredicate {
     fn check(&self, event: &Event) -> bool {
-        match event {
-            Event::Log(l) => l.get(&self.target).map_or(false, |v| match &self.arg {
-                CheckFieldsPredicateArg::String(s) => s.as_bytes() == v.as_bytes(),
-                CheckFieldsPredicateArg::Integer(i) => match v {
-                    Value::Integer(vi) => *i == *vi,
-                    Value::Float(vf) => *i == *vf as i64,
-                    _ => false,
-                },
-                CheckFieldsPredicateArg::Float(f) => match v {
-                    Value::Float(vf) => *f == *vf,
-                    Value::Integer(vi) => *f == *vi as f64,
-                    _ => false,
-                },
-                CheckFieldsPredicateArg::Boolean(b) => match v {
-                    Value::Boolean(vb) => *b == *vb,
-                    _ => false,
-                },
-            }),
-            Event::Metric(m) => m
-                .tags
-                .as_ref()
-                .and_then(|t| t.get(self.target.as_ref()))
-                .map_or(false, |v| match &self.arg {
-                    CheckFieldsPredicateArg::String(s) => s.as_bytes() == v.as_bytes(),
-                    _ => false,
-                }),
-        }
+        target_value(event, &self.target).map_or(false, |v| match (&self.arg, v.as_ref()) {
+            (CheckFieldsPredicateArg::String(s), v) => s.as_bytes() == v.as_bytes(),
+            (CheckFieldsPredicateArg::Integer(i), Value::Integer(vi)) => *i == *vi,
+            (CheckFieldsPredicateArg::Integer(i), Value::Float(vf)) => *i == *vf as i64,
+            (CheckFieldsPredicateArg::Float(f), Value::Float(vf)) => *f == *vf,
+            (CheckFieldsPredicateArg::Float(f), Value::Integer(vi)) => *f == *vi as f64,
+            (CheckFieldsPredicateArg::Boolean(b), Value::Boolean(vb)) => *b == *vb,
+            _ => false,
+        })
     }
 }
 
@@ -103,12 +123,7 @@ impl ContainsPredicate {
 
 impl CheckFieldsPredicate for ContainsPredicate {
     fn check(&self, event: &Event) -> bool {
-        match event {
-            Event::Log(l) => l
-                .get(&self.target)
-                .map_or(false, |v| v.to_string_lossy().contains(&self.arg)),
-            _ => false,
-        }
+        target_value(event, &self.target).map_or(false, |v| v.to_string_lossy().contains(&self.arg))
     }
 }
 
@@ -137,12 +152,8 @@ impl StartsWithPredicate {
 
 impl CheckFieldsPredicate for StartsWithPredicate {
     fn check(&self, event: &Event) -> bool {
-        match event {
-            Event::Log(l) => l
-                .get(&self.target)
-                .map_or(false, |v| v.to_string_lossy().starts_with(&self.arg)),
-            _ => false,
-        }
+        target_value(event, &self.target)
+            .map_or(false, |v| v.to_string_lossy().starts_with(&self.arg))
     }
 }
 
@@ -171,12 +182,8 @@ impl EndsWithPredicate {
 
 impl CheckFieldsPredicate for EndsWithPredicate {
     fn check(&self, event: &Event) -> bool {
-        match event {
-            Event::Log(l) => l
-                .get(&self.target)
-                .map_or(false, |v| v.to_string_lossy().ends_with(&self.arg)),
-            _ => false,
-        }
+        target_value(event, &self.target)
+            .map_or(false, |v| v.to_string_lossy().ends_with(&self.arg))
     }
 }
 
@@ -200,6 +207,12 @@ impl NotEqualsPredicate {
                 CheckFieldsPredicateArg::Integer(a) => format!("{}", a),
                 CheckFieldsPredicateArg::Float(a) => format!("{}", a),
                 CheckFieldsPredicateArg::Boolean(a) => format!("{}", a),
+                CheckFieldsPredicateArg::Array(_) => {
+                    return Err(
+                        "not_equals predicate requires a string, number or boolean argument"
+                            .to_owned(),
+                    )
+                }
             },
         }))
     }
@@ -207,17 +220,9 @@ impl NotEqualsPredicate {
 
 impl CheckFieldsPredicate for NotEqualsPredicate {
     fn check(&self, event: &Event) -> bool {
-        match event {
-            Event::Log(l) => l
-                .get(&self.target)
-                .map(|f| f.as_bytes())
-                .map_or(false, |b| b != self.arg.as_bytes()),
-            Event::Metric(m) => m
-                .tags
-                .as_ref()
-                .and_then(|t| t.get(self.target.as_ref()))
-                .map_or(false, |v| v.as_bytes() != self.arg.as_bytes()),
-        }
+        target_value(event, &self.target)
+            .map(|f| f.as_bytes())
+            .map_or(false, |b| b != self.arg.as_bytes())
     }
 }
 
@@ -247,17 +252,9 @@ impl RegexPredicate {
 
 impl CheckFieldsPredicate for RegexPredicate {
     fn check(&self, event: &Event) -> bool {
-        match event {
-            Event::Log(log) => log
-                .get(&self.target)
-                .map(|field| field.to_string_lossy())
-                .map_or(false, |field| self.regex.is_match(&field)),
-            Event::Metric(metric) => metric
-                .tags
-                .as_ref()
-                .and_then(|tags| tags.get(self.target.as_ref()))
-                .map_or(false, |field| self.regex.is_match(field)),
-        }
+        target_value(event, &self.target)
+            .map(|field| field.to_string_lossy())
+            .map_or(false, |field| self.regex.is_match(&field))
     }
 }
 
@@ -286,18 +283,259 @@ impl ExistsPredicate {
 
 impl CheckFieldsPredicate for ExistsPredicate {
     fn check(&self, event: &Event) -> bool {
-        (match event {
-            Event::Log(l) => l.get(&self.target).is_some(),
-            Event::Metric(m) => m
-                .tags
-                .as_ref()
-                .map_or(false, |t| t.contains_key(self.target.as_ref())),
-        }) == self.arg
+        target_value(event, &self.target).is_some() == self.arg
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct CompareNumberPredicate {
+    target: Atom,
+    arg: f64,
+    compare: fn(f64, f64) -> bool,
+}
+
+impl CompareNumberPredicate {
+    pub fn new(
+        target: String,
+        arg: &CheckFieldsPredicateArg,
+        predicate: &str,
+        compare: fn(f64, f64) -> bool,
+    ) -> Result<Box<dyn CheckFieldsPredicate>, String> {
+        match arg.as_number() {
+            Some(arg) => Ok(Box::new(Self {
+                target: target.into(),
+                arg,
+                compare,
+            })),
+            None => Err(format!(
+                "{} predicate requires a numeric argument",
+                predicate
+            )),
+        }
+    }
+}
+
+impl CheckFieldsPredicate for CompareNumberPredicate {
+    fn check(&self, event: &Event) -> bool {
+        target_value(event, &self.target)
+            .and_then(|v| as_number(&v))
+            .map_or(false, |v| (self.compare)(v, self.arg))
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct BetweenPredicate {
+    target: Atom,
+    min: f64,
+    max: f64,
+}
+
+impl BetweenPredicate {
+    pub fn new(
+        target: String,
+        arg: &CheckFieldsPredicateArg,
+    ) -> Result<Box<dyn CheckFieldsPredicate>, String> {
+        let bounds = match arg {
+            CheckFieldsPredicateArg::Array(bounds) if bounds.len() == 2 => {
+                (bounds[0].as_number(), bounds[1].as_number())
+            }
+            _ => (None, None),
+        };
+        match bounds {
+            (Some(min), Some(max)) if min <= max => Ok(Box::new(Self {
+                target: target.into(),
+                min,
+                max,
+            })),
+            _ => Err(
+                "between predicate requires an array of a lower and an upper numeric bound"
+                    .to_owned(),
+            ),
+        }
+    }
+}
+
+impl CheckFieldsPredicate for BetweenPredicate {
+    fn check(&self, event: &Event) -> bool {
+        target_value(event, &self.target)
+            .and_then(|v| as_number(&v))
+            .map_or(false, |v| self.min <= v && v <= self.max)
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct LengthEqualsPredicate {
+    target: Atom,
+    arg: usize,
+}
+
+impl LengthEqualsPredicate {
+    pub fn new(
+        target: String,
+        arg: &CheckFieldsPredicateArg,
+    ) -> Result<Box<dyn CheckFieldsPredicate>, String> {
+        match arg {
+            CheckFieldsPredicateArg::Integer(i) if *i >= 0 => Ok(Box::new(Self {
+                target: target.into(),
+                arg: *i as usize,
+            })),
+            _ => Err("length_eq predicate requires a non-negative integer argument".to_owned()),
+        }
+    }
+}
+
+impl CheckFieldsPredicate for LengthEqualsPredicate {
+    fn check(&self, event: &Event) -> bool {
+        target_value(event, &self.target).map_or(false, |v| match v.as_ref() {
+            Value::Bytes(bytes) => String::from_utf8_lossy(bytes).chars().count() == self.arg,
+            Value::Array(array) => array.len() == self.arg,
+            Value::Map(map) => map.len() == self.arg,
+            _ => false,
+        })
     }
 }
 
 //------------------------------------------------------------------------------
 
+#[derive(Debug, Clone, PartialEq)]
+struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    fn parse(network: &str) -> Result<Self, String> {
+        let mut parts = network.splitn(2, '/');
+        let addr = parts
+            .next()
+            .unwrap_or_default()
+            .parse::<IpAddr>()
+            .map_err(|error| format!("Invalid network \"{}\": {}", network, error))?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match parts.next() {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| format!("Invalid prefix length in network \"{}\"", network))?,
+            None => max_prefix,
+        };
+        Ok(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::max_value()
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::max_value()
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IpCidrPredicate {
+    target: Atom,
+    networks: Vec<IpNetwork>,
+}
+
+impl IpCidrPredicate {
+    pub fn new(
+        target: String,
+        arg: &CheckFieldsPredicateArg,
+    ) -> Result<Box<dyn CheckFieldsPredicate>, String> {
+        let networks = match arg {
+            CheckFieldsPredicateArg::String(network) => vec![IpNetwork::parse(network)?],
+            CheckFieldsPredicateArg::Array(networks) => networks
+                .iter()
+                .map(|network| match network {
+                    CheckFieldsPredicateArg::String(network) => IpNetwork::parse(network),
+                    _ => Err("ip_cidr predicate requires string networks".to_owned()),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => {
+                return Err(
+                    "ip_cidr predicate requires a network or an array of networks".to_owned(),
+                )
+            }
+        };
+        Ok(Box::new(Self {
+            target: target.into(),
+            networks,
+        }))
+    }
+}
+
+impl CheckFieldsPredicate for IpCidrPredicate {
+    fn check(&self, event: &Event) -> bool {
+        target_value(event, &self.target)
+            .and_then(|v| v.to_string_lossy().trim().parse::<IpAddr>().ok())
+            .map_or(false, |ip| {
+                self.networks.iter().any(|network| network.contains(ip))
+            })
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+enum Combinator {
+    Any,
+    All,
+    Not,
+}
+
+/// Combines nested conditions, which can be of any type.
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct CombinatorPredicate {
+    combinator: Combinator,
+    #[derivative(Debug = "ignore")]
+    conditions: Vec<Box<dyn Condition>>,
+}
+
+impl CheckFieldsPredicate for CombinatorPredicate {
+    fn check(&self, event: &Event) -> bool {
+        match self.combinator {
+            Combinator::Any => self.conditions.iter().any(|c| c.check(event)),
+            Combinator::All => self.conditions.iter().all(|c| c.check(event)),
+            Combinator::Not => !self.conditions.iter().all(|c| c.check(event)),
+        }
+    }
+}
+
+fn build_combinator(
+    combinator: Combinator,
+    conditions: &[&AnyCondition],
+) -> Result<Box<dyn CheckFieldsPredicate>, String> {
+    let conditions = conditions
+        .iter()
+        .map(|condition| condition.build())
+        .collect::<crate::Result<_>>()
+        .map_err(|error| error.to_string())?;
+    Ok(Box::new(CombinatorPredicate {
+        combinator,
+        conditions,
+    }))
+}
+
+//------------------------------------------------------------------------------
+
 fn build_predicate(
     predicate: &str,
     target: String,
@@ -318,6 +556,13 @@ fn build_predicate(
         "ends_with" => EndsWithPredicate::new(target, arg),
         "exists" => ExistsPredicate::new(target, arg),
         "regex" => RegexPredicate::new(target, arg),
+        "gt" => CompareNumberPredicate::new(target, arg, predicate, |v, arg| v > arg),
+        "gte" => CompareNumberPredicate::new(target, arg, predicate, |v, arg| v >= arg),
+        "lt" => CompareNumberPredicate::new(target, arg, predicate, |v, arg| v < arg),
+        "lte" => CompareNumberPredicate::new(target, arg, predicate, |v, arg| v <= arg),
+        "between" => BetweenPredicate::new(target, arg),
+        "length_eq" => LengthEqualsPredicate::new(target, arg),
+        "ip_cidr" => IpCidrPredicate::new(target, arg),
         _ => Err(format!("predicate type '{}' not recognized", predicate)),
     }
 }
@@ -365,8 +610,17 @@ fn build_predicates(
 
 //------------------------------------------------------------------------------
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default)]
 pub struct CheckFieldsConfig {
+    /// Passes if at least one of the conditions passes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    any: Vec<AnyCondition>,
+    /// Passes if all of the conditions pass.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    all: Vec<AnyCondition>,
+    /// Passes if the condition fails.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    not: Option<Box<AnyCondition>>,
     #[serde(flatten, default)]
     predicates: IndexMap<String, CheckFieldsPredicateArg>,
 }
@@ -378,7 +632,34 @@ inventory::submit! {
 #[typetag::serde(name = "check_fields")]
 impl ConditionConfig for CheckFieldsConfig {
     fn build(&self) -> crate::Result<Box<dyn Condition>> {
+        let mut combinators = Vec::new();
+        if !self.any.is_empty() {
+            combinators.push(("any", Combinator::Any, self.any.iter().collect::<Vec<_>>()));
+        }
+        if !self.all.is_empty() {
+            combinators.push(("all", Combinator::All, self.all.iter().collect()));
+        }
+        if let Some(not) = &self.not {
+            combinators.push(("not", Combinator::Not, vec![not.as_ref()]));
+        }
+
         build_predicates(&self.predicates)
+            .and_then(|mut preds| {
+                let mut errors = Vec::new();
+                for (name, combinator, conditions) in combinators {
+                    match build_combinator(combinator, &conditions) {
+                        Ok(pred) => {
+                            preds.insert(name.to_owned(), pred);
+                        }
+                        Err(err) => errors.push(err),
+                    }
+                }
+                if errors.is_empty() {
+                    Ok(preds)
+                } else {
+                    Err(errors)
+                }
+            })
             .map(|preds| -> Box<dyn Condition> { Box::new(CheckFields { predicates: preds }) })
             .map_err(|errs| {
                 if errs.len() > 1 {
@@ -427,8 +708,21 @@ impl Condition for CheckFields {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::event::metric::{Metric, MetricKind, MetricValue};
     use crate::Event;
 
+    fn build(config: &str) -> crate::Result<Box<dyn Condition>> {
+        toml::from_str::<CheckFieldsConfig>(config).unwrap().build()
+    }
+
+    fn log_event(fields: &[(&str, Value)]) -> Event {
+        let mut event = Event::new_empty_log();
+        for (name, value) in fields {
+            event.as_mut_log().insert(*name, value.clone());
+        }
+        event
+    }
+
     #[test]
     fn check_predicate_errors() {
         let cases = vec![
@@ -449,11 +743,14 @@ mod test {
             preds.insert(pred.into(), CheckFieldsPredicateArg::String("foo".into()));
 
             assert_eq!(
-                CheckFieldsConfig { predicates: preds }
-                    .build()
-                    .err()
-                    .unwrap()
-                    .to_string(),
+                CheckFieldsConfig {
+                    predicates: preds,
+                    ..Default::default()
+                }
+                .build()
+                .err()
+                .unwrap()
+                .to_string(),
                 exp.to_owned()
             );
         }
@@ -463,7 +760,8 @@ mod test {
 
         assert_eq!(
             CheckFieldsConfig {
-                predicates: aggregated_preds
+                predicates: aggregated_preds,
+                ..Default::default()
             }
             .build()
             .err()
@@ -485,7 +783,12 @@ mod test {
             CheckFieldsPredicateArg::String("bar".into()),
         );
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("neither");
         assert_eq!(cond.check(&event), false);
@@ -528,7 +831,12 @@ mod test {
             CheckFieldsPredicateArg::String("bar".into()),
         );
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("neither");
         assert_eq!(cond.check(&event), false);
@@ -571,7 +879,12 @@ mod test {
             CheckFieldsPredicateArg::String("bar".into()),
         );
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("neither");
         assert_eq!(cond.check(&event), false);
@@ -614,7 +927,12 @@ mod test {
             CheckFieldsPredicateArg::String("bar".into()),
         );
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("neither");
         assert_eq!(cond.check(&event), false);
@@ -657,7 +975,12 @@ mod test {
             CheckFieldsPredicateArg::String("bar".into()),
         );
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("neither");
         assert_eq!(cond.check(&event), false);
@@ -700,7 +1023,12 @@ mod test {
             CheckFieldsPredicateArg::String("bar".into()),
         );
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("not foo");
         assert_eq!(cond.check(&event), false);
@@ -743,7 +1071,12 @@ mod test {
             CheckFieldsPredicateArg::String("end$".into()),
         );
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("starts with a bang");
         assert_eq!(cond.check(&event), false);
@@ -780,7 +1113,12 @@ mod test {
         preds.insert("foo.exists".into(), CheckFieldsPredicateArg::Boolean(true));
         preds.insert("bar.exists".into(), CheckFieldsPredicateArg::Boolean(false));
 
-        let cond = CheckFieldsConfig { predicates: preds }.build().unwrap();
+        let cond = CheckFieldsConfig {
+            predicates: preds,
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
 
         let mut event = Event::from("ignored field");
         assert_eq!(cond.check(&event), false);
@@ -800,4 +1138,208 @@ mod test {
             Err("predicates failed: [ bar.exists: false ]".to_owned())
         );
     }
+
+    #[test]
+    fn check_field_numeric() {
+        let cond = build(
+            r#"
+                "status.gte" = 500
+                "status.lt" = 600
+                "duration.between" = [0.5, 2.0]
+            "#,
+        )
+        .unwrap();
+
+        let event = log_event(&[("status", 503.into()), ("duration", 1.5.into())]);
+        assert_eq!(cond.check(&event), true);
+
+        let event = log_event(&[("status", "503".into()), ("duration", "2".into())]);
+        assert_eq!(cond.check(&event), true);
+
+        let event = log_event(&[("status", 404.into()), ("duration", 0.1.into())]);
+        assert_eq!(
+            cond.check_with_context(&event),
+            Err("predicates failed: [ status.gte: 500, duration.between: [0.5, 2.0] ]".to_owned())
+        );
+
+        let event = log_event(&[("status", "not a number".into()), ("duration", 1.into())]);
+        assert_eq!(cond.check(&event), false);
+    }
+
+    #[test]
+    fn check_field_length_eq() {
+        let cond = build(
+            r#"
+                "code.length_eq" = 3
+                "tags.length_eq" = 2
+            "#,
+        )
+        .unwrap();
+
+        let tags = Value::Array(vec!["a".into(), "b".into()]);
+        let event = log_event(&[("code", "äbc".into()), ("tags", tags.clone())]);
+        assert_eq!(cond.check(&event), true);
+
+        let event = log_event(&[("code", "abcd".into()), ("tags", tags)]);
+        assert_eq!(cond.check(&event), false);
+    }
+
+    #[test]
+    fn check_field_ip_cidr() {
+        let cond =
+            build(r#""client.ip_cidr" = ["10.0.0.0/8", "192.168.1.0/24", "fd00::/8"]"#).unwrap();
+
+        for ip in &["10.1.2.3", "192.168.1.255", "fd12::1"] {
+            assert_eq!(cond.check(&log_event(&[("client", (*ip).into())])), true);
+        }
+        for ip in &["11.0.0.1", "192.168.2.1", "fe80::1", "not an ip"] {
+            assert_eq!(cond.check(&log_event(&[("client", (*ip).into())])), false);
+        }
+
+        let cond = build(r#""client.ip_cidr" = "0.0.0.0/0""#).unwrap();
+        assert_eq!(
+            cond.check(&log_event(&[("client", "8.8.8.8".into())])),
+            true
+        );
+
+        assert_eq!(
+            build(r#""client.ip_cidr" = "10.0.0.0/33""#)
+                .err()
+                .unwrap()
+                .to_string(),
+            r#"Invalid prefix length in network "10.0.0.0/33""#
+        );
+    }
+
+    #[test]
+    fn check_field_combinators() {
+        let cond = build(
+            r#"
+                "message.exists" = true
+                any = [
+                    { "level.eq" = "error" },
+                    { "status.gte" = 500 },
+                ]
+                not = { type = "check_fields", "path.starts_with" = "/health" }
+            "#,
+        )
+        .unwrap();
+
+        let event = log_event(&[("message", "a".into()), ("level", "error".into())]);
+        assert_eq!(cond.check(&event), true);
+
+        let event = log_event(&[("message", "a".into()), ("status", 502.into())]);
+        assert_eq!(cond.check(&event), true);
+
+        let event = log_event(&[("message", "a".into()), ("level", "info".into())]);
+        assert_eq!(
+            cond.check_with_context(&event),
+            Err("predicates failed: [ any ]".to_owned())
+        );
+
+        let event = log_event(&[
+            ("message", "a".into()),
+            ("level", "error".into()),
+            ("path", "/health/live".into()),
+        ]);
+        assert_eq!(
+            cond.check_with_context(&event),
+            Err("predicates failed: [ not ]".to_owned())
+        );
+
+        let cond = build(
+            r#"
+                all = [
+                    { type = "is_log" },
+                    { any = [{ "a.eq" = 1 }, { "b.eq" = 1 }] },
+                ]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cond.check(&log_event(&[("b", 1.into())])), true);
+        assert_eq!(cond.check(&log_event(&[("c", 1.into())])), false);
+
+        assert_eq!(
+            build(r#"any = [{ "a.not_real" = 1 }]"#)
+                .err()
+                .unwrap()
+                .to_string(),
+            "predicate type 'not_real' not recognized"
+        );
+    }
+
+    #[test]
+    fn check_field_metric() {
+        let cond = build(
+            r#"
+                "metric.name.starts_with" = "http_"
+                "metric.kind.eq" = "absolute"
+                "metric.value.gt" = 10
+                "tags.method.eq" = "GET"
+            "#,
+        )
+        .unwrap();
+
+        let metric = |name: &str, kind, value, method: &str| {
+            Event::Metric(Metric {
+                name: name.into(),
+                timestamp: None,
+                tags: Some(
+                    vec![("method".to_owned(), method.to_owned())]
+                        .into_iter()
+                        .collect(),
+                ),
+                kind,
+                value: MetricValue::Gauge { value },
+            })
+        };
+
+        assert_eq!(
+            cond.check(&metric("http_requests", MetricKind::Absolute, 11.0, "GET")),
+            true
+        );
+        assert_eq!(
+            cond.check_with_context(&metric(
+                "rpc_requests",
+                MetricKind::Incremental,
+                10.0,
+                "PUT"
+            )),
+            Err(concat!(
+                "predicates failed: [ metric.name.starts_with: \"http_\", ",
+                "metric.kind.eq: \"absolute\", metric.value.gt: 10, tags.method.eq: \"GET\" ]"
+            )
+            .to_owned())
+        );
+    }
+
+    #[test]
+    fn check_field_metric_tags_with_and_without_prefix() {
+        let cond = build(
+            r#"
+                "metric.name.eq" = "requests"
+                "tags.metric.name.eq" = "spoofed"
+            "#,
+        )
+        .unwrap();
+        let bare = build(r#""method.exists" = true"#).unwrap();
+
+        let event = Event::Metric(Metric {
+            name: "requests".into(),
+            timestamp: None,
+            tags: Some(
+                vec![
+                    ("metric.name".to_owned(), "spoofed".to_owned()),
+                    ("method".to_owned(), "GET".to_owned()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            kind: MetricKind::Absolute,
+            value: MetricValue::Gauge { value: 1.0 },
+        });
+
+        assert_eq!(cond.check(&event), true);
+        assert_eq!(bare.check(&event), true);
+    }
 }
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    conditions::{Condition, ConditionConfig, ConditionDescription},
+    Event,
+};
+
+//------------------------------------------------------------------------------
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct IsTraceConfig {}
+
+inventory::submit! {
+    ConditionDescription::new::<IsTraceConfig>("is_trace")
+}
+
+#[typetag::serde(name = "is_trace")]
+impl ConditionConfig for IsTraceConfig {
+    fn build(&self) -> crate::Result<Box<dyn Condition>> {
+        Ok(Box::new(IsTrace {}))
+    }
+}
+
+//------------------------------------------------------------------------------
+
+pub struct IsTrace {}
+
+impl Condition for IsTrace {
+    fn check(&self, e: &Event) -> bool {
+        match e {
+            Event::Trace(_) => true,
+            _ => false,
+        }
+    }
+
+    fn check_with_context(&self, e: &Event) -> Result<(), String> {
+        if self.check(e) {
+            Ok(())
+        } else {
+            Err("event is not a trace type".to_string())
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{event::Trace, Event};
+    use chrono::Utc;
+
+    #[test]
+    fn is_trace_basic() {
+        let cond = IsTraceConfig {}.build().unwrap();
+
+        assert_eq!(cond.check(&Event::from("just a log")), false);
+        assert_eq!(
+            cond.check(&Event::from(Trace::new(
+                "5b8aa5a2d2c872e8321cf37308d69df2",
+                "051581bf3cb55c13",
+                "test span",
+                Utc::now(),
+                Utc::now(),
+            ))),
+            true
+        );
+    }
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 pub mod check_fields;
 pub mod is_log;
 pub mod is_metric;
+pub mod is_trace;
 
 pub use check_fields::CheckFieldsConfig;
 
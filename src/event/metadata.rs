@@ -0,0 +1,183 @@
+use super::Value;
+use std::{borrow::Cow, collections::BTreeMap, iter};
+
+/// Key prefix that addresses an event's metadata instead of its fields,
+/// e.g. `%metadata.source_name`.
+pub const PREFIX: &str = "%metadata.";
+
+pub const SOURCE_NAME: &str = "source_name";
+pub const SOURCE_TYPE: &str = "source_type";
+pub const INGEST_TIMESTAMP: &str = "ingest_timestamp";
+pub const TLS_CLIENT_IDENTITY: &str = "tls_client_identity";
+pub const KAFKA_TOPIC: &str = "kafka_topic";
+pub const KAFKA_PARTITION: &str = "kafka_partition";
+pub const KAFKA_OFFSET: &str = "kafka_offset";
+
+/// Information about an event that is kept apart from its fields, so it is
+/// never written out by sinks unless explicitly copied into a field.
+///
+/// The keys the topology sets on every log, `source_name`, `source_type` and
+/// `ingest_timestamp`, have slots of their own, so setting them costs no
+/// allocation: the source's name and type are shared by all of its events.
+/// Anything else is kept in a map that is only allocated when the first such
+/// key is inserted.
+#[derive(Clone, Debug, Default)]
+pub struct EventMetadata {
+    source_name: Option<Value>,
+    source_type: Option<Value>,
+    ingest_timestamp: Option<Value>,
+    fields: Option<Box<BTreeMap<Cow<'static, str>, Value>>>,
+}
+
+impl EventMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self.slot(key) {
+            Some(slot) => slot.as_ref(),
+            None => self.fields.as_ref()?.get(key),
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match key {
+            SOURCE_NAME => self.source_name.as_mut(),
+            SOURCE_TYPE => self.source_type.as_mut(),
+            INGEST_TIMESTAMP => self.ingest_timestamp.as_mut(),
+            _ => self.fields.as_mut()?.get_mut(key),
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'static, str>>,
+        value: impl Into<Value>,
+    ) -> Option<Value> {
+        let key = key.into();
+        match self.slot_mut(&key) {
+            Some(slot) => slot.replace(value.into()),
+            None => self.fields_mut().insert(key, value.into()),
+        }
+    }
+
+    /// Inserts `value` unless the key is already set, as it is for events
+    /// that arrive from another Vector instance.
+    pub fn insert_if_absent(&mut self, key: impl Into<Cow<'static, str>>, value: impl Into<Value>) {
+        let key = key.into();
+        match self.slot_mut(&key) {
+            Some(slot) => {
+                if slot.is_none() {
+                    *slot = Some(value.into());
+                }
+            }
+            None => {
+                self.fields_mut().entry(key).or_insert_with(|| value.into());
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        match self.slot_mut(key) {
+            Some(slot) => slot.take(),
+            None => self.fields.as_mut()?.remove(key),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.slots()
+            .filter_map(|(key, slot)| Some((key, slot.as_ref()?)))
+            .chain(
+                self.fields
+                    .iter()
+                    .flat_map(|fields| fields.iter())
+                    .map(|(key, value)| (&**key, value)),
+            )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.source_name.is_none()
+            && self.source_type.is_none()
+            && self.ingest_timestamp.is_none()
+            && self.fields.as_ref().map_or(true, |fields| fields.is_empty())
+    }
+
+    fn slot(&self, key: &str) -> Option<&Option<Value>> {
+        match key {
+            SOURCE_NAME => Some(&self.source_name),
+            SOURCE_TYPE => Some(&self.source_type),
+            INGEST_TIMESTAMP => Some(&self.ingest_timestamp),
+            _ => None,
+        }
+    }
+
+    fn slot_mut(&mut self, key: &str) -> Option<&mut Option<Value>> {
+        match key {
+            SOURCE_NAME => Some(&mut self.source_name),
+            SOURCE_TYPE => Some(&mut self.source_type),
+            INGEST_TIMESTAMP => Some(&mut self.ingest_timestamp),
+            _ => None,
+        }
+    }
+
+    fn slots(&self) -> impl Iterator<Item = (&str, &Option<Value>)> {
+        iter::once((SOURCE_NAME, &self.source_name))
+            .chain(iter::once((SOURCE_TYPE, &self.source_type)))
+            .chain(iter::once((INGEST_TIMESTAMP, &self.ingest_timestamp)))
+    }
+
+    fn fields_mut(&mut self) -> &mut BTreeMap<Cow<'static, str>, Value> {
+        self.fields.get_or_insert_with(Default::default)
+    }
+}
+
+// Metadata that was never set equals metadata that was emptied.
+impl PartialEq for EventMetadata {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl From<BTreeMap<String, Value>> for EventMetadata {
+    fn from(fields: BTreeMap<String, Value>) -> Self {
+        let mut metadata = Self::new();
+        for (key, value) in fields {
+            metadata.insert(key, value);
+        }
+        metadata
+    }
+}
+
+impl IntoIterator for EventMetadata {
+    type Item = (Cow<'static, str>, Value);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let slots = vec![
+            (SOURCE_NAME, self.source_name),
+            (SOURCE_TYPE, self.source_type),
+            (INGEST_TIMESTAMP, self.ingest_timestamp),
+        ];
+        slots
+            .into_iter()
+            .filter_map(|(key, value)| Some((Cow::Borrowed(key), value?)))
+            .chain(self.fields.map(|fields| *fields).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Returns the metadata key addressed by `key`, if it starts with
+/// [`PREFIX`].
+pub(super) fn strip_prefix(key: &str) -> Option<&str> {
+    if key.starts_with(PREFIX) {
+        Some(&key[PREFIX.len()..])
+    } else {
+        None
+    }
+}
@@ -1,3 +1,4 @@
+use super::{sketch::DDSketch, Value};
 use chrono::{DateTime, Utc};
 use derive_is_enum_variant::is_enum_variant;
 use serde::{Deserialize, Serialize};
@@ -48,6 +49,9 @@ pub enum MetricValue {
         count: u32,
         sum: f64,
     },
+    /// A mergeable summary of a distribution, which keeps a bounded number
+    /// of bins instead of every sample.
+    Sketch(DDSketch),
 }
 
 impl Metric {
@@ -113,10 +117,57 @@ impl Metric {
                     *sum += sum2;
                 }
             }
+            (MetricValue::Sketch(ref mut sketch), MetricValue::Sketch(sketch2)) => {
+                if !sketch.merge(sketch2) {
+                    warn!(
+                        message = "Sketches with different accuracies can't be merged, keeping the newer one.",
+                        name = %self.name,
+                        rate_limit_secs = 30
+                    );
+                    *sketch = sketch2.clone();
+                }
+            }
             _ => {}
         }
     }
 
+    /// Looks up a field of the metric by name, as used in templates and
+    /// conditions. `metric.name`, `metric.kind` and `metric.value` refer to
+    /// the metric itself, `tags.<name>` and any other name to a tag. The
+    /// prefix allows reaching tags that are named like the metric's own
+    /// fields.
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        match name {
+            "metric.name" => Some(self.name.as_str().into()),
+            "metric.kind" => Some(
+                match self.kind {
+                    MetricKind::Incremental => "incremental",
+                    MetricKind::Absolute => "absolute",
+                }
+                .into(),
+            ),
+            "metric.value" => match &self.value {
+                MetricValue::Counter { value } | MetricValue::Gauge { value } => {
+                    Some(Value::Float(*value))
+                }
+                MetricValue::Set { values } => Some(Value::Integer(values.len() as i64)),
+                // Distributions and aggregates have no single value.
+                _ => None,
+            },
+            tag => self
+                .tags
+                .as_ref()
+                .and_then(|tags| {
+                    if tag.starts_with("tags.") {
+                        tags.get(&tag["tags.".len()..]).or_else(|| tags.get(tag))
+                    } else {
+                        tags.get(tag)
+                    }
+                })
+                .map(|value| value.as_str().into()),
+        }
+    }
+
     pub fn reset(&mut self) {
         match &mut self.value {
             MetricValue::Counter { ref mut value } => {
@@ -159,6 +210,9 @@ impl Metric {
                 *count = 0;
                 *sum = 0.0;
             }
+            MetricValue::Sketch(ref mut sketch) => {
+                sketch.clear();
+            }
         }
     }
 }
@@ -320,4 +374,31 @@ mod test {
             }
         )
     }
+
+    #[test]
+    fn merge_sketches_with_different_accuracies() {
+        let mut coarse = DDSketch::new(0.05);
+        coarse.insert(1.0);
+        let mut fine = DDSketch::new(0.01);
+        fine.insert(2.0);
+
+        let mut sketch = Metric {
+            name: "latency".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Sketch(coarse),
+        };
+
+        let delta = Metric {
+            name: "latency".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Sketch(fine.clone()),
+        };
+
+        sketch.add(&delta);
+        assert_eq!(sketch.value, MetricValue::Sketch(fine));
+    }
 }
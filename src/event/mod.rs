@@ -7,16 +7,23 @@ use metric::{MetricKind, MetricValue};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value as JsonValue;
+use sketch::DDSketch;
 use std::{collections::BTreeMap, iter::FromIterator};
 use string_cache::DefaultAtom as Atom;
+use trace::{SpanEvent, SpanKind, SpanStatus, StatusCode};
 
 pub mod discriminant;
 pub mod merge;
 pub mod merge_state;
+pub mod metadata;
 pub mod metric;
+pub mod sketch;
+pub mod trace;
 mod util;
 
+pub use metadata::EventMetadata;
 pub use metric::Metric;
+pub use trace::Trace;
 
 pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/event.proto.rs"));
@@ -40,11 +47,21 @@ lazy_static! {
 pub enum Event {
     Log(LogEvent),
     Metric(Metric),
+    Trace(Trace),
 }
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct LogEvent {
     fields: BTreeMap<String, Value>,
+    metadata: EventMetadata,
+}
+
+// Metadata such as the ingest time differs between otherwise identical
+// events, so it does not take part in comparisons.
+impl PartialEq for LogEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields == other.fields
+    }
 }
 
 impl Event {
@@ -93,25 +110,82 @@ impl Event {
             _ => panic!("failed type coercion, {:?} is not a metric", self),
         }
     }
+
+    pub fn as_trace(&self) -> &Trace {
+        match self {
+            Event::Trace(trace) => trace,
+            _ => panic!("failed type coercion, {:?} is not a trace", self),
+        }
+    }
+
+    pub fn as_mut_trace(&mut self) -> &mut Trace {
+        match self {
+            Event::Trace(trace) => trace,
+            _ => panic!("failed type coercion, {:?} is not a trace", self),
+        }
+    }
+
+    pub fn into_trace(self) -> Trace {
+        match self {
+            Event::Trace(trace) => trace,
+            _ => panic!("failed type coercion, {:?} is not a trace", self),
+        }
+    }
+
+    /// Metadata is only kept for logs, metrics and traces have none, so
+    /// this returns `None` for them.
+    pub fn metadata(&self) -> Option<&EventMetadata> {
+        match self {
+            Event::Log(log) => Some(log.metadata()),
+            _ => None,
+        }
+    }
+
+    /// Like `metadata`, `None` for metrics and traces, so anything set on
+    /// them, such as the source they came from, is not kept.
+    pub fn metadata_mut(&mut self) -> Option<&mut EventMetadata> {
+        match self {
+            Event::Log(log) => Some(log.metadata_mut()),
+            _ => None,
+        }
+    }
 }
 
 impl LogEvent {
     pub fn new() -> Self {
         Self {
             fields: BTreeMap::new(),
+            metadata: EventMetadata::new(),
         }
     }
 
+    pub fn metadata(&self) -> &EventMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut EventMetadata {
+        &mut self.metadata
+    }
+
     pub fn get(&self, key: &Atom) -> Option<&Value> {
-        util::log::get(&self.fields, key)
+        match metadata::strip_prefix(key) {
+            Some(key) => self.metadata.get(key),
+            None => util::log::get(&self.fields, key),
+        }
     }
 
     pub fn get_mut(&mut self, key: &Atom) -> Option<&mut Value> {
-        util::log::get_mut(&mut self.fields, key)
+        match metadata::strip_prefix(key) {
+            Some(key) => self.metadata.get_mut(key),
+            None => util::log::get_mut(&mut self.fields, key),
+        }
     }
 
     pub fn contains(&self, key: &Atom) -> bool {
-        util::log::contains(&self.fields, key)
+        match metadata::strip_prefix(key) {
+            Some(key) => self.metadata.contains(key),
+            None => util::log::contains(&self.fields, key),
+        }
     }
 
     pub fn insert<K, V>(&mut self, key: K, value: V) -> Option<Value>
@@ -119,7 +193,10 @@ impl LogEvent {
         K: AsRef<str>,
         V: Into<Value>,
     {
-        util::log::insert(&mut self.fields, key.as_ref(), value.into())
+        match metadata::strip_prefix(key.as_ref()) {
+            Some(key) => self.metadata.insert(key.to_owned(), value),
+            None => util::log::insert(&mut self.fields, key.as_ref(), value.into()),
+        }
     }
 
     pub fn insert_flat<K, V>(&mut self, key: K, value: V)
@@ -131,11 +208,14 @@ impl LogEvent {
     }
 
     pub fn remove(&mut self, key: &Atom) -> Option<Value> {
-        util::log::remove(&mut self.fields, &key, false)
+        self.remove_prune(key, false)
     }
 
     pub fn remove_prune(&mut self, key: &Atom, prune: bool) -> Option<Value> {
-        util::log::remove(&mut self.fields, &key, prune)
+        match metadata::strip_prefix(key) {
+            Some(key) => self.metadata.remove(key),
+            None => util::log::remove(&mut self.fields, &key, prune),
+        }
     }
 
     pub fn keys<'a>(&'a self) -> impl Iterator<Item = String> + 'a {
@@ -499,7 +579,14 @@ impl From<proto::EventWrapper> for Event {
                     .filter_map(|(k, v)| decode_value(v).map(|value| (k, value)))
                     .collect::<BTreeMap<_, _>>();
 
-                Event::Log(LogEvent { fields })
+                let metadata = proto
+                    .metadata
+                    .into_iter()
+                    .filter_map(|(k, v)| decode_value(v).map(|value| (k, value)))
+                    .collect::<BTreeMap<_, _>>()
+                    .into();
+
+                Event::Log(LogEvent { fields, metadata })
             }
             EventProto::Metric(proto) => {
                 let kind = match proto.kind() {
@@ -543,6 +630,23 @@ impl From<proto::EventWrapper> for Event {
                         count: summary.count,
                         sum: summary.sum,
                     },
+                    MetricProto::Sketch(sketch) => {
+                        let sketch = DDSketch {
+                            alpha: sketch.alpha,
+                            keys: sketch.keys,
+                            counts: sketch.counts,
+                            count: sketch.count,
+                            sum: sketch.sum,
+                            min: sketch.min,
+                            max: sketch.max,
+                        };
+                        if sketch.is_valid() {
+                            MetricValue::Sketch(sketch)
+                        } else {
+                            error!("encoded event contains invalid sketch, dropping its values");
+                            MetricValue::Sketch(DDSketch::default())
+                        }
+                    }
                 };
 
                 Event::Metric(Metric {
@@ -553,10 +657,92 @@ impl From<proto::EventWrapper> for Event {
                     value,
                 })
             }
+            EventProto::Trace(proto) => {
+                let kind = match proto.kind() {
+                    proto::trace::Kind::Unspecified => SpanKind::Unspecified,
+                    proto::trace::Kind::Internal => SpanKind::Internal,
+                    proto::trace::Kind::Server => SpanKind::Server,
+                    proto::trace::Kind::Client => SpanKind::Client,
+                    proto::trace::Kind::Producer => SpanKind::Producer,
+                    proto::trace::Kind::Consumer => SpanKind::Consumer,
+                };
+
+                let parent_span_id = if !proto.parent_span_id.is_empty() {
+                    Some(proto.parent_span_id)
+                } else {
+                    None
+                };
+
+                let events = proto
+                    .events
+                    .into_iter()
+                    .map(|event| SpanEvent {
+                        name: event.name,
+                        timestamp: decode_timestamp(event.timestamp),
+                        attributes: decode_fields(event.attributes),
+                    })
+                    .collect();
+
+                let status = proto
+                    .status
+                    .map(|status| SpanStatus {
+                        code: match status.code() {
+                            proto::span_status::Code::Unset => StatusCode::Unset,
+                            proto::span_status::Code::Ok => StatusCode::Ok,
+                            proto::span_status::Code::Error => StatusCode::Error,
+                        },
+                        message: if !status.message.is_empty() {
+                            Some(status.message)
+                        } else {
+                            None
+                        },
+                    })
+                    .unwrap_or_default();
+
+                Event::Trace(Trace {
+                    trace_id: proto.trace_id,
+                    span_id: proto.span_id,
+                    parent_span_id,
+                    name: proto.name,
+                    kind,
+                    start_time: decode_timestamp(proto.start_time),
+                    end_time: decode_timestamp(proto.end_time),
+                    attributes: decode_fields(proto.attributes),
+                    events,
+                    status,
+                })
+            }
         }
     }
 }
 
+fn decode_fields(fields: BTreeMap<String, proto::Value>) -> BTreeMap<String, Value> {
+    fields
+        .into_iter()
+        .filter_map(|(k, v)| decode_value(v).map(|value| (k, value)))
+        .collect()
+}
+
+fn decode_timestamp(timestamp: Option<prost_types::Timestamp>) -> DateTime<Utc> {
+    timestamp
+        .map(|ts| chrono::Utc.timestamp(ts.seconds, ts.nanos as u32))
+        .unwrap_or_else(|| chrono::Utc.timestamp(0, 0))
+}
+
+fn encode_fields(fields: BTreeMap<String, Value>) -> BTreeMap<String, proto::Value> {
+    fields
+        .into_iter()
+        .map(|(k, v)| (k, encode_value(v)))
+        .collect()
+}
+
+fn encode_timestamp(timestamp: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: timestamp.timestamp(),
+        nanos: timestamp.timestamp_subsec_nanos() as i32,
+    }
+}
+
 fn encode_value(value: Value) -> proto::Value {
     proto::Value {
         kind: match value {
@@ -593,13 +779,18 @@ fn encode_array(items: Vec<Value>) -> proto::ValueArray {
 impl From<Event> for proto::EventWrapper {
     fn from(event: Event) -> Self {
         match event {
-            Event::Log(LogEvent { fields }) => {
+            Event::Log(LogEvent { fields, metadata }) => {
                 let fields = fields
                     .into_iter()
                     .map(|(k, v)| (k.to_string(), encode_value(v)))
                     .collect::<BTreeMap<_, _>>();
 
-                let event = EventProto::Log(Log { fields });
+                let metadata = metadata
+                    .into_iter()
+                    .map(|(k, v)| (k.into_owned(), encode_value(v)))
+                    .collect::<BTreeMap<_, _>>();
+
+                let event = EventProto::Log(Log { fields, metadata });
 
                 proto::EventWrapper { event: Some(event) }
             }
@@ -660,6 +851,15 @@ impl From<Event> for proto::EventWrapper {
                         count,
                         sum,
                     }),
+                    MetricValue::Sketch(sketch) => MetricProto::Sketch(proto::Sketch {
+                        alpha: sketch.alpha,
+                        keys: sketch.keys,
+                        counts: sketch.counts,
+                        count: sketch.count,
+                        sum: sketch.sum,
+                        min: sketch.min,
+                        max: sketch.max,
+                    }),
                 };
 
                 let event = EventProto::Metric(proto::Metric {
@@ -670,6 +870,62 @@ impl From<Event> for proto::EventWrapper {
                     value: Some(metric),
                 });
 
+                proto::EventWrapper { event: Some(event) }
+            }
+            Event::Trace(Trace {
+                trace_id,
+                span_id,
+                parent_span_id,
+                name,
+                kind,
+                start_time,
+                end_time,
+                attributes,
+                events,
+                status,
+            }) => {
+                let kind = match kind {
+                    SpanKind::Unspecified => proto::trace::Kind::Unspecified,
+                    SpanKind::Internal => proto::trace::Kind::Internal,
+                    SpanKind::Server => proto::trace::Kind::Server,
+                    SpanKind::Client => proto::trace::Kind::Client,
+                    SpanKind::Producer => proto::trace::Kind::Producer,
+                    SpanKind::Consumer => proto::trace::Kind::Consumer,
+                }
+                .into();
+
+                let events = events
+                    .into_iter()
+                    .map(|event| proto::SpanEvent {
+                        name: event.name,
+                        timestamp: Some(encode_timestamp(event.timestamp)),
+                        attributes: encode_fields(event.attributes),
+                    })
+                    .collect();
+
+                let status = proto::SpanStatus {
+                    code: match status.code {
+                        StatusCode::Unset => proto::span_status::Code::Unset,
+                        StatusCode::Ok => proto::span_status::Code::Ok,
+                        StatusCode::Error => proto::span_status::Code::Error,
+                    }
+                    .into(),
+                    message: status.message.unwrap_or_default(),
+                };
+
+                let event = EventProto::Trace(proto::Trace {
+                    trace_id,
+                    span_id,
+                    parent_span_id: parent_span_id.unwrap_or_default(),
+                    name,
+                    kind,
+                    start_time: Some(encode_timestamp(start_time)),
+                    end_time: Some(encode_timestamp(end_time)),
+                    attributes: encode_fields(attributes),
+                    events,
+                    status: Some(status),
+                });
+
                 proto::EventWrapper { event: Some(event) }
             }
         }
@@ -690,9 +946,7 @@ impl From<Event> for Vec<u8> {
 
 impl From<Bytes> for Event {
     fn from(message: Bytes) -> Self {
-        let mut event = Event::Log(LogEvent {
-            fields: BTreeMap::new(),
-        });
+        let mut event = Event::new_empty_log();
 
         event
             .as_mut_log()
@@ -729,10 +983,24 @@ impl From<Metric> for Event {
     }
 }
 
+impl From<Trace> for Event {
+    fn from(trace: Trace) -> Self {
+        Event::Trace(trace)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{Atom, Event, LogSchema, Value};
+    use super::{
+        metadata,
+        metric::{Metric, MetricKind, MetricValue},
+        proto,
+        sketch::DDSketch,
+        Atom, Event, LogSchema, SpanEvent, SpanKind, SpanStatus, StatusCode, Trace, Value,
+    };
+    use chrono::Utc;
     use regex::Regex;
+    use std::collections::BTreeMap;
     use std::collections::HashSet;
 
     #[test]
@@ -824,6 +1092,105 @@ mod test {
         );
     }
 
+    #[test]
+    fn metadata_is_kept_apart_from_fields() {
+        let mut event = Event::from("raw log line");
+        let log = event.as_mut_log();
+        log.insert("%metadata.source_name", "in");
+        log.metadata_mut().insert(metadata::KAFKA_OFFSET, 42);
+
+        assert_eq!(log[&Atom::from("%metadata.source_name")], "in".into());
+        assert!(log.contains(&Atom::from("%metadata.kafka_offset")));
+        assert!(!log.contains(&Atom::from("source_name")));
+        assert_eq!(log.keys().count(), 2);
+
+        let json = serde_json::to_value(&log).unwrap();
+        assert!(json.get("source_name").is_none());
+        assert!(json.get("%metadata").is_none());
+
+        assert_eq!(
+            log.remove(&Atom::from("%metadata.kafka_offset")),
+            Some(Value::from(42))
+        );
+        assert!(event
+            .metadata()
+            .unwrap()
+            .get(metadata::KAFKA_OFFSET)
+            .is_none());
+    }
+
+    #[test]
+    fn metadata_proto_roundtrip() {
+        let mut event = Event::from("raw log line");
+        let metadata = event.as_mut_log().metadata_mut();
+        metadata.insert(metadata::SOURCE_TYPE, "kafka");
+        metadata.insert(metadata::KAFKA_OFFSET, 42);
+        metadata.insert(metadata::INGEST_TIMESTAMP, Utc::now());
+
+        let decoded = Event::from(proto::EventWrapper::from(event.clone()));
+
+        // Comparing events leaves out their metadata.
+        assert_eq!(decoded, event);
+        assert_eq!(decoded.metadata(), event.metadata());
+        assert_eq!(
+            decoded.metadata().unwrap().get(metadata::SOURCE_TYPE),
+            Some(&Value::from("kafka"))
+        );
+    }
+
+    #[test]
+    fn drops_invalid_sketches_on_decode() {
+        let mut sketch = DDSketch::default();
+        sketch.insert(1.0);
+        sketch.insert(2.0);
+        sketch.keys.reverse();
+        let event = Event::Metric(Metric {
+            name: "latency".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Sketch(sketch),
+        });
+
+        let decoded = Event::from(proto::EventWrapper::from(event));
+
+        assert_eq!(
+            decoded.as_metric().value,
+            MetricValue::Sketch(DDSketch::default())
+        );
+    }
+
+    #[test]
+    fn trace_proto_roundtrip() {
+        let now = Utc::now();
+        let mut trace = Trace::new(
+            "5b8aa5a2d2c872e8321cf37308d69df2",
+            "051581bf3cb55c13",
+            "GET /",
+            now,
+            now,
+        );
+        trace.parent_span_id = Some("8c4fe4d1a35b9b2a".into());
+        trace.kind = SpanKind::Server;
+        trace
+            .attributes
+            .insert("http.status_code".into(), 500.into());
+        trace.events.push(SpanEvent {
+            name: "exception".into(),
+            timestamp: now,
+            attributes: BTreeMap::new(),
+        });
+        trace.status = SpanStatus {
+            code: StatusCode::Error,
+            message: Some("internal error".into()),
+        };
+        let event = Event::from(trace);
+
+        let decoded = Event::from(proto::EventWrapper::from(event.clone()));
+
+        assert_eq!(decoded, event);
+    }
+
     #[test]
     fn partial_log_schema() {
         let toml = r#"
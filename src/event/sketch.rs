@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+
+/// The relative accuracy sketches are created with unless configured
+/// otherwise.
+pub const DEFAULT_ALPHA: f64 = 0.01;
+
+/// Values closer to zero than this all fall into the zero bin.
+const MIN_INDEXABLE_VALUE: f64 = 1e-9;
+
+/// A mergeable quantile sketch, following DDSketch
+/// (https://arxiv.org/abs/1908.10693). Values are counted in logarithmically
+/// sized bins, so any quantile estimate is within `alpha` of the true value,
+/// relative to it, and two sketches with the same `alpha` merge losslessly.
+///
+/// Bins are kept as parallel `keys` and `counts`, ordered by key. Key `0`
+/// holds values closer to zero than 1e-9, positive keys hold positive
+/// values and negative keys hold negative values, so keys sort in the same
+/// order as the values they hold.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DDSketch {
+    pub alpha: f64,
+    pub keys: Vec<i32>,
+    pub counts: Vec<u32>,
+    pub count: u32,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for DDSketch {
+    fn default() -> Self {
+        Self::new(DEFAULT_ALPHA)
+    }
+}
+
+impl DDSketch {
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha,
+            keys: Vec::new(),
+            counts: Vec::new(),
+            count: 0,
+            sum: 0.0,
+            min: std::f64::INFINITY,
+            max: std::f64::NEG_INFINITY,
+        }
+    }
+
+    /// Builds a sketch from the samples of a distribution metric.
+    pub fn from_distribution(values: &[f64], sample_rates: &[u32]) -> Self {
+        let mut sketch = Self::default();
+        for (value, rate) in values.iter().zip(sample_rates) {
+            sketch.insert_n(*value, *rate);
+        }
+        sketch
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Whether the sketch is well formed, i.e. `alpha` is between 0 and 1
+    /// and there's a count for each key, with the keys strictly ascending.
+    /// Sketches from elsewhere need checking before use, as the other
+    /// methods rely on this.
+    pub fn is_valid(&self) -> bool {
+        self.alpha > 0.0
+            && self.alpha < 1.0
+            && self.keys.len() == self.counts.len()
+            && self.keys.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    pub fn insert(&mut self, value: f64) {
+        self.insert_n(value, 1);
+    }
+
+    /// Inserts `value` as if it was observed `n` times, e.g. for a sampled
+    /// statsd timer. Counts saturate rather than wrap around.
+    pub fn insert_n(&mut self, value: f64, n: u32) {
+        if n == 0 || !value.is_finite() {
+            return;
+        }
+
+        let key = self.key(value);
+        match self.keys.binary_search(&key) {
+            Ok(index) => self.counts[index] = self.counts[index].saturating_add(n),
+            Err(index) => {
+                self.keys.insert(index, key);
+                self.counts.insert(index, n);
+            }
+        }
+        self.count = self.count.saturating_add(n);
+        self.sum += value * f64::from(n);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Adds the observations of `other` to this sketch. Returns `false`, and
+    /// leaves this sketch as is, if the sketches have different accuracies.
+    pub fn merge(&mut self, other: &Self) -> bool {
+        if self.alpha != other.alpha {
+            return false;
+        }
+
+        let mut keys = Vec::with_capacity(self.keys.len() + other.keys.len());
+        let mut counts = Vec::with_capacity(keys.capacity());
+        let (mut i, mut j) = (0, 0);
+        while i < self.keys.len() || j < other.keys.len() {
+            let key = match (self.keys.get(i), other.keys.get(j)) {
+                (Some(a), Some(b)) => *a.min(b),
+                (Some(a), None) => *a,
+                (None, Some(b)) => *b,
+                (None, None) => unreachable!(),
+            };
+            let mut count = 0;
+            if self.keys.get(i) == Some(&key) {
+                count = self.counts[i];
+                i += 1;
+            }
+            if other.keys.get(j) == Some(&key) {
+                count = count.saturating_add(other.counts[j]);
+                j += 1;
+            }
+            keys.push(key);
+            counts.push(count);
+        }
+
+        self.keys = keys;
+        self.counts = counts;
+        self.count = self.count.saturating_add(other.count);
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.alpha);
+    }
+
+    /// Estimates the value at quantile `q`, between 0 and 1.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.is_empty() || q < 0.0 || q > 1.0 {
+            return None;
+        }
+
+        let rank = (q * f64::from(self.count - 1)).round() as u32;
+        if rank == 0 {
+            return Some(self.min);
+        } else if rank == self.count - 1 {
+            return Some(self.max);
+        }
+
+        let mut seen = 0;
+        for (key, count) in self.keys.iter().zip(&self.counts) {
+            seen = seen.saturating_add(*count);
+            if seen > rank {
+                // The bin's value can be outside of the range actually seen.
+                return Some(self.bin_value(*key).max(self.min).min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// Estimates how many values are less than or equal to each of the
+    /// upper bounds in `buckets`, which must be ascending.
+    pub fn bucket_counts(&self, buckets: &[f64]) -> Vec<u32> {
+        let mut counts = vec![0; buckets.len()];
+        for (key, count) in self.keys.iter().zip(&self.counts) {
+            let value = self.bin_value(*key);
+            if let Some(index) = buckets.iter().position(|bound| value <= *bound) {
+                counts[index] = counts[index].saturating_add(*count);
+            }
+        }
+        // Cumulative, as Prometheus histograms are.
+        for index in 1..counts.len() {
+            counts[index] = counts[index].saturating_add(counts[index - 1]);
+        }
+        counts
+    }
+
+    fn gamma_ln(&self) -> f64 {
+        ((1.0 + self.alpha) / (1.0 - self.alpha)).ln()
+    }
+
+    /// Shifts keys so the smallest indexable value gets key 1.
+    fn bias(&self) -> i32 {
+        1 - (MIN_INDEXABLE_VALUE.ln() / self.gamma_ln()).ceil() as i32
+    }
+
+    fn key(&self, value: f64) -> i32 {
+        let magnitude = value.abs();
+        if magnitude < MIN_INDEXABLE_VALUE {
+            return 0;
+        }
+        let key = (magnitude.ln() / self.gamma_ln()).ceil() as i32 + self.bias();
+        if value < 0.0 {
+            -key
+        } else {
+            key
+        }
+    }
+
+    /// The value representing a bin, which is within `alpha` of any value
+    /// in it.
+    fn bin_value(&self, key: i32) -> f64 {
+        if key == 0 {
+            return 0.0;
+        }
+        let gamma_ln = self.gamma_ln();
+        let upper = (f64::from(key.abs() - self.bias()) * gamma_ln).exp();
+        let value = 2.0 * upper / (1.0 + gamma_ln.exp());
+        if key < 0 {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sketch(values: impl IntoIterator<Item = f64>) -> DDSketch {
+        let mut sketch = DDSketch::default();
+        for value in values {
+            sketch.insert(value);
+        }
+        sketch
+    }
+
+    fn assert_accurate(sketch: &DDSketch, q: f64, expected: f64) {
+        let estimate = sketch.quantile(q).unwrap();
+        assert!(
+            (estimate - expected).abs() <= expected.abs() * sketch.alpha,
+            "quantile {} estimated as {}, expected {}",
+            q,
+            estimate,
+            expected
+        );
+    }
+
+    #[test]
+    fn quantiles_are_within_relative_accuracy() {
+        let sketch = sketch((1..=1000).map(f64::from));
+
+        assert_eq!(sketch.count, 1000);
+        assert_eq!(sketch.sum, 500_500.0);
+        assert_eq!(sketch.quantile(0.0), Some(1.0));
+        assert_accurate(&sketch, 0.5, 501.0);
+        assert_accurate(&sketch, 0.99, 990.0);
+        assert_eq!(sketch.quantile(1.0), Some(1000.0));
+    }
+
+    #[test]
+    fn handles_zero_and_negative_values() {
+        let sketch = sketch(vec![-100.0, -10.0, 0.0, 10.0, 100.0]);
+
+        assert_eq!(sketch.quantile(0.0), Some(-100.0));
+        assert_accurate(&sketch, 0.25, -10.0);
+        assert_eq!(sketch.quantile(0.5), Some(0.0));
+        assert_accurate(&sketch, 0.75, 10.0);
+    }
+
+    #[test]
+    fn validates_sketches() {
+        assert!(sketch(vec![-1.0, 0.0, 1.0]).is_valid());
+
+        let mut unsorted = sketch(vec![1.0, 2.0]);
+        unsorted.keys.reverse();
+        assert!(!unsorted.is_valid());
+
+        let mut missing_count = sketch(vec![1.0, 2.0]);
+        missing_count.counts.pop();
+        assert!(!missing_count.is_valid());
+
+        assert!(!DDSketch::new(0.0).is_valid());
+        assert!(!DDSketch::new(1.0).is_valid());
+        assert!(!DDSketch::new(std::f64::NAN).is_valid());
+    }
+
+    #[test]
+    fn merging_equals_inserting_everything() {
+        let mut merged = sketch((1..=500).map(f64::from));
+        assert!(merged.merge(&sketch((501..=1000).map(f64::from))));
+
+        assert_eq!(merged, sketch((1..=1000).map(f64::from)));
+        assert!(!merged.merge(&DDSketch::new(0.05)));
+    }
+
+    #[test]
+    fn counts_saturate() {
+        let mut sketch = DDSketch::new(0.01);
+        sketch.insert_n(1.0, std::u32::MAX);
+        sketch.insert_n(1.0, 1);
+        sketch.insert_n(2.0, 1);
+        assert_eq!(sketch.counts, vec![std::u32::MAX, 1]);
+        assert_eq!(sketch.count, std::u32::MAX);
+
+        let mut merged = sketch.clone();
+        assert!(merged.merge(&sketch));
+        assert_eq!(merged.counts, vec![std::u32::MAX, 2]);
+        assert_eq!(merged.count, std::u32::MAX);
+        assert_eq!(merged.bucket_counts(&[1.5, 5.0]), vec![std::u32::MAX; 2]);
+    }
+
+    #[test]
+    fn bucket_counts_are_cumulative() {
+        let sketch = sketch(vec![0.5, 1.5, 1.5, 3.0, 100.0]);
+
+        assert_eq!(sketch.bucket_counts(&[1.0, 2.0, 5.0]), vec![1, 3, 4]);
+    }
+}
@@ -0,0 +1,156 @@
+use super::Value;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A single span, following the OpenTelemetry span data model. Trace and
+/// span IDs are kept as lowercase hex strings.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trace {
+    pub trace_id: String,
+    pub span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub attributes: BTreeMap<String, Value>,
+    pub events: Vec<SpanEvent>,
+    pub status: SpanStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanKind {
+    Unspecified,
+    Internal,
+    Server,
+    Client,
+    Producer,
+    Consumer,
+}
+
+/// A timestamped annotation within a span.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpanEvent {
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    pub attributes: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpanStatus {
+    pub code: StatusCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusCode {
+    Unset,
+    Ok,
+    Error,
+}
+
+impl Default for SpanStatus {
+    fn default() -> Self {
+        Self {
+            code: StatusCode::Unset,
+            message: None,
+        }
+    }
+}
+
+impl SpanKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpanKind::Unspecified => "unspecified",
+            SpanKind::Internal => "internal",
+            SpanKind::Server => "server",
+            SpanKind::Client => "client",
+            SpanKind::Producer => "producer",
+            SpanKind::Consumer => "consumer",
+        }
+    }
+}
+
+impl StatusCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatusCode::Unset => "unset",
+            StatusCode::Ok => "ok",
+            StatusCode::Error => "error",
+        }
+    }
+}
+
+impl Trace {
+    /// Creates a root span of unspecified kind, without attributes or events.
+    pub fn new(
+        trace_id: impl Into<String>,
+        span_id: impl Into<String>,
+        name: impl Into<String>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            trace_id: trace_id.into(),
+            span_id: span_id.into(),
+            parent_span_id: None,
+            name: name.into(),
+            kind: SpanKind::Unspecified,
+            start_time,
+            end_time,
+            attributes: BTreeMap::new(),
+            events: Vec::new(),
+            status: SpanStatus::default(),
+        }
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        self.end_time - self.start_time
+    }
+
+    /// Looks up a value by name, as used by conditions and templates.
+    /// `trace.trace_id`, `trace.span_id`, `trace.parent_span_id`,
+    /// `trace.name`, `trace.kind`, `trace.status` and `trace.duration_ns`
+    /// name the span's own data, any other name is an attribute.
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        match name {
+            "trace.trace_id" => Some(self.trace_id.as_str().into()),
+            "trace.span_id" => Some(self.span_id.as_str().into()),
+            "trace.parent_span_id" => self.parent_span_id.as_ref().map(Into::into),
+            "trace.name" => Some(self.name.as_str().into()),
+            "trace.kind" => Some(self.kind.as_str().into()),
+            "trace.status" => Some(self.status.code.as_str().into()),
+            "trace.duration_ns" => self.duration().num_nanoseconds().map(Into::into),
+            _ => self.attributes.get(name).cloned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn lookup() {
+        let mut trace = Trace::new(
+            "5b8aa5a2d2c872e8321cf37308d69df2",
+            "051581bf3cb55c13",
+            "GET /",
+            Utc.timestamp(1_500_000_000, 0),
+            Utc.timestamp(1_500_000_000, 1_500),
+        );
+        trace.kind = SpanKind::Server;
+        trace.attributes.insert("http.method".into(), "GET".into());
+
+        assert_eq!(trace.lookup("trace.kind"), Some("server".into()));
+        assert_eq!(trace.lookup("trace.duration_ns"), Some(1_500.into()));
+        assert_eq!(trace.lookup("trace.parent_span_id"), None);
+        assert_eq!(trace.lookup("http.method"), Some("GET".into()));
+    }
+}
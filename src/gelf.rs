@@ -0,0 +1,379 @@
+//! GELF (Graylog Extended Log Format) decoding and encoding, shared by the
+//! `gelf` source and the `gelf` encoding of the socket sinks.
+
+use crate::event::{self, LogEvent, Value};
+#[cfg(feature = "sources-gelf")]
+use chrono::{TimeZone, Utc};
+#[cfg(feature = "sources-gelf")]
+use flate2::read::{GzDecoder, ZlibDecoder};
+use lazy_static::lazy_static;
+use serde_json::{Map, Number, Value as JsonValue};
+use snafu::{ResultExt, Snafu};
+#[cfg(feature = "sources-gelf")]
+use std::{borrow::Cow, io::Read};
+
+/// The version of GELF we write.
+pub const VERSION: &str = "1.1";
+
+/// The fields GELF defines itself, besides `host`, `short_message` and
+/// `timestamp`, which map to the log schema's keys. Any other field is an
+/// additional field, prefixed with `_`.
+const STANDARD_FIELDS: &[&str] = &["full_message", "level", "facility", "line", "file"];
+
+lazy_static! {
+    static ref HOSTNAME: String = hostname::get_hostname().unwrap_or_default();
+}
+
+#[cfg(feature = "sources-gelf")]
+#[derive(Debug, Snafu)]
+pub enum DecodeError {
+    #[snafu(display("Decompression failed: {}", source))]
+    Decompression { source: std::io::Error },
+    #[snafu(display("Message is larger than {} bytes", max_length))]
+    TooLong { max_length: usize },
+    #[snafu(display("Invalid JSON: {}", source))]
+    InvalidJson { source: serde_json::Error },
+    #[snafu(display("Message is not a JSON object"))]
+    NotAnObject,
+    #[snafu(display("Message has no short_message"))]
+    MissingShortMessage,
+}
+
+#[derive(Debug, Snafu)]
+pub enum EncodeError {
+    #[snafu(display("Event has no message for short_message"))]
+    MissingMessage,
+    #[snafu(display("Unable to serialize: {}", source))]
+    Serialize { source: serde_json::Error },
+}
+
+/// Decodes a GELF message, which may be zlib or gzip compressed, into a log
+/// event.
+#[cfg(feature = "sources-gelf")]
+pub fn decode(payload: &[u8], max_length: usize) -> Result<LogEvent, DecodeError> {
+    parse(&decompress(payload, max_length)?)
+}
+
+/// Decompresses zlib and gzip payloads, told apart by their headers, up to
+/// `max_length` bytes. Anything else is returned as it is.
+#[cfg(feature = "sources-gelf")]
+fn decompress(payload: &[u8], max_length: usize) -> Result<Cow<[u8]>, DecodeError> {
+    let mut decompressed = Vec::new();
+    let read = match payload {
+        [0x1f, 0x8b, ..] => GzDecoder::new(payload)
+            .take(max_length as u64 + 1)
+            .read_to_end(&mut decompressed),
+        [cmf, flg, ..] if is_zlib_header(*cmf, *flg) => ZlibDecoder::new(payload)
+            .take(max_length as u64 + 1)
+            .read_to_end(&mut decompressed),
+        _ if payload.len() > max_length => return Err(DecodeError::TooLong { max_length }),
+        _ => return Ok(Cow::Borrowed(payload)),
+    };
+    read.context(Decompression)?;
+
+    if decompressed.len() > max_length {
+        Err(DecodeError::TooLong { max_length })
+    } else {
+        Ok(Cow::Owned(decompressed))
+    }
+}
+
+/// Deflate compression with a header checksum that checks out, as GELF
+/// messages are JSON objects which can't start like this.
+#[cfg(feature = "sources-gelf")]
+fn is_zlib_header(cmf: u8, flg: u8) -> bool {
+    cmf & 0x0f == 8 && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0
+}
+
+/// Parses an uncompressed GELF message. `short_message`, `host` and
+/// `timestamp` go to the log schema's keys, additional fields lose their
+/// `_` prefix, and the other fields keep their names.
+#[cfg(feature = "sources-gelf")]
+pub fn parse(payload: &[u8]) -> Result<LogEvent, DecodeError> {
+    // Messages sent over UDP may still end with TCP's null byte delimiter.
+    let end = payload
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |end| end + 1);
+    let object = match serde_json::from_slice(&payload[..end]).context(InvalidJson)? {
+        JsonValue::Object(object) => object,
+        _ => return Err(DecodeError::NotAnObject),
+    };
+
+    let schema = event::log_schema();
+    let mut log = LogEvent::new();
+    for (key, value) in object {
+        match key.as_str() {
+            "short_message" => {
+                log.insert(schema.message_key().clone(), value);
+            }
+            "host" => {
+                log.insert(schema.host_key().clone(), value);
+            }
+            "timestamp" => {
+                if let Some(timestamp) = value.as_f64().and_then(parse_timestamp) {
+                    log.insert(schema.timestamp_key().clone(), timestamp);
+                }
+            }
+            // Additional fields, which may be paths flattened by `encode`.
+            field if field.starts_with('_') => {
+                log.insert(&field[1..], value);
+            }
+            field => {
+                log.insert_flat(field, value);
+            }
+        }
+    }
+
+    if !log.contains(schema.message_key()) {
+        return Err(DecodeError::MissingShortMessage);
+    }
+    if !log.contains(schema.timestamp_key()) {
+        log.insert(schema.timestamp_key().clone(), Utc::now());
+    }
+
+    Ok(log)
+}
+
+#[cfg(feature = "sources-gelf")]
+fn parse_timestamp(seconds: f64) -> Option<chrono::DateTime<Utc>> {
+    // Timestamps are seconds with decimal fractions, so round to the
+    // microseconds a double can hold for current dates.
+    if !seconds.is_finite() || seconds.abs() > 1e12 {
+        return None;
+    }
+    let micros = (seconds * 1e6).round() as i64;
+    Utc.timestamp_opt(
+        micros.div_euclid(1_000_000),
+        micros.rem_euclid(1_000_000) as u32 * 1000,
+    )
+    .single()
+}
+
+/// Encodes a log event as an uncompressed GELF message. Fields other than
+/// the log schema's are flattened into additional fields.
+pub fn encode(log: &LogEvent) -> Result<Vec<u8>, EncodeError> {
+    let schema = event::log_schema();
+    let mut object = Map::new();
+
+    object.insert("version".into(), VERSION.into());
+    let host = log
+        .get(schema.host_key())
+        .map(Value::to_string_lossy)
+        .unwrap_or_else(|| HOSTNAME.clone());
+    object.insert("host".into(), host.into());
+    let message = log
+        .get(schema.message_key())
+        .map(Value::to_string_lossy)
+        .ok_or(EncodeError::MissingMessage)?;
+    object.insert("short_message".into(), message.into());
+    if let Some(Value::Timestamp(timestamp)) = log.get(schema.timestamp_key()) {
+        let seconds = timestamp.timestamp_millis() as f64 / 1000.0;
+        if let Some(seconds) = Number::from_f64(seconds) {
+            object.insert("timestamp".into(), seconds.into());
+        }
+    }
+
+    for (key, value) in log.all_fields() {
+        if key == schema.message_key().as_ref()
+            || key == schema.host_key().as_ref()
+            || key == schema.timestamp_key().as_ref()
+            || key == "version"
+        {
+            continue;
+        }
+        if STANDARD_FIELDS.contains(&key.as_str()) {
+            object.insert(key, serde_json::to_value(value).context(Serialize)?);
+        } else {
+            object.insert(additional_field_name(&key), additional_field_value(value));
+        }
+    }
+
+    serde_json::to_vec(&object).context(Serialize)
+}
+
+/// Additional field names may only hold word characters, dots and dashes,
+/// and `_id` is reserved.
+fn additional_field_name(key: &str) -> String {
+    let mut name = String::with_capacity(key.len() + 2);
+    name.push('_');
+    if key == "id" {
+        name.push('_');
+    }
+    name.extend(key.chars().map(|c| match c {
+        'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' | '-' => c,
+        _ => '_',
+    }));
+    name
+}
+
+/// Additional field values may only be strings or numbers.
+fn additional_field_value(value: &Value) -> JsonValue {
+    match value {
+        Value::Integer(integer) => (*integer).into(),
+        Value::Float(float) => Number::from_f64(*float)
+            .map(JsonValue::Number)
+            .unwrap_or_else(|| value.to_string_lossy().into()),
+        value => value.to_string_lossy().into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::encode;
+    use crate::event::{self, Event, LogEvent};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn encode_json(log: &LogEvent) -> serde_json::Value {
+        serde_json::from_slice(&encode(log).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn encodes_standard_and_additional_fields() {
+        let mut event = Event::from("hello");
+        let log = event.as_mut_log();
+        log.insert(
+            event::log_schema().timestamp_key().clone(),
+            Utc.timestamp(1_500_000_000, 123_000_000),
+        );
+        log.insert(event::log_schema().host_key().clone(), "web-1");
+        log.insert("level", 6);
+        log.insert("id", "abc");
+        log.insert("user.name", "bob");
+        log.insert("tags[0]", "a b");
+        log.insert("ok", true);
+        log.insert("took", 1.5);
+
+        assert_eq!(
+            encode_json(log),
+            json!({
+                "version": "1.1",
+                "host": "web-1",
+                "short_message": "hello",
+                "timestamp": 1_500_000_000.123,
+                "level": 6,
+                "__id": "abc",
+                "_user.name": "bob",
+                "_tags_0_": "a b",
+                "_ok": "true",
+                "_took": 1.5,
+            })
+        );
+    }
+
+    #[test]
+    fn requires_message() {
+        let mut log = LogEvent::new();
+        log.insert("foo", "bar");
+
+        assert!(encode(&log).is_err());
+    }
+
+    #[cfg(feature = "sources-gelf")]
+    mod decode {
+        use super::super::{decode, encode, parse};
+        use crate::event::{self, Event, Value};
+        use chrono::{TimeZone, Utc};
+        use flate2::{
+            write::{GzEncoder, ZlibEncoder},
+            Compression,
+        };
+        use std::io::Write;
+
+        const MESSAGE: &[u8] = br#"{
+            "version": "1.1",
+            "host": "example.org",
+            "short_message": "A short message",
+            "full_message": "Backtrace here\n\nmore stuff",
+            "timestamp": 1385053862.3072,
+            "level": 1,
+            "_user_id": 9001,
+            "_some_info": "foo",
+            "_kubernetes.pod": "web"
+        }"#;
+
+        #[test]
+        fn parses_fields() {
+            let log = parse(MESSAGE).unwrap();
+            let schema = event::log_schema();
+
+            assert_eq!(log[schema.message_key()], "A short message".into());
+            assert_eq!(log[schema.host_key()], "example.org".into());
+            assert_eq!(
+                log[schema.timestamp_key()],
+                Value::Timestamp(Utc.timestamp(1_385_053_862, 307_200_000))
+            );
+            assert_eq!(
+                log[&"full_message".into()],
+                "Backtrace here\n\nmore stuff".into()
+            );
+            assert_eq!(log[&"level".into()], Value::Integer(1));
+            assert_eq!(log[&"version".into()], "1.1".into());
+            assert_eq!(log[&"user_id".into()], Value::Integer(9001));
+            assert_eq!(log[&"some_info".into()], "foo".into());
+            assert_eq!(log[&"kubernetes.pod".into()], "web".into());
+        }
+
+        #[test]
+        fn decodes_compressed_messages() {
+            let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+            gzip.write_all(MESSAGE).unwrap();
+            let gzip = gzip.finish().unwrap();
+            let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+            zlib.write_all(MESSAGE).unwrap();
+            let zlib = zlib.finish().unwrap();
+
+            let expected = parse(MESSAGE).unwrap();
+            let schema = event::log_schema();
+            for payload in &[gzip, zlib] {
+                let log = decode(payload, 1024).unwrap();
+                assert_eq!(log[schema.message_key()], expected[schema.message_key()]);
+                assert_eq!(log[&"user_id".into()], expected[&"user_id".into()]);
+            }
+        }
+
+        #[test]
+        fn limits_decompressed_length() {
+            let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+            zlib.write_all(MESSAGE).unwrap();
+            let zlib = zlib.finish().unwrap();
+
+            assert!(decode(&zlib, 64).is_err());
+            assert!(decode(MESSAGE, 64).is_err());
+        }
+
+        #[test]
+        fn rejects_invalid_messages() {
+            assert!(parse(b"not json").is_err());
+            assert!(parse(b"[1, 2]").is_err());
+            assert!(parse(br#"{"host": "example.org"}"#).is_err());
+        }
+
+        #[test]
+        fn ignores_trailing_null_bytes() {
+            let mut payload = MESSAGE.to_vec();
+            payload.push(0);
+
+            assert!(parse(&payload).is_ok());
+        }
+
+        #[test]
+        fn roundtrips() {
+            let mut event = Event::from("hello");
+            let log = event.as_mut_log();
+            log.insert(
+                event::log_schema().timestamp_key().clone(),
+                Utc.timestamp(1_500_000_000, 123_000_000),
+            );
+            log.insert(event::log_schema().host_key().clone(), "web-1");
+            log.insert("level", 6);
+            log.insert("user.name", "bob");
+            log.insert("count", 3);
+
+            let mut decoded = parse(&encode(log).unwrap()).unwrap();
+            decoded.remove(&"version".into());
+            assert_eq!(&decoded, log);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::InternalEvent;
+use crate::sources::beats::DecodeError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct BeatsEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for BeatsEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "received one event.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", 1,
+            "component_kind" => "source",
+            "component_type" => "beats",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "source",
+            "component_type" => "beats",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct BeatsEventDecodeError {
+    pub error: DecodeError,
+}
+
+impl InternalEvent for BeatsEventDecodeError {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to decode beats event.",
+            error = %self.error,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "decode_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "beats",
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use super::InternalEvent;
+use crate::sources::util::DecodeError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct DecoderParseFailed {
+    pub error: DecodeError,
+}
+
+impl InternalEvent for DecoderParseFailed {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to parse frame, skipping it.",
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("decoder_parse_errors", 1,
+            "component_kind" => "source",
+        );
+    }
+}
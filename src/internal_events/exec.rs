@@ -0,0 +1,185 @@
+use super::InternalEvent;
+use metrics::counter;
+use std::process::ExitStatus;
+#[cfg(feature = "sources-exec")]
+use std::time::Duration;
+
+#[cfg(feature = "sources-exec")]
+#[derive(Debug)]
+pub struct ExecEventReceived {
+    pub byte_size: usize,
+}
+
+#[cfg(feature = "sources-exec")]
+impl InternalEvent for ExecEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "received one event.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", 1,
+            "component_kind" => "source",
+            "component_type" => "exec",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "source",
+            "component_type" => "exec",
+        );
+    }
+}
+
+#[cfg(feature = "sources-exec")]
+#[derive(Debug)]
+pub struct ExecCommandExited<'a> {
+    pub command: &'a str,
+    pub exit_status: ExitStatus,
+    pub elapsed: Duration,
+}
+
+#[cfg(feature = "sources-exec")]
+impl<'a> InternalEvent for ExecCommandExited<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "command exited.",
+            command = %self.command,
+            exit_status = %self.exit_status,
+            elapsed_ms = %self.elapsed.as_millis(),
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "command_executed", 1,
+            "component_kind" => "source",
+            "component_type" => "exec",
+        );
+    }
+}
+
+#[cfg(feature = "sources-exec")]
+#[derive(Debug)]
+pub struct ExecFailed<'a> {
+    pub command: &'a str,
+    pub error: std::io::Error,
+}
+
+#[cfg(feature = "sources-exec")]
+impl<'a> InternalEvent for ExecFailed<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "unable to run command.",
+            command = %self.command,
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "processing_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "exec",
+            "error_type" => "command_failed",
+        );
+    }
+}
+
+#[cfg(feature = "sinks-exec")]
+#[derive(Debug)]
+pub struct ExecEventSent {
+    pub byte_size: usize,
+}
+
+#[cfg(feature = "sinks-exec")]
+impl InternalEvent for ExecEventSent {
+    fn emit_logs(&self) {
+        trace!(message = "wrote one event.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", 1,
+            "component_kind" => "sink",
+            "component_type" => "exec",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "sink",
+            "component_type" => "exec",
+        );
+    }
+}
+
+#[cfg(feature = "sinks-exec")]
+#[derive(Debug)]
+pub struct ExecSinkCommandExited<'a> {
+    pub command: &'a str,
+    pub exit_status: ExitStatus,
+}
+
+#[cfg(feature = "sinks-exec")]
+impl<'a> InternalEvent for ExecSinkCommandExited<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "command exited.",
+            command = %self.command,
+            exit_status = %self.exit_status,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "command_exited", 1,
+            "component_kind" => "sink",
+            "component_type" => "exec",
+        );
+    }
+}
+
+#[cfg(feature = "sinks-exec")]
+#[derive(Debug)]
+pub struct ExecSinkFailed<'a> {
+    pub command: &'a str,
+    pub error: std::io::Error,
+}
+
+#[cfg(feature = "sinks-exec")]
+impl<'a> InternalEvent for ExecSinkFailed<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "unable to write to command.",
+            command = %self.command,
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "processing_errors", 1,
+            "component_kind" => "sink",
+            "component_type" => "exec",
+            "error_type" => "command_failed",
+        );
+    }
+}
+
+#[cfg(feature = "sinks-exec")]
+#[derive(Debug)]
+pub struct ExecSinkStderr<'a> {
+    pub command: &'a str,
+    pub line: &'a str,
+}
+
+#[cfg(feature = "sinks-exec")]
+impl<'a> InternalEvent for ExecSinkStderr<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "command wrote to stderr.",
+            command = %self.command,
+            line = %self.line,
+        );
+    }
+}
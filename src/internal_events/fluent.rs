@@ -0,0 +1,105 @@
+use super::InternalEvent;
+#[cfg(feature = "sources-fluent")]
+use crate::sources::fluent::DecodeError;
+use metrics::counter;
+#[cfg(feature = "sinks-fluent")]
+use string_cache::DefaultAtom as Atom;
+
+#[cfg(feature = "sources-fluent")]
+#[derive(Debug)]
+pub struct FluentMessageReceived {
+    pub byte_size: usize,
+    pub entries: usize,
+}
+
+#[cfg(feature = "sources-fluent")]
+impl InternalEvent for FluentMessageReceived {
+    fn emit_logs(&self) {
+        trace!(message = "received one message.", entries = self.entries);
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", self.entries as u64,
+            "component_kind" => "source",
+            "component_type" => "fluent",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "source",
+            "component_type" => "fluent",
+        );
+    }
+}
+
+#[cfg(feature = "sources-fluent")]
+#[derive(Debug)]
+pub struct FluentMessageDecodeError {
+    pub error: DecodeError,
+}
+
+#[cfg(feature = "sources-fluent")]
+impl InternalEvent for FluentMessageDecodeError {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to decode fluent message.",
+            error = %self.error,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "decode_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "fluent",
+        );
+    }
+}
+
+#[cfg(feature = "sinks-fluent")]
+#[derive(Debug)]
+pub struct FluentEventSent {
+    pub byte_size: usize,
+}
+
+#[cfg(feature = "sinks-fluent")]
+impl InternalEvent for FluentEventSent {
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", 1,
+            "component_kind" => "sink",
+            "component_type" => "fluent",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "sink",
+            "component_type" => "fluent",
+        );
+    }
+}
+
+#[cfg(feature = "sinks-fluent")]
+#[derive(Debug)]
+pub struct FluentMissingKeys {
+    pub keys: Vec<Atom>,
+}
+
+#[cfg(feature = "sinks-fluent")]
+impl InternalEvent for FluentMissingKeys {
+    fn emit_logs(&self) {
+        warn!(
+            message = "keys do not exist on the event; dropping event.",
+            missing_keys = ?self.keys,
+            rate_limit_secs = 30,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "missing_keys", 1,
+            "component_kind" => "sink",
+            "component_type" => "fluent",
+        );
+    }
+}
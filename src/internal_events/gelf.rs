@@ -0,0 +1,93 @@
+use super::InternalEvent;
+use crate::gelf::DecodeError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct GelfMessageReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for GelfMessageReceived {
+    fn emit_logs(&self) {
+        trace!(message = "received one message.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", 1,
+            "component_kind" => "source",
+            "component_type" => "gelf",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "source",
+            "component_type" => "gelf",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct GelfDecodeError {
+    pub error: DecodeError,
+}
+
+impl InternalEvent for GelfDecodeError {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to decode gelf message.",
+            error = %self.error,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "decode_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "gelf",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct GelfChunkDropped {
+    pub reason: &'static str,
+}
+
+impl InternalEvent for GelfChunkDropped {
+    fn emit_logs(&self) {
+        warn!(
+            message = "dropping gelf chunks.",
+            reason = self.reason,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "chunks_dropped", 1,
+            "component_kind" => "source",
+            "component_type" => "gelf",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct GelfUdpSocketError {
+    pub error: std::io::Error,
+}
+
+impl InternalEvent for GelfUdpSocketError {
+    fn emit_logs(&self) {
+        error!(message = "error reading datagram.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "socket_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "gelf",
+            "mode" => "udp",
+        );
+    }
+}
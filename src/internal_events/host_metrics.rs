@@ -0,0 +1,28 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct HostMetricsCollectError {
+    pub collector: &'static str,
+    pub error: std::io::Error,
+}
+
+impl InternalEvent for HostMetricsCollectError {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to collect host metrics.",
+            collector = %self.collector,
+            error = %self.error,
+            rate_limit_secs = 60,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "collect_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "host_metrics",
+            "collector" => self.collector,
+        );
+    }
+}
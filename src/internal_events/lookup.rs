@@ -0,0 +1,97 @@
+use super::InternalEvent;
+use metrics::counter;
+use std::path::Path;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug)]
+pub struct LookupEventProcessed;
+
+impl InternalEvent for LookupEventProcessed {
+    fn emit_metrics(&self) {
+        counter!("events_processed", 1,
+            "component_kind" => "transform",
+            "component_type" => "lookup",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct LookupMissingField<'a> {
+    pub field: &'a Atom,
+}
+
+impl InternalEvent for LookupMissingField<'_> {
+    fn emit_logs(&self) {
+        debug!(message = "field does not exist.", field = %self.field, rate_limit_secs = 30);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("processing_error", 1,
+            "component_kind" => "transform",
+            "component_type" => "lookup",
+            "error_type" => "missing_field",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct LookupFailedMatch;
+
+impl InternalEvent for LookupFailedMatch {
+    fn emit_logs(&self) {
+        trace!(
+            message = "no matching row in lookup table.",
+            rate_limit_secs = 30
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("lookup_misses", 1,
+            "component_kind" => "transform",
+            "component_type" => "lookup",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct LookupTableLoaded<'a> {
+    pub path: &'a Path,
+    pub rows: usize,
+}
+
+impl InternalEvent for LookupTableLoaded<'_> {
+    fn emit_logs(&self) {
+        info!(message = "loaded lookup table.", path = ?self.path, rows = self.rows);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("lookup_table_loads", 1,
+            "component_kind" => "transform",
+            "component_type" => "lookup",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct LookupTableLoadFailed<'a> {
+    pub path: &'a Path,
+    pub error: crate::Error,
+}
+
+impl InternalEvent for LookupTableLoadFailed<'_> {
+    fn emit_logs(&self) {
+        error!(
+            message = "failed to reload lookup table; keeping the previous contents.",
+            path = ?self.path,
+            error = %self.error,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("processing_error", 1,
+            "component_kind" => "transform",
+            "component_type" => "lookup",
+            "error_type" => "load_failed",
+        );
+    }
+}
@@ -1,10 +1,28 @@
+#[cfg(feature = "sources-beats")]
+mod beats;
 mod blackhole;
+#[cfg(feature = "sources-decoding")]
+mod decoding;
 mod elasticsearch;
+#[cfg(any(feature = "sources-exec", feature = "sinks-exec"))]
+mod exec;
 mod file;
+#[cfg(any(feature = "sources-fluent", feature = "sinks-fluent"))]
+mod fluent;
+#[cfg(feature = "sources-gelf")]
+mod gelf;
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics"))]
+mod host_metrics;
+#[cfg(feature = "transforms-lookup")]
+mod lookup;
 #[cfg(feature = "transforms-lua")]
 mod lua;
 #[cfg(feature = "sources-prometheus")]
 mod prometheus;
+#[cfg(feature = "sources-prometheus_remote_write")]
+mod prometheus_remote_write;
+#[cfg(feature = "transforms-redact")]
+mod redact;
 mod regex;
 mod syslog;
 mod tcp;
@@ -12,13 +30,31 @@ mod udp;
 mod unix;
 mod vector;
 
+#[cfg(feature = "sources-beats")]
+pub use self::beats::*;
 pub use self::blackhole::*;
+#[cfg(feature = "sources-decoding")]
+pub use self::decoding::*;
 pub use self::elasticsearch::*;
+#[cfg(any(feature = "sources-exec", feature = "sinks-exec"))]
+pub use self::exec::*;
 pub use self::file::*;
+#[cfg(any(feature = "sources-fluent", feature = "sinks-fluent"))]
+pub use self::fluent::*;
+#[cfg(feature = "sources-gelf")]
+pub use self::gelf::*;
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics"))]
+pub use self::host_metrics::*;
+#[cfg(feature = "transforms-lookup")]
+pub use self::lookup::*;
 #[cfg(feature = "transforms-lua")]
 pub use self::lua::*;
 #[cfg(feature = "sources-prometheus")]
 pub use self::prometheus::*;
+#[cfg(feature = "sources-prometheus_remote_write")]
+pub use self::prometheus_remote_write::*;
+#[cfg(feature = "transforms-redact")]
+pub use self::redact::*;
 pub use self::regex::*;
 pub use self::syslog::*;
 pub use self::tcp::*;
@@ -1,6 +1,8 @@
 use super::InternalEvent;
-use crate::sources::prometheus::parser::ParserError;
+use crate::sources::prometheus::{file_sd::FileSdError, parser::ParserError};
+use http::StatusCode;
 use metrics::counter;
+use std::{path::Path, time::Duration};
 
 #[derive(Debug)]
 pub struct PrometheusRequestCompleted;
@@ -50,3 +52,75 @@ impl InternalEvent for PrometheusHttpError {
         );
     }
 }
+
+#[derive(Debug)]
+pub struct PrometheusHttpResponseError<'a> {
+    pub url: &'a str,
+    pub code: StatusCode,
+}
+
+impl InternalEvent for PrometheusHttpResponseError<'_> {
+    fn emit_logs(&self) {
+        error!(
+            message = "unexpected response status",
+            url = %self.url,
+            code = %self.code,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("http_error_response", 1,
+            "component_kind" => "source",
+            "component_type" => "prometheus",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct PrometheusRequestTimeout<'a> {
+    pub url: &'a str,
+    pub timeout: Duration,
+}
+
+impl InternalEvent for PrometheusRequestTimeout<'_> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "scrape timed out",
+            url = %self.url,
+            timeout = ?self.timeout,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("request_timeouts", 1,
+            "component_kind" => "source",
+            "component_type" => "prometheus",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct PrometheusFileSdError<'a> {
+    pub path: &'a Path,
+    pub error: FileSdError,
+}
+
+impl InternalEvent for PrometheusFileSdError<'_> {
+    fn emit_logs(&self) {
+        error!(
+            message = "failed to read target file",
+            path = ?self.path,
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("file_sd_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "prometheus",
+        );
+    }
+}
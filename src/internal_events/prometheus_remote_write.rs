@@ -0,0 +1,51 @@
+use super::InternalEvent;
+use crate::prometheus_remote_write::DecodeError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct PrometheusRemoteWriteReceived {
+    pub byte_size: usize,
+    pub count: usize,
+}
+
+impl InternalEvent for PrometheusRemoteWriteReceived {
+    fn emit_logs(&self) {
+        trace!(message = "received write request.", count = %self.count);
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", self.count as u64,
+            "component_kind" => "source",
+            "component_type" => "prometheus_remote_write",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "source",
+            "component_type" => "prometheus_remote_write",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct PrometheusRemoteWriteDecodeError {
+    pub error: DecodeError,
+}
+
+impl InternalEvent for PrometheusRemoteWriteDecodeError {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to decode write request.",
+            error = %self.error,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "decode_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "prometheus_remote_write",
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct RedactEventProcessed;
+
+impl InternalEvent for RedactEventProcessed {
+    fn emit_metrics(&self) {
+        counter!("events_processed", 1,
+            "component_kind" => "transform",
+            "component_type" => "redact",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct RedactPerformed<'a> {
+    pub detector: &'a str,
+    pub count: usize,
+}
+
+impl InternalEvent for RedactPerformed<'_> {
+    fn emit_logs(&self) {
+        trace!(
+            message = "redacted values.",
+            detector = self.detector,
+            count = self.count
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("redactions", self.count as u64,
+            "component_kind" => "transform",
+            "component_type" => "redact",
+            "detector" => self.detector.to_owned(),
+        );
+    }
+}
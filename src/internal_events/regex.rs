@@ -4,11 +4,13 @@ use std::borrow::Cow;
 use string_cache::DefaultAtom as Atom;
 
 #[derive(Debug)]
-pub struct RegexEventProcessed;
+pub struct RegexEventProcessed {
+    pub count: usize,
+}
 
 impl InternalEvent for RegexEventProcessed {
     fn emit_metrics(&self) {
-        counter!("events_processed", 1,
+        counter!("events_processed", self.count as u64,
             "component_kind" => "transform",
             "component_type" => "regex_parser",
         );
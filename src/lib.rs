@@ -15,6 +15,7 @@ pub mod config_paths;
 pub mod dns;
 pub mod event;
 pub mod expiring_hash_map;
+pub mod gelf;
 pub mod generate;
 #[macro_use]
 pub mod internal_events;
@@ -22,6 +23,8 @@ pub mod internal_events;
 pub mod kafka;
 pub mod list;
 pub mod metrics;
+#[cfg(feature = "snap")]
+pub mod prometheus_remote_write;
 pub mod region;
 pub mod runtime;
 pub mod serde;
@@ -29,6 +32,8 @@ pub mod shutdown;
 pub mod sinks;
 pub mod sources;
 pub mod stream;
+#[cfg(feature = "syslog_loose")]
+pub mod syslog_message;
 pub mod template;
 pub mod test_util;
 pub mod tls;
@@ -0,0 +1,118 @@
+//! The Prometheus remote write wire format, snappy-compressed protobuf
+//! `WriteRequest`s, shared by the `prometheus_remote_write` source and sink.
+
+use prost::Message;
+use snafu::{ResultExt, Snafu};
+
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/prometheus.rs"));
+}
+
+/// The label holding a series' metric name.
+pub const NAME_LABEL: &str = "__name__";
+
+/// The version of the remote write protocol we speak.
+pub const VERSION: &str = "0.1.0";
+
+#[derive(Debug, Snafu)]
+pub enum DecodeError {
+    #[snafu(display("Snappy decompression failed: {}", source))]
+    Decompression { source: snap::Error },
+    #[snafu(display(
+        "Decompressed body of {} bytes is larger than {} bytes",
+        length,
+        max_length
+    ))]
+    TooLarge { length: usize, max_length: usize },
+    #[snafu(display("Invalid WriteRequest: {}", source))]
+    InvalidProtobuf { source: prost::DecodeError },
+}
+
+/// Decodes a write request, refusing to decompress it into more than
+/// `max_length` bytes. The decompressed length comes from the body itself,
+/// so it has to be checked before allocating room for it.
+pub fn decode(body: &[u8], max_length: usize) -> Result<proto::WriteRequest, DecodeError> {
+    let length = snap::raw::decompress_len(body).context(Decompression)?;
+    if length > max_length {
+        return Err(DecodeError::TooLarge { length, max_length });
+    }
+    let body = snap::raw::Decoder::new()
+        .decompress_vec(body)
+        .context(Decompression)?;
+    proto::WriteRequest::decode(&body[..]).context(InvalidProtobuf)
+}
+
+pub fn encode(request: &proto::WriteRequest) -> Vec<u8> {
+    let mut body = Vec::with_capacity(request.encoded_len());
+    request.encode(&mut body).unwrap();
+    snap::raw::Encoder::new()
+        .compress_vec(&body)
+        .expect("WriteRequest too large to compress")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, proto, DecodeError, NAME_LABEL};
+
+    fn label(name: &str, value: &str) -> proto::Label {
+        proto::Label {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn roundtrips() {
+        let request = proto::WriteRequest {
+            timeseries: vec![proto::TimeSeries {
+                labels: vec![label(NAME_LABEL, "up"), label("job", "vector")],
+                samples: vec![proto::Sample {
+                    value: 1.0,
+                    timestamp: 1_500_000_000_000,
+                }],
+            }],
+        };
+
+        assert_eq!(decode(&encode(&request), 1024).unwrap(), request);
+    }
+
+    #[test]
+    fn rejects_uncompressed_body() {
+        let request = proto::WriteRequest {
+            timeseries: vec![proto::TimeSeries {
+                labels: vec![label(NAME_LABEL, "up")],
+                samples: vec![],
+            }],
+        };
+        let mut body = Vec::new();
+        prost::Message::encode(&request, &mut body).unwrap();
+
+        match decode(&body, 1024) {
+            Err(DecodeError::Decompression { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_protobuf() {
+        let body = snap::raw::Encoder::new()
+            .compress_vec(b"\xff\xff\xff")
+            .unwrap();
+
+        match decode(&body, 1024) {
+            Err(DecodeError::InvalidProtobuf { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_large_decompressed_length() {
+        // A snappy body whose header claims about 4 GiB of output.
+        let body = [0xff, 0xff, 0xff, 0xff, 0x0f];
+
+        match decode(&body, 1024 * 1024) {
+            Err(DecodeError::TooLarge { length, .. }) => assert_eq!(length, 0xffff_ffff),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -68,6 +68,7 @@ impl Sink for BlackholeSink {
                 .map(|v| v.as_bytes().len())
                 .unwrap_or(0),
             Event::Metric(metric) => serde_json::to_string(&metric).map(|v| v.len()).unwrap_or(0),
+            Event::Trace(trace) => serde_json::to_string(&trace).map(|v| v.len()).unwrap_or(0),
         };
 
         self.total_events += 1;
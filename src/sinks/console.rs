@@ -90,6 +90,7 @@ fn encode_event(
             }
         },
         Event::Metric(metric) => serde_json::to_string(&metric),
+        Event::Trace(trace) => serde_json::to_string(&trace),
     }
 }
 
@@ -2,6 +2,7 @@ use crate::{
     dns::Resolver,
     event::{
         metric::{Metric, MetricKind, MetricValue},
+        sketch::DDSketch,
         Event,
     },
     sinks::util::{
@@ -261,6 +262,81 @@ fn stats(values: &[f64], counts: &[u32]) -> Option<DatadogStats> {
     })
 }
 
+fn sketch_stats(sketch: &DDSketch) -> Option<DatadogStats> {
+    if sketch.is_empty() {
+        return None;
+    }
+
+    Some(DatadogStats {
+        min: sketch.min,
+        max: sketch.max,
+        median: sketch.quantile(0.5)?,
+        avg: sketch.sum / f64::from(sketch.count),
+        sum: sketch.sum,
+        count: f64::from(sketch.count),
+        quantiles: vec![(0.95, sketch.quantile(0.95)?)],
+    })
+}
+
+/// Datadog has no histogram type, so distributions are sent as a series of
+/// gauges for each statistic.
+// https://docs.datadoghq.com/developers/metrics/metrics_type/?tab=histogram#metric-type-definition
+fn encode_stats(
+    fullname: &str,
+    ts: i64,
+    interval: i64,
+    tags: Option<Vec<String>>,
+    s: DatadogStats,
+) -> Vec<DatadogMetric> {
+    let mut result = vec![
+        DatadogMetric {
+            metric: format!("{}.min", fullname),
+            r#type: DatadogMetricType::Gauge,
+            interval: Some(interval),
+            points: vec![DatadogPoint(ts, s.min)],
+            tags: tags.clone(),
+        },
+        DatadogMetric {
+            metric: format!("{}.avg", fullname),
+            r#type: DatadogMetricType::Gauge,
+            interval: Some(interval),
+            points: vec![DatadogPoint(ts, s.avg)],
+            tags: tags.clone(),
+        },
+        DatadogMetric {
+            metric: format!("{}.count", fullname),
+            r#type: DatadogMetricType::Rate,
+            interval: Some(interval),
+            points: vec![DatadogPoint(ts, s.count)],
+            tags: tags.clone(),
+        },
+        DatadogMetric {
+            metric: format!("{}.median", fullname),
+            r#type: DatadogMetricType::Gauge,
+            interval: Some(interval),
+            points: vec![DatadogPoint(ts, s.median)],
+            tags: tags.clone(),
+        },
+        DatadogMetric {
+            metric: format!("{}.max", fullname),
+            r#type: DatadogMetricType::Gauge,
+            interval: Some(interval),
+            points: vec![DatadogPoint(ts, s.max)],
+            tags: tags.clone(),
+        },
+    ];
+    for (q, v) in s.quantiles {
+        result.push(DatadogMetric {
+            metric: format!("{}.{}percentile", fullname, (q * 100.0) as u32),
+            r#type: DatadogMetricType::Gauge,
+            interval: Some(interval),
+            points: vec![DatadogPoint(ts, v)],
+            tags: tags.clone(),
+        })
+    }
+    result
+}
+
 fn encode_events(events: Vec<Metric>, interval: i64, namespace: &str) -> DatadogRequest {
     let series = events
         .into_iter()
@@ -280,64 +356,10 @@ fn encode_events(events: Vec<Metric>, interval: i64, namespace: &str) -> Datadog
                     MetricValue::Distribution {
                         values,
                         sample_rates,
-                    } => {
-                        // https://docs.datadoghq.com/developers/metrics/metrics_type/?tab=histogram#metric-type-definition
-                        if let Some(s) = stats(&values, &sample_rates) {
-                            let mut result = vec![
-                                DatadogMetric {
-                                    metric: format!("{}.min", &fullname),
-                                    r#type: DatadogMetricType::Gauge,
-                                    interval: Some(interval),
-                                    points: vec![DatadogPoint(ts, s.min)],
-                                    tags: tags.clone(),
-                                },
-                                DatadogMetric {
-                                    metric: format!("{}.avg", &fullname),
-                                    r#type: DatadogMetricType::Gauge,
-                                    interval: Some(interval),
-                                    points: vec![DatadogPoint(ts, s.avg)],
-                                    tags: tags.clone(),
-                                },
-                                DatadogMetric {
-                                    metric: format!("{}.count", &fullname),
-                                    r#type: DatadogMetricType::Rate,
-                                    interval: Some(interval),
-                                    points: vec![DatadogPoint(ts, s.count)],
-                                    tags: tags.clone(),
-                                },
-                                DatadogMetric {
-                                    metric: format!("{}.median", &fullname),
-                                    r#type: DatadogMetricType::Gauge,
-                                    interval: Some(interval),
-                                    points: vec![DatadogPoint(ts, s.median)],
-                                    tags: tags.clone(),
-                                },
-                                DatadogMetric {
-                                    metric: format!("{}.max", &fullname),
-                                    r#type: DatadogMetricType::Gauge,
-                                    interval: Some(interval),
-                                    points: vec![DatadogPoint(ts, s.max)],
-                                    tags: tags.clone(),
-                                },
-                            ];
-                            for (q, v) in s.quantiles {
-                                result.push(DatadogMetric {
-                                    metric: format!(
-                                        "{}.{}percentile",
-                                        &fullname,
-                                        (q * 100.0) as u32
-                                    ),
-                                    r#type: DatadogMetricType::Gauge,
-                                    interval: Some(interval),
-                                    points: vec![DatadogPoint(ts, v)],
-                                    tags: tags.clone(),
-                                })
-                            }
-                            Some(result)
-                        } else {
-                            None
-                        }
-                    }
+                    } => stats(&values, &sample_rates)
+                        .map(|s| encode_stats(&fullname, ts, interval, tags, s)),
+                    MetricValue::Sketch(sketch) => sketch_stats(&sketch)
+                        .map(|s| encode_stats(&fullname, ts, interval, tags, s)),
                     MetricValue::Set { values } => Some(vec![DatadogMetric {
                         metric: fullname,
                         r#type: DatadogMetricType::Gauge,
@@ -589,4 +611,22 @@ mod tests {
             r#"{"series":[{"metric":"requests.min","type":"gauge","interval":60,"points":[[1542182950,1.0]],"tags":null},{"metric":"requests.avg","type":"gauge","interval":60,"points":[[1542182950,1.875]],"tags":null},{"metric":"requests.count","type":"rate","interval":60,"points":[[1542182950,8.0]],"tags":null},{"metric":"requests.median","type":"gauge","interval":60,"points":[[1542182950,2.0]],"tags":null},{"metric":"requests.max","type":"gauge","interval":60,"points":[[1542182950,3.0]],"tags":null},{"metric":"requests.95percentile","type":"gauge","interval":60,"points":[[1542182950,3.0]],"tags":null}]}"#
         );
     }
+
+    #[test]
+    fn encode_sketch() {
+        let events = vec![Metric {
+            name: "requests".into(),
+            timestamp: Some(ts()),
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Sketch(DDSketch::from_distribution(&[1.0, 3.0], &[2, 1])),
+        }];
+        let input = encode_events(events, 60, "");
+        let json = serde_json::to_string(&input).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"series":[{"metric":"requests.min","type":"gauge","interval":60,"points":[[1542182950,1.0]],"tags":null},{"metric":"requests.avg","type":"gauge","interval":60,"points":[[1542182950,1.6666666666666667]],"tags":null},{"metric":"requests.count","type":"rate","interval":60,"points":[[1542182950,3.0]],"tags":null},{"metric":"requests.median","type":"gauge","interval":60,"points":[[1542182950,1.0]],"tags":null},{"metric":"requests.max","type":"gauge","interval":60,"points":[[1542182950,3.0]],"tags":null},{"metric":"requests.95percentile","type":"gauge","interval":60,"points":[[1542182950,3.0]],"tags":null}]}"#
+        );
+    }
 }
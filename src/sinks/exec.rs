@@ -0,0 +1,402 @@
+use crate::{
+    buffers::Acker,
+    event::{self, Event},
+    internal_events::{ExecEventSent, ExecSinkCommandExited, ExecSinkFailed, ExecSinkStderr},
+    sinks::{
+        streaming_sink::{self, StreamingSink},
+        util::encoding::{EncodingConfig, EncodingConfiguration},
+    },
+    topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
+};
+use async_trait::async_trait;
+use futures::pin_mut;
+use futures::stream::{Stream, StreamExt};
+use futures01::future;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::{
+    path::PathBuf,
+    process::{ExitStatus, Stdio},
+    time::Duration,
+};
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdin, Command},
+    time::delay_for,
+};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("The command must not be empty"))]
+    EmptyCommand,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ExecSinkConfig {
+    /// The program to run followed by its arguments.
+    pub command: Vec<String>,
+    pub encoding: EncodingConfig<Encoding>,
+    pub working_directory: Option<PathBuf>,
+    #[serde(default = "default_respawn_interval_secs")]
+    pub respawn_interval_secs: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    Text,
+    Json,
+}
+
+pub fn default_respawn_interval_secs() -> u64 {
+    1
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<ExecSinkConfig>("exec")
+}
+
+#[typetag::serde(name = "exec")]
+impl SinkConfig for ExecSinkConfig {
+    fn build(&self, mut cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        if self.command.is_empty() {
+            return Err(BuildError::EmptyCommand.into());
+        }
+
+        let sink = ExecSink {
+            command: self.command.clone(),
+            command_line: self.command.join(" "),
+            working_directory: self.working_directory.clone(),
+            respawn_interval: Duration::from_secs(self.respawn_interval_secs),
+            encoding: self.encoding.clone(),
+            acker: cx.acker(),
+            process: None,
+        };
+        let sink = streaming_sink::compat::adapt_to_topology(&mut cx, sink);
+
+        Ok((sink, Box::new(future::ok(()))))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "exec"
+    }
+}
+
+fn encode_event(
+    mut event: Event,
+    encoding: &EncodingConfig<Encoding>,
+) -> Result<Vec<u8>, serde_json::Error> {
+    encoding.apply_rules(&mut event);
+    let mut bytes = match event {
+        Event::Log(log) => match encoding.codec {
+            Encoding::Json => serde_json::to_vec(&log)?,
+            Encoding::Text => log
+                .get(&event::log_schema().message_key())
+                .map(|value| value.as_bytes().to_vec())
+                .unwrap_or_default(),
+        },
+        Event::Metric(metric) => serde_json::to_vec(&metric)?,
+        Event::Trace(trace) => serde_json::to_vec(&trace)?,
+    };
+    bytes.push(b'\n');
+    Ok(bytes)
+}
+
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+}
+
+struct ExecSink {
+    command: Vec<String>,
+    command_line: String,
+    working_directory: Option<PathBuf>,
+    respawn_interval: Duration,
+    encoding: EncodingConfig<Encoding>,
+    acker: Acker,
+    process: Option<Process>,
+}
+
+#[async_trait]
+impl StreamingSink for ExecSink {
+    async fn run(
+        &mut self,
+        input: impl Stream<Item = Event> + Send + Sync + 'static,
+    ) -> crate::Result<()> {
+        pin_mut!(input);
+        loop {
+            tokio::select! {
+                event = input.next() => match event {
+                    Some(event) => self.process_event(event).await,
+                    None => break,
+                },
+                _ = self.supervise() => {}
+            }
+        }
+
+        // Closing stdin lets the command finish its work and exit.
+        if let Some(process) = self.process.take() {
+            drop(process.stdin);
+            self.reap(process.child).await;
+        }
+        Ok(())
+    }
+}
+
+impl ExecSink {
+    async fn process_event(&mut self, event: Event) {
+        let bytes = match encode_event(event, &self.encoding) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                error!(message = "Unable to encode event.", error = %error);
+                // Acknowledgements are counted rather than tied to events,
+                // so dropped events have to be acknowledged too.
+                self.acker.ack(1);
+                return;
+            }
+        };
+
+        self.write(&bytes).await;
+        emit!(ExecEventSent {
+            byte_size: bytes.len(),
+        });
+        self.acker.ack(1);
+    }
+
+    /// Keeps the command running between events: waits for it to exit,
+    /// reports that, and starts it again after `respawn_interval`.
+    async fn supervise(&mut self) {
+        if let Some(process) = &mut self.process {
+            let result = (&mut process.child).await;
+            self.process = None;
+            self.report_exit(result);
+            delay_for(self.respawn_interval).await;
+        }
+
+        match self.spawn() {
+            Ok(process) => self.process = Some(process),
+            Err(error) => {
+                emit!(ExecSinkFailed {
+                    command: &self.command_line,
+                    error,
+                });
+                delay_for(self.respawn_interval).await;
+            }
+        }
+    }
+
+    /// Writes to the command's stdin, (re)starting the command until a
+    /// write succeeds. A full pipe holds the write back, which is how the
+    /// command applies backpressure.
+    async fn write(&mut self, bytes: &[u8]) {
+        loop {
+            if self.process.is_none() {
+                match self.spawn() {
+                    Ok(process) => self.process = Some(process),
+                    Err(error) => {
+                        emit!(ExecSinkFailed {
+                            command: &self.command_line,
+                            error,
+                        });
+                        delay_for(self.respawn_interval).await;
+                        continue;
+                    }
+                }
+            }
+            let process = self.process.as_mut().unwrap();
+
+            match process.stdin.write_all(bytes).await {
+                Ok(()) => return,
+                Err(error) => {
+                    emit!(ExecSinkFailed {
+                        command: &self.command_line,
+                        error,
+                    });
+                    // Most often the command exited and the pipe broke, but
+                    // make sure it is gone before starting another.
+                    let mut process = self.process.take().unwrap();
+                    let _ = process.child.kill();
+                    self.reap(process.child).await;
+                    delay_for(self.respawn_interval).await;
+                }
+            }
+        }
+    }
+
+    fn spawn(&self) -> io::Result<Process> {
+        let mut command = Command::new(&self.command[0]);
+        command
+            .args(&self.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(working_directory) = &self.working_directory {
+            command.current_dir(working_directory);
+        }
+
+        let mut child = command.spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        if let Some(stderr) = child.stderr.take() {
+            let command = self.command_line.clone();
+            tokio::spawn(async move {
+                let mut stderr = BufReader::new(stderr);
+                let mut line = String::new();
+                while let Ok(size) = stderr.read_line(&mut line).await {
+                    if size == 0 {
+                        break;
+                    }
+                    emit!(ExecSinkStderr {
+                        command: &command,
+                        line: line.trim_end(),
+                    });
+                    line.clear();
+                }
+            });
+        }
+
+        Ok(Process { child, stdin })
+    }
+
+    async fn reap(&self, child: Child) {
+        self.report_exit(child.await);
+    }
+
+    fn report_exit(&self, result: io::Result<ExitStatus>) {
+        match result {
+            Ok(exit_status) => emit!(ExecSinkCommandExited {
+                command: &self.command_line,
+                exit_status,
+            }),
+            Err(error) => emit!(ExecSinkFailed {
+                command: &self.command_line,
+                error,
+            }),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::{encode_event, Encoding, EncodingConfig, ExecSink, ExecSinkConfig};
+    use crate::{
+        buffers::Acker,
+        event::Event,
+        sinks::streaming_sink::StreamingSink,
+        test_util::{runtime, temp_file},
+        topology::config::{SinkConfig, SinkContext},
+    };
+    use futures::stream::{self, StreamExt};
+    use std::{fs, path::Path, sync::atomic::Ordering, time::Duration};
+    use tokio::time::delay_for;
+
+    #[test]
+    fn encodes_text() {
+        let event = Event::from("foo");
+        assert_eq!(
+            encode_event(event, &EncodingConfig::from(Encoding::Text)).unwrap(),
+            b"foo\n"
+        );
+    }
+
+    #[test]
+    fn encodes_json() {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert("message", "foo");
+        assert_eq!(
+            encode_event(event, &EncodingConfig::from(Encoding::Json)).unwrap(),
+            b"{\"message\":\"foo\"}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_empty_command() {
+        let config: ExecSinkConfig = toml::from_str(
+            r#"
+            command = []
+            encoding = "text"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config
+            .build(SinkContext::new_test(runtime().executor()))
+            .is_err());
+    }
+
+    fn sink(script: &str, path: &Path, acker: Acker) -> ExecSink {
+        let command = vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            script.to_owned(),
+            path.to_string_lossy().into_owned(),
+        ];
+        ExecSink {
+            command_line: command.join(" "),
+            command,
+            working_directory: None,
+            respawn_interval: Duration::from_secs(0),
+            encoding: EncodingConfig::from(Encoding::Text),
+            acker,
+            process: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_events_to_stdin() {
+        let path = temp_file();
+        let (acker, ack_counter) = Acker::new_for_testing();
+        let mut sink = sink(r#"cat > "$0""#, &path, acker);
+
+        let events = vec!["one", "two", "three"].into_iter().map(Event::from);
+        sink.run(stream::iter(events)).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(ack_counter.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn restarts_exited_command() {
+        let path = temp_file();
+        let (acker, ack_counter) = Acker::new_for_testing();
+        // Each run of the command handles a single line and exits.
+        let mut sink = sink(r#"read line && echo "$line" >> "$0""#, &path, acker);
+
+        // Space the events out so the command exits between them.
+        let events = stream::iter(vec!["one", "two", "three"]).then(|message| async move {
+            delay_for(Duration::from_millis(300)).await;
+            Event::from(message)
+        });
+        sink.run(events).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(ack_counter.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn restarts_command_between_events() {
+        let path = temp_file();
+        let (acker, ack_counter) = Acker::new_for_testing();
+        // The first run of the command exits straight away, and only the
+        // one started in its place reads events.
+        let mut sink = sink(
+            r#"[ -e "$0.ran" ] || { touch "$0.ran"; exit 1; }; cat >> "$0""#,
+            &path,
+            acker,
+        );
+
+        let events = stream::iter(vec!["one"]).then(|message| async move {
+            delay_for(Duration::from_millis(300)).await;
+            Event::from(message)
+        });
+        sink.run(events).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+        assert_eq!(ack_counter.load(Ordering::Relaxed), 1);
+    }
+}
@@ -63,7 +63,7 @@ impl SinkConfig for FileSinkConfig {
     }
 
     fn input_type(&self) -> DataType {
-        DataType::Log
+        DataType::Any
     }
 
     fn sink_type(&self) -> &'static str {
@@ -208,13 +208,19 @@ async fn open_file(path: impl AsRef<std::path::Path>) -> std::io::Result<File> {
 
 pub fn encode_event(encoding: &EncodingConfigWithDefault<Encoding>, mut event: Event) -> Vec<u8> {
     encoding.apply_rules(&mut event);
-    let log = event.into_log();
-    match encoding.codec {
-        Encoding::Ndjson => serde_json::to_vec(&log).expect("Unable to encode event as JSON."),
-        Encoding::Text => log
-            .get(&event::log_schema().message_key())
-            .map(|v| v.to_string_lossy().into_bytes())
-            .unwrap_or_default(),
+    match event {
+        Event::Log(log) => match encoding.codec {
+            Encoding::Ndjson => serde_json::to_vec(&log).expect("Unable to encode event as JSON."),
+            Encoding::Text => log
+                .get(&event::log_schema().message_key())
+                .map(|v| v.to_string_lossy().into_bytes())
+                .unwrap_or_default(),
+        },
+        // Metrics and traces have no message, so they're always written as JSON.
+        Event::Metric(metric) => {
+            serde_json::to_vec(&metric).expect("Unable to encode metric as JSON.")
+        }
+        Event::Trace(trace) => serde_json::to_vec(&trace).expect("Unable to encode trace as JSON."),
     }
 }
 
@@ -242,14 +248,50 @@ impl StreamingSink for FileSink {
 mod tests {
     use super::*;
     use crate::{
-        event,
+        event::{self, Trace},
         test_util::{
             self, lines_from_file, random_events_with_stream, random_lines_with_stream, temp_dir,
             temp_file,
         },
     };
+    use chrono::Utc;
     use futures::stream;
 
+    #[test]
+    fn writes_traces_as_json() {
+        test_util::trace_init();
+
+        let template = temp_file();
+
+        let config = FileSinkConfig {
+            path: template.clone().into(),
+            idle_timeout_secs: None,
+            encoding: Encoding::Text.into(),
+        };
+
+        let mut sink = FileSink::new(&config);
+        let now = Utc::now();
+        let trace = Trace::new(
+            "5b8aa5a2d2c872e8321cf37308d69df2",
+            "051581bf3cb55c13",
+            "GET /",
+            now,
+            now,
+        );
+        let events = stream::iter(vec![Event::from(trace)]);
+
+        let mut rt = crate::test_util::runtime();
+        let _ = rt
+            .block_on_std(async move { sink.run(events).await })
+            .unwrap();
+
+        let output = lines_from_file(template);
+        assert_eq!(output.len(), 1);
+        let span: serde_json::Value = serde_json::from_str(&output[0]).unwrap();
+        assert_eq!(span["trace_id"], "5b8aa5a2d2c872e8321cf37308d69df2");
+        assert_eq!(span["name"], "GET /");
+    }
+
     #[test]
     fn single_partition() {
         test_util::trace_init();
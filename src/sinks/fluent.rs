@@ -0,0 +1,168 @@
+use crate::{
+    event::{self, Event, Value},
+    internal_events::{FluentEventSent, FluentMissingKeys},
+    sinks::util::{tcp::TcpSink, StreamSink},
+    template::Template,
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures01::{stream::iter_ok, Sink};
+use rmpv::Value as MsgpackValue;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FluentSinkConfig {
+    pub address: String,
+    #[serde(default = "default_tag")]
+    pub tag: Template,
+    pub tls: Option<TlsConfig>,
+}
+
+fn default_tag() -> Template {
+    Template::from(r#"{{ tag ?? "vector" }}"#)
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Missing host in address field"))]
+    MissingHost,
+    #[snafu(display("Missing port in address field"))]
+    MissingPort,
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<FluentSinkConfig>("fluent")
+}
+
+#[typetag::serde(name = "fluent")]
+impl SinkConfig for FluentSinkConfig {
+    fn build(&self, cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        let uri = self.address.parse::<http::Uri>()?;
+
+        let host = uri.host().ok_or(BuildError::MissingHost)?.to_string();
+        let port = uri.port_u16().ok_or(BuildError::MissingPort)?;
+
+        let tls = MaybeTlsSettings::from_config(&self.tls, false)?;
+
+        let tag = self.tag.clone();
+        let sink = TcpSink::new(host.clone(), port, cx.resolver(), tls);
+        let sink = StreamSink::new(sink, cx.acker())
+            .with_flat_map(move |event| iter_ok(encode_event(event, &tag)));
+        let healthcheck = super::util::tcp::tcp_healthcheck(host, port, cx.resolver());
+
+        Ok((Box::new(sink), healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "fluent"
+    }
+}
+
+/// Encodes the event as a Message mode Forward protocol message, with the
+/// event's timestamp as its `EventTime` and its other fields as its record.
+fn encode_event(event: Event, tag: &Template) -> Option<Bytes> {
+    let tag = tag
+        .render_string(&event)
+        .map_err(|keys| emit!(FluentMissingKeys { keys }))
+        .ok()?;
+
+    let mut log = event.into_log();
+    let timestamp = match log.remove(&event::log_schema().timestamp_key()) {
+        Some(Value::Timestamp(timestamp)) => timestamp,
+        _ => Utc::now(),
+    };
+    let record = log
+        .into_iter()
+        .map(|(key, value)| (key.into(), from_value(value)))
+        .collect();
+
+    let message = MsgpackValue::Array(vec![
+        tag.into(),
+        event_time(timestamp),
+        MsgpackValue::Map(record),
+    ]);
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &message).unwrap();
+
+    emit!(FluentEventSent {
+        byte_size: buf.len()
+    });
+
+    Some(buf.into())
+}
+
+fn event_time(timestamp: DateTime<Utc>) -> MsgpackValue {
+    let mut data = (timestamp.timestamp() as u32).to_be_bytes().to_vec();
+    data.extend_from_slice(&timestamp.timestamp_subsec_nanos().to_be_bytes());
+    MsgpackValue::Ext(0, data)
+}
+
+fn from_value(value: Value) -> MsgpackValue {
+    match value {
+        Value::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned().into(),
+        Value::Integer(integer) => integer.into(),
+        Value::Float(float) => float.into(),
+        Value::Boolean(boolean) => boolean.into(),
+        Value::Timestamp(timestamp) => timestamp.to_rfc3339().into(),
+        Value::Map(fields) => MsgpackValue::Map(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.into(), from_value(value)))
+                .collect(),
+        ),
+        Value::Array(values) => MsgpackValue::Array(values.into_iter().map(from_value).collect()),
+        Value::Null => MsgpackValue::Nil,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{default_tag, encode_event};
+    use crate::event::{self, Event};
+    use chrono::{TimeZone, Utc};
+    use rmpv::Value as MsgpackValue;
+
+    #[test]
+    fn encodes_message_mode() {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert(
+            event::log_schema().timestamp_key().clone(),
+            Utc.timestamp(1_500_000_000, 5),
+        );
+        event.as_mut_log().insert("count", 3);
+
+        let encoded = encode_event(event, &default_tag()).unwrap();
+        let message = rmpv::decode::read_value(&mut &encoded[..]).unwrap();
+
+        assert_eq!(
+            message,
+            MsgpackValue::Array(vec![
+                "vector".into(),
+                MsgpackValue::Ext(0, vec![0x59, 0x68, 0x2f, 0x00, 0, 0, 0, 5]),
+                MsgpackValue::Map(vec![
+                    ("count".into(), 3.into()),
+                    ("message".into(), "hello".into()),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn uses_tag_field() {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("tag", "app.log");
+
+        let encoded = encode_event(event, &default_tag()).unwrap();
+        let message = rmpv::decode::read_value(&mut &encoded[..]).unwrap();
+
+        assert_eq!(message.as_array().unwrap()[0], "app.log".into());
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{
     dns::Resolver,
-    event::metric::{Metric, MetricValue},
+    event::{
+        metric::{Metric, MetricValue},
+        sketch::DDSketch,
+    },
     sinks::util::{
         http::{
             Error as HttpError, HttpBatchService, HttpClient, HttpRetryLogic,
@@ -367,6 +370,11 @@ fn encode_events(events: Vec<Metric>, namespace: &str) -> String {
 
                 influx_line_protocol(fullname, "distribution", tags, fields, ts, &mut output);
             }
+            MetricValue::Sketch(sketch) => {
+                let fields = encode_sketch(&sketch);
+
+                influx_line_protocol(fullname, "sketch", tags, fields, ts, &mut output);
+            }
         }
     }
 
@@ -436,6 +444,28 @@ fn encode_distribution(values: &[f64], counts: &[u32]) -> Option<HashMap<String,
     Some(fields)
 }
 
+/// Encodes a sketch with the same statistics as a distribution, estimating
+/// the quantiles from it.
+fn encode_sketch(sketch: &DDSketch) -> Option<HashMap<String, Field>> {
+    let median = sketch.quantile(0.5)?;
+    let p95 = sketch.quantile(0.95)?;
+    let count = f64::from(sketch.count);
+
+    let fields: HashMap<String, Field> = vec![
+        ("min".to_owned(), Field::Float(sketch.min)),
+        ("max".to_owned(), Field::Float(sketch.max)),
+        ("median".to_owned(), Field::Float(median)),
+        ("avg".to_owned(), Field::Float(sketch.sum / count)),
+        ("sum".to_owned(), Field::Float(sketch.sum)),
+        ("count".to_owned(), Field::Float(count)),
+        ("quantile_0.95".to_owned(), Field::Float(p95)),
+    ]
+    .into_iter()
+    .collect();
+
+    Some(fields)
+}
+
 // https://v2.docs.influxdata.com/v2.0/reference/syntax/line-protocol/
 fn influx_line_protocol(
     measurement: String,
@@ -983,6 +1013,41 @@ mod tests {
         assert_eq!("1542182950000000011", line_protocol1.3);
     }
 
+    #[test]
+    fn test_encode_sketch() {
+        let events = vec![Metric {
+            name: "requests".into(),
+            timestamp: Some(ts()),
+            tags: Some(tags()),
+            kind: MetricKind::Incremental,
+            value: MetricValue::Sketch(DDSketch::from_distribution(&[1.0, 3.0], &[2, 1])),
+        }];
+
+        let line_protocols = encode_events(events, "ns");
+        let line_protocols: Vec<&str> = line_protocols.split('\n').collect();
+        assert_eq!(line_protocols.len(), 1);
+
+        let line_protocol = split_line_protocol(line_protocols[0]);
+        assert_eq!("ns.requests", line_protocol.0);
+        assert_eq!(
+            "metric_type=sketch,normal_tag=value,true_tag=true",
+            line_protocol.1
+        );
+        assert_fields(
+            line_protocol.2.to_string(),
+            [
+                "avg=1.6666666666666667",
+                "count=3",
+                "max=3",
+                "median=1",
+                "min=1",
+                "quantile_0.95=3",
+                "sum=5",
+            ]
+            .to_vec(),
+        );
+    }
+
     #[test]
     fn test_encode_distribution() {
         let events = vec![
@@ -23,8 +23,12 @@ pub mod console;
 pub mod datadog;
 #[cfg(feature = "sinks-elasticsearch")]
 pub mod elasticsearch;
+#[cfg(feature = "sinks-exec")]
+pub mod exec;
 #[cfg(feature = "sinks-file")]
 pub mod file;
+#[cfg(feature = "sinks-fluent")]
+pub mod fluent;
 #[cfg(feature = "sinks-gcp")]
 pub mod gcp;
 #[cfg(feature = "sinks-honeycomb")]
@@ -47,6 +51,8 @@ pub mod new_relic_logs;
 pub mod papertrail;
 #[cfg(feature = "sinks-prometheus")]
 pub mod prometheus;
+#[cfg(feature = "sinks-prometheus_remote_write")]
+pub mod prometheus_remote_write;
 #[cfg(feature = "sinks-pulsar")]
 pub mod pulsar;
 #[cfg(feature = "sinks-sematext_logs")]
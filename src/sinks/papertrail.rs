@@ -1,5 +1,6 @@
 use crate::{
     event::log_schema,
+    gelf,
     sinks::util::{
         encoding::{EncodingConfig, EncodingConfiguration},
         tcp::{tcp_healthcheck, TcpSink},
@@ -94,6 +95,10 @@ fn encode_event(
             .get(&log_schema().message_key())
             .map(|v| v.to_string_lossy())
             .unwrap_or_default(),
+        Encoding::Gelf => gelf::encode(&log)
+            .map(|b| String::from_utf8_lossy(&b).into_owned())
+            .map_err(|error| error!(message = "Unable to encode.", %error))
+            .ok()?,
     };
 
     formatter
@@ -1,7 +1,7 @@
 use crate::{
     buffers::Acker,
     event::metric::{Metric, MetricKind, MetricValue},
-    sinks::util::MetricEntry,
+    sinks::util::{default_histogram_buckets, MetricEntry},
     topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
     Event,
 };
@@ -41,12 +41,6 @@ pub struct PrometheusSinkConfig {
     pub flush_period_secs: u64,
 }
 
-pub fn default_histogram_buckets() -> Vec<f64> {
-    vec![
-        0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
-    ]
-}
-
 pub fn default_address() -> SocketAddr {
     use std::net::{IpAddr, Ipv4Addr};
 
@@ -145,6 +139,7 @@ fn encode_metric_header(namespace: &str, metric: &Metric) -> String {
         MetricValue::Set { .. } => "gauge",
         MetricValue::AggregatedHistogram { .. } => "histogram",
         MetricValue::AggregatedSummary { .. } => "summary",
+        MetricValue::Sketch(_) => "histogram",
     };
 
     s.push_str(&format!("# HELP {} {}\n", fullname, name));
@@ -255,6 +250,27 @@ fn encode_metric_datum(namespace: &str, buckets: &[f64], expired: bool, metric:
                 s.push_str(&format!("{}_sum{} {}\n", fullname, tags, sum));
                 s.push_str(&format!("{}_count{} {}\n", fullname, tags, count));
             }
+            MetricValue::Sketch(sketch) => {
+                // sketches are estimated into the configured buckets
+                let counts = sketch.bucket_counts(buckets);
+                for (b, c) in buckets.iter().zip(counts.iter()) {
+                    s.push_str(&format!(
+                        "{}_bucket{} {}\n",
+                        fullname,
+                        encode_tags_with_extra(tags, "le".to_string(), b.to_string()),
+                        c
+                    ));
+                }
+                s.push_str(&format!(
+                    "{}_bucket{} {}\n",
+                    fullname,
+                    encode_tags_with_extra(tags, "le".to_string(), "+Inf".to_string()),
+                    sketch.count
+                ));
+                let tags = encode_tags(tags);
+                s.push_str(&format!("{}_sum{} {}\n", fullname, tags, sketch.sum));
+                s.push_str(&format!("{}_count{} {}\n", fullname, tags, sketch.count));
+            }
         }
     }
 
@@ -418,7 +434,10 @@ impl Sink for PrometheusSink {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::event::metric::{Metric, MetricKind, MetricValue};
+    use crate::event::{
+        metric::{Metric, MetricKind, MetricValue},
+        sketch::DDSketch,
+    };
     use pretty_assertions::assert_eq;
 
     fn tags() -> BTreeMap<String, String> {
@@ -534,6 +553,26 @@ mod tests {
         assert_eq!(frame, "requests_bucket{le=\"0\"} 0\nrequests_bucket{le=\"2.5\"} 6\nrequests_bucket{le=\"5\"} 8\nrequests_bucket{le=\"+Inf\"} 8\nrequests_sum 15\nrequests_count 8\n".to_owned());
     }
 
+    #[test]
+    fn test_encode_sketch() {
+        let metric = Metric {
+            name: "requests".to_owned(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+            value: MetricValue::Sketch(DDSketch::from_distribution(&[1.0, 2.0, 3.0], &[3, 3, 2])),
+        };
+
+        let header = encode_metric_header("", &metric);
+        let frame = encode_metric_datum("", &[0.0, 2.5, 5.0], false, &metric);
+
+        assert_eq!(
+            header,
+            "# HELP requests requests\n# TYPE requests histogram\n".to_owned()
+        );
+        assert_eq!(frame, "requests_bucket{le=\"0\"} 0\nrequests_bucket{le=\"2.5\"} 6\nrequests_bucket{le=\"5\"} 8\nrequests_bucket{le=\"+Inf\"} 8\nrequests_sum 15\nrequests_count 8\n".to_owned());
+    }
+
     #[test]
     fn test_encode_histogram() {
         let metric = Metric {
@@ -0,0 +1,686 @@
+use crate::{
+    event::metric::{Metric, MetricValue},
+    event::Event,
+    prometheus_remote_write::{self, proto, NAME_LABEL},
+    sinks::util::{
+        default_histogram_buckets,
+        http::{Auth, BatchedHttpSink, HttpSink},
+        BatchEventsConfig, MetricEntry, TowerRequestConfig,
+    },
+    tls::{TlsOptions, TlsSettings},
+    topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
+};
+use chrono::Utc;
+use futures01::{future, Sink};
+use http::{Method, Uri};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+const MIN_FLUSH_PERIOD_SECS: u64 = 1;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Flush period must be greater or equal to {} secs", min))]
+    FlushPeriodTooShort { min: u64 },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteWriteConfig {
+    pub endpoint: String,
+    pub namespace: Option<String>,
+    #[serde(default = "default_histogram_buckets")]
+    pub buckets: Vec<f64>,
+    #[serde(default = "default_flush_period_secs")]
+    pub flush_period_secs: u64,
+    pub tenant_id: Option<String>,
+    pub auth: Option<Auth>,
+    #[serde(default)]
+    pub batch: BatchEventsConfig,
+    #[serde(default)]
+    pub request: TowerRequestConfig,
+    pub tls: Option<TlsOptions>,
+}
+
+fn default_flush_period_secs() -> u64 {
+    60
+}
+
+lazy_static! {
+    static ref REQUEST_DEFAULTS: TowerRequestConfig = TowerRequestConfig {
+        retry_attempts: Some(5),
+        ..Default::default()
+    };
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<RemoteWriteConfig>("prometheus_remote_write")
+}
+
+#[typetag::serde(name = "prometheus_remote_write")]
+impl SinkConfig for RemoteWriteConfig {
+    fn build(&self, cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        self.endpoint.parse::<Uri>().context(super::UriParseError)?;
+        if self.flush_period_secs < MIN_FLUSH_PERIOD_SECS {
+            return Err(Box::new(BuildError::FlushPeriodTooShort {
+                min: MIN_FLUSH_PERIOD_SECS,
+            }));
+        }
+
+        let batch = self.batch.unwrap_or(1000, 1);
+        let request = self.request.unwrap_with(&REQUEST_DEFAULTS);
+        let tls_settings = TlsSettings::from_options(&self.tls)?;
+
+        let sink = RemoteWriteSink {
+            config: self.clone(),
+            state: Mutex::new(Totals::default()),
+        };
+        let sink = BatchedHttpSink::new(sink, Vec::new(), request, batch, tls_settings, &cx)
+            .sink_map_err(|e| error!("Fatal prometheus_remote_write sink error: {}", e));
+
+        // Remote write has no endpoint to check without sending samples.
+        let healthcheck = Box::new(future::ok(()));
+
+        Ok((Box::new(sink), healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "prometheus_remote_write"
+    }
+}
+
+struct RemoteWriteSink {
+    config: RemoteWriteConfig,
+    state: Mutex<Totals>,
+}
+
+/// Running totals of incremental metrics, as remote write expects cumulative
+/// values, along with when each series was last updated.
+#[derive(Default)]
+struct Totals {
+    metrics: HashMap<MetricEntry, i64>,
+    last_expired: i64,
+}
+
+impl Totals {
+    /// Folds `metric` into the running totals, returning the metric to send.
+    fn update(&mut self, metric: Metric, now: i64, flush_period_secs: u64) -> Metric {
+        // Series not updated for a whole flush period are forgotten, so that
+        // churning labels don't grow the totals forever. An incremental
+        // series that comes back starts again from zero, which Prometheus
+        // treats as a counter reset.
+        let flush_period_secs = flush_period_secs as i64;
+        if now - self.last_expired >= flush_period_secs {
+            self.metrics
+                .retain(|_, updated| now - *updated < flush_period_secs);
+            self.last_expired = now;
+        }
+
+        if metric.kind.is_absolute() {
+            let entry = MetricEntry(metric.clone());
+            self.metrics.remove(&entry);
+            self.metrics.insert(entry, now);
+            return metric;
+        }
+
+        let new = MetricEntry(metric.into_absolute());
+        let total = match self.metrics.remove_entry(&new) {
+            Some((MetricEntry(mut existing), _)) => {
+                existing.add(&metric);
+                existing.timestamp = metric.timestamp;
+                existing
+            }
+            None => new.0,
+        };
+        self.metrics.insert(MetricEntry(total.clone()), now);
+        total
+    }
+}
+
+impl HttpSink for RemoteWriteSink {
+    type Input = Metric;
+    type Output = Vec<Metric>;
+
+    fn encode_event(&self, event: Event) -> Option<Self::Input> {
+        let mut metric = event.into_metric();
+        // Stamped here rather than in `build_request`, so that retried
+        // requests carry the same samples.
+        metric.timestamp = metric.timestamp.or_else(|| Some(Utc::now()));
+
+        if let MetricValue::Distribution {
+            values,
+            sample_rates,
+        } = &metric.value
+        {
+            metric.value = distribution_to_histogram(values, sample_rates, &self.config.buckets);
+        }
+
+        // Sets and summaries can't be accumulated, so they are sent as they
+        // come.
+        if metric.value.is_set() || metric.value.is_aggregated_summary() {
+            return Some(metric);
+        }
+
+        let now = Utc::now().timestamp();
+        let mut state = self.state.lock().unwrap();
+        Some(state.update(metric, now, self.config.flush_period_secs))
+    }
+
+    fn build_request(&self, metrics: Self::Output) -> http::Request<Vec<u8>> {
+        let mut series = SeriesSet::default();
+        for metric in &metrics {
+            encode_metric(
+                self.config.namespace.as_ref().map(String::as_str),
+                &self.config.buckets,
+                metric,
+                &mut series,
+            );
+        }
+        let body = prometheus_remote_write::encode(&series.finish());
+
+        let mut builder = http::Request::builder();
+        builder.method(Method::POST);
+        builder.uri(self.config.endpoint.as_str());
+        builder.header("Content-Type", "application/x-protobuf");
+        builder.header("Content-Encoding", "snappy");
+        builder.header(
+            "X-Prometheus-Remote-Write-Version",
+            prometheus_remote_write::VERSION,
+        );
+        if let Some(tenant_id) = &self.config.tenant_id {
+            builder.header("X-Scope-OrgID", tenant_id.as_str());
+        }
+
+        let mut request = builder.body(body).unwrap();
+        if let Some(auth) = &self.config.auth {
+            auth.apply(&mut request);
+        }
+
+        request
+    }
+}
+
+/// Samples keyed by their series' labels and by timestamp, so that a metric
+/// updated several times within a batch doesn't yield duplicate samples.
+#[derive(Default)]
+struct SeriesSet(BTreeMap<BTreeMap<String, String>, BTreeMap<i64, f64>>);
+
+impl SeriesSet {
+    fn insert(
+        &mut self,
+        name: String,
+        tags: &Option<BTreeMap<String, String>>,
+        extra: Option<(&str, String)>,
+        timestamp: i64,
+        value: f64,
+    ) {
+        let mut labels = tags.clone().unwrap_or_default();
+        if let Some((label, value)) = extra {
+            labels.insert(label.to_owned(), value);
+        }
+        labels.insert(NAME_LABEL.to_owned(), name);
+
+        self.0.entry(labels).or_default().insert(timestamp, value);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert_histogram(
+        &mut self,
+        name: &str,
+        tags: &Option<BTreeMap<String, String>>,
+        timestamp: i64,
+        buckets: &[f64],
+        counts: &[u32],
+        count: u32,
+        sum: f64,
+    ) {
+        for (bucket, bucket_count) in buckets.iter().zip(counts) {
+            self.insert(
+                format!("{}_bucket", name),
+                tags,
+                Some(("le", bucket.to_string())),
+                timestamp,
+                f64::from(*bucket_count),
+            );
+        }
+        self.insert(
+            format!("{}_bucket", name),
+            tags,
+            Some(("le", "+Inf".to_owned())),
+            timestamp,
+            f64::from(count),
+        );
+        self.insert(format!("{}_sum", name), tags, None, timestamp, sum);
+        self.insert(
+            format!("{}_count", name),
+            tags,
+            None,
+            timestamp,
+            f64::from(count),
+        );
+    }
+
+    fn finish(self) -> proto::WriteRequest {
+        let timeseries = self
+            .0
+            .into_iter()
+            .map(|(labels, samples)| proto::TimeSeries {
+                labels: labels
+                    .into_iter()
+                    .map(|(name, value)| proto::Label { name, value })
+                    .collect(),
+                samples: samples
+                    .into_iter()
+                    .map(|(timestamp, value)| proto::Sample { value, timestamp })
+                    .collect(),
+            })
+            .collect();
+
+        proto::WriteRequest { timeseries }
+    }
+}
+
+fn encode_namespace(namespace: Option<&str>, name: &str) -> String {
+    match namespace {
+        Some(namespace) => format!("{}_{}", namespace, name),
+        None => name.to_owned(),
+    }
+}
+
+/// Buckets a distribution into the cumulative counts of a histogram.
+fn distribution_to_histogram(values: &[f64], sample_rates: &[u32], buckets: &[f64]) -> MetricValue {
+    let mut counts = vec![0; buckets.len()];
+    let mut sum = 0.0;
+    let mut count = 0;
+    for (value, rate) in values.iter().zip(sample_rates) {
+        buckets
+            .iter()
+            .enumerate()
+            .skip_while(|&(_, bucket)| bucket < value)
+            .for_each(|(i, _)| counts[i] += rate);
+
+        sum += value * f64::from(*rate);
+        count += rate;
+    }
+
+    MetricValue::AggregatedHistogram {
+        buckets: buckets.to_vec(),
+        counts,
+        count,
+        sum,
+    }
+}
+
+/// Converts a metric into series the same way the `prometheus` sink exposes
+/// it: histograms into `_bucket`, `_sum` and `_count` series, and summaries
+/// into quantile, `_sum` and `_count` series.
+fn encode_metric(
+    namespace: Option<&str>,
+    buckets: &[f64],
+    metric: &Metric,
+    series: &mut SeriesSet,
+) {
+    let name = encode_namespace(namespace, &metric.name);
+    let timestamp = metric
+        .timestamp
+        .map(|timestamp| timestamp.timestamp_millis())
+        .unwrap_or(0);
+    let tags = &metric.tags;
+
+    match &metric.value {
+        MetricValue::Counter { value } | MetricValue::Gauge { value } => {
+            series.insert(name, tags, None, timestamp, *value);
+        }
+        MetricValue::Set { values } => {
+            series.insert(name, tags, None, timestamp, values.len() as f64);
+        }
+        MetricValue::Distribution {
+            values,
+            sample_rates,
+        } => {
+            if let MetricValue::AggregatedHistogram {
+                buckets,
+                counts,
+                count,
+                sum,
+            } = distribution_to_histogram(values, sample_rates, buckets)
+            {
+                series.insert_histogram(&name, tags, timestamp, &buckets, &counts, count, sum);
+            }
+        }
+        MetricValue::AggregatedHistogram {
+            buckets,
+            counts,
+            count,
+            sum,
+        } => series.insert_histogram(&name, tags, timestamp, buckets, counts, *count, *sum),
+        MetricValue::Sketch(sketch) => {
+            // sketches are estimated into the configured buckets
+            let counts = sketch.bucket_counts(buckets);
+            series.insert_histogram(
+                &name,
+                tags,
+                timestamp,
+                buckets,
+                &counts,
+                sketch.count,
+                sketch.sum,
+            );
+        }
+        MetricValue::AggregatedSummary {
+            quantiles,
+            values,
+            count,
+            sum,
+        } => {
+            for (quantile, value) in quantiles.iter().zip(values) {
+                series.insert(
+                    name.clone(),
+                    tags,
+                    Some(("quantile", quantile.to_string())),
+                    timestamp,
+                    *value,
+                );
+            }
+            series.insert(format!("{}_sum", name), tags, None, timestamp, *sum);
+            series.insert(
+                format!("{}_count", name),
+                tags,
+                None,
+                timestamp,
+                f64::from(*count),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::metric::{Metric, MetricKind, MetricValue},
+        prometheus_remote_write::decode,
+    };
+    use chrono::{DateTime, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    fn config() -> RemoteWriteConfig {
+        toml::from_str(
+            r#"
+            endpoint = "http://localhost:9090/api/v1/write"
+            namespace = "vector"
+            tenant_id = "tenant"
+            auth = { strategy = "bearer", token = "secret" }
+        "#,
+        )
+        .unwrap()
+    }
+
+    fn sink() -> RemoteWriteSink {
+        RemoteWriteSink {
+            config: config(),
+            state: Mutex::new(Totals::default()),
+        }
+    }
+
+    fn timestamp() -> DateTime<Utc> {
+        Utc.timestamp(1_500_000_000, 0)
+    }
+
+    fn tags() -> BTreeMap<String, String> {
+        vec![("code".to_owned(), "200".to_owned())]
+            .into_iter()
+            .collect()
+    }
+
+    fn metric(name: &str, kind: MetricKind, value: MetricValue) -> Metric {
+        Metric {
+            name: name.into(),
+            timestamp: Some(timestamp()),
+            tags: Some(tags()),
+            kind,
+            value,
+        }
+    }
+
+    /// Flattens a request into `(labels, value)` pairs for easier comparison.
+    fn samples(request: http::Request<Vec<u8>>) -> Vec<(String, f64)> {
+        decode(request.body(), std::usize::MAX)
+            .unwrap()
+            .timeseries
+            .into_iter()
+            .flat_map(|series| {
+                let labels = series
+                    .labels
+                    .iter()
+                    .map(|label| format!("{}={}", label.name, label.value))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                series.samples.into_iter().map(move |sample| {
+                    assert_eq!(sample.timestamp, 1_500_000_000_000);
+                    (labels.clone(), sample.value)
+                })
+            })
+            .collect()
+    }
+
+    fn sample(labels: &str, value: f64) -> (String, f64) {
+        (labels.to_owned(), value)
+    }
+
+    #[test]
+    fn sets_headers() {
+        let request = sink().build_request(vec![]);
+
+        assert_eq!(request.uri(), "http://localhost:9090/api/v1/write");
+        let headers = request.headers();
+        assert_eq!(headers["Content-Type"], "application/x-protobuf");
+        assert_eq!(headers["Content-Encoding"], "snappy");
+        assert_eq!(headers["X-Prometheus-Remote-Write-Version"], "0.1.0");
+        assert_eq!(headers["X-Scope-OrgID"], "tenant");
+        assert_eq!(headers["Authorization"], "Bearer secret");
+    }
+
+    #[test]
+    fn accumulates_incremental_counters() {
+        let sink = sink();
+        let counter = |value| {
+            Event::Metric(metric(
+                "hits",
+                MetricKind::Incremental,
+                MetricValue::Counter { value },
+            ))
+        };
+
+        let first = sink.encode_event(counter(1.0)).unwrap();
+        let second = sink.encode_event(counter(2.0)).unwrap();
+
+        assert_eq!(first.kind, MetricKind::Absolute);
+        assert_eq!(first.value, MetricValue::Counter { value: 1.0 });
+        assert_eq!(second.value, MetricValue::Counter { value: 3.0 });
+        assert_eq!(
+            samples(sink.build_request(vec![first, second])),
+            vec![sample("__name__=vector_hits,code=200", 3.0)]
+        );
+    }
+
+    #[test]
+    fn encodes_histogram() {
+        let sink = sink();
+        let histogram = metric(
+            "requests",
+            MetricKind::Absolute,
+            MetricValue::AggregatedHistogram {
+                buckets: vec![1.0, 2.1],
+                counts: vec![1, 3],
+                count: 4,
+                sum: 12.5,
+            },
+        );
+
+        assert_eq!(
+            samples(sink.build_request(vec![histogram])),
+            vec![
+                sample("__name__=vector_requests_bucket,code=200,le=+Inf", 4.0),
+                sample("__name__=vector_requests_bucket,code=200,le=1", 1.0),
+                sample("__name__=vector_requests_bucket,code=200,le=2.1", 3.0),
+                sample("__name__=vector_requests_count,code=200", 4.0),
+                sample("__name__=vector_requests_sum,code=200", 12.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_summary() {
+        let sink = sink();
+        let summary = metric(
+            "latency",
+            MetricKind::Absolute,
+            MetricValue::AggregatedSummary {
+                quantiles: vec![0.5, 0.99],
+                values: vec![2.0, 7.0],
+                count: 10,
+                sum: 30.0,
+            },
+        );
+
+        assert_eq!(
+            samples(sink.build_request(vec![summary])),
+            vec![
+                sample("__name__=vector_latency,code=200,quantile=0.5", 2.0),
+                sample("__name__=vector_latency,code=200,quantile=0.99", 7.0),
+                sample("__name__=vector_latency_count,code=200", 10.0),
+                sample("__name__=vector_latency_sum,code=200", 30.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn expires_idle_series() {
+        let mut totals = Totals::default();
+        let counter = |name: &str, value| {
+            metric(
+                name,
+                MetricKind::Incremental,
+                MetricValue::Counter { value },
+            )
+        };
+
+        totals.update(counter("hits", 1.0), 1000, 60);
+        totals.update(counter("misses", 1.0), 1000, 60);
+        let hits = totals.update(counter("hits", 1.0), 1030, 60);
+        assert_eq!(hits.value, MetricValue::Counter { value: 2.0 });
+        assert_eq!(totals.metrics.len(), 2);
+
+        // `misses` hasn't been updated for a whole flush period.
+        let hits = totals.update(counter("hits", 1.0), 1070, 60);
+        assert_eq!(hits.value, MetricValue::Counter { value: 3.0 });
+        assert_eq!(totals.metrics.len(), 1);
+
+        let misses = totals.update(counter("misses", 1.0), 1070, 60);
+        assert_eq!(misses.value, MetricValue::Counter { value: 1.0 });
+    }
+
+    #[test]
+    fn rejects_short_flush_period() {
+        let mut config = config();
+        config.flush_period_secs = 0;
+        let rt = crate::test_util::runtime();
+        assert!(config.build(SinkContext::new_test(rt.executor())).is_err());
+    }
+
+    #[test]
+    fn encodes_distribution_as_histogram() {
+        let mut config = config();
+        config.buckets = vec![1.0, 5.0];
+        let sink = RemoteWriteSink {
+            config,
+            state: Mutex::new(Totals::default()),
+        };
+        let distribution = Event::Metric(metric(
+            "sizes",
+            MetricKind::Incremental,
+            MetricValue::Distribution {
+                values: vec![0.5, 3.0, 10.0],
+                sample_rates: vec![1, 2, 1],
+            },
+        ));
+
+        let first = sink.encode_event(distribution.clone()).unwrap();
+        let second = sink.encode_event(distribution).unwrap();
+
+        assert_eq!(
+            first.value,
+            MetricValue::AggregatedHistogram {
+                buckets: vec![1.0, 5.0],
+                counts: vec![1, 3],
+                count: 4,
+                sum: 16.5,
+            }
+        );
+        assert_eq!(
+            samples(sink.build_request(vec![second])),
+            vec![
+                sample("__name__=vector_sizes_bucket,code=200,le=+Inf", 8.0),
+                sample("__name__=vector_sizes_bucket,code=200,le=1", 2.0),
+                sample("__name__=vector_sizes_bucket,code=200,le=5", 6.0),
+                sample("__name__=vector_sizes_count,code=200", 8.0),
+                sample("__name__=vector_sizes_sum,code=200", 33.0),
+            ]
+        );
+    }
+
+    #[cfg(feature = "sources-prometheus_remote_write")]
+    #[test]
+    fn sends_to_prometheus_remote_write_source() {
+        use crate::{
+            shutdown::ShutdownSignal,
+            sources::prometheus_remote_write::PrometheusRemoteWriteConfig,
+            test_util::{self, collect_n},
+            topology::config::{GlobalOptions, SourceConfig},
+        };
+        use futures01::{stream, sync::mpsc};
+
+        test_util::trace_init();
+        let mut rt = test_util::runtime();
+        let address = test_util::next_addr();
+
+        let (tx, rx) = mpsc::channel(100);
+        let source: PrometheusRemoteWriteConfig =
+            toml::from_str(&format!("address = \"{}\"", address)).unwrap();
+        rt.spawn(
+            source
+                .build(
+                    "default",
+                    &GlobalOptions::default(),
+                    ShutdownSignal::noop(),
+                    tx,
+                )
+                .unwrap(),
+        );
+        test_util::wait_for_tcp(address);
+
+        let config: RemoteWriteConfig =
+            toml::from_str(&format!("endpoint = \"http://{}/\"", address)).unwrap();
+        let (sink, _) = config.build(SinkContext::new_test(rt.executor())).unwrap();
+        let gauge = metric(
+            "temperature",
+            MetricKind::Absolute,
+            MetricValue::Gauge { value: 21.5 },
+        );
+        let pump = sink.send_all(stream::iter_ok(vec![Event::Metric(gauge.clone())]));
+        rt.block_on(pump).unwrap();
+
+        let events = rt.block_on(collect_n(rx, 1)).unwrap();
+        assert_eq!(events, vec![Event::Metric(gauge)]);
+    }
+}
@@ -36,6 +36,10 @@ impl Hash for MetricEntry {
                     quantile.to_bits().hash(state);
                 }
             }
+            MetricValue::Sketch(sketch) => {
+                // Only sketches of the same accuracy can be merged.
+                sketch.alpha.to_bits().hash(state);
+            }
             _ => {}
         }
     }
@@ -87,6 +91,7 @@ impl MetricBuffer {
     //   Absolute Counter             => Counter
     //   Gauge                        => Absolute Gauge
     //   Distribution                 => Distribution
+    //   Sketch                       => Sketch
     //   Set                          => Set
     //
     // Deduplicated metrics
@@ -270,6 +275,7 @@ mod test {
     use crate::{
         buffers::Acker,
         event::metric::{Metric, MetricValue},
+        event::sketch::DDSketch,
         runtime::Runtime,
         test_util::runtime,
         Event,
@@ -848,6 +854,50 @@ mod test {
         );
     }
 
+    #[test]
+    fn metric_buffer_sketches() {
+        let (sink, _rt, mut clock, sent_batches) = sink();
+
+        let sketch = |values: &[f64]| {
+            let mut sketch = DDSketch::default();
+            for value in values {
+                sketch.insert(*value);
+            }
+            sketch
+        };
+        let events = (1..5).map(|i| {
+            Event::Metric(Metric {
+                name: "latency".into(),
+                timestamp: None,
+                tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
+                value: MetricValue::Sketch(sketch(&[i as f64])),
+            })
+        });
+
+        let (sink, _) = clock.enter(|_| {
+            sink.sink_map_err(drop)
+                .send_all(futures01::stream::iter_ok(events))
+                .wait()
+                .unwrap()
+        });
+        drop(sink);
+
+        let buffer = Arc::try_unwrap(sent_batches).unwrap().into_inner().unwrap();
+
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(
+            buffer[0].clone().finish(),
+            [Metric {
+                name: "latency".into(),
+                timestamp: None,
+                tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
+                value: MetricValue::Sketch(sketch(&[1.0, 2.0, 3.0, 4.0])),
+            }]
+        );
+    }
+
     #[test]
     fn metric_buffer_compress_distribution() {
         let values = vec![2.0, 2.0, 3.0, 1.0, 2.0, 2.0, 3.0];
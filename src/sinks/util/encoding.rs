@@ -162,8 +162,8 @@ pub trait EncodingConfiguration<E> {
                         log_event.remove(&Atom::from(removal));
                     }
                 }
-                Event::Metric(_) => {
-                    // Metrics don't get affected by this one!
+                Event::Metric(_) | Event::Trace(_) => {
+                    // Metrics and traces don't get affected by this one!
                 }
             }
         }
@@ -176,7 +176,7 @@ pub trait EncodingConfiguration<E> {
                         log_event.remove(field);
                     }
                 }
-                Event::Metric(_) => (), // Metrics don't get affected by this one!
+                Event::Metric(_) | Event::Trace(_) => (), // Metrics and traces don't get affected by this one!
             }
         }
     }
@@ -201,7 +201,7 @@ pub trait EncodingConfiguration<E> {
                         TimestampFormat::RFC3339 => (),
                     }
                 }
-                Event::Metric(_) => (), // Metrics don't get affected by this one!
+                Event::Metric(_) | Event::Trace(_) => (), // Metrics and traces don't get affected by this one!
             }
         }
     }
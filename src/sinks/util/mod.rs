@@ -15,7 +15,10 @@ pub mod udp;
 pub mod unix;
 pub mod uri;
 
-use crate::event::{self, Event};
+use crate::{
+    event::{self, Event},
+    gelf,
+};
 use bytes::Bytes;
 use encoding::{EncodingConfig, EncodingConfiguration};
 use serde::{Deserialize, Serialize};
@@ -46,19 +49,20 @@ enum SinkBuildError {
 pub enum Encoding {
     Text,
     Json,
+    Gelf,
 }
 
 /**
 * Encodes the given event into raw bytes that can be sent into a Sink, according to
-* the given encoding.  If there are any errors encoding the event, logs a warning
-* and returns None.
+* the given encoding.  GELF messages end with a null byte, the others with a newline.
+* If there are any errors encoding the event, logs a warning and returns None.
 **/
 pub fn encode_event(mut event: Event, encoding: &EncodingConfig<Encoding>) -> Option<Bytes> {
     encoding.apply_rules(&mut event);
     let log = event.into_log();
 
-    let b = match encoding.codec {
-        Encoding::Json => serde_json::to_vec(&log),
+    let b: crate::Result<Vec<u8>> = match encoding.codec {
+        Encoding::Json => serde_json::to_vec(&log).map_err(Into::into),
         Encoding::Text => {
             let bytes = log
                 .get(&event::log_schema().message_key())
@@ -66,12 +70,25 @@ pub fn encode_event(mut event: Event, encoding: &EncodingConfig<Encoding>) -> Op
                 .unwrap_or_default();
             Ok(bytes)
         }
+        Encoding::Gelf => gelf::encode(&log).map_err(Into::into),
+    };
+    let delimiter = match encoding.codec {
+        Encoding::Gelf => b'\0',
+        Encoding::Json | Encoding::Text => b'\n',
     };
 
     b.map(|mut b| {
-        b.push(b'\n');
+        b.push(delimiter);
         Bytes::from(b)
     })
     .map_err(|error| error!(message = "Unable to encode.", %error))
     .ok()
 }
+
+/// The histogram buckets distributions are aggregated into by the Prometheus
+/// sinks, matching the Prometheus client libraries' defaults.
+pub fn default_histogram_buckets() -> Vec<f64> {
+    vec![
+        0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+    ]
+}
@@ -0,0 +1,579 @@
+use super::util::{SocketListenAddr, TcpSource};
+use crate::{
+    event::{self, Event, LogEvent},
+    internal_events::{BeatsEventDecodeError, BeatsEventReceived},
+    shutdown::ShutdownSignal,
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use flate2::read::ZlibDecoder;
+use futures01::sync::mpsc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+use tokio01::codec::Decoder;
+
+/// The Lumberjack protocol version Beats speak.
+const VERSION: u8 = b'2';
+const WINDOW_SIZE: u8 = b'W';
+const COMPRESSED: u8 = b'C';
+const JSON: u8 = b'J';
+const ACK: u8 = b'A';
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BeatsConfig {
+    pub address: SocketListenAddr,
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+    pub host_key: Option<String>,
+    tls: Option<TlsConfig>,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_length() -> usize {
+    bytesize::mib(8u64) as usize
+}
+
+#[cfg(test)]
+impl BeatsConfig {
+    pub fn new(address: SocketListenAddr, tls: Option<TlsConfig>) -> Self {
+        Self {
+            address,
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+            max_length: default_max_length(),
+            host_key: None,
+            tls,
+        }
+    }
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<BeatsConfig>("beats")
+}
+
+#[typetag::serde(name = "beats")]
+impl SourceConfig for BeatsConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        let host_key = self
+            .host_key
+            .clone()
+            .unwrap_or_else(|| event::log_schema().host_key().to_string());
+        let source = BeatsSource {
+            max_length: self.max_length,
+            host_key,
+        };
+        let tls = MaybeTlsSettings::from_config(&self.tls, true)?;
+        source.run(self.address, self.shutdown_timeout_secs, tls, shutdown, out)
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "beats"
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BeatsSource {
+    max_length: usize,
+    host_key: String,
+}
+
+impl TcpSource for BeatsSource {
+    type Decoder = BeatsDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        BeatsDecoder::new(self.max_length)
+    }
+
+    fn build_event(&self, frame: BeatsFrame, host: Bytes) -> Option<Event> {
+        let byte_size = frame.payload.len();
+        match parse_event(&frame.payload) {
+            Ok(mut log) => {
+                emit!(BeatsEventReceived { byte_size });
+                if !log.contains(&self.host_key.as_str().into()) {
+                    log.insert(&self.host_key, host);
+                }
+                Some(log.into())
+            }
+            Err(error) => {
+                emit!(BeatsEventDecodeError { error });
+                None
+            }
+        }
+    }
+
+    fn build_ack(&self, frame: &BeatsFrame) -> Option<Bytes> {
+        if frame.last_in_window {
+            let mut ack = BytesMut::with_capacity(6);
+            ack.put_slice(&[VERSION, ACK]);
+            ack.put_u32_be(frame.sequence);
+            Some(ack.freeze())
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum DecodeError {
+    #[snafu(display("Unsupported protocol version {:?}", char::from(*version)))]
+    UnsupportedVersion { version: u8 },
+    #[snafu(display("Unsupported frame type {:?}", char::from(*frame_type)))]
+    UnsupportedFrameType { frame_type: u8 },
+    #[snafu(display("Frame of {} bytes is larger than {} bytes", length, max_length))]
+    FrameTooLong { length: usize, max_length: usize },
+    #[snafu(display("Decompression failed: {}", source))]
+    Decompression { source: io::Error },
+    #[snafu(display("Compressed frame ends within a frame"))]
+    TruncatedFrame,
+    #[snafu(display("Compressed frame contains another compressed frame"))]
+    NestedCompression,
+    #[snafu(display("Invalid JSON: {}", source))]
+    InvalidJson { source: serde_json::Error },
+    #[snafu(display("Event is not a JSON object"))]
+    NotAnObject,
+}
+
+impl From<DecodeError> for io::Error {
+    fn from(error: DecodeError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// One JSON data frame. The last frame of a window is acked once its event
+/// has been sent on, which acks the whole window.
+#[derive(Debug, PartialEq)]
+struct BeatsFrame {
+    sequence: u32,
+    payload: Bytes,
+    last_in_window: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Frame {
+    WindowSize(u32),
+    Compressed(Bytes),
+    Json { sequence: u32, payload: Bytes },
+}
+
+/// Decodes Lumberjack v2 window size, compressed and JSON data frames.
+#[derive(Debug)]
+struct BeatsDecoder {
+    max_length: usize,
+    window_size: Option<u32>,
+    frames: VecDeque<BeatsFrame>,
+}
+
+impl BeatsDecoder {
+    fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            window_size: None,
+            frames: VecDeque::new(),
+        }
+    }
+
+    fn handle(&mut self, frame: Frame) -> Result<(), DecodeError> {
+        match frame {
+            Frame::WindowSize(size) => self.window_size = Some(size),
+            Frame::Compressed(compressed) => {
+                let mut buf = Vec::new();
+                ZlibDecoder::new(&compressed[..])
+                    .take(self.max_length as u64 + 1)
+                    .read_to_end(&mut buf)
+                    .context(Decompression)?;
+                if buf.len() > self.max_length {
+                    return Err(DecodeError::FrameTooLong {
+                        length: buf.len(),
+                        max_length: self.max_length,
+                    });
+                }
+
+                let mut buf = BytesMut::from(buf);
+                while !buf.is_empty() {
+                    // Lumberjack never nests compressed frames, and allowing
+                    // it would let a client recurse arbitrarily deep.
+                    match decode_frame(&mut buf, self.max_length)? {
+                        Some(Frame::Compressed(_)) => return Err(DecodeError::NestedCompression),
+                        Some(frame) => self.handle(frame)?,
+                        None => return Err(DecodeError::TruncatedFrame),
+                    }
+                }
+            }
+            Frame::Json { sequence, payload } => {
+                // Without a window size, every frame is acked.
+                let last_in_window = self.window_size.map_or(true, |size| sequence >= size);
+                self.frames.push_back(BeatsFrame {
+                    sequence,
+                    payload,
+                    last_in_window,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Decoder for BeatsDecoder {
+    type Item = BeatsFrame;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<BeatsFrame>, io::Error> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+            match decode_frame(buf, self.max_length)? {
+                Some(frame) => self.handle(frame)?,
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Splits the next frame off `buf`, if all of it has arrived.
+fn decode_frame(buf: &mut BytesMut, max_length: usize) -> Result<Option<Frame>, DecodeError> {
+    if buf.len() < 2 {
+        return Ok(None);
+    }
+    let (version, frame_type) = (buf[0], buf[1]);
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion { version });
+    }
+
+    let header_length = match frame_type {
+        WINDOW_SIZE | COMPRESSED => 6,
+        JSON => 10,
+        frame_type => return Err(DecodeError::UnsupportedFrameType { frame_type }),
+    };
+    if buf.len() < header_length {
+        return Ok(None);
+    }
+    if frame_type == WINDOW_SIZE {
+        let size = BigEndian::read_u32(&buf[2..6]);
+        buf.advance(header_length);
+        return Ok(Some(Frame::WindowSize(size)));
+    }
+
+    let length = BigEndian::read_u32(&buf[header_length - 4..header_length]) as usize;
+    if length > max_length {
+        return Err(DecodeError::FrameTooLong { length, max_length });
+    }
+    if buf.len() < header_length + length {
+        buf.reserve(header_length + length - buf.len());
+        return Ok(None);
+    }
+
+    let header = buf.split_to(header_length);
+    let payload = buf.split_to(length).freeze();
+    Ok(Some(match frame_type {
+        COMPRESSED => Frame::Compressed(payload),
+        _ => Frame::Json {
+            sequence: BigEndian::read_u32(&header[2..6]),
+            payload,
+        },
+    }))
+}
+
+/// Builds an event from a Beat's JSON event. `@timestamp` and `message` go
+/// to the log schema's keys, and everything else, such as `@metadata`,
+/// `agent` and `host`, keeps its name.
+fn parse_event(payload: &[u8]) -> Result<LogEvent, DecodeError> {
+    let object = match serde_json::from_slice(payload).context(InvalidJson)? {
+        JsonValue::Object(object) => object,
+        _ => return Err(DecodeError::NotAnObject),
+    };
+
+    let schema = event::log_schema();
+    let mut log = LogEvent::new();
+    for (key, value) in object {
+        match key.as_str() {
+            "@timestamp" => match value
+                .as_str()
+                .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+            {
+                Some(timestamp) => {
+                    log.insert(
+                        schema.timestamp_key().clone(),
+                        timestamp.with_timezone(&Utc),
+                    );
+                }
+                None => log.insert_flat(key, value),
+            },
+            "message" => {
+                log.insert(schema.message_key().clone(), value);
+            }
+            _ => log.insert_flat(key, value),
+        }
+    }
+
+    if !log.contains(schema.timestamp_key()) {
+        log.insert(schema.timestamp_key().clone(), Utc::now());
+    }
+
+    Ok(log)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BeatsConfig, BeatsDecoder, BeatsFrame, BeatsSource, TcpSource};
+    use crate::{
+        event::{self, Value},
+        shutdown::ShutdownSignal,
+        test_util::{collect_n, next_addr, wait_for_tcp},
+        topology::config::{GlobalOptions, SourceConfig},
+    };
+    use bytes::{BufMut, Bytes, BytesMut};
+    use chrono::{TimeZone, Utc};
+    use flate2::{write::ZlibEncoder, Compression};
+    use futures01::sync::mpsc;
+    use serde_json::json;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+    use tokio01::codec::Decoder;
+
+    fn window(size: u32) -> Vec<u8> {
+        let mut frame = b"2W".to_vec();
+        frame.extend_from_slice(&size.to_be_bytes());
+        frame
+    }
+
+    fn json(sequence: u32, event: serde_json::Value) -> Vec<u8> {
+        let payload = serde_json::to_vec(&event).unwrap();
+        let mut frame = b"2J".to_vec();
+        frame.extend_from_slice(&sequence.to_be_bytes());
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&payload);
+        frame
+    }
+
+    fn compressed(frames: &[u8]) -> Vec<u8> {
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(frames).unwrap();
+        let payload = zlib.finish().unwrap();
+        let mut frame = b"2C".to_vec();
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&payload);
+        frame
+    }
+
+    fn message(message: &str) -> serde_json::Value {
+        json!({ "message": message })
+    }
+
+    fn decode_all(input: &[u8]) -> Vec<BeatsFrame> {
+        let mut decoder = BeatsDecoder::new(1024);
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(input);
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.decode(&mut buf).unwrap() {
+            frames.push(frame);
+        }
+        assert!(buf.is_empty());
+        frames
+    }
+
+    fn acks(frames: &[BeatsFrame]) -> Vec<(u32, bool)> {
+        frames
+            .iter()
+            .map(|frame| (frame.sequence, frame.last_in_window))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_json_frames() {
+        let mut input = window(2);
+        input.extend(json(1, message("one")));
+        input.extend(json(2, message("two")));
+
+        let frames = decode_all(&input);
+        assert_eq!(acks(&frames), vec![(1, false), (2, true)]);
+        assert_eq!(
+            frames[0].payload,
+            Bytes::from(serde_json::to_vec(&message("one")).unwrap())
+        );
+    }
+
+    #[test]
+    fn decodes_compressed_frames() {
+        let mut inner = window(3);
+        inner.extend(json(1, message("one")));
+        inner.extend(json(2, message("two")));
+        let mut input = compressed(&inner);
+        input.extend(compressed(&json(3, message("three"))));
+
+        let frames = decode_all(&input);
+        assert_eq!(acks(&frames), vec![(1, false), (2, false), (3, true)]);
+    }
+
+    #[test]
+    fn waits_for_whole_frames() {
+        let mut input = window(1);
+        input.extend(json(1, message("one")));
+
+        let mut decoder = BeatsDecoder::new(1024);
+        let mut buf = BytesMut::new();
+        for byte in &input[..input.len() - 1] {
+            buf.extend_from_slice(&[*byte]);
+            assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        }
+        buf.extend_from_slice(&input[input.len() - 1..]);
+        assert_eq!(decoder.decode(&mut buf).unwrap().unwrap().sequence, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_frames() {
+        for input in &[
+            b"1W\0\0\0\x01".to_vec(),
+            b"2X\0\0\0\x01".to_vec(),
+            json(1, json!({ "message": "x".repeat(2048) })),
+            b"2C\0\0\0\x03abc".to_vec(),
+            compressed(&json(1, message("one"))[..8]),
+            compressed(&compressed(&json(1, message("one")))),
+        ] {
+            let mut decoder = BeatsDecoder::new(1024);
+            let mut buf = BytesMut::new();
+            buf.extend_from_slice(input);
+            assert!(decoder.decode(&mut buf).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_nested_compressed_frames() {
+        let mut input = json(1, message("one"));
+        for _ in 0..16 {
+            input = compressed(&input);
+        }
+
+        let mut decoder = BeatsDecoder::new(1024);
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&input);
+        let error = decoder.decode(&mut buf).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Compressed frame contains another compressed frame"
+        );
+    }
+
+    #[test]
+    fn builds_events_and_acks() {
+        let source = BeatsSource {
+            max_length: 1024,
+            host_key: "host".into(),
+        };
+        let payload = json!({
+            "@timestamp": "2020-04-01T12:00:00.123Z",
+            "@metadata": { "beat": "filebeat", "type": "_doc", "version": "7.6.2" },
+            "agent": { "type": "filebeat", "hostname": "web-1" },
+            "host": { "name": "web-1" },
+            "log": { "offset": 42 },
+            "message": "hello",
+        });
+        let frame = BeatsFrame {
+            sequence: 7,
+            payload: serde_json::to_vec(&payload).unwrap().into(),
+            last_in_window: true,
+        };
+
+        assert_eq!(
+            source.build_ack(&frame),
+            Some(Bytes::from(&b"2A\0\0\0\x07"[..]))
+        );
+
+        let event = source.build_event(frame, "127.0.0.1".into()).unwrap();
+        let log = event.as_log();
+        assert_eq!(log[&event::log_schema().message_key()], "hello".into());
+        assert_eq!(
+            log[&event::log_schema().timestamp_key()],
+            Value::Timestamp(Utc.timestamp(1_585_742_400, 123_000_000))
+        );
+        assert_eq!(log[&"@metadata.beat".into()], "filebeat".into());
+        assert_eq!(log[&"agent.type".into()], "filebeat".into());
+        assert_eq!(log[&"host.name".into()], "web-1".into());
+        assert_eq!(log[&"log.offset".into()], Value::Integer(42));
+    }
+
+    #[test]
+    fn inserts_peer_host_and_skips_invalid_events() {
+        let source = BeatsSource {
+            max_length: 1024,
+            host_key: "host".into(),
+        };
+        let frame = |payload: &[u8]| BeatsFrame {
+            sequence: 1,
+            payload: payload.into(),
+            last_in_window: true,
+        };
+
+        let event = source
+            .build_event(frame(br#"{"message":"hi"}"#), "127.0.0.1".into())
+            .unwrap();
+        assert_eq!(event.as_log()[&"host".into()], "127.0.0.1".into());
+        assert!(source
+            .build_event(frame(b"not json"), "127.0.0.1".into())
+            .is_none());
+        assert!(source.build_ack(&frame(b"not json")).is_some());
+    }
+
+    #[test]
+    fn acks_windows_over_tcp() {
+        let addr = next_addr();
+        let (tx, rx) = mpsc::channel(10);
+        let source = BeatsConfig::new(addr.into(), None)
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .unwrap();
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+        rt.spawn(source);
+        wait_for_tcp(addr);
+
+        let mut inner = json(1, message("one"));
+        inner.extend(json(2, message("two")));
+        let mut input = window(2);
+        input.extend(compressed(&inner));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(&input).unwrap();
+
+        let events = rt.block_on(collect_n(rx, 2)).ok().unwrap();
+        let messages: Vec<_> = events
+            .iter()
+            .map(|event| event.as_log()[&event::log_schema().message_key()].clone())
+            .collect();
+        assert_eq!(messages, vec!["one".into(), "two".into()]);
+
+        let mut ack = [0; 6];
+        stream.read_exact(&mut ack).unwrap();
+        let mut expected = BytesMut::with_capacity(6);
+        expected.put_slice(b"2A");
+        expected.put_u32_be(2);
+        assert_eq!(&ack[..], &expected[..]);
+    }
+}
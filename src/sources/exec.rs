@@ -0,0 +1,647 @@
+use crate::{
+    event::{self, Event},
+    internal_events::{ExecCommandExited, ExecEventReceived, ExecFailed},
+    shutdown::{ShutdownSignal, ShutdownSignalToken},
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+};
+use bytes::Bytes;
+use futures01::{
+    future,
+    sync::{mpsc, oneshot},
+    Future, Sink,
+};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::{
+    cmp,
+    io::{BufRead, BufReader, Read},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::dispatcher;
+
+/// How often a running command is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a command gets to exit after `SIGTERM` before it is killed.
+const TERMINATE_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// How long the output of a command that exited is read before the next
+/// run, in case processes it started in the background still hold it open.
+const OUTPUT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("The command must not be empty"))]
+    EmptyCommand,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExecConfig {
+    /// The program to run followed by its arguments.
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub scheduled: ScheduledConfig,
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+    pub working_directory: Option<PathBuf>,
+    #[serde(default = "crate::serde::default_true")]
+    pub include_stderr: bool,
+    pub host_key: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    Scheduled,
+    Streaming,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Scheduled
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledConfig {
+    #[serde(default = "default_exec_interval_secs")]
+    pub exec_interval_secs: u64,
+}
+
+impl Default for ScheduledConfig {
+    fn default() -> Self {
+        Self {
+            exec_interval_secs: default_exec_interval_secs(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct StreamingConfig {
+    #[serde(default = "crate::serde::default_true")]
+    pub respawn_on_exit: bool,
+    #[serde(default = "default_respawn_interval_secs")]
+    pub respawn_interval_secs: u64,
+    #[serde(default = "default_respawn_max_interval_secs")]
+    pub respawn_max_interval_secs: u64,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            respawn_on_exit: true,
+            respawn_interval_secs: default_respawn_interval_secs(),
+            respawn_max_interval_secs: default_respawn_max_interval_secs(),
+        }
+    }
+}
+
+pub fn default_exec_interval_secs() -> u64 {
+    60
+}
+
+pub fn default_respawn_interval_secs() -> u64 {
+    1
+}
+
+pub fn default_respawn_max_interval_secs() -> u64 {
+    60
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<ExecConfig>("exec")
+}
+
+#[typetag::serde(name = "exec")]
+impl SourceConfig for ExecConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        if self.command.is_empty() {
+            return Err(BuildError::EmptyCommand.into());
+        }
+
+        let exec = Exec {
+            config: self.clone(),
+            command_line: self.command.join(" "),
+            host: hostname::get_hostname(),
+            out,
+        };
+
+        Ok(Box::new(future::lazy(move || {
+            info!(message = "Starting exec source.", command = %exec.command_line);
+
+            let (shutdown_tx, shutdown_rx) = channel();
+            let (done_tx, done_rx) = oneshot::channel();
+
+            let span = info_span!("exec");
+            let dispatcher = dispatcher::get_default(|d| d.clone());
+            thread::spawn(move || {
+                dispatcher::with_default(&dispatcher, || {
+                    span.in_scope(|| exec.run(Shutdown::new(shutdown_rx)))
+                });
+                let _ = done_tx.send(());
+            });
+
+            // The shutdown token is handed to the exec thread, which holds
+            // onto it until the running command has been terminated.
+            shutdown
+                .map(move |token| {
+                    let _ = shutdown_tx.send(token);
+                })
+                .select(done_rx.map_err(|_| ()))
+                .map(|_| ())
+                .map_err(|_| ())
+        })))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "exec"
+    }
+}
+
+/// The exec thread's view of the source's shutdown signal.
+struct Shutdown {
+    rx: Receiver<ShutdownSignalToken>,
+    token: Option<ShutdownSignalToken>,
+}
+
+impl Shutdown {
+    fn new(rx: Receiver<ShutdownSignalToken>) -> Self {
+        Self { rx, token: None }
+    }
+
+    /// Waits up to `timeout` for shutdown to begin, returning whether it
+    /// has. The source being dropped counts as shutdown too.
+    fn wait(&mut self, timeout: Duration) -> bool {
+        if self.token.is_some() {
+            return true;
+        }
+        match self.rx.recv_timeout(timeout) {
+            Ok(token) => {
+                self.token = Some(token);
+                true
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        }
+    }
+}
+
+struct Exec {
+    config: ExecConfig,
+    command_line: String,
+    host: Option<String>,
+    out: mpsc::Sender<Event>,
+}
+
+impl Exec {
+    fn run(self, mut shutdown: Shutdown) {
+        match self.config.mode {
+            Mode::Scheduled => {
+                let interval = Duration::from_secs(self.config.scheduled.exec_interval_secs);
+                loop {
+                    let started = Instant::now();
+                    if self.run_command(&mut shutdown) {
+                        break;
+                    }
+                    // A run taking longer than the interval delays the next
+                    // one rather than overlapping it.
+                    let remaining = interval.checked_sub(started.elapsed()).unwrap_or_default();
+                    if shutdown.wait(remaining) {
+                        break;
+                    }
+                }
+            }
+            Mode::Streaming => {
+                let streaming = &self.config.streaming;
+                let initial = Duration::from_secs(streaming.respawn_interval_secs);
+                let max = Duration::from_secs(streaming.respawn_max_interval_secs);
+                let mut backoff = initial;
+                loop {
+                    let started = Instant::now();
+                    if self.run_command(&mut shutdown) || !streaming.respawn_on_exit {
+                        break;
+                    }
+                    // A command which keeps exiting right away is restarted
+                    // less and less often, one which ran for a while is
+                    // restarted promptly.
+                    if started.elapsed() >= max {
+                        backoff = initial;
+                    }
+                    if shutdown.wait(backoff) {
+                        break;
+                    }
+                    backoff = cmp::min(backoff * 2, max);
+                }
+            }
+        }
+    }
+
+    /// Runs the command until it exits or shutdown begins, sending each
+    /// line of its output. Returns whether shutdown began.
+    fn run_command(&self, shutdown: &mut Shutdown) -> bool {
+        let mut command = Command::new(&self.config.command[0]);
+        command
+            .args(&self.config.command[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(if self.config.include_stderr {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            });
+        if let Some(working_directory) = &self.config.working_directory {
+            command.current_dir(working_directory);
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(error) => {
+                emit!(ExecFailed {
+                    command: &self.command_line,
+                    error,
+                });
+                return false;
+            }
+        };
+        let started = Instant::now();
+
+        let (done_tx, done_rx) = channel();
+        let mut readers = 0;
+        if let Some(stdout) = child.stdout.take() {
+            self.read_lines(stdout, "stdout", child.id(), done_tx.clone());
+            readers += 1;
+        }
+        if let Some(stderr) = child.stderr.take() {
+            self.read_lines(stderr, "stderr", child.id(), done_tx.clone());
+            readers += 1;
+        }
+        drop(done_tx);
+
+        loop {
+            match child.try_wait() {
+                Ok(Some(exit_status)) => {
+                    emit!(ExecCommandExited {
+                        command: &self.command_line,
+                        exit_status,
+                        elapsed: started.elapsed(),
+                    });
+                    break;
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    emit!(ExecFailed {
+                        command: &self.command_line,
+                        error,
+                    });
+                    terminate(&mut child);
+                    break;
+                }
+            }
+
+            if shutdown.wait(POLL_INTERVAL) {
+                terminate(&mut child);
+                // Children of the command may still hold its output open,
+                // so the readers are left to finish on their own.
+                return true;
+            }
+        }
+
+        // Processes the command left running in the background may hold
+        // its output open indefinitely, so the readers are only waited for
+        // a while, and left to finish on their own after that.
+        let deadline = Instant::now() + OUTPUT_GRACE_PERIOD;
+        while readers > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                debug!(
+                    message = "Command output still open after it exited, not waiting for it.",
+                    command = %self.command_line,
+                );
+                break;
+            }
+            match done_rx.recv_timeout(cmp::min(POLL_INTERVAL, deadline - now)) {
+                Ok(()) => readers -= 1,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if shutdown.wait(Duration::from_secs(0)) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Sends each line of `output` on a thread of its own, which notifies
+    /// `done` once the output is closed.
+    fn read_lines<R>(&self, output: R, stream: &'static str, pid: u32, done: Sender<()>)
+    where
+        R: Read + Send + 'static,
+    {
+        let context = LineContext {
+            command: self.command_line.clone(),
+            host: self.host.clone(),
+            host_key: self
+                .config
+                .host_key
+                .clone()
+                .unwrap_or_else(|| event::log_schema().host_key().to_string()),
+            pid,
+            stream,
+        };
+        let mut out = self.out.clone();
+
+        thread::spawn(move || {
+            let mut output = BufReader::new(output);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match output.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(byte_size) => {
+                        emit!(ExecEventReceived { byte_size });
+                        out = match out.send(context.create_event(&line)).wait() {
+                            Ok(out) => out,
+                            Err(_) => break,
+                        };
+                    }
+                    Err(error) => {
+                        error!(
+                            message = "Unable to read command output.",
+                            error = %error,
+                            stream = %context.stream,
+                        );
+                        break;
+                    }
+                }
+            }
+            let _ = done.send(());
+        });
+    }
+}
+
+/// What is known about a line of output besides its contents.
+struct LineContext {
+    command: String,
+    host: Option<String>,
+    host_key: String,
+    pid: u32,
+    stream: &'static str,
+}
+
+impl LineContext {
+    fn create_event(&self, line: &[u8]) -> Event {
+        let mut event = Event::from(Bytes::from(trim_newline(line)));
+
+        let log = event.as_mut_log();
+        if let Some(host) = &self.host {
+            log.insert(&self.host_key, host);
+        }
+        log.insert("command", &self.command);
+        log.insert("pid", self.pid as i64);
+        log.insert("stream", self.stream);
+
+        event
+    }
+}
+
+/// Removes a trailing `\n` or `\r\n` from a line.
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = if line.ends_with(b"\n") {
+        &line[..line.len() - 1]
+    } else {
+        line
+    };
+    if line.ends_with(b"\r") {
+        &line[..line.len() - 1]
+    } else {
+        line
+    }
+}
+
+/// Asks the command to exit with `SIGTERM`, killing it if it has not
+/// within the grace period.
+fn terminate(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        use nix::{
+            sys::signal::{kill, Signal},
+            unistd::Pid,
+        };
+
+        if kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM).is_ok() {
+            let deadline = Instant::now() + TERMINATE_GRACE_PERIOD;
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = child.try_wait() {
+                    return;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::{ExecConfig, Mode, ScheduledConfig, StreamingConfig};
+    use crate::{
+        event::{self, Event, Value},
+        shutdown::ShutdownSignal,
+        sources::Source,
+        test_util::{collect_n, runtime},
+        topology::config::{GlobalOptions, SourceConfig},
+    };
+    use futures01::{sync::mpsc, Future};
+    use nix::{sys::signal::kill, unistd::Pid};
+    use std::time::Duration;
+    use stream_cancel::Tripwire;
+    use tokio01::util::FutureExt;
+
+    fn config(script: &str, mode: Mode) -> ExecConfig {
+        ExecConfig {
+            command: vec!["sh".into(), "-c".into(), script.into()],
+            mode,
+            scheduled: ScheduledConfig {
+                exec_interval_secs: 1,
+            },
+            streaming: StreamingConfig {
+                respawn_on_exit: true,
+                respawn_interval_secs: 1,
+                respawn_max_interval_secs: 1,
+            },
+            working_directory: None,
+            include_stderr: true,
+            host_key: None,
+        }
+    }
+
+    fn build(config: &ExecConfig, shutdown: ShutdownSignal) -> (mpsc::Receiver<Event>, Source) {
+        let (tx, rx) = mpsc::channel(10);
+        let source = config
+            .build("default", &GlobalOptions::default(), shutdown, tx)
+            .unwrap();
+        (rx, source)
+    }
+
+    fn field<'a>(event: &'a Event, name: &str) -> &'a Value {
+        event.as_log().get(&name.into()).unwrap()
+    }
+
+    fn message(event: &Event) -> String {
+        event.as_log()[&event::log_schema().message_key()].to_string_lossy()
+    }
+
+    #[test]
+    fn scheduled_runs_command() {
+        let mut rt = runtime();
+        let config = config("echo hello; echo oops >&2", Mode::Scheduled);
+        let (rx, source) = build(&config, ShutdownSignal::noop());
+        rt.spawn(source);
+
+        let mut events = rt.block_on(collect_n(rx, 2)).unwrap();
+        events.sort_by_key(|event| field(event, "stream").to_string_lossy());
+
+        assert_eq!(message(&events[0]), "oops");
+        assert_eq!(field(&events[0], "stream").to_string_lossy(), "stderr");
+        assert_eq!(message(&events[1]), "hello");
+        assert_eq!(field(&events[1], "stream").to_string_lossy(), "stdout");
+        assert_eq!(
+            field(&events[1], "command").to_string_lossy(),
+            "sh -c echo hello; echo oops >&2"
+        );
+        assert_eq!(field(&events[0], "pid"), field(&events[1], "pid"));
+        assert!(events[0]
+            .as_log()
+            .get(&event::log_schema().timestamp_key())
+            .is_some());
+    }
+
+    #[test]
+    fn scheduled_runs_repeatedly() {
+        let mut rt = runtime();
+        let mut config = config("echo tick", Mode::Scheduled);
+        config.include_stderr = false;
+        let (rx, source) = build(&config, ShutdownSignal::noop());
+        rt.spawn(source);
+
+        let events = rt.block_on(collect_n(rx, 2)).unwrap();
+
+        assert_eq!(message(&events[0]), "tick");
+        assert_eq!(message(&events[1]), "tick");
+        assert_ne!(field(&events[0], "pid"), field(&events[1], "pid"));
+    }
+
+    #[test]
+    fn streaming_respawns_command() {
+        let mut rt = runtime();
+        let config = config("echo started; exit 1", Mode::Streaming);
+        let (rx, source) = build(&config, ShutdownSignal::noop());
+        rt.spawn(source);
+
+        let events = rt.block_on(collect_n(rx, 2)).unwrap();
+
+        assert_eq!(message(&events[0]), "started");
+        assert_eq!(message(&events[1]), "started");
+        assert_ne!(field(&events[0], "pid"), field(&events[1], "pid"));
+    }
+
+    #[test]
+    fn streaming_without_respawn_finishes() {
+        let mut rt = runtime();
+        let mut config = config("echo once", Mode::Streaming);
+        config.streaming.respawn_on_exit = false;
+        let (rx, source) = build(&config, ShutdownSignal::noop());
+
+        rt.block_on(source.timeout(Duration::from_secs(10)))
+            .unwrap();
+        let events = rt.block_on(collect_n(rx, 1)).unwrap();
+
+        assert_eq!(message(&events[0]), "once");
+    }
+
+    #[test]
+    fn shutdown_terminates_command() {
+        let mut rt = runtime();
+        let (begin_trigger, begin_tripwire) = Tripwire::new();
+        let (complete_trigger, complete_tripwire) = Tripwire::new();
+        let shutdown = ShutdownSignal::new(begin_tripwire, complete_trigger);
+
+        let config = config("echo started; exec sleep 60", Mode::Streaming);
+        let (rx, source) = build(&config, shutdown);
+        rt.spawn(source);
+
+        let events = rt.block_on(collect_n(rx, 1)).unwrap();
+        let pid = match field(&events[0], "pid") {
+            Value::Integer(pid) => *pid as i32,
+            other => panic!("unexpected pid: {:?}", other),
+        };
+        assert!(kill(Pid::from_raw(pid), None).is_ok());
+
+        drop(begin_trigger);
+        rt.block_on(complete_tripwire.timeout(Duration::from_secs(10)))
+            .unwrap();
+
+        assert!(kill(Pid::from_raw(pid), None).is_err());
+    }
+
+    #[test]
+    fn background_children_dont_block_runs_or_shutdown() {
+        let mut rt = runtime();
+        let (begin_trigger, begin_tripwire) = Tripwire::new();
+        let (complete_trigger, complete_tripwire) = Tripwire::new();
+        let shutdown = ShutdownSignal::new(begin_tripwire, complete_trigger);
+
+        // The backgrounded `sleep` keeps the command's output open after
+        // the command itself has exited.
+        let config = config("sleep 60 & echo hi", Mode::Scheduled);
+        let (rx, source) = build(&config, shutdown);
+        rt.spawn(source);
+
+        let events = rt
+            .block_on(collect_n(rx, 2).timeout(Duration::from_secs(20)))
+            .unwrap();
+        assert_eq!(message(&events[0]), "hi");
+        assert_eq!(message(&events[1]), "hi");
+
+        drop(begin_trigger);
+        rt.block_on(complete_tripwire.timeout(Duration::from_secs(10)))
+            .unwrap();
+    }
+
+    #[test]
+    fn rejects_empty_command() {
+        let mut config = config("", Mode::Scheduled);
+        config.command = vec![];
+        let (tx, _rx) = mpsc::channel(10);
+
+        assert!(config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx
+            )
+            .is_err());
+    }
+}
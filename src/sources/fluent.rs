@@ -0,0 +1,992 @@
+use super::util::{SocketListenAddr, TcpSource};
+use crate::{
+    event::{self, Event, Value},
+    internal_events::{FluentMessageDecodeError, FluentMessageReceived},
+    shutdown::ShutdownSignal,
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+};
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, TimeZone, Utc};
+use flate2::read::MultiGzDecoder;
+use futures01::sync::mpsc;
+use rmpv::Value as MsgpackValue;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Read},
+};
+use tokio01::codec::Decoder;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FluentConfig {
+    pub address: SocketListenAddr,
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+    pub host_key: Option<String>,
+    tls: Option<TlsConfig>,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_length() -> usize {
+    bytesize::mib(8u64) as usize
+}
+
+#[cfg(test)]
+impl FluentConfig {
+    pub fn new(address: SocketListenAddr, tls: Option<TlsConfig>) -> Self {
+        Self {
+            address,
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+            max_length: default_max_length(),
+            host_key: None,
+            tls,
+        }
+    }
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<FluentConfig>("fluent")
+}
+
+#[typetag::serde(name = "fluent")]
+impl SourceConfig for FluentConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        let host_key = self
+            .host_key
+            .clone()
+            .unwrap_or_else(|| event::log_schema().host_key().to_string());
+        let source = FluentSource {
+            max_length: self.max_length,
+            host_key,
+        };
+        let tls = MaybeTlsSettings::from_config(&self.tls, true)?;
+        source.run(self.address, self.shutdown_timeout_secs, tls, shutdown, out)
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "fluent"
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FluentSource {
+    max_length: usize,
+    host_key: String,
+}
+
+impl TcpSource for FluentSource {
+    type Decoder = FluentDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        FluentDecoder::new(self.max_length)
+    }
+
+    fn build_event(&self, frame: FluentFrame, host: Bytes) -> Option<Event> {
+        let entry = frame.entry?;
+
+        let mut event = Event::new_empty_log();
+        let log = event.as_mut_log();
+        for (key, value) in entry.record {
+            log.insert_flat(key, value);
+        }
+        log.insert(event::log_schema().timestamp_key().clone(), entry.timestamp);
+        log.insert("tag", entry.tag);
+        log.insert(&self.host_key, host);
+
+        Some(event)
+    }
+
+    fn build_ack(&self, frame: &FluentFrame) -> Option<Bytes> {
+        frame.chunk.as_ref().map(|chunk| {
+            let ack = MsgpackValue::Map(vec![("ack".into(), chunk.as_str().into())]);
+            let mut buf = Vec::new();
+            rmpv::encode::write_value(&mut buf, &ack).unwrap();
+            buf.into()
+        })
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum DecodeError {
+    #[snafu(display("Message is not an array"))]
+    NotAnArray,
+    #[snafu(display("Message is missing its tag"))]
+    MissingTag,
+    #[snafu(display("Message is missing its entries"))]
+    MissingEntries,
+    #[snafu(display("Invalid entry: {:?}", entry))]
+    InvalidEntry { entry: MsgpackValue },
+    #[snafu(display("Invalid event time: {:?}", time))]
+    InvalidTime { time: MsgpackValue },
+    #[snafu(display("Record is not a map: {:?}", record))]
+    InvalidRecord { record: MsgpackValue },
+    #[snafu(display("Invalid options: {:?}", options))]
+    InvalidOptions { options: MsgpackValue },
+    #[snafu(display("Unsupported compression {:?}", compression))]
+    UnsupportedCompression { compression: String },
+    #[snafu(display("Decompression failed: {}", source))]
+    Decompression { source: io::Error },
+    #[snafu(display("Decompressed entries are larger than {} bytes", max_length))]
+    DecompressedTooLong { max_length: usize },
+    #[snafu(display("Invalid packed entries: {:?}", source))]
+    InvalidPackedEntries { source: rmpv::decode::Error },
+    #[snafu(display("Invalid packed entries: {}", source))]
+    MalformedPackedEntries { source: ScanError },
+    #[snafu(display("Packed entries are truncated"))]
+    TruncatedPackedEntries,
+}
+
+/// One entry of a Forward protocol message. The last entry of a message
+/// carries its `chunk` option, so the message is acked once all its
+/// entries have been sent on. A message without entries yields a frame
+/// without one, just for the ack.
+#[derive(Debug, PartialEq)]
+struct FluentFrame {
+    entry: Option<FluentEntry>,
+    chunk: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct FluentEntry {
+    tag: String,
+    timestamp: DateTime<Utc>,
+    record: BTreeMap<String, Value>,
+}
+
+/// Decodes Forward protocol messages in any of its modes: Message,
+/// Forward, PackedForward and CompressedPackedForward.
+#[derive(Debug)]
+struct FluentDecoder {
+    max_length: usize,
+    scanner: MessageScanner,
+    frames: VecDeque<FluentFrame>,
+}
+
+impl FluentDecoder {
+    fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            scanner: MessageScanner::new(max_length),
+            frames: VecDeque::new(),
+        }
+    }
+
+    fn parse_message(&self, message: MsgpackValue) -> Result<Vec<FluentFrame>, DecodeError> {
+        let mut parts = match message {
+            MsgpackValue::Array(parts) => parts.into_iter(),
+            _ => return Err(DecodeError::NotAnArray),
+        };
+        let tag = parts
+            .next()
+            .and_then(into_string)
+            .ok_or(DecodeError::MissingTag)?;
+        let second = parts.next().ok_or(DecodeError::MissingEntries)?;
+
+        let (entries, options) = match second {
+            // Message mode: [tag, time, record, options]
+            time @ MsgpackValue::Integer(_)
+            | time @ MsgpackValue::F32(_)
+            | time @ MsgpackValue::F64(_)
+            | time @ MsgpackValue::Ext(_, _) => {
+                let record = parts.next().ok_or(DecodeError::MissingEntries)?;
+                let options = parse_options(parts.next())?;
+                (vec![parse_entry(&tag, time, record)?], options)
+            }
+            // Forward mode: [tag, [[time, record], ...], options]
+            MsgpackValue::Array(entries) => {
+                let options = parse_options(parts.next())?;
+                let entries = entries
+                    .into_iter()
+                    .map(|entry| parse_array_entry(&tag, entry))
+                    .collect::<Result<_, _>>()?;
+                (entries, options)
+            }
+            // PackedForward mode: [tag, msgpack stream of [time, record], options]
+            MsgpackValue::String(entries) => {
+                let options = parse_options(parts.next())?;
+                let entries = self.parse_packed_entries(&tag, entries.as_bytes(), &options)?;
+                (entries, options)
+            }
+            MsgpackValue::Binary(entries) => {
+                let options = parse_options(parts.next())?;
+                let entries = self.parse_packed_entries(&tag, &entries, &options)?;
+                (entries, options)
+            }
+            entries => return Err(DecodeError::InvalidEntry { entry: entries }),
+        };
+
+        let mut frames: Vec<FluentFrame> = entries
+            .into_iter()
+            .map(|entry| FluentFrame {
+                entry: Some(entry),
+                chunk: None,
+            })
+            .collect();
+        if let Some(chunk) = options.chunk {
+            match frames.last_mut() {
+                Some(last) => last.chunk = Some(chunk),
+                None => frames.push(FluentFrame {
+                    entry: None,
+                    chunk: Some(chunk),
+                }),
+            }
+        }
+
+        Ok(frames)
+    }
+
+    fn parse_packed_entries(
+        &self,
+        tag: &str,
+        packed: &[u8],
+        options: &FluentOptions,
+    ) -> Result<Vec<FluentEntry>, DecodeError> {
+        let decompressed;
+        let packed = match options.compressed.as_deref() {
+            None | Some("text") => packed,
+            // CompressedPackedForward mode
+            Some("gzip") => {
+                let mut buf = Vec::new();
+                MultiGzDecoder::new(packed)
+                    .take(self.max_length as u64 + 1)
+                    .read_to_end(&mut buf)
+                    .context(Decompression)?;
+                if buf.len() > self.max_length {
+                    return Err(DecodeError::DecompressedTooLong {
+                        max_length: self.max_length,
+                    });
+                }
+                decompressed = buf;
+                &decompressed[..]
+            }
+            Some(compression) => {
+                return Err(DecodeError::UnsupportedCompression {
+                    compression: compression.into(),
+                })
+            }
+        };
+
+        let mut scanner = MessageScanner::new(self.max_length);
+        let mut packed = packed;
+        let mut entries = Vec::new();
+        while !packed.is_empty() {
+            let length = scanner
+                .scan(packed)
+                .context(MalformedPackedEntries)?
+                .ok_or(DecodeError::TruncatedPackedEntries)?;
+            let entry = rmpv::decode::read_value(&mut &packed[..length])
+                .context(InvalidPackedEntries)?;
+            entries.push(parse_array_entry(tag, entry)?);
+            packed = &packed[length..];
+        }
+        Ok(entries)
+    }
+}
+
+impl Decoder for FluentDecoder {
+    type Item = FluentFrame;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<FluentFrame>, io::Error> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+            if buf.is_empty() {
+                return Ok(None);
+            }
+
+            // Find where the message ends before handing it to `rmpv`, which
+            // trusts the lengths on the wire and has no recursion limit.
+            // Without knowing where an invalid message ends, there's no
+            // skipping past it, so those errors close the connection.
+            let byte_size = match self.scanner.scan(&buf[..]) {
+                Ok(Some(byte_size)) => byte_size,
+                Ok(None) => return Ok(None),
+                Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+            };
+            let message = rmpv::decode::read_value(&mut &buf[..byte_size]).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid msgpack: {:?}", error),
+                )
+            })?;
+            buf.advance(byte_size);
+
+            match self.parse_message(message) {
+                Ok(frames) => {
+                    emit!(FluentMessageReceived {
+                        byte_size,
+                        entries: frames.iter().filter(|frame| frame.entry.is_some()).count(),
+                    });
+                    self.frames.extend(frames);
+                }
+                Err(error) => emit!(FluentMessageDecodeError { error }),
+            }
+        }
+    }
+}
+
+/// How deeply arrays and maps may be nested within a single message.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Snafu)]
+pub enum ScanError {
+    #[snafu(display("Invalid msgpack marker 0x{:02x}", marker))]
+    InvalidMarker { marker: u8 },
+    #[snafu(display("Message is larger than {} bytes", max_length))]
+    TooLong { max_length: usize },
+    #[snafu(display("Message is nested more than {} levels deep", max_depth))]
+    TooDeep { max_depth: usize },
+}
+
+/// Finds the end of a msgpack value without decoding or allocating it,
+/// checking every declared length against `max_length` and the nesting
+/// depth against `MAX_DEPTH`. Scanning resumes where it left off when more
+/// of the value arrives, so a large message is only walked once.
+#[derive(Debug)]
+struct MessageScanner {
+    max_length: usize,
+    /// Where the next value header starts.
+    offset: usize,
+    /// The number of values still to come in each enclosing array or map.
+    pending: Vec<usize>,
+}
+
+impl MessageScanner {
+    fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            offset: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the length of the value at the start of `buf` once all of it
+    /// is there. `buf` must only have been appended to since the last call.
+    fn scan(&mut self, buf: &[u8]) -> Result<Option<usize>, ScanError> {
+        loop {
+            let (header, payload, children) = match value_header(&buf[self.offset..])? {
+                Some(header) => header,
+                None => return self.incomplete(buf),
+            };
+
+            let end = self.offset.saturating_add(header).saturating_add(payload);
+            if end > self.max_length || children > self.max_length {
+                self.reset();
+                return Err(ScanError::TooLong {
+                    max_length: self.max_length,
+                });
+            }
+            if end > buf.len() {
+                return self.incomplete(buf);
+            }
+            self.offset = end;
+
+            if children > 0 {
+                if self.pending.len() >= MAX_DEPTH {
+                    self.reset();
+                    return Err(ScanError::TooDeep {
+                        max_depth: MAX_DEPTH,
+                    });
+                }
+                self.pending.push(children);
+                continue;
+            }
+
+            // The value is complete, and so is every enclosing array or map
+            // it was the last value of.
+            loop {
+                match self.pending.last_mut() {
+                    None => {
+                        let length = self.offset;
+                        self.reset();
+                        return Ok(Some(length));
+                    }
+                    Some(remaining) if *remaining > 1 => {
+                        *remaining -= 1;
+                        break;
+                    }
+                    Some(_) => {
+                        self.pending.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    fn incomplete(&mut self, buf: &[u8]) -> Result<Option<usize>, ScanError> {
+        if buf.len() > self.max_length {
+            self.reset();
+            Err(ScanError::TooLong {
+                max_length: self.max_length,
+            })
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn reset(&mut self) {
+        self.offset = 0;
+        self.pending.clear();
+    }
+}
+
+/// Reads the msgpack header at the start of `buf`, returning its length,
+/// the length of the payload that follows it, and the number of values
+/// contained in it if it's an array or map.
+fn value_header(buf: &[u8]) -> Result<Option<(usize, usize, usize)>, ScanError> {
+    let marker = match buf.first() {
+        Some(&marker) => marker,
+        None => return Ok(None),
+    };
+    // The number of bytes holding the length, and how much else follows it.
+    let (length_bytes, extra) = match marker {
+        0x00..=0x7f | 0xc0 | 0xc2 | 0xc3 | 0xe0..=0xff => return Ok(Some((1, 0, 0))),
+        0x80..=0x8f => return Ok(Some((1, 0, 2 * usize::from(marker & 0x0f)))),
+        0x90..=0x9f => return Ok(Some((1, 0, usize::from(marker & 0x0f)))),
+        0xa0..=0xbf => return Ok(Some((1, usize::from(marker & 0x1f), 0))),
+        0xca | 0xd2 => return Ok(Some((1, 4, 0))),
+        0xcb | 0xd3 | 0xcf => return Ok(Some((1, 8, 0))),
+        0xcc | 0xd0 => return Ok(Some((1, 1, 0))),
+        0xcd | 0xd1 => return Ok(Some((1, 2, 0))),
+        0xce => return Ok(Some((1, 4, 0))),
+        // fixext 1, 2, 4, 8 and 16, plus their type byte
+        0xd4..=0xd8 => return Ok(Some((1, 1 + (1 << (marker - 0xd4)), 0))),
+        0xc4 | 0xd9 => (1, 0),
+        0xc5 | 0xda => (2, 0),
+        0xc6 | 0xdb => (4, 0),
+        0xc7 => (1, 1),
+        0xc8 => (2, 1),
+        0xc9 => (4, 1),
+        0xdc | 0xde => (2, 0),
+        0xdd | 0xdf => (4, 0),
+        0xc1 => return Err(ScanError::InvalidMarker { marker }),
+    };
+
+    if buf.len() < 1 + length_bytes {
+        return Ok(None);
+    }
+    let length = buf[1..=length_bytes]
+        .iter()
+        .fold(0usize, |length, &byte| (length << 8) | usize::from(byte));
+    let header = 1 + length_bytes + extra;
+    Ok(Some(match marker {
+        0xdc | 0xdd => (header, 0, length),
+        0xde | 0xdf => (header, 0, length.saturating_mul(2)),
+        _ => (header, length, 0),
+    }))
+}
+
+#[derive(Debug, Default)]
+struct FluentOptions {
+    chunk: Option<String>,
+    compressed: Option<String>,
+}
+
+fn parse_options(options: Option<MsgpackValue>) -> Result<FluentOptions, DecodeError> {
+    let options = match options {
+        None | Some(MsgpackValue::Nil) => return Ok(FluentOptions::default()),
+        Some(MsgpackValue::Map(options)) => options,
+        Some(options) => return Err(DecodeError::InvalidOptions { options }),
+    };
+
+    let mut parsed = FluentOptions::default();
+    for (key, value) in options {
+        match key.as_str() {
+            Some("chunk") => parsed.chunk = into_string(value),
+            Some("compressed") => parsed.compressed = into_string(value),
+            // `size` is only a hint, and any other option is unknown to us.
+            _ => (),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_array_entry(tag: &str, entry: MsgpackValue) -> Result<FluentEntry, DecodeError> {
+    match entry {
+        MsgpackValue::Array(parts) if parts.len() == 2 => {
+            let mut parts = parts.into_iter();
+            let time = parts.next().unwrap();
+            let record = parts.next().unwrap();
+            parse_entry(tag, time, record)
+        }
+        entry => Err(DecodeError::InvalidEntry { entry }),
+    }
+}
+
+fn parse_entry(
+    tag: &str,
+    time: MsgpackValue,
+    record: MsgpackValue,
+) -> Result<FluentEntry, DecodeError> {
+    let timestamp = parse_time(time)?;
+    let record = match record {
+        MsgpackValue::Map(fields) => fields
+            .into_iter()
+            .map(|(key, value)| (key_to_string(key), into_value(value)))
+            .collect(),
+        record => return Err(DecodeError::InvalidRecord { record }),
+    };
+    Ok(FluentEntry {
+        tag: tag.into(),
+        timestamp,
+        record,
+    })
+}
+
+/// Times are either seconds, or an `EventTime` extension holding big-endian
+/// 32-bit seconds and nanoseconds.
+fn parse_time(time: MsgpackValue) -> Result<DateTime<Utc>, DecodeError> {
+    let timestamp = match &time {
+        MsgpackValue::Integer(seconds) => seconds
+            .as_i64()
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single()),
+        MsgpackValue::F32(_) | MsgpackValue::F64(_) => time
+            .as_f64()
+            .filter(|seconds| seconds.abs() < 1e15)
+            .and_then(|seconds| {
+                let nanos = (seconds - seconds.floor()) * 1e9;
+                Utc.timestamp_opt(seconds.floor() as i64, nanos as u32)
+                    .single()
+            }),
+        MsgpackValue::Ext(0, data) if data.len() == 8 => {
+            let seconds = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+            let nanos = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+            Utc.timestamp_opt(i64::from(seconds), nanos).single()
+        }
+        _ => None,
+    };
+    timestamp.ok_or(DecodeError::InvalidTime { time })
+}
+
+fn into_string(value: MsgpackValue) -> Option<String> {
+    match value {
+        MsgpackValue::String(string) => string.into_str(),
+        MsgpackValue::Binary(bytes) => String::from_utf8(bytes).ok(),
+        _ => None,
+    }
+}
+
+fn key_to_string(key: MsgpackValue) -> String {
+    match key {
+        MsgpackValue::String(string) => String::from_utf8_lossy(string.as_bytes()).into_owned(),
+        MsgpackValue::Binary(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        key => key.to_string(),
+    }
+}
+
+fn into_value(value: MsgpackValue) -> Value {
+    match value {
+        MsgpackValue::Nil => Value::Null,
+        MsgpackValue::Boolean(boolean) => Value::Boolean(boolean),
+        MsgpackValue::Integer(integer) => match integer.as_i64() {
+            Some(integer) => Value::Integer(integer),
+            None => Value::Float(integer.as_f64().unwrap_or(std::f64::NAN)),
+        },
+        MsgpackValue::F32(float) => Value::Float(f64::from(float)),
+        MsgpackValue::F64(float) => Value::Float(float),
+        MsgpackValue::String(string) => Value::Bytes(string.into_bytes().into()),
+        MsgpackValue::Binary(bytes) | MsgpackValue::Ext(_, bytes) => Value::Bytes(bytes.into()),
+        MsgpackValue::Array(values) => Value::Array(values.into_iter().map(into_value).collect()),
+        MsgpackValue::Map(fields) => Value::Map(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key_to_string(key), into_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FluentConfig, FluentDecoder, FluentFrame, FluentSource};
+    use crate::{
+        event::{self, Event, Value},
+        shutdown::ShutdownSignal,
+        sources::util::TcpSource,
+        test_util::{next_addr, wait_for_tcp, CollectCurrent},
+        topology::config::{GlobalOptions, SourceConfig},
+    };
+    use bytes::{Bytes, BytesMut};
+    use chrono::{TimeZone, Utc};
+    use flate2::{write::GzEncoder, Compression};
+    use futures01::{sync::mpsc, Future};
+    use rmpv::Value as MsgpackValue;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+    use tokio01::codec::Decoder;
+
+    fn encode(value: MsgpackValue) -> Vec<u8> {
+        let mut buf = Vec::new();
+        rmpv::encode::write_value(&mut buf, &value).unwrap();
+        buf
+    }
+
+    fn record(message: &str) -> MsgpackValue {
+        MsgpackValue::Map(vec![("message".into(), message.into())])
+    }
+
+    fn event_time(seconds: u32, nanos: u32) -> MsgpackValue {
+        let mut data = seconds.to_be_bytes().to_vec();
+        data.extend_from_slice(&nanos.to_be_bytes());
+        MsgpackValue::Ext(0, data)
+    }
+
+    fn chunk(chunk: &str) -> MsgpackValue {
+        MsgpackValue::Map(vec![("chunk".into(), chunk.into())])
+    }
+
+    fn decode(input: &[u8]) -> Vec<FluentFrame> {
+        let mut decoder = FluentDecoder::new(1024 * 1024);
+        let mut buf = BytesMut::from(input);
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.decode(&mut buf).unwrap() {
+            frames.push(frame);
+        }
+        assert!(buf.is_empty());
+        frames
+    }
+
+    fn messages(frames: &[FluentFrame]) -> Vec<Value> {
+        frames
+            .iter()
+            .filter_map(|frame| frame.entry.as_ref())
+            .map(|entry| entry.record["message"].clone())
+            .collect()
+    }
+
+    #[test]
+    fn decodes_message_mode() {
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            1_500_000_000.into(),
+            record("hello"),
+        ])));
+
+        assert_eq!(frames.len(), 1);
+        let entry = frames[0].entry.as_ref().unwrap();
+        assert_eq!(entry.tag, "app.log");
+        assert_eq!(entry.timestamp, Utc.timestamp(1_500_000_000, 0));
+        assert_eq!(entry.record["message"], "hello".into());
+        assert_eq!(frames[0].chunk, None);
+    }
+
+    #[test]
+    fn decodes_event_time() {
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            event_time(1_500_000_000, 123_456_789),
+            record("hello"),
+        ])));
+
+        assert_eq!(
+            frames[0].entry.as_ref().unwrap().timestamp,
+            Utc.timestamp(1_500_000_000, 123_456_789)
+        );
+    }
+
+    #[test]
+    fn decodes_forward_mode() {
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            MsgpackValue::Array(vec![
+                MsgpackValue::Array(vec![1.into(), record("one")]),
+                MsgpackValue::Array(vec![event_time(2, 0), record("two")]),
+            ]),
+            chunk("abc"),
+        ])));
+
+        assert_eq!(messages(&frames), vec!["one".into(), "two".into()]);
+        assert_eq!(frames[0].chunk, None);
+        assert_eq!(frames[1].chunk, Some("abc".into()));
+    }
+
+    #[test]
+    fn decodes_packed_forward_mode() {
+        let mut packed = encode(MsgpackValue::Array(vec![1.into(), record("one")]));
+        packed.extend(encode(MsgpackValue::Array(vec![2.into(), record("two")])));
+
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            MsgpackValue::Binary(packed),
+        ])));
+
+        assert_eq!(messages(&frames), vec!["one".into(), "two".into()]);
+    }
+
+    #[test]
+    fn decodes_compressed_packed_forward_mode() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&encode(MsgpackValue::Array(vec![1.into(), record("one")])))
+            .unwrap();
+        let mut compressed = gzip.finish().unwrap();
+        // Each chunk may be compressed separately.
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&encode(MsgpackValue::Array(vec![2.into(), record("two")])))
+            .unwrap();
+        compressed.extend(gzip.finish().unwrap());
+
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            MsgpackValue::Binary(compressed),
+            MsgpackValue::Map(vec![
+                ("compressed".into(), "gzip".into()),
+                ("chunk".into(), "abc".into()),
+            ]),
+        ])));
+
+        assert_eq!(messages(&frames), vec!["one".into(), "two".into()]);
+        assert_eq!(frames[1].chunk, Some("abc".into()));
+    }
+
+    #[test]
+    fn waits_for_whole_message() {
+        let input = encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            1.into(),
+            record("hello"),
+        ]));
+        let mut decoder = FluentDecoder::new(1024);
+        let mut buf = BytesMut::from(&input[..input.len() - 3]);
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(&input[input.len() - 3..]);
+        assert!(decoder.decode(&mut buf).unwrap().is_some());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn decodes_message_byte_by_byte() {
+        let input = encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            MsgpackValue::Array(vec![
+                MsgpackValue::Array(vec![1.into(), record("one")]),
+                MsgpackValue::Array(vec![event_time(2, 0), record("two")]),
+            ]),
+        ]));
+        let mut decoder = FluentDecoder::new(1024);
+        let mut buf = BytesMut::new();
+        let mut frames = Vec::new();
+        for byte in input {
+            buf.extend_from_slice(&[byte]);
+            while let Some(frame) = decoder.decode(&mut buf).unwrap() {
+                frames.push(frame);
+            }
+        }
+
+        assert_eq!(messages(&frames), vec!["one".into(), "two".into()]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn rejects_lengths_over_max_length() {
+        // An array32, map32 and str32 claiming about 4 billion elements or
+        // bytes, with none of them present.
+        for header in &[
+            &[0xdd, 0xff, 0xff, 0xff, 0xff][..],
+            &[0xdf, 0xff, 0xff, 0xff, 0xff][..],
+            &[0x92, 0xdb, 0xff, 0xff, 0xff, 0xff][..],
+        ] {
+            let mut decoder = FluentDecoder::new(1024);
+            let mut buf = BytesMut::from(*header);
+            assert!(decoder.decode(&mut buf).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_deeply_nested_messages() {
+        let mut decoder = FluentDecoder::new(1024 * 1024);
+        let mut buf = BytesMut::from(&vec![0x91; 100_000][..]);
+        assert!(decoder.decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_packed_entries() {
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            MsgpackValue::Binary(vec![0xdd, 0xff, 0xff, 0xff, 0xff]),
+        ])));
+        assert!(frames.is_empty());
+
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            MsgpackValue::Binary(vec![0x91; 1000]),
+        ])));
+        assert!(frames.is_empty());
+    }
+
+    #[test]
+    fn skips_invalid_messages() {
+        let mut input = encode(MsgpackValue::Array(vec!["app.log".into(), "oops".into()]));
+        input.extend(encode("not a message".into()));
+        input.extend(encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            1.into(),
+            record("hello"),
+        ])));
+
+        assert_eq!(messages(&decode(&input)), vec!["hello".into()]);
+    }
+
+    #[test]
+    fn acks_empty_forward_messages() {
+        let frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            MsgpackValue::Array(vec![]),
+            chunk("abc"),
+        ])));
+
+        assert_eq!(
+            frames,
+            vec![FluentFrame {
+                entry: None,
+                chunk: Some("abc".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn builds_events_and_acks() {
+        let source = FluentSource {
+            max_length: 1024,
+            host_key: "host".into(),
+        };
+        let mut frames = decode(&encode(MsgpackValue::Array(vec![
+            "app.log".into(),
+            1.into(),
+            MsgpackValue::Map(vec![
+                ("message".into(), "hello".into()),
+                ("kubernetes.pod".into(), "web".into()),
+            ]),
+            chunk("abc"),
+        ])));
+        let frame = frames.remove(0);
+
+        let ack = source.build_ack(&frame).unwrap();
+        assert_eq!(
+            rmpv::decode::read_value(&mut &ack[..]).unwrap(),
+            MsgpackValue::Map(vec![("ack".into(), "abc".into())])
+        );
+
+        let event = source.build_event(frame, Bytes::from("127.0.0.1")).unwrap();
+        let log = event.as_log();
+        assert_eq!(log[&"message".into()], "hello".into());
+        assert_eq!(log.keys().filter(|key| key == "kubernetes.pod").count(), 1);
+        assert_eq!(log[&"tag".into()], "app.log".into());
+        assert_eq!(log[&"host".into()], "127.0.0.1".into());
+        assert_eq!(
+            log[&event::log_schema().timestamp_key()],
+            Value::Timestamp(Utc.timestamp(1, 0))
+        );
+    }
+
+    #[test]
+    fn acks_over_tcp() {
+        let addr = next_addr();
+        let (tx, rx) = mpsc::channel(10);
+        let source = FluentConfig::new(addr.into(), None)
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .unwrap();
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+        rt.spawn(source);
+        wait_for_tcp(addr);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(&encode(MsgpackValue::Array(vec![
+                "app.log".into(),
+                MsgpackValue::Array(vec![
+                    MsgpackValue::Array(vec![1.into(), record("one")]),
+                    MsgpackValue::Array(vec![2.into(), record("two")]),
+                ]),
+                chunk("abc"),
+            ])))
+            .unwrap();
+
+        let mut ack = [0; 9];
+        stream.read_exact(&mut ack).unwrap();
+        assert_eq!(
+            rmpv::decode::read_value(&mut &ack[..]).unwrap(),
+            MsgpackValue::Map(vec![("ack".into(), "abc".into())])
+        );
+
+        let (_, events) = CollectCurrent::new(rx).wait().unwrap();
+        let messages: Vec<_> = events
+            .iter()
+            .map(|event| event.as_log()[&event::log_schema().message_key()].clone())
+            .collect();
+        assert_eq!(messages, vec!["one".into(), "two".into()]);
+    }
+
+    #[cfg(feature = "sinks-fluent")]
+    #[test]
+    fn it_works_with_fluent_sink() {
+        use crate::{
+            sinks::fluent::FluentSinkConfig,
+            topology::config::{SinkConfig, SinkContext},
+        };
+        use futures01::{stream, Sink};
+
+        let addr = next_addr();
+        let (tx, rx) = mpsc::channel(10);
+        let source = FluentConfig::new(addr.into(), None)
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .unwrap();
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+        rt.spawn(source);
+        wait_for_tcp(addr);
+
+        let sink: FluentSinkConfig =
+            toml::from_str(&format!(r#"address = "localhost:{}""#, addr.port())).unwrap();
+        let (sink, _) = sink.build(SinkContext::new_test(rt.executor())).unwrap();
+
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("tag", "app.log");
+        event.as_mut_log().insert(
+            event::log_schema().timestamp_key(),
+            Utc.timestamp(1_500_000_000, 123_456_789),
+        );
+        let _ = rt
+            .block_on(sink.send_all(stream::iter_ok(vec![event.clone()])))
+            .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let (_, output) = CollectCurrent::new(rx).wait().unwrap();
+        assert_eq!(output.len(), 1);
+        let mut output = output.into_iter().next().unwrap();
+        output.as_mut_log().remove(&event::log_schema().host_key());
+        assert_eq!(output, event);
+    }
+}
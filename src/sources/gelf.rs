@@ -0,0 +1,634 @@
+use super::util::{SocketListenAddr, TcpSource};
+use crate::{
+    event::{self, Event},
+    gelf,
+    internal_events::{GelfChunkDropped, GelfDecodeError, GelfMessageReceived, GelfUdpSocketError},
+    shutdown::ShutdownSignal,
+    stream::StreamExt,
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+};
+use bytes::Bytes;
+use codec::BytesDelimitedCodec;
+use futures01::{future, sync::mpsc, Future, Sink, Stream};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    io, mem,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use tokio01::{
+    codec::BytesCodec,
+    net::{UdpFramed, UdpSocket},
+};
+
+/// Chunked messages start with these two bytes.
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// The magic bytes, the message id, the sequence number and the sequence
+/// count.
+const CHUNK_HEADER_LENGTH: usize = 12;
+/// GELF allows at most this many chunks per message.
+const MAX_CHUNKS: u8 = 128;
+
+#[derive(Deserialize, Serialize, Debug)]
+// TODO: add back when serde-rs/serde#1358 is addressed
+// #[serde(deny_unknown_fields)]
+pub struct GelfConfig {
+    #[serde(flatten)]
+    pub mode: Mode,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Mode {
+    Tcp {
+        address: SocketListenAddr,
+        tls: Option<TlsConfig>,
+        #[serde(default = "default_shutdown_timeout_secs")]
+        shutdown_timeout_secs: u64,
+    },
+    Udp {
+        address: SocketAddr,
+        #[serde(default = "default_chunk_timeout_secs")]
+        chunk_timeout_secs: u64,
+        #[serde(default = "default_max_chunked_bytes")]
+        max_chunked_bytes: usize,
+    },
+}
+
+fn default_max_length() -> usize {
+    bytesize::mib(1u64) as usize
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_chunk_timeout_secs() -> u64 {
+    5
+}
+
+fn default_max_chunked_bytes() -> usize {
+    bytesize::mib(32u64) as usize
+}
+
+impl GelfConfig {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            max_length: default_max_length(),
+        }
+    }
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<GelfConfig>("gelf")
+}
+
+#[typetag::serde(name = "gelf")]
+impl SourceConfig for GelfConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        match self.mode.clone() {
+            Mode::Tcp {
+                address,
+                tls,
+                shutdown_timeout_secs,
+            } => {
+                let source = GelfTcpSource {
+                    max_length: self.max_length,
+                };
+                let tls = MaybeTlsSettings::from_config(&tls, true)?;
+                source.run(address, shutdown_timeout_secs, tls, shutdown, out)
+            }
+            Mode::Udp {
+                address,
+                chunk_timeout_secs,
+                max_chunked_bytes,
+            } => {
+                let chunks =
+                    Chunks::new(Duration::from_secs(chunk_timeout_secs), max_chunked_bytes);
+                Ok(udp(address, self.max_length, chunks, shutdown, out))
+            }
+        }
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "gelf"
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GelfTcpSource {
+    max_length: usize,
+}
+
+impl TcpSource for GelfTcpSource {
+    type Decoder = BytesDelimitedCodec;
+
+    fn decoder(&self) -> Self::Decoder {
+        BytesDelimitedCodec::new_with_max_length(b'\0', self.max_length)
+    }
+
+    fn build_event(&self, frame: Bytes, host: Bytes) -> Option<Event> {
+        build_event(&frame, self.max_length, host)
+    }
+}
+
+fn udp(
+    address: SocketAddr,
+    max_length: usize,
+    mut chunks: Chunks,
+    shutdown: ShutdownSignal,
+    out: mpsc::Sender<Event>,
+) -> super::Source {
+    let out = out.sink_map_err(|e| error!("error sending event: {:?}", e));
+
+    Box::new(
+        future::lazy(move || {
+            let socket = UdpSocket::bind(&address).expect("failed to bind to udp listener socket");
+
+            info!(message = "listening.", %address);
+
+            Ok(socket)
+        })
+        .and_then(move |socket| {
+            UdpFramed::new(socket, BytesCodec::new())
+                .take_until(shutdown)
+                .map_err(|error: io::Error| emit!(GelfUdpSocketError { error }))
+                .filter_map(move |(datagram, addr)| {
+                    let payload = chunks.insert(datagram.freeze(), Instant::now())?;
+                    build_event(&payload, max_length, addr.ip().to_string().into())
+                })
+                .forward(out)
+                .map(|_| ())
+        }),
+    )
+}
+
+/// Decodes a GELF message, using the peer's address as the host if the
+/// message has none.
+fn build_event(payload: &[u8], max_length: usize, host: Bytes) -> Option<Event> {
+    match gelf::decode(payload, max_length) {
+        Ok(mut log) => {
+            emit!(GelfMessageReceived {
+                byte_size: payload.len()
+            });
+            let host_key = event::log_schema().host_key();
+            if !log.contains(host_key) {
+                log.insert(host_key.clone(), host);
+            }
+            Some(log.into())
+        }
+        Err(error) => {
+            emit!(GelfDecodeError { error });
+            None
+        }
+    }
+}
+
+/// Reassembles chunked UDP messages. Messages which don't complete within
+/// `timeout` are dropped, and so are the oldest messages when the chunks
+/// held, along with the bookkeeping for each message, would exceed
+/// `max_bytes`.
+struct Chunks {
+    timeout: Duration,
+    max_bytes: usize,
+    bytes: usize,
+    messages: HashMap<[u8; 8], PartialMessage>,
+    /// Message ids in the order their first chunks arrived.
+    arrivals: VecDeque<([u8; 8], Instant)>,
+}
+
+struct PartialMessage {
+    started: Instant,
+    chunks: Vec<Option<Bytes>>,
+    received: usize,
+    bytes: usize,
+}
+
+impl Chunks {
+    fn new(timeout: Duration, max_bytes: usize) -> Self {
+        Self {
+            timeout,
+            max_bytes,
+            bytes: 0,
+            messages: HashMap::new(),
+            arrivals: VecDeque::new(),
+        }
+    }
+
+    /// Takes a datagram and returns the message it completes, if any.
+    /// Datagrams which aren't chunks are whole messages.
+    fn insert(&mut self, datagram: Bytes, now: Instant) -> Option<Bytes> {
+        self.expire(now);
+
+        if !datagram.starts_with(&CHUNK_MAGIC) {
+            return Some(datagram);
+        }
+        if datagram.len() < CHUNK_HEADER_LENGTH {
+            emit!(GelfChunkDropped {
+                reason: "chunk header is truncated"
+            });
+            return None;
+        }
+
+        let mut id = [0; 8];
+        id.copy_from_slice(&datagram[2..10]);
+        let sequence = datagram[10];
+        let count = datagram[11];
+        if count == 0 || count > MAX_CHUNKS || sequence >= count {
+            emit!(GelfChunkDropped {
+                reason: "invalid chunk sequence"
+            });
+            return None;
+        }
+        let chunk = datagram.slice_from(CHUNK_HEADER_LENGTH);
+        if chunk.len() > self.max_bytes {
+            emit!(GelfChunkDropped {
+                reason: "chunk is larger than max_chunked_bytes"
+            });
+            return None;
+        }
+        if count == 1 {
+            return Some(chunk);
+        }
+
+        let overhead = message_overhead(count);
+        if self.messages.contains_key(&id) {
+            self.evict(chunk.len());
+        } else {
+            self.evict(chunk.len() + overhead);
+        }
+
+        let arrivals = &mut self.arrivals;
+        let bytes = &mut self.bytes;
+        let message = self.messages.entry(id).or_insert_with(|| {
+            arrivals.push_back((id, now));
+            *bytes += overhead;
+            PartialMessage {
+                started: now,
+                chunks: vec![None; count as usize],
+                received: 0,
+                bytes: overhead,
+            }
+        });
+        if message.chunks.len() != count as usize {
+            emit!(GelfChunkDropped {
+                reason: "chunk count differs from earlier chunks"
+            });
+            return None;
+        }
+        if message.chunks[sequence as usize].is_some() {
+            return None;
+        }
+        message.received += 1;
+        message.bytes += chunk.len();
+        self.bytes += chunk.len();
+        message.chunks[sequence as usize] = Some(chunk);
+
+        if message.received < message.chunks.len() {
+            return None;
+        }
+
+        let message = self.messages.remove(&id).unwrap();
+        self.bytes -= message.bytes;
+        let mut payload = Vec::with_capacity(message.bytes - overhead);
+        for chunk in message.chunks.into_iter().flatten() {
+            payload.extend_from_slice(&chunk);
+        }
+        Some(payload.into())
+    }
+
+    /// Drops messages whose first chunk arrived more than `timeout` ago.
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(id, started)) = self.arrivals.front() {
+            if self.is_pending(&id, started) {
+                if now.duration_since(started) < self.timeout {
+                    break;
+                }
+                self.remove(&id);
+                emit!(GelfChunkDropped {
+                    reason: "message timed out before all its chunks arrived"
+                });
+            }
+            self.arrivals.pop_front();
+        }
+    }
+
+    /// Drops the oldest messages until `incoming` more bytes fit.
+    fn evict(&mut self, incoming: usize) {
+        while self.bytes + incoming > self.max_bytes {
+            let (id, started) = match self.arrivals.pop_front() {
+                Some(arrival) => arrival,
+                None => break,
+            };
+            if self.is_pending(&id, started) {
+                self.remove(&id);
+                emit!(GelfChunkDropped {
+                    reason: "chunks exceeded max_chunked_bytes"
+                });
+            }
+        }
+    }
+
+    /// Whether the message which arrived at `started` is still incomplete,
+    /// as the id may since have been reused.
+    fn is_pending(&self, id: &[u8; 8], started: Instant) -> bool {
+        self.messages
+            .get(id)
+            .map_or(false, |message| message.started == started)
+    }
+
+    fn remove(&mut self, id: &[u8; 8]) {
+        if let Some(message) = self.messages.remove(id) {
+            self.bytes -= message.bytes;
+        }
+    }
+}
+
+/// The memory held for a message of `count` chunks besides the chunks
+/// themselves, so that empty chunks still count against `max_bytes`.
+fn message_overhead(count: u8) -> usize {
+    mem::size_of::<([u8; 8], PartialMessage)>()
+        + mem::size_of::<([u8; 8], Instant)>()
+        + count as usize * mem::size_of::<Option<Bytes>>()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{message_overhead, Chunks, GelfConfig, Mode};
+    use crate::{
+        event::{self, Event, Value},
+        runtime,
+        shutdown::ShutdownSignal,
+        test_util::{collect_n, next_addr, wait_for_tcp},
+        topology::config::{GlobalOptions, SourceConfig},
+    };
+    use bytes::Bytes;
+    use flate2::{write::ZlibEncoder, Compression};
+    use futures01::sync::mpsc;
+    use std::{
+        io::Write,
+        net::{SocketAddr, TcpStream, UdpSocket},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    const MESSAGE: &str =
+        r#"{"version":"1.1","host":"example.org","short_message":"hello","_user_id":42}"#;
+
+    fn chunk(id: u8, sequence: u8, count: u8, data: &[u8]) -> Bytes {
+        let mut chunk = vec![0x1e, 0x0f, id, 0, 0, 0, 0, 0, 0, 0, sequence, count];
+        chunk.extend_from_slice(data);
+        chunk.into()
+    }
+
+    fn build(mode: Mode) -> (runtime::Runtime, mpsc::Receiver<Event>) {
+        let (tx, rx) = mpsc::channel(10);
+        let source = GelfConfig::new(mode)
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .unwrap();
+        let mut rt = runtime::Runtime::new().unwrap();
+        rt.spawn(source);
+        (rt, rx)
+    }
+
+    fn udp_mode(address: SocketAddr) -> Mode {
+        Mode::Udp {
+            address,
+            chunk_timeout_secs: 5,
+            max_chunked_bytes: 1024,
+        }
+    }
+
+    #[test]
+    fn passes_whole_messages_through() {
+        let mut chunks = Chunks::new(Duration::from_secs(5), 1024);
+
+        assert_eq!(
+            chunks.insert(Bytes::from(MESSAGE), Instant::now()),
+            Some(Bytes::from(MESSAGE))
+        );
+        assert_eq!(
+            chunks.insert(chunk(1, 0, 1, b"single"), Instant::now()),
+            Some(Bytes::from("single"))
+        );
+    }
+
+    #[test]
+    fn reassembles_chunks_in_any_order() {
+        let mut chunks = Chunks::new(Duration::from_secs(5), 1024);
+        let now = Instant::now();
+
+        assert_eq!(chunks.insert(chunk(1, 2, 3, b"c"), now), None);
+        assert_eq!(chunks.insert(chunk(2, 0, 2, b"x"), now), None);
+        assert_eq!(chunks.insert(chunk(1, 0, 3, b"a"), now), None);
+        assert_eq!(chunks.insert(chunk(1, 0, 3, b"a"), now), None);
+        assert_eq!(
+            chunks.insert(chunk(1, 1, 3, b"b"), now),
+            Some(Bytes::from("abc"))
+        );
+        assert_eq!(
+            chunks.insert(chunk(2, 1, 2, b"y"), now),
+            Some(Bytes::from("xy"))
+        );
+        assert!(chunks.messages.is_empty());
+        assert_eq!(chunks.bytes, 0);
+    }
+
+    #[test]
+    fn drops_invalid_chunks() {
+        let mut chunks = Chunks::new(Duration::from_secs(5), 1024);
+        let now = Instant::now();
+
+        assert_eq!(chunks.insert(Bytes::from(&[0x1e, 0x0f, 1][..]), now), None);
+        assert_eq!(chunks.insert(chunk(1, 0, 0, b"a"), now), None);
+        assert_eq!(chunks.insert(chunk(1, 2, 2, b"a"), now), None);
+        assert_eq!(chunks.insert(chunk(1, 0, 129, b"a"), now), None);
+        assert_eq!(chunks.insert(chunk(1, 0, 2, b"a"), now), None);
+        assert_eq!(chunks.insert(chunk(1, 1, 3, b"b"), now), None);
+        assert_eq!(
+            chunks.insert(chunk(1, 1, 2, b"b"), now),
+            Some(Bytes::from("ab"))
+        );
+    }
+
+    #[test]
+    fn expires_incomplete_messages() {
+        let mut chunks = Chunks::new(Duration::from_secs(5), 1024);
+        let now = Instant::now();
+
+        assert_eq!(chunks.insert(chunk(1, 0, 2, b"a"), now), None);
+        assert_eq!(
+            chunks.insert(chunk(2, 0, 2, b"x"), now + Duration::from_secs(6)),
+            None
+        );
+        assert_eq!(chunks.bytes, message_overhead(2) + 1);
+        assert_eq!(
+            chunks.insert(chunk(1, 1, 2, b"b"), now + Duration::from_secs(6)),
+            None
+        );
+        assert_eq!(
+            chunks.insert(chunk(2, 1, 2, b"y"), now + Duration::from_secs(7)),
+            Some(Bytes::from("xy"))
+        );
+    }
+
+    #[test]
+    fn evicts_oldest_messages_over_budget() {
+        let max_bytes = 2 * (message_overhead(2) + 2);
+        let mut chunks = Chunks::new(Duration::from_secs(5), max_bytes);
+        let now = Instant::now();
+
+        assert_eq!(chunks.insert(chunk(1, 0, 2, b"aa"), now), None);
+        assert_eq!(chunks.insert(chunk(2, 0, 2, b"xx"), now), None);
+        assert_eq!(chunks.insert(chunk(3, 0, 2, b"mm"), now), None);
+        assert_eq!(chunks.bytes, max_bytes);
+        assert_eq!(chunks.insert(chunk(1, 1, 2, b"bb"), now), None);
+        assert_eq!(
+            chunks.insert(chunk(1, 0, 2, b"aa"), now),
+            Some(Bytes::from("aabb"))
+        );
+        assert_eq!(chunks.bytes, 0);
+        assert_eq!(chunks.insert(chunk(3, 1, 2, b"nn"), now), None);
+        let too_long = vec![b'x'; max_bytes + 1];
+        assert_eq!(chunks.insert(chunk(4, 0, 1, &too_long), now), None);
+    }
+
+    #[test]
+    fn counts_empty_chunks_against_budget() {
+        let max_bytes = 10 * message_overhead(128);
+        let mut chunks = Chunks::new(Duration::from_secs(5), max_bytes);
+        let now = Instant::now();
+
+        for id in 0..=255 {
+            assert_eq!(chunks.insert(chunk(id, 0, 128, b""), now), None);
+            assert!(chunks.bytes <= max_bytes);
+        }
+        assert_eq!(chunks.messages.len(), 10);
+    }
+
+    #[test]
+    fn receives_chunked_compressed_udp_messages() {
+        let address = next_addr();
+        let (mut rt, rx) = build(udp_mode(address));
+        thread::sleep(Duration::from_millis(100));
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(MESSAGE.as_bytes()).unwrap();
+        let zlib = zlib.finish().unwrap();
+        let (first, second) = zlib.split_at(zlib.len() / 2);
+
+        let socket = UdpSocket::bind(next_addr()).unwrap();
+        socket.send_to(&chunk(7, 1, 2, second), address).unwrap();
+        thread::sleep(Duration::from_millis(1));
+        socket.send_to(&chunk(7, 0, 2, first), address).unwrap();
+        thread::sleep(Duration::from_millis(1));
+        socket
+            .send_to(br#"{"short_message":"plain"}"#, address)
+            .unwrap();
+
+        let events = rt.block_on(collect_n(rx, 2)).ok().unwrap();
+        let first = events[0].as_log();
+        assert_eq!(first[&event::log_schema().message_key()], "hello".into());
+        assert_eq!(first[&event::log_schema().host_key()], "example.org".into());
+        assert_eq!(first[&"user_id".into()], Value::Integer(42));
+        let second = events[1].as_log();
+        assert_eq!(second[&event::log_schema().message_key()], "plain".into());
+        assert_eq!(second[&event::log_schema().host_key()], "127.0.0.1".into());
+    }
+
+    #[test]
+    fn receives_null_delimited_tcp_messages() {
+        let address = next_addr();
+        let (mut rt, rx) = build(Mode::Tcp {
+            address: address.into(),
+            tls: None,
+            shutdown_timeout_secs: 30,
+        });
+        wait_for_tcp(address);
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(format!("{}\0not json\0{}\0", MESSAGE, MESSAGE).as_bytes())
+            .unwrap();
+
+        let events = rt.block_on(collect_n(rx, 2)).ok().unwrap();
+        for event in events {
+            assert_eq!(
+                event.as_log()[&event::log_schema().message_key()],
+                "hello".into()
+            );
+        }
+    }
+
+    #[cfg(feature = "sinks-socket")]
+    #[test]
+    fn it_works_with_gelf_socket_sink() {
+        use crate::{
+            sinks::socket::SocketSinkConfig,
+            test_util::CollectCurrent,
+            topology::config::{SinkConfig, SinkContext},
+        };
+        use chrono::{TimeZone, Utc};
+        use futures01::{stream, Future, Sink};
+
+        let address = next_addr();
+        let (mut rt, rx) = build(Mode::Tcp {
+            address: address.into(),
+            tls: None,
+            shutdown_timeout_secs: 30,
+        });
+        wait_for_tcp(address);
+
+        let sink: SocketSinkConfig = toml::from_str(&format!(
+            r#"
+            address = "{}"
+            mode = "tcp"
+            encoding = "gelf"
+            "#,
+            address
+        ))
+        .unwrap();
+        let (sink, _) = sink.build(SinkContext::new_test(rt.executor())).unwrap();
+
+        let mut event = Event::from("hello");
+        let log = event.as_mut_log();
+        log.insert(event::log_schema().host_key().clone(), "web-1");
+        log.insert(
+            event::log_schema().timestamp_key().clone(),
+            Utc.timestamp(1_500_000_000, 123_000_000),
+        );
+        log.insert("level", 6);
+        log.insert("request.path", "/");
+        let _ = rt
+            .block_on(sink.send_all(stream::iter_ok(vec![event.clone()])))
+            .unwrap();
+
+        thread::sleep(Duration::from_millis(50));
+
+        let (_, output) = CollectCurrent::new(rx).wait().unwrap();
+        assert_eq!(output.len(), 1);
+        let mut output = output.into_iter().next().unwrap();
+        output.as_mut_log().remove(&"version".into());
+        assert_eq!(output, event);
+    }
+}
@@ -0,0 +1,833 @@
+//! Collects metrics about the host Vector runs on from `/proc` and `/sys`.
+
+use crate::{
+    event::metric::{Metric, MetricKind, MetricValue},
+    internal_events::HostMetricsCollectError,
+    shutdown::ShutdownSignal,
+    stream::StreamExt,
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+    Event,
+};
+use chrono::{DateTime, Utc};
+use futures01::{stream, sync::mpsc, Future, Sink, Stream};
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio01::timer::Interval;
+
+/// `/proc/stat` reports CPU times in units of `USER_HZ`, which is 100 on
+/// every Linux platform.
+const USER_HZ: f64 = 100.0;
+
+/// `/proc/diskstats` reports sectors of 512 bytes, whatever the device's
+/// actual sector size.
+const SECTOR_SIZE: f64 = 512.0;
+
+const CPU_MODES: &[&str] = &[
+    "user", "nice", "system", "idle", "iowait", "irq", "softirq", "steal",
+];
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid filter pattern {:?}: {}", pattern, source))]
+    InvalidPattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct HostMetricsConfig {
+    #[serde(default = "default_scrape_interval_secs")]
+    scrape_interval_secs: u64,
+    #[serde(default = "default_collectors")]
+    collectors: Vec<Collector>,
+    #[serde(default = "default_namespace")]
+    namespace: String,
+    #[serde(default = "default_procfs_root")]
+    procfs_root: PathBuf,
+    #[serde(default = "default_sysfs_root")]
+    sysfs_root: PathBuf,
+    #[serde(default)]
+    disk: DiskConfig,
+    #[serde(default)]
+    filesystem: FilesystemConfig,
+    #[serde(default)]
+    network: NetworkConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Collector {
+    Cpu,
+    Disk,
+    Filesystem,
+    Load,
+    Memory,
+    Network,
+    Swap,
+}
+
+impl Collector {
+    fn name(self) -> &'static str {
+        match self {
+            Collector::Cpu => "cpu",
+            Collector::Disk => "disk",
+            Collector::Filesystem => "filesystem",
+            Collector::Load => "load",
+            Collector::Memory => "memory",
+            Collector::Network => "network",
+            Collector::Swap => "swap",
+        }
+    }
+}
+
+/// Glob patterns a name must match one of, if any are given, and must not
+/// match any of.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FilterList {
+    #[serde(default)]
+    includes: Vec<String>,
+    #[serde(default)]
+    excludes: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DiskConfig {
+    #[serde(default)]
+    devices: FilterList,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            devices: FilterList {
+                includes: vec![],
+                excludes: vec!["loop*".into(), "ram*".into()],
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FilesystemConfig {
+    #[serde(default)]
+    devices: FilterList,
+    #[serde(default)]
+    mountpoints: FilterList,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    #[serde(default)]
+    devices: FilterList,
+}
+
+pub fn default_scrape_interval_secs() -> u64 {
+    15
+}
+
+pub fn default_collectors() -> Vec<Collector> {
+    vec![
+        Collector::Cpu,
+        Collector::Disk,
+        Collector::Filesystem,
+        Collector::Load,
+        Collector::Memory,
+        Collector::Network,
+        Collector::Swap,
+    ]
+}
+
+pub fn default_namespace() -> String {
+    "host".into()
+}
+
+pub fn default_procfs_root() -> PathBuf {
+    "/proc".into()
+}
+
+pub fn default_sysfs_root() -> PathBuf {
+    "/sys".into()
+}
+
+impl Default for HostMetricsConfig {
+    fn default() -> Self {
+        Self {
+            scrape_interval_secs: default_scrape_interval_secs(),
+            collectors: default_collectors(),
+            namespace: default_namespace(),
+            procfs_root: default_procfs_root(),
+            sysfs_root: default_sysfs_root(),
+            disk: DiskConfig::default(),
+            filesystem: FilesystemConfig::default(),
+            network: NetworkConfig::default(),
+        }
+    }
+}
+
+inventory::submit! {
+    SourceDescription::new::<HostMetricsConfig>("host_metrics")
+}
+
+#[typetag::serde(name = "host_metrics")]
+impl SourceConfig for HostMetricsConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        let host_metrics = HostMetrics::new(self.clone(), hostname::get_hostname())?;
+
+        let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
+        let interval = Duration::from_secs(self.scrape_interval_secs);
+
+        let task = Interval::new(Instant::now(), interval)
+            .take_until(shutdown)
+            .map_err(|e| error!("timer error: {:?}", e))
+            .map(move |_| stream::iter_ok(host_metrics.capture_metrics()))
+            .flatten()
+            .forward(out)
+            .map(|_| ());
+
+        Ok(Box::new(task))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn source_type(&self) -> &'static str {
+        "host_metrics"
+    }
+}
+
+struct Filter {
+    includes: Vec<Pattern>,
+    excludes: Vec<Pattern>,
+}
+
+impl Filter {
+    fn new(list: &FilterList) -> Result<Self, BuildError> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| Pattern::new(pattern).context(InvalidPattern { pattern }))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            includes: compile(&list.includes)?,
+            excludes: compile(&list.excludes)?,
+        })
+    }
+
+    fn is_match(&self, value: &str) -> bool {
+        (self.includes.is_empty() || self.includes.iter().any(|p| p.matches(value)))
+            && !self.excludes.iter().any(|p| p.matches(value))
+    }
+}
+
+struct HostMetrics {
+    config: HostMetricsConfig,
+    host: Option<String>,
+    disk_devices: Filter,
+    filesystem_devices: Filter,
+    filesystem_mountpoints: Filter,
+    network_devices: Filter,
+}
+
+impl HostMetrics {
+    fn new(config: HostMetricsConfig, host: Option<String>) -> Result<Self, BuildError> {
+        Ok(Self {
+            disk_devices: Filter::new(&config.disk.devices)?,
+            filesystem_devices: Filter::new(&config.filesystem.devices)?,
+            filesystem_mountpoints: Filter::new(&config.filesystem.mountpoints)?,
+            network_devices: Filter::new(&config.network.devices)?,
+            config,
+            host,
+        })
+    }
+
+    /// Runs every enabled collector. A collector that fails is skipped
+    /// until the next scrape.
+    fn capture_metrics(&self) -> Vec<Event> {
+        let timestamp = Utc::now();
+        let mut metrics = Vec::new();
+
+        for &collector in &self.config.collectors {
+            let result = match collector {
+                Collector::Cpu => self.cpu_metrics(timestamp),
+                Collector::Disk => self.disk_metrics(timestamp),
+                Collector::Filesystem => self.filesystem_metrics(timestamp),
+                Collector::Load => self.load_metrics(timestamp),
+                Collector::Memory => self.memory_metrics(timestamp),
+                Collector::Network => self.network_metrics(timestamp),
+                Collector::Swap => self.swap_metrics(timestamp),
+            };
+            match result {
+                Ok(collected) => metrics.extend(collected.into_iter().map(Event::Metric)),
+                Err(error) => {
+                    emit!(HostMetricsCollectError {
+                        collector: collector.name(),
+                        error,
+                    });
+                }
+            }
+        }
+
+        metrics
+    }
+
+    fn cpu_metrics(&self, timestamp: DateTime<Utc>) -> io::Result<Vec<Metric>> {
+        let stat = fs::read_to_string(self.config.procfs_root.join("stat"))?;
+
+        let mut metrics = Vec::new();
+        for line in stat.lines() {
+            let mut fields = line.split_whitespace();
+            // The aggregate `cpu` line is left out, as it is the sum of the
+            // per CPU lines.
+            let cpu = match fields.next() {
+                Some(name) if name.starts_with("cpu") && name.len() > 3 => &name[3..],
+                _ => continue,
+            };
+            for (mode, value) in CPU_MODES.iter().zip(fields) {
+                if let Ok(value) = value.parse::<f64>() {
+                    metrics.push(self.counter(
+                        "cpu_seconds_total",
+                        timestamp,
+                        &[("cpu", cpu), ("mode", mode)],
+                        value / USER_HZ,
+                    ));
+                }
+            }
+        }
+        Ok(metrics)
+    }
+
+    fn disk_metrics(&self, timestamp: DateTime<Utc>) -> io::Result<Vec<Metric>> {
+        let diskstats = fs::read_to_string(self.config.procfs_root.join("diskstats"))?;
+
+        let mut metrics = Vec::new();
+        for line in diskstats.lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.len() < 14 || !self.disk_devices.is_match(fields[2]) {
+                continue;
+            }
+            let tags = [("device", fields[2])];
+            let field = |index: usize| fields[index].parse::<f64>().unwrap_or_default();
+
+            metrics.extend(vec![
+                self.counter("disk_reads_completed_total", timestamp, &tags, field(3)),
+                self.counter(
+                    "disk_read_bytes_total",
+                    timestamp,
+                    &tags,
+                    field(5) * SECTOR_SIZE,
+                ),
+                self.counter(
+                    "disk_read_time_seconds_total",
+                    timestamp,
+                    &tags,
+                    field(6) / 1000.0,
+                ),
+                self.counter("disk_writes_completed_total", timestamp, &tags, field(7)),
+                self.counter(
+                    "disk_written_bytes_total",
+                    timestamp,
+                    &tags,
+                    field(9) * SECTOR_SIZE,
+                ),
+                self.counter(
+                    "disk_write_time_seconds_total",
+                    timestamp,
+                    &tags,
+                    field(10) / 1000.0,
+                ),
+                self.counter(
+                    "disk_io_time_seconds_total",
+                    timestamp,
+                    &tags,
+                    field(12) / 1000.0,
+                ),
+            ]);
+        }
+        Ok(metrics)
+    }
+
+    fn filesystem_metrics(&self, timestamp: DateTime<Utc>) -> io::Result<Vec<Metric>> {
+        let mounts = fs::read_to_string(self.config.procfs_root.join("mounts"))?;
+
+        let mut metrics = Vec::new();
+        for line in mounts.lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.len() < 3 {
+                continue;
+            }
+            let device = unescape_mount_field(fields[0]);
+            let mountpoint = unescape_mount_field(fields[1]);
+            if !self.filesystem_devices.is_match(&device)
+                || !self.filesystem_mountpoints.is_match(&mountpoint)
+            {
+                continue;
+            }
+
+            // Mounts we may not look into, such as those of other users'
+            // FUSE filesystems, are skipped rather than failing the rest.
+            let stat = match nix::sys::statvfs::statvfs(Path::new(&mountpoint)) {
+                Ok(stat) => stat,
+                Err(_) => continue,
+            };
+            // Pseudo filesystems like `proc` and `sysfs` have no blocks.
+            if stat.blocks() == 0 {
+                continue;
+            }
+
+            let fragment_size = stat.fragment_size() as f64;
+            let total = stat.blocks() as f64 * fragment_size;
+            let free = stat.blocks_free() as f64 * fragment_size;
+            let available = stat.blocks_available() as f64 * fragment_size;
+            let tags = [
+                ("device", device.as_str()),
+                ("mountpoint", mountpoint.as_str()),
+                ("filesystem", fields[2]),
+            ];
+
+            metrics.extend(vec![
+                self.gauge("filesystem_total_bytes", timestamp, &tags, total),
+                self.gauge("filesystem_free_bytes", timestamp, &tags, free),
+                self.gauge("filesystem_available_bytes", timestamp, &tags, available),
+                self.gauge("filesystem_used_bytes", timestamp, &tags, total - free),
+            ]);
+        }
+        Ok(metrics)
+    }
+
+    fn load_metrics(&self, timestamp: DateTime<Utc>) -> io::Result<Vec<Metric>> {
+        let loadavg = fs::read_to_string(self.config.procfs_root.join("loadavg"))?;
+
+        Ok(["load1", "load5", "load15"]
+            .iter()
+            .zip(loadavg.split_whitespace())
+            .filter_map(|(name, value)| {
+                let value = value.parse().ok()?;
+                Some(self.gauge(name, timestamp, &[], value))
+            })
+            .collect())
+    }
+
+    fn memory_metrics(&self, timestamp: DateTime<Utc>) -> io::Result<Vec<Metric>> {
+        let meminfo = self.meminfo()?;
+
+        let mut metrics = [
+            ("memory_total_bytes", "MemTotal"),
+            ("memory_free_bytes", "MemFree"),
+            ("memory_available_bytes", "MemAvailable"),
+            ("memory_buffers_bytes", "Buffers"),
+            ("memory_cached_bytes", "Cached"),
+        ]
+        .iter()
+        .filter_map(|(name, field)| {
+            let value = *meminfo.get(*field)?;
+            Some(self.gauge(name, timestamp, &[], value))
+        })
+        .collect::<Vec<_>>();
+
+        if let (Some(total), Some(free)) = (meminfo.get("MemTotal"), meminfo.get("MemFree")) {
+            let buffers = meminfo.get("Buffers").cloned().unwrap_or_default();
+            let cached = meminfo.get("Cached").cloned().unwrap_or_default();
+            let used = (total - free - buffers - cached).max(0.0);
+            metrics.push(self.gauge("memory_used_bytes", timestamp, &[], used));
+        }
+        Ok(metrics)
+    }
+
+    fn swap_metrics(&self, timestamp: DateTime<Utc>) -> io::Result<Vec<Metric>> {
+        let meminfo = self.meminfo()?;
+
+        let mut metrics = Vec::new();
+        if let (Some(&total), Some(&free)) = (meminfo.get("SwapTotal"), meminfo.get("SwapFree")) {
+            metrics.push(self.gauge("swap_total_bytes", timestamp, &[], total));
+            metrics.push(self.gauge("swap_free_bytes", timestamp, &[], free));
+            metrics.push(self.gauge("swap_used_bytes", timestamp, &[], total - free));
+        }
+        Ok(metrics)
+    }
+
+    fn network_metrics(&self, timestamp: DateTime<Utc>) -> io::Result<Vec<Metric>> {
+        let counters = [
+            ("network_receive_bytes_total", "rx_bytes"),
+            ("network_receive_packets_total", "rx_packets"),
+            ("network_receive_errors_total", "rx_errors"),
+            ("network_receive_drop_total", "rx_dropped"),
+            ("network_transmit_bytes_total", "tx_bytes"),
+            ("network_transmit_packets_total", "tx_packets"),
+            ("network_transmit_errors_total", "tx_errors"),
+            ("network_transmit_drop_total", "tx_dropped"),
+        ];
+
+        let mut devices = fs::read_dir(self.config.sysfs_root.join("class/net"))?
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|device| self.network_devices.is_match(device))
+            .collect::<Vec<_>>();
+        devices.sort();
+
+        let mut metrics = Vec::new();
+        for device in devices {
+            let statistics = self
+                .config
+                .sysfs_root
+                .join("class/net")
+                .join(&device)
+                .join("statistics");
+            for (name, file) in counters.iter() {
+                let value = fs::read_to_string(statistics.join(file))
+                    .ok()
+                    .and_then(|value| value.trim().parse().ok());
+                if let Some(value) = value {
+                    metrics.push(self.counter(
+                        name,
+                        timestamp,
+                        &[("device", device.as_str())],
+                        value,
+                    ));
+                }
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// Reads `/proc/meminfo` into byte values keyed by field name.
+    fn meminfo(&self) -> io::Result<HashMap<String, f64>> {
+        let meminfo = fs::read_to_string(self.config.procfs_root.join("meminfo"))?;
+
+        Ok(meminfo
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, ':');
+                let name = parts.next()?;
+                let mut value = parts.next()?.split_whitespace();
+                let number = value.next()?.parse::<f64>().ok()?;
+                let bytes = match value.next() {
+                    Some("kB") => number * 1024.0,
+                    _ => number,
+                };
+                Some((name.to_owned(), bytes))
+            })
+            .collect())
+    }
+
+    fn counter(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        tags: &[(&str, &str)],
+        value: f64,
+    ) -> Metric {
+        self.metric(name, timestamp, tags, MetricValue::Counter { value })
+    }
+
+    fn gauge(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        tags: &[(&str, &str)],
+        value: f64,
+    ) -> Metric {
+        self.metric(name, timestamp, tags, MetricValue::Gauge { value })
+    }
+
+    fn metric(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        tags: &[(&str, &str)],
+        value: MetricValue,
+    ) -> Metric {
+        let mut tags = tags
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        if let Some(host) = &self.host {
+            tags.insert("host".into(), host.clone());
+        }
+
+        let name = if self.config.namespace.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}_{}", self.config.namespace, name)
+        };
+
+        Metric {
+            name,
+            timestamp: Some(timestamp),
+            tags: if !tags.is_empty() { Some(tags) } else { None },
+            kind: MetricKind::Absolute,
+            value,
+        }
+    }
+}
+
+/// Undoes the octal escaping `/proc/mounts` applies to spaces, tabs,
+/// newlines and backslashes in device names and mountpoints.
+fn unescape_mount_field(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let escape = rest.get(index + 1..index + 4);
+        match escape.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{unescape_mount_field, Collector, HostMetrics, HostMetricsConfig};
+    use crate::{
+        event::metric::{Metric, MetricValue},
+        test_util::temp_dir,
+    };
+    use std::{collections::BTreeMap, fs, path::Path};
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// Builds a fake `/proc` and `/sys` under a temporary directory.
+    fn fake_root() -> HostMetricsConfig {
+        let root = temp_dir();
+        write(
+            &root,
+            "proc/stat",
+            "cpu  300 0 200 1000 0 0 0 0 0 0\n\
+             cpu0 100 0 100 500 0 0 0 0 0 0\n\
+             cpu1 200 0 100 500 0 0 0 0 0 0\n\
+             intr 12345 0 0\n\
+             ctxt 67890\n",
+        );
+        write(&root, "proc/loadavg", "0.25 0.50 1.00 1/120 4242\n");
+        write(
+            &root,
+            "proc/meminfo",
+            "MemTotal:        2048 kB\n\
+             MemFree:          512 kB\n\
+             MemAvailable:    1024 kB\n\
+             Buffers:          128 kB\n\
+             Cached:           256 kB\n\
+             SwapTotal:       1024 kB\n\
+             SwapFree:         768 kB\n\
+             HugePages_Total:    0\n",
+        );
+        write(
+            &root,
+            "proc/diskstats",
+            "   7       0 loop0 10 0 20 1 0 0 0 0 0 1 1\n\
+                8       0 sda 100 5 2000 1500 50 7 1000 2500 0 3000 4000\n",
+        );
+        write(
+            &root,
+            "proc/mounts",
+            &format!(
+                "proc /proc proc rw 0 0\n/dev/fake {} ext4 rw,relatime 0 0\n",
+                root.display()
+            ),
+        );
+        write(&root, "sys/class/net/eth0/statistics/rx_bytes", "1000\n");
+        write(&root, "sys/class/net/eth0/statistics/tx_bytes", "2000\n");
+        write(&root, "sys/class/net/lo/statistics/rx_bytes", "10\n");
+
+        HostMetricsConfig {
+            procfs_root: root.join("proc"),
+            sysfs_root: root.join("sys"),
+            ..HostMetricsConfig::default()
+        }
+    }
+
+    fn collect(config: HostMetricsConfig, collectors: &[Collector]) -> Vec<Metric> {
+        let config = HostMetricsConfig {
+            collectors: collectors.to_vec(),
+            ..config
+        };
+        HostMetrics::new(config, Some("myhost".into()))
+            .unwrap()
+            .capture_metrics()
+            .into_iter()
+            .map(|event| event.into_metric())
+            .collect()
+    }
+
+    /// Flattens metrics into `name{tags}` keys, leaving out the `host` tag.
+    fn values(metrics: &[Metric]) -> BTreeMap<String, f64> {
+        metrics
+            .iter()
+            .map(|metric| {
+                let tags = metric
+                    .tags
+                    .iter()
+                    .flatten()
+                    .filter(|(name, _)| *name != "host")
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect::<Vec<_>>();
+                let value = match metric.value {
+                    MetricValue::Counter { value } | MetricValue::Gauge { value } => value,
+                    ref other => panic!("unexpected value: {:?}", other),
+                };
+                (format!("{}{{{}}}", metric.name, tags.join(",")), value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_config() {
+        let config: HostMetricsConfig = toml::from_str(
+            r#"
+            collectors = ["cpu", "network"]
+            scrape_interval_secs = 5
+            [network.devices]
+            excludes = ["lo"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.collectors, vec![Collector::Cpu, Collector::Network]);
+        assert_eq!(config.scrape_interval_secs, 5);
+        assert_eq!(config.network.devices.excludes, vec!["lo".to_owned()]);
+        assert_eq!(config.disk.devices.excludes.len(), 2);
+    }
+
+    #[test]
+    fn collects_cpu_and_load() {
+        let metrics = collect(fake_root(), &[Collector::Cpu, Collector::Load]);
+        let values = values(&metrics);
+
+        assert_eq!(values["host_cpu_seconds_total{cpu=0,mode=user}"], 1.0);
+        assert_eq!(values["host_cpu_seconds_total{cpu=1,mode=idle}"], 5.0);
+        assert_eq!(values["host_load1{}"], 0.25);
+        assert_eq!(values["host_load15{}"], 1.0);
+        assert_eq!(values.len(), 2 * 8 + 3);
+        assert!(metrics
+            .iter()
+            .all(|metric| metric.tags.as_ref().unwrap()["host"] == "myhost"));
+        match metrics[0].value {
+            MetricValue::Counter { .. } => {}
+            ref other => panic!("unexpected value: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn collects_memory_and_swap() {
+        let values = values(&collect(fake_root(), &[Collector::Memory, Collector::Swap]));
+
+        assert_eq!(values["host_memory_total_bytes{}"], 2048.0 * 1024.0);
+        assert_eq!(values["host_memory_available_bytes{}"], 1024.0 * 1024.0);
+        assert_eq!(values["host_memory_used_bytes{}"], 1152.0 * 1024.0);
+        assert_eq!(values["host_swap_used_bytes{}"], 256.0 * 1024.0);
+        assert_eq!(values.len(), 6 + 3);
+    }
+
+    #[test]
+    fn collects_disks_matching_filters() {
+        let values = values(&collect(fake_root(), &[Collector::Disk]));
+
+        assert_eq!(values["host_disk_reads_completed_total{device=sda}"], 100.0);
+        assert_eq!(
+            values["host_disk_read_bytes_total{device=sda}"],
+            2000.0 * 512.0
+        );
+        assert_eq!(
+            values["host_disk_write_time_seconds_total{device=sda}"],
+            2.5
+        );
+        assert_eq!(values["host_disk_io_time_seconds_total{device=sda}"], 3.0);
+        assert_eq!(values.len(), 7);
+    }
+
+    #[test]
+    fn collects_network_matching_filters() {
+        let mut config = fake_root();
+        config.namespace = String::new();
+        config.network.devices.excludes = vec!["lo".into()];
+        let values = values(&collect(config, &[Collector::Network]));
+
+        assert_eq!(
+            values.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("network_receive_bytes_total{device=eth0}".into(), 1000.0),
+                ("network_transmit_bytes_total{device=eth0}".into(), 2000.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_filesystems() {
+        let config = fake_root();
+        let mountpoint = config.procfs_root.parent().unwrap().display().to_string();
+        let metrics = collect(config, &[Collector::Filesystem]);
+
+        // The `proc` mount has no blocks and is skipped.
+        assert_eq!(metrics.len(), 4);
+        let tags = metrics[0].tags.as_ref().unwrap();
+        assert_eq!(tags["device"], "/dev/fake");
+        assert_eq!(tags["mountpoint"], mountpoint);
+        assert_eq!(tags["filesystem"], "ext4");
+
+        let values = values(&metrics);
+        let key = |name| {
+            format!(
+                "{}{{device=/dev/fake,filesystem=ext4,mountpoint={}}}",
+                name, mountpoint
+            )
+        };
+        assert!(values[&key("host_filesystem_total_bytes")] > 0.0);
+        assert_eq!(
+            values[&key("host_filesystem_used_bytes")],
+            values[&key("host_filesystem_total_bytes")]
+                - values[&key("host_filesystem_free_bytes")]
+        );
+    }
+
+    #[test]
+    fn skips_failed_collectors() {
+        let config = HostMetricsConfig {
+            procfs_root: temp_dir(),
+            ..fake_root()
+        };
+        let metrics = collect(config, &[Collector::Cpu, Collector::Network]);
+
+        assert_eq!(metrics.len(), 3);
+    }
+
+    #[test]
+    fn unescapes_mount_fields() {
+        assert_eq!(unescape_mount_field("/mnt/my\\040disk"), "/mnt/my disk");
+        assert_eq!(unescape_mount_field("/mnt/a\\134b"), "/mnt/a\\b");
+        assert_eq!(unescape_mount_field("/mnt/odd\\"), "/mnt/odd\\");
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     event::{self, Event},
     shutdown::ShutdownSignal,
-    sources::util::{ErrorMessage, HttpSource},
+    sources::util::{Decoder, DecodingConfig, ErrorMessage, Framing, HttpSource},
     tls::TlsConfig,
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
 };
@@ -24,6 +24,8 @@ pub struct SimpleHttpConfig {
     #[serde(default)]
     headers: Vec<String>,
     tls: Option<TlsConfig>,
+    /// Takes precedence over `encoding` when set.
+    decoding: Option<DecodingConfig>,
 }
 
 inventory::submit! {
@@ -33,6 +35,7 @@ inventory::submit! {
 #[derive(Clone)]
 struct SimpleHttpSource {
     encoding: Encoding,
+    decoder: Option<Decoder>,
     headers: Vec<String>,
 }
 
@@ -52,8 +55,13 @@ impl HttpSource for SimpleHttpSource {
         body: FullBody,
         header_map: HeaderMap,
     ) -> Result<Vec<Event>, ErrorMessage> {
-        decode_body(body, self.encoding)
-            .map(|events| add_headers(events, &self.headers, header_map))
+        let events = match &self.decoder {
+            Some(decoder) => decoder.clone().decode_all(body.collect()).map_err(|error| {
+                ErrorMessage::new(StatusCode::BAD_REQUEST, format!("Bad request: {}", error))
+            }),
+            None => decode_body(body, self.encoding),
+        };
+        events.map(|events| add_headers(events, &self.headers, header_map))
     }
 }
 
@@ -66,15 +74,24 @@ impl SourceConfig for SimpleHttpConfig {
         _shutdown: ShutdownSignal,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
+        let decoder = self
+            .decoding
+            .as_ref()
+            .map(|decoding| decoding.build(Framing::NewlineDelimited { max_length: None }))
+            .transpose()?;
         let source = SimpleHttpSource {
             encoding: self.encoding,
+            decoder,
             headers: self.headers.clone(),
         };
         source.run(self.address, "", &self.tls, out)
     }
 
     fn output_type(&self) -> DataType {
-        DataType::Log
+        self.decoding
+            .as_ref()
+            .map(DecodingConfig::output_type)
+            .unwrap_or(DataType::Log)
     }
 
     fn source_type(&self) -> &'static str {
@@ -93,7 +110,9 @@ fn add_headers(
             .map(HeaderValue::as_bytes)
             .unwrap_or_default();
         for event in events.iter_mut() {
-            event.as_mut_log().insert(header_name as &str, value);
+            if let Event::Log(log) = event {
+                log.insert(header_name as &str, value);
+            }
         }
     }
 
@@ -193,7 +212,7 @@ fn json_value_to_type_string(value: &JsonValue) -> &'static str {
 
 #[cfg(test)]
 mod tests {
-    use super::{Encoding, SimpleHttpConfig};
+    use super::{DecodingConfig, Encoding, SimpleHttpConfig};
     use warp::http::HeaderMap;
 
     use crate::shutdown::ShutdownSignal;
@@ -213,6 +232,15 @@ mod tests {
         rt: &mut Runtime,
         encoding: Encoding,
         headers: Vec<String>,
+    ) -> (mpsc::Receiver<Event>, SocketAddr) {
+        source_with_decoding(rt, encoding, headers, None)
+    }
+
+    fn source_with_decoding(
+        rt: &mut Runtime,
+        encoding: Encoding,
+        headers: Vec<String>,
+        decoding: Option<DecodingConfig>,
     ) -> (mpsc::Receiver<Event>, SocketAddr) {
         test_util::trace_init();
         let (sender, recv) = mpsc::channel(100);
@@ -223,6 +251,7 @@ mod tests {
                 encoding,
                 headers,
                 tls: None,
+                decoding,
             }
             .build(
                 "default",
@@ -385,6 +414,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn http_decoding_overrides_encoding() {
+        let decoding = toml::from_str(r#"codec = "logfmt""#).unwrap();
+
+        let mut rt = test_util::runtime();
+        let (rx, addr) = source_with_decoding(&mut rt, Encoding::Json, vec![], Some(decoding));
+
+        assert_eq!(
+            200,
+            send(
+                addr,
+                "level=info msg=started\nlevel=warn msg=\"slow request\""
+            )
+        );
+
+        let mut events = rt.block_on(collect_n(rx, 2)).unwrap();
+        {
+            let event = events.remove(0);
+            let log = event.as_log();
+            assert_eq!(log[&Atom::from("level")], "info".into());
+            assert_eq!(log[&Atom::from("msg")], "started".into());
+        }
+        {
+            let event = events.remove(0);
+            let log = event.as_log();
+            assert_eq!(log[&Atom::from("level")], "warn".into());
+            assert_eq!(log[&Atom::from("msg")], "slow request".into());
+            assert!(log.get(&event::log_schema().timestamp_key()).is_some());
+        }
+    }
+
     #[test]
     fn http_headers() {
         let mut headers = HeaderMap::new();
@@ -1,12 +1,12 @@
 use crate::{
-    event::Event,
+    event::{metadata, Event, Value},
     kafka::{KafkaCompression, KafkaTlsConfig},
     shutdown::ShutdownSignal,
+    sources::util::{Decoder, DecodingConfig, Framing},
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
 };
-use bytes::Bytes;
 use futures::compat::Compat;
-use futures01::{future, sync::mpsc, Future, Poll, Sink, Stream};
+use futures01::{future, stream, sync::mpsc, Future, Poll, Sink, Stream};
 use owning_ref::OwningHandle;
 use rdkafka::{
     config::ClientConfig,
@@ -47,6 +47,8 @@ pub struct KafkaSourceConfig {
     key_field: Option<String>,
     librdkafka_options: Option<HashMap<String, String>>,
     tls: Option<KafkaTlsConfig>,
+    #[serde(default)]
+    decoding: DecodingConfig,
 }
 
 fn default_session_timeout_ms() -> u64 {
@@ -86,7 +88,7 @@ impl SourceConfig for KafkaSourceConfig {
     }
 
     fn output_type(&self) -> DataType {
-        DataType::Log
+        self.decoding.output_type()
     }
 
     fn source_type(&self) -> &'static str {
@@ -98,6 +100,8 @@ fn kafka_source(
     config: KafkaSourceConfig,
     out: mpsc::Sender<Event>,
 ) -> crate::Result<super::Source> {
+    // Each message is a single frame unless `decoding` says otherwise.
+    let decoder = config.decoding.build(Framing::Bytes)?;
     let consumer = Arc::new(create_consumer(config.clone())?);
     let source = future::lazy(move || {
         let consumer_ref = Arc::clone(&consumer);
@@ -120,28 +124,40 @@ fn kafka_source(
                             Some(Err(e)) => {
                                 return Err(error!(message = "Cannot extract payload", error = ?e))
                             }
-                            Some(Ok(payload)) => Bytes::from(payload),
+                            Some(Ok(payload)) => payload,
                         };
-                        let mut event = Event::from(payload);
-
-                        if let Some(key_field) = &config.key_field {
-                            match msg.key_view::<[u8]>() {
-                                None => (),
-                                Some(Err(e)) => {
-                                    return Err(error!(message = "Cannot extract key", error = ?e))
-                                }
-                                Some(Ok(key)) => {
-                                    event.as_mut_log().insert(key_field.clone(), key);
-                                }
+                        let mut events = decode_payload(decoder.clone(), payload);
+
+                        let key = match msg.key_view::<[u8]>() {
+                            Some(Err(e)) => {
+                                return Err(error!(message = "Cannot extract key", error = ?e))
+                            }
+                            Some(Ok(key)) => Some(key),
+                            None => None,
+                        };
+
+                        let topic = Value::from(msg.topic());
+                        for event in events.iter_mut() {
+                            if let Some(metadata) = event.metadata_mut() {
+                                metadata.insert(metadata::KAFKA_TOPIC, topic.clone());
+                                metadata.insert(metadata::KAFKA_PARTITION, msg.partition() as i64);
+                                metadata.insert(metadata::KAFKA_OFFSET, msg.offset());
+                            }
+
+                            if let (Some(key_field), Some(key), Event::Log(log)) =
+                                (&config.key_field, key, event)
+                            {
+                                log.insert(key_field.clone(), key);
                             }
                         }
                         consumer_ref.store_offset(&msg).map_err(
                             |e| error!(message = "Cannot store offset for the message", error = ?e),
                         )?;
-                        Ok(event)
+                        Ok(stream::iter_ok::<_, ()>(events))
                     }
                 }
             })
+            .flatten()
             .forward(out.sink_map_err(|e| error!(message = "Error sending to sink", error = ?e)))
             .map(|_| ())
     });
@@ -149,6 +165,10 @@ fn kafka_source(
     Ok(Box::new(source))
 }
 
+fn decode_payload(mut decoder: Decoder, payload: &[u8]) -> Vec<Event> {
+    decoder.decode_message(payload)
+}
+
 fn create_consumer(config: KafkaSourceConfig) -> crate::Result<StreamConsumer> {
     let mut client_config = ClientConfig::new();
     client_config
@@ -202,7 +222,8 @@ impl Stream for OwnedConsumerStream {
 
 #[cfg(test)]
 mod test {
-    use super::{kafka_source, KafkaSourceConfig};
+    use super::{decode_payload, kafka_source, DecodingConfig, Framing, KafkaSourceConfig};
+    use crate::event::{self, Value};
     use futures01::sync::mpsc;
 
     fn make_config() -> KafkaSourceConfig {
@@ -234,6 +255,37 @@ mod test {
         };
         assert!(kafka_source(config, mpsc::channel(1).0).is_err());
     }
+
+    #[test]
+    fn kafka_source_decodes_payload() {
+        let decoding: DecodingConfig = toml::from_str(
+            r#"
+            codec = "json"
+            [framing]
+            method = "newline_delimited"
+            "#,
+        )
+        .unwrap();
+        let decoder = decoding.build(Framing::Bytes).unwrap();
+
+        let events = decode_payload(decoder, b"{\"a\":1}\nnot json\n{\"a\":2}");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].as_log()[&"a".into()], Value::Integer(2));
+    }
+
+    #[test]
+    fn kafka_source_keeps_empty_payload() {
+        let decoder = DecodingConfig::default().build(Framing::Bytes).unwrap();
+
+        let events = decode_payload(decoder, b"");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].as_log()[&event::log_schema().message_key()],
+            "".into()
+        );
+    }
 }
 
 #[cfg(feature = "kafka-integration-tests")]
@@ -1,10 +1,20 @@
 use futures01::Future;
 use snafu::Snafu;
 
+#[cfg(feature = "sources-beats")]
+pub mod beats;
 #[cfg(feature = "sources-docker")]
 pub mod docker;
+#[cfg(feature = "sources-exec")]
+pub mod exec;
 #[cfg(feature = "sources-file")]
 pub mod file;
+#[cfg(feature = "sources-fluent")]
+pub mod fluent;
+#[cfg(feature = "sources-gelf")]
+pub mod gelf;
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics"))]
+pub mod host_metrics;
 #[cfg(feature = "sources-http")]
 pub mod http;
 #[cfg(feature = "sources-internal_metrics")]
@@ -19,6 +29,8 @@ pub mod kubernetes;
 pub mod logplex;
 #[cfg(feature = "sources-prometheus")]
 pub mod prometheus;
+#[cfg(feature = "sources-prometheus_remote_write")]
+pub mod prometheus_remote_write;
 #[cfg(feature = "sources-socket")]
 pub mod socket;
 #[cfg(feature = "sources-splunk_hec")]
@@ -0,0 +1,290 @@
+//! Target discovery from files in the format of Prometheus'
+//! `file_sd_configs`: JSON lists of target groups, each a list of
+//! `host:port` targets and the labels to attach to them.
+
+use crate::internal_events::PrometheusFileSdError;
+use http::Uri;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FileSdConfig {
+    /// Paths of target files. The last path element may be a glob pattern,
+    /// such as `targets/*.json`.
+    pub files: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TargetGroup {
+    pub targets: Vec<String>,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum FileSdError {
+    #[snafu(display("Invalid target file pattern {:?}: {}", pattern, source))]
+    InvalidPattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
+    #[snafu(display("Could not read target file: {}", source))]
+    Read { source: std::io::Error },
+    #[snafu(display("Invalid target file: {}", source))]
+    Parse { source: serde_json::Error },
+    #[snafu(display("Invalid target {:?}: {}", target, source))]
+    InvalidTarget {
+        target: String,
+        source: http::uri::InvalidUri,
+    },
+}
+
+/// The URL a discovered `host:port` target is scraped at, which is
+/// `http://<address>/metrics` unless the group's `__scheme__` or
+/// `__metrics_path__` labels say otherwise.
+pub fn target_url(labels: &BTreeMap<String, String>, address: &str) -> String {
+    let scheme = labels
+        .get("__scheme__")
+        .map(String::as_str)
+        .unwrap_or("http");
+    let path = labels
+        .get("__metrics_path__")
+        .map(String::as_str)
+        .unwrap_or("/metrics");
+    format!("{}://{}{}", scheme, address, path)
+}
+
+struct TargetFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    groups: Vec<TargetGroup>,
+}
+
+/// Tracks the target groups of a set of target files. Files are only read
+/// again once their modification time or size changes, and a file that
+/// fails to parse keeps the groups it last had.
+pub struct FileDiscovery {
+    patterns: Vec<String>,
+    files: HashMap<PathBuf, TargetFile>,
+}
+
+impl FileDiscovery {
+    pub fn new(configs: &[FileSdConfig]) -> Result<Self, FileSdError> {
+        let patterns = configs
+            .iter()
+            .flat_map(|config| config.files.iter().cloned())
+            .collect::<Vec<_>>();
+        for pattern in &patterns {
+            glob::Pattern::new(pattern).context(InvalidPattern { pattern })?;
+        }
+
+        Ok(Self {
+            patterns,
+            files: HashMap::new(),
+        })
+    }
+
+    /// Returns the target groups of every matching file, reading the files
+    /// which changed since the last call.
+    pub fn target_groups(&mut self) -> Vec<TargetGroup> {
+        let paths = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| glob::glob(pattern).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+
+        // Forget files which were removed or no longer match.
+        self.files.retain(|path, _| paths.contains(path));
+
+        let mut groups = Vec::new();
+        for path in paths {
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    emit!(PrometheusFileSdError {
+                        path: &path,
+                        error: FileSdError::Read { source: error },
+                    });
+                    continue;
+                }
+            };
+            let modified = metadata.modified().ok();
+            let len = metadata.len();
+
+            let unchanged = self.files.get(&path).map_or(false, |file| {
+                file.modified.is_some() && file.modified == modified && file.len == len
+            });
+            if !unchanged {
+                match read_target_file(&path) {
+                    Ok(mut groups) => {
+                        drop_invalid_targets(&path, &mut groups);
+                        self.files.insert(
+                            path.clone(),
+                            TargetFile {
+                                modified,
+                                len,
+                                groups,
+                            },
+                        );
+                    }
+                    Err(error) => {
+                        emit!(PrometheusFileSdError { path: &path, error });
+                    }
+                }
+            }
+
+            if let Some(file) = self.files.get(&path) {
+                groups.extend(file.groups.iter().cloned());
+            }
+        }
+        groups
+    }
+}
+
+fn read_target_file(path: &Path) -> Result<Vec<TargetGroup>, FileSdError> {
+    let contents = fs::read(path).context(Read)?;
+    serde_json::from_slice(&contents).context(Parse)
+}
+
+/// Drops the targets which don't make a valid URL, so that one bad entry
+/// doesn't take the rest of the file with it.
+fn drop_invalid_targets(path: &Path, groups: &mut [TargetGroup]) {
+    for group in groups {
+        let labels = &group.labels;
+        group.targets.retain(|target| {
+            match target_url(labels, target).parse::<Uri>() {
+                Ok(_) => true,
+                Err(source) => {
+                    emit!(PrometheusFileSdError {
+                        path,
+                        error: FileSdError::InvalidTarget {
+                            target: target.clone(),
+                            source,
+                        },
+                    });
+                    false
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileDiscovery, FileSdConfig, TargetGroup};
+    use crate::test_util::temp_dir;
+    use std::fs;
+
+    fn discovery(pattern: String) -> FileDiscovery {
+        FileDiscovery::new(&[FileSdConfig {
+            files: vec![pattern],
+        }])
+        .unwrap()
+    }
+
+    fn group(targets: &[&str], labels: &[(&str, &str)]) -> TargetGroup {
+        TargetGroup {
+            targets: targets.iter().map(|target| target.to_string()).collect(),
+            labels: labels
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reads_target_groups() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("a.json"),
+            r#"[{"targets": ["a:9100", "b:9100"], "labels": {"env": "prod"}}]"#,
+        )
+        .unwrap();
+        fs::write(dir.join("b.json"), r#"[{"targets": ["c:9100"]}]"#).unwrap();
+        fs::write(dir.join("ignored.txt"), "not targets").unwrap();
+
+        let mut discovery = discovery(format!("{}/*.json", dir.display()));
+
+        assert_eq!(
+            discovery.target_groups(),
+            vec![
+                group(&["a:9100", "b:9100"], &[("env", "prod")]),
+                group(&["c:9100"], &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn rereads_changed_files() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("targets.json");
+        fs::write(&path, r#"[{"targets": ["a:9100"]}]"#).unwrap();
+
+        let mut discovery = discovery(path.to_string_lossy().into_owned());
+        assert_eq!(discovery.target_groups(), vec![group(&["a:9100"], &[])]);
+
+        fs::write(&path, r#"[{"targets": ["a:9100", "b:9100"]}]"#).unwrap();
+        assert_eq!(
+            discovery.target_groups(),
+            vec![group(&["a:9100", "b:9100"], &[])]
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(discovery.target_groups(), vec![]);
+    }
+
+    #[test]
+    fn keeps_last_good_targets() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("targets.json");
+        fs::write(&path, r#"[{"targets": ["a:9100"]}]"#).unwrap();
+
+        let mut discovery = discovery(path.to_string_lossy().into_owned());
+        assert_eq!(discovery.target_groups(), vec![group(&["a:9100"], &[])]);
+
+        fs::write(&path, r#"[{"targets": ["a:9100", "#).unwrap();
+        assert_eq!(discovery.target_groups(), vec![group(&["a:9100"], &[])]);
+    }
+
+    #[test]
+    fn drops_invalid_targets() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("targets.json");
+        fs::write(
+            &path,
+            r#"[{"targets": ["a:9100", "b c:9100", "d:9100"]}, {"targets": ["e:9100"], "labels": {"__metrics_path__": "no slash"}}]"#,
+        )
+        .unwrap();
+
+        let mut discovery = discovery(path.to_string_lossy().into_owned());
+        assert_eq!(
+            discovery.target_groups(),
+            vec![
+                group(&["a:9100", "d:9100"], &[]),
+                group(&[], &[("__metrics_path__", "no slash")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_pattern() {
+        assert!(FileDiscovery::new(&[FileSdConfig {
+            files: vec!["targets/[.json".into()],
+        }])
+        .is_err());
+    }
+}
@@ -1,30 +1,65 @@
+use self::file_sd::{target_url, FileDiscovery, FileSdConfig, TargetGroup};
 use crate::{
-    internal_events::{PrometheusHttpError, PrometheusParseError, PrometheusRequestCompleted},
+    event::metric::Metric,
+    internal_events::{
+        PrometheusHttpError, PrometheusHttpResponseError, PrometheusParseError,
+        PrometheusRequestCompleted, PrometheusRequestTimeout,
+    },
     shutdown::ShutdownSignal,
+    sinks::util::http::Auth,
+    tls::{tls_connector_builder, MaybeTlsSettings, TlsOptions, TlsSettings},
     topology::config::GlobalOptions,
     Event,
 };
-use futures01::{sync::mpsc, Future, Sink, Stream};
+use futures01::{stream, sync::mpsc, Future, Sink, Stream};
 use http::Uri;
+use hyper::client::HttpConnector;
 use hyper_openssl::HttpsConnector;
 use serde::{Deserialize, Serialize};
-use snafu::ResultExt;
-use std::time::{Duration, Instant};
-use tokio01::timer::Interval;
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio01::{timer::Interval, util::FutureExt};
 
+pub mod file_sd;
 pub mod parser;
 
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("At least one of `hosts` or `file_sd_configs` must be set"))]
+    NoTargets,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 struct PrometheusConfig {
+    #[serde(default)]
     hosts: Vec<String>,
     #[serde(default = "default_scrape_interval_secs")]
     scrape_interval_secs: u64,
+    #[serde(default = "default_scrape_timeout_secs")]
+    scrape_timeout_secs: u64,
+    job_name: Option<String>,
+    #[serde(default)]
+    instance_tag: bool,
+    #[serde(default)]
+    honor_labels: bool,
+    #[serde(default)]
+    file_sd_configs: Vec<FileSdConfig>,
+    auth: Option<Auth>,
+    tls: Option<TlsOptions>,
 }
 
 pub fn default_scrape_interval_secs() -> u64 {
     15
 }
 
+pub fn default_scrape_timeout_secs() -> u64 {
+    10
+}
+
 #[typetag::serde(name = "prometheus")]
 impl crate::topology::config::SourceConfig for PrometheusConfig {
     fn build(
@@ -34,12 +69,23 @@ impl crate::topology::config::SourceConfig for PrometheusConfig {
         _shutdown: ShutdownSignal,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
-        let mut urls = Vec::new();
+        if self.hosts.is_empty() && self.file_sd_configs.is_empty() {
+            return Err(BuildError::NoTargets.into());
+        }
+
+        let mut targets = Vec::new();
         for host in self.hosts.iter() {
             let base_uri = host.parse::<Uri>().context(super::UriParseError)?;
-            urls.push(format!("{}metrics", base_uri));
+            let instance = base_uri
+                .authority_part()
+                .map(|authority| authority.to_string())
+                .unwrap_or_default();
+            targets.push(self.target(format!("{}metrics", base_uri), &instance, &BTreeMap::new()));
         }
-        Ok(prometheus(urls, self.scrape_interval_secs, out))
+        let discovery = FileDiscovery::new(&self.file_sd_configs)?;
+        let client = http_client(&self.tls)?;
+
+        Ok(prometheus(self.clone(), targets, discovery, client, out))
     }
 
     fn output_type(&self) -> crate::topology::config::DataType {
@@ -51,42 +97,98 @@ impl crate::topology::config::SourceConfig for PrometheusConfig {
     }
 }
 
-fn prometheus(urls: Vec<String>, interval: u64, out: mpsc::Sender<Event>) -> super::Source {
+impl PrometheusConfig {
+    /// Builds a target whose labels are its `job` and `instance`, when
+    /// enabled, overridden by the labels of its target group. Labels
+    /// starting with `__` are for discovery only and are dropped.
+    fn target(
+        &self,
+        url: String,
+        instance: &str,
+        group_labels: &BTreeMap<String, String>,
+    ) -> Target {
+        let mut labels = BTreeMap::new();
+        if let Some(job_name) = &self.job_name {
+            labels.insert("job".to_owned(), job_name.clone());
+        }
+        if self.instance_tag {
+            labels.insert("instance".to_owned(), instance.to_owned());
+        }
+        labels.extend(
+            group_labels
+                .iter()
+                .filter(|(name, _)| !name.starts_with("__"))
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+
+        Target { url, labels }
+    }
+
+    /// Builds the targets of discovered target groups.
+    fn discovered_targets(&self, groups: &[TargetGroup]) -> Vec<Target> {
+        groups
+            .iter()
+            .flat_map(|group| {
+                group.targets.iter().map(move |address| {
+                    let url = target_url(&group.labels, address);
+                    self.target(url, address, &group.labels)
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Target {
+    url: String,
+    /// Labels attached to every metric scraped from the target.
+    labels: BTreeMap<String, String>,
+}
+
+type Client = hyper::Client<HttpsConnector<HttpConnector>>;
+
+fn http_client(tls: &Option<TlsOptions>) -> crate::Result<Client> {
+    let settings = MaybeTlsSettings::from(TlsSettings::from_options(tls)?);
+
+    let mut http = HttpConnector::new(4);
+    http.enforce_http(false);
+    let mut https = HttpsConnector::with_connector(http, tls_connector_builder(&settings)?)?;
+
+    let settings = settings.tls().cloned();
+    https.set_callback(move |c, _uri| {
+        if let Some(settings) = &settings {
+            settings.apply_connect_configuration(c);
+        }
+
+        Ok(())
+    });
+
+    Ok(hyper::Client::builder().build(https))
+}
+
+fn prometheus(
+    config: PrometheusConfig,
+    targets: Vec<Target>,
+    mut discovery: FileDiscovery,
+    client: Client,
+    out: mpsc::Sender<Event>,
+) -> super::Source {
     let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
+    let interval = Duration::from_secs(config.scrape_interval_secs);
+    let config = Arc::new(config);
 
-    let task = Interval::new(Instant::now(), Duration::from_secs(interval))
+    let task = Interval::new(Instant::now(), interval)
         .map_err(|e| error!("timer error: {:?}", e))
-        .map(move |_| futures01::stream::iter_ok(urls.clone()))
-        .flatten()
-        .map(move |url| {
-            let https = HttpsConnector::new(4).expect("TLS initialization failed");
-            let client = hyper::Client::builder().build(https);
-
-            let request = hyper::Request::get(&url)
-                .body(hyper::Body::empty())
-                .expect("error creating request");
-
-            client
-                .request(request)
-                .and_then(|response| response.into_body().concat2())
-                .map(|body| {
-                    emit!(PrometheusRequestCompleted);
-
-                    let packet = String::from_utf8_lossy(&body);
-                    let metrics = parser::parse(&packet)
-                        .map_err(|error| {
-                            emit!(PrometheusParseError { error });
-                        })
-                        .unwrap_or_default()
-                        .into_iter()
-                        .map(Event::Metric);
-
-                    futures01::stream::iter_ok(metrics)
-                })
-                .flatten_stream()
-                .map_err(|error| {
-                    emit!(PrometheusHttpError { error });
-                })
+        .map(move |_| {
+            let mut targets = targets.clone();
+            targets.extend(config.discovered_targets(&discovery.target_groups()));
+
+            // A failed scrape only skips its target for this interval.
+            let scrapes = targets.into_iter().map(|target| {
+                scrape(&client, &config, target)
+                    .then(|result| Ok(stream::iter_ok::<_, ()>(result.unwrap_or_default())))
+            });
+            stream::futures_unordered(scrapes).flatten()
         })
         .flatten()
         .forward(out)
@@ -95,22 +197,271 @@ fn prometheus(urls: Vec<String>, interval: u64, out: mpsc::Sender<Event>) -> sup
     Box::new(task)
 }
 
-#[cfg(feature = "sinks-prometheus")]
+fn scrape(
+    client: &Client,
+    config: &PrometheusConfig,
+    target: Target,
+) -> impl Future<Item = Vec<Event>, Error = ()> {
+    let mut request = hyper::Request::get(&target.url)
+        .body(hyper::Body::empty())
+        .expect("error creating request");
+    if let Some(auth) = &config.auth {
+        auth.apply(&mut request);
+    }
+
+    let timeout = Duration::from_secs(config.scrape_timeout_secs);
+    let honor_labels = config.honor_labels;
+    let url = target.url.clone();
+
+    client
+        .request(request)
+        .and_then(|response| {
+            let (parts, body) = response.into_parts();
+            body.concat2().map(move |body| (parts.status, body))
+        })
+        .timeout(timeout)
+        .map_err(move |error| {
+            if error.is_elapsed() {
+                emit!(PrometheusRequestTimeout { url: &url, timeout });
+            } else if error.is_inner() {
+                let error = error.into_inner().unwrap();
+                emit!(PrometheusHttpError { error });
+            } else {
+                error!("timer error: {:?}", error);
+            }
+        })
+        .and_then(move |(status, body)| {
+            if !status.is_success() {
+                emit!(PrometheusHttpResponseError {
+                    url: &target.url,
+                    code: status,
+                });
+                return Err(());
+            }
+            emit!(PrometheusRequestCompleted);
+
+            let packet = String::from_utf8_lossy(&body);
+            let metrics = parser::parse(&packet)
+                .map_err(|error| {
+                    emit!(PrometheusParseError { error });
+                })
+                .unwrap_or_default()
+                .into_iter()
+                .map(|mut metric| {
+                    apply_target_labels(&mut metric, &target.labels, honor_labels);
+                    Event::Metric(metric)
+                })
+                .collect();
+
+            Ok(metrics)
+        })
+}
+
+/// Adds a target's labels to a scraped metric. With `honor_labels`, tags
+/// the metric already has are kept. Otherwise the target's label wins and
+/// the scraped one is kept as `exported_<name>`, as Prometheus does.
+fn apply_target_labels(metric: &mut Metric, labels: &BTreeMap<String, String>, honor_labels: bool) {
+    if labels.is_empty() {
+        return;
+    }
+
+    let tags = metric.tags.get_or_insert_with(BTreeMap::new);
+    for (name, value) in labels {
+        if !tags.contains_key(name) {
+            tags.insert(name.clone(), value.clone());
+        } else if !honor_labels {
+            if let Some(scraped) = tags.insert(name.clone(), value.clone()) {
+                tags.insert(format!("exported_{}", name), scraped);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{
-        sinks::prometheus::PrometheusSinkConfig,
-        test_util::{block_on, next_addr, runtime},
-        topology::{self, config},
+        event::metric::{MetricKind, MetricValue},
+        test_util::{collect_n, next_addr, runtime},
+        topology::config::SourceConfig,
     };
     use hyper::service::{make_service_fn, service_fn_ok};
-    use hyper::{Body, Response, Server};
+    use hyper::{Body, Response, Server, StatusCode};
     use pretty_assertions::assert_eq;
-    use std::{thread, time::Duration};
 
+    fn tags(tags: &[(&str, &str)]) -> BTreeMap<String, String> {
+        tags.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn gauge(tags: Option<BTreeMap<String, String>>) -> Metric {
+        Metric {
+            name: "up".into(),
+            timestamp: None,
+            tags,
+            kind: MetricKind::Absolute,
+            value: MetricValue::Gauge { value: 1.0 },
+        }
+    }
+
+    #[test]
+    fn adds_target_labels() {
+        let mut metric = gauge(Some(tags(&[("code", "200")])));
+        apply_target_labels(&mut metric, &tags(&[("job", "node")]), false);
+        assert_eq!(metric.tags, Some(tags(&[("code", "200"), ("job", "node")])));
+
+        let mut metric = gauge(None);
+        apply_target_labels(&mut metric, &tags(&[("job", "node")]), false);
+        assert_eq!(metric.tags, Some(tags(&[("job", "node")])));
+
+        let mut metric = gauge(None);
+        apply_target_labels(&mut metric, &BTreeMap::new(), false);
+        assert_eq!(metric.tags, None);
+    }
+
+    #[test]
+    fn resolves_label_conflicts() {
+        let mut metric = gauge(Some(tags(&[("job", "pushed")])));
+        apply_target_labels(&mut metric, &tags(&[("job", "node")]), false);
+        assert_eq!(
+            metric.tags,
+            Some(tags(&[("exported_job", "pushed"), ("job", "node")]))
+        );
+
+        let mut metric = gauge(Some(tags(&[("job", "pushed")])));
+        apply_target_labels(&mut metric, &tags(&[("job", "node")]), true);
+        assert_eq!(metric.tags, Some(tags(&[("job", "pushed")])));
+    }
+
+    #[test]
+    fn builds_discovered_targets() {
+        let config: PrometheusConfig = toml::from_str(
+            r#"
+            job_name = "node"
+            instance_tag = true
+        "#,
+        )
+        .unwrap();
+        let groups = vec![
+            TargetGroup {
+                targets: vec!["a:9100".into(), "b:9100".into()],
+                labels: tags(&[("env", "prod")]),
+            },
+            TargetGroup {
+                targets: vec!["c:8443".into()],
+                labels: tags(&[
+                    ("__scheme__", "https"),
+                    ("__metrics_path__", "/stats"),
+                    ("job", "api"),
+                ]),
+            },
+        ];
+
+        assert_eq!(
+            config.discovered_targets(&groups),
+            vec![
+                Target {
+                    url: "http://a:9100/metrics".into(),
+                    labels: tags(&[("env", "prod"), ("instance", "a:9100"), ("job", "node")]),
+                },
+                Target {
+                    url: "http://b:9100/metrics".into(),
+                    labels: tags(&[("env", "prod"), ("instance", "b:9100"), ("job", "node")]),
+                },
+                Target {
+                    url: "https://c:8443/stats".into(),
+                    labels: tags(&[("instance", "c:8443"), ("job", "api")]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn requires_targets() {
+        let config: PrometheusConfig = toml::from_str(r#"job_name = "node""#).unwrap();
+        let (tx, _rx) = mpsc::channel(10);
+        let error = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("file_sd_configs"));
+    }
+
+    #[test]
+    fn scrapes_with_auth_and_target_labels() {
+        crate::test_util::trace_init();
+        let mut rt = runtime();
+        let in_addr = next_addr();
+
+        let make_svc = make_service_fn(|_| {
+            service_fn_ok(move |request: hyper::Request<Body>| {
+                // "user:password"
+                match request.headers().get("Authorization") {
+                    Some(value) if value == "Basic dXNlcjpwYXNzd29yZA==" => {
+                        Response::new(Body::from("up{job=\"pushed\"} 1\n"))
+                    }
+                    _ => Response::builder()
+                        .status(StatusCode::UNAUTHORIZED)
+                        .body(Body::empty())
+                        .unwrap(),
+                }
+            })
+        });
+        let server = Server::bind(&in_addr).serve(make_svc);
+        rt.spawn(server.map_err(|e| {
+            error!("server error: {:?}", e);
+        }));
+
+        let config: PrometheusConfig = toml::from_str(&format!(
+            r#"
+            hosts = ["http://{}"]
+            job_name = "node"
+            instance_tag = true
+            auth = {{ strategy = "basic", user = "user", password = "password" }}
+        "#,
+            in_addr
+        ))
+        .unwrap();
+        let (tx, rx) = mpsc::channel(10);
+        let source = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .unwrap();
+        rt.spawn(source);
+
+        let events = rt.block_on(collect_n(rx, 1)).unwrap();
+        let metric = events[0].as_metric();
+        assert_eq!(metric.name, "up");
+        assert_eq!(
+            metric.tags,
+            Some(tags(&[
+                ("exported_job", "pushed"),
+                ("instance", &in_addr.to_string()),
+                ("job", "node"),
+            ]))
+        );
+    }
+
+    #[cfg(feature = "sinks-prometheus")]
     #[test]
     fn test_prometheus_routing() {
+        use crate::{
+            sinks::prometheus::PrometheusSinkConfig,
+            test_util::block_on,
+            topology::{self, config},
+        };
+        use std::thread;
+
         let mut rt = runtime();
         let in_addr = next_addr();
         let out_addr = next_addr();
@@ -158,10 +509,14 @@ mod test {
         let mut config = config::Config::empty();
         config.add_source(
             "in",
-            PrometheusConfig {
-                hosts: vec![format!("http://{}", in_addr)],
-                scrape_interval_secs: 1,
-            },
+            toml::from_str::<PrometheusConfig>(&format!(
+                r#"
+                hosts = ["http://{}"]
+                scrape_interval_secs = 1
+            "#,
+                in_addr
+            ))
+            .unwrap(),
         );
         config.add_sink(
             "out",
@@ -0,0 +1,310 @@
+use crate::{
+    event::metric::{Metric, MetricKind, MetricValue},
+    internal_events::{PrometheusRemoteWriteDecodeError, PrometheusRemoteWriteReceived},
+    prometheus_remote_write::{self, proto, NAME_LABEL},
+    shutdown::ShutdownSignal,
+    sources::util::{ErrorMessage, HttpSource},
+    tls::TlsConfig,
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+    Event,
+};
+use bytes::{Buf, BytesMut};
+use chrono::{TimeZone, Utc};
+use futures01::sync::mpsc;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, net::SocketAddr};
+use warp::filters::body::FullBody;
+use warp::http::{HeaderMap, StatusCode};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PrometheusRemoteWriteConfig {
+    address: SocketAddr,
+    #[serde(default = "default_max_body_size")]
+    max_body_size: usize,
+    tls: Option<TlsConfig>,
+}
+
+fn default_max_body_size() -> usize {
+    bytesize::mib(10u64) as usize
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<PrometheusRemoteWriteConfig>("prometheus_remote_write")
+}
+
+#[derive(Clone)]
+struct RemoteWriteSource {
+    max_body_size: usize,
+}
+
+impl HttpSource for RemoteWriteSource {
+    fn build_event(&self, body: FullBody, _: HeaderMap) -> Result<Vec<Event>, ErrorMessage> {
+        let body = body.collect::<BytesMut>();
+        let request =
+            prometheus_remote_write::decode(&body, self.max_body_size).map_err(|error| {
+                let message = format!("Invalid write request: {}", error);
+                let status = match error {
+                    prometheus_remote_write::DecodeError::TooLarge { .. } => {
+                        StatusCode::PAYLOAD_TOO_LARGE
+                    }
+                    _ => StatusCode::BAD_REQUEST,
+                };
+                emit!(PrometheusRemoteWriteDecodeError { error });
+                ErrorMessage::new(status, message)
+            })?;
+
+        let events = decode_request(request);
+        emit!(PrometheusRemoteWriteReceived {
+            byte_size: body.len(),
+            count: events.len(),
+        });
+        Ok(events)
+    }
+
+    fn max_body_size(&self) -> Option<u64> {
+        Some(self.max_body_size as u64)
+    }
+}
+
+#[typetag::serde(name = "prometheus_remote_write")]
+impl SourceConfig for PrometheusRemoteWriteConfig {
+    fn build(
+        &self,
+        _: &str,
+        _: &GlobalOptions,
+        _: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        let source = RemoteWriteSource {
+            max_body_size: self.max_body_size,
+        };
+        source.run(self.address, "", &self.tls, out)
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn source_type(&self) -> &'static str {
+        "prometheus_remote_write"
+    }
+}
+
+/// Converts every sample into an absolute gauge. Remote write carries no
+/// metric types, so counters and the series of histograms and summaries
+/// arrive as plain gauges too. Series without a name are dropped.
+fn decode_request(request: proto::WriteRequest) -> Vec<Event> {
+    request
+        .timeseries
+        .into_iter()
+        .flat_map(|series| {
+            let mut name = None;
+            let mut tags = BTreeMap::new();
+            for label in series.labels {
+                if label.name == NAME_LABEL {
+                    name = Some(label.value);
+                } else {
+                    tags.insert(label.name, label.value);
+                }
+            }
+            let tags = if !tags.is_empty() { Some(tags) } else { None };
+
+            let samples = if name.is_some() {
+                series.samples
+            } else {
+                Vec::new()
+            };
+            samples.into_iter().map(move |sample| {
+                Event::Metric(Metric {
+                    name: name.clone().unwrap_or_default(),
+                    timestamp: Utc.timestamp_millis_opt(sample.timestamp).single(),
+                    tags: tags.clone(),
+                    kind: MetricKind::Absolute,
+                    value: MetricValue::Gauge {
+                        value: sample.value,
+                    },
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_request, default_max_body_size, PrometheusRemoteWriteConfig};
+    use crate::{
+        event::metric::{Metric, MetricKind, MetricValue},
+        prometheus_remote_write::{encode, proto, NAME_LABEL},
+        runtime::Runtime,
+        shutdown::ShutdownSignal,
+        test_util::{self, collect_n},
+        topology::config::{GlobalOptions, SourceConfig},
+        Event,
+    };
+    use chrono::{TimeZone, Utc};
+    use futures01::sync::mpsc;
+    use http::Method;
+    use pretty_assertions::assert_eq;
+    use std::net::SocketAddr;
+
+    fn label(name: &str, value: &str) -> proto::Label {
+        proto::Label {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    fn request() -> proto::WriteRequest {
+        proto::WriteRequest {
+            timeseries: vec![
+                proto::TimeSeries {
+                    labels: vec![
+                        label(NAME_LABEL, "http_requests_total"),
+                        label("code", "200"),
+                    ],
+                    samples: vec![
+                        proto::Sample {
+                            value: 10.0,
+                            timestamp: 1_500_000_000_000,
+                        },
+                        proto::Sample {
+                            value: 12.0,
+                            timestamp: 1_500_000_015_000,
+                        },
+                    ],
+                },
+                proto::TimeSeries {
+                    labels: vec![label(NAME_LABEL, "up")],
+                    samples: vec![proto::Sample {
+                        value: 1.0,
+                        timestamp: 1_500_000_000_500,
+                    }],
+                },
+            ],
+        }
+    }
+
+    fn gauge(name: &str, tags: Option<Vec<(&str, &str)>>, value: f64, millis: i64) -> Event {
+        Event::Metric(Metric {
+            name: name.into(),
+            timestamp: Some(Utc.timestamp_millis(millis)),
+            tags: tags.map(|tags| {
+                tags.into_iter()
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                    .collect()
+            }),
+            kind: MetricKind::Absolute,
+            value: MetricValue::Gauge { value },
+        })
+    }
+
+    #[test]
+    fn decodes_samples_as_gauges() {
+        assert_eq!(
+            decode_request(request()),
+            vec![
+                gauge(
+                    "http_requests_total",
+                    Some(vec![("code", "200")]),
+                    10.0,
+                    1_500_000_000_000
+                ),
+                gauge(
+                    "http_requests_total",
+                    Some(vec![("code", "200")]),
+                    12.0,
+                    1_500_000_015_000
+                ),
+                gauge("up", None, 1.0, 1_500_000_000_500),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_series_without_name() {
+        let request = proto::WriteRequest {
+            timeseries: vec![proto::TimeSeries {
+                labels: vec![label("job", "vector")],
+                samples: vec![proto::Sample {
+                    value: 1.0,
+                    timestamp: 1_500_000_000_000,
+                }],
+            }],
+        };
+
+        assert_eq!(decode_request(request), vec![]);
+    }
+
+    fn source(rt: &mut Runtime, max_body_size: usize) -> (mpsc::Receiver<Event>, SocketAddr) {
+        test_util::trace_init();
+        let (sender, recv) = mpsc::channel(100);
+        let address = test_util::next_addr();
+        rt.spawn(
+            PrometheusRemoteWriteConfig {
+                address,
+                max_body_size,
+                tls: None,
+            }
+                .build(
+                    "default",
+                    &GlobalOptions::default(),
+                    ShutdownSignal::noop(),
+                    sender,
+                )
+                .unwrap(),
+        );
+        (recv, address)
+    }
+
+    fn send(address: SocketAddr, body: Vec<u8>) -> u16 {
+        reqwest::Client::new()
+            .request(Method::POST, &format!("http://{}/", address))
+            .header("Content-Encoding", "snappy")
+            .header("Content-Type", "application/x-protobuf")
+            .body(body)
+            .send()
+            .unwrap()
+            .status()
+            .as_u16()
+    }
+
+    #[test]
+    fn receives_write_requests() {
+        let mut rt = test_util::runtime();
+        let (rx, address) = source(&mut rt, default_max_body_size());
+
+        assert_eq!(200, send(address, encode(&request())));
+
+        let events = rt.block_on(collect_n(rx, 3)).unwrap();
+        assert_eq!(events, decode_request(request()));
+    }
+
+    #[test]
+    fn rejects_invalid_body() {
+        let mut rt = test_util::runtime();
+        let (_rx, address) = source(&mut rt, default_max_body_size());
+
+        assert_eq!(400, send(address, b"not snappy".to_vec()));
+    }
+
+    #[test]
+    fn rejects_large_bodies() {
+        let mut rt = test_util::runtime();
+        let (_rx, address) = source(&mut rt, 256);
+
+        // Too large once decompressed.
+        let body = encode(&proto::WriteRequest {
+            timeseries: vec![proto::TimeSeries {
+                labels: vec![label(NAME_LABEL, "up"), label("job", &"a".repeat(2000))],
+                samples: vec![],
+            }],
+        });
+        assert!(body.len() <= 256);
+        assert_eq!(413, send(address, body));
+        // A snappy header claiming about 4 GiB.
+        assert_eq!(413, send(address, vec![0xff, 0xff, 0xff, 0xff, 0x0f]));
+        // Too large as sent.
+        assert_eq!(413, send(address, vec![0; 257]));
+    }
+}
@@ -78,6 +78,7 @@ impl SourceConfig for SocketConfig {
         match self.mode.clone() {
             Mode::Tcp(config) => {
                 let tcp = tcp::RawTcpSource {
+                    decoder: config.decoding.build(config.default_framing())?,
                     config: config.clone(),
                 };
                 let tls = MaybeTlsSettings::from_config(&config.tls, true)?;
@@ -94,7 +95,8 @@ impl SourceConfig for SocketConfig {
                     .host_key
                     .clone()
                     .unwrap_or(event::log_schema().host_key().clone());
-                Ok(udp::udp(config.address, host_key, shutdown, out))
+                let decoder = config.decoding.build(udp::default_framing())?;
+                Ok(udp::udp(config.address, host_key, decoder, shutdown, out))
             }
             #[cfg(unix)]
             Mode::Unix(config) => {
@@ -102,13 +104,19 @@ impl SourceConfig for SocketConfig {
                     .host_key
                     .clone()
                     .unwrap_or(event::log_schema().host_key().to_string());
-                Ok(unix::unix(config.path, config.max_length, host_key, out))
+                let decoder = config.decoding.build(config.default_framing())?;
+                Ok(unix::unix(config.path, decoder, host_key, out))
             }
         }
     }
 
     fn output_type(&self) -> DataType {
-        DataType::Log
+        match &self.mode {
+            Mode::Tcp(config) => config.decoding.output_type(),
+            Mode::Udp(config) => config.decoding.output_type(),
+            #[cfg(unix)]
+            Mode::Unix(config) => config.decoding.output_type(),
+        }
     }
 
     fn source_type(&self) -> &'static str {
@@ -126,6 +134,7 @@ mod test {
     use crate::event;
     use crate::runtime;
     use crate::shutdown::{ShutdownSignal, SourceShutdownCoordinator};
+    use crate::sources::util::Codec;
     use crate::test_util::{
         block_on, collect_n, next_addr, send_lines, send_lines_tls, wait_for_tcp, CollectN,
     };
@@ -177,6 +186,43 @@ mod test {
         );
     }
 
+    #[test]
+    fn tcp_decodes_json() {
+        let (tx, rx) = mpsc::channel(10);
+
+        let addr = next_addr();
+
+        let mut config = TcpConfig::new(addr.into());
+        config.decoding.codec = Codec::Json;
+
+        let server = SocketConfig::from(config)
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .unwrap();
+        let mut rt = runtime::Runtime::new().unwrap();
+        rt.spawn(server);
+        wait_for_tcp(addr);
+
+        let lines = vec![
+            r#"{"a":"b"}"#.to_owned(),
+            "not json".to_owned(),
+            r#"{"a":"c"}"#.to_owned(),
+        ];
+        rt.block_on(send_lines(addr, lines.into_iter())).unwrap();
+
+        let events = rt.block_on(collect_n(rx, 2)).ok().unwrap();
+        assert_eq!(events[0].as_log()[&"a".into()], "b".into());
+        assert_eq!(events[1].as_log()[&"a".into()], "c".into());
+        assert_eq!(
+            events[1].as_log()[&event::log_schema().host_key()],
+            "127.0.0.1".into()
+        );
+    }
+
     #[test]
     fn tcp_continue_after_long_line() {
         let (tx, rx) = mpsc::channel(10);
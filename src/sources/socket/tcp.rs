@@ -1,11 +1,10 @@
 use crate::{
     event::{self, Event},
     internal_events::TcpEventReceived,
-    sources::util::{SocketListenAddr, TcpSource},
+    sources::util::{Decoder, DecodingConfig, Framing, SocketListenAddr, TcpSource},
     tls::TlsConfig,
 };
 use bytes::Bytes;
-use codec::{self, BytesDelimitedCodec};
 use serde::{Deserialize, Serialize};
 use string_cache::DefaultAtom as Atom;
 use tracing::field;
@@ -20,6 +19,8 @@ pub struct TcpConfig {
     pub shutdown_timeout_secs: u64,
     pub host_key: Option<Atom>,
     pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
 }
 
 fn default_max_length() -> usize {
@@ -38,6 +39,14 @@ impl TcpConfig {
             host_key: None,
             shutdown_timeout_secs: default_shutdown_timeout_secs(),
             tls: Default::default(),
+            decoding: Default::default(),
+        }
+    }
+
+    /// Lines are split on newlines unless `decoding` says otherwise.
+    pub fn default_framing(&self) -> Framing {
+        Framing::NewlineDelimited {
+            max_length: Some(self.max_length),
         }
     }
 }
@@ -45,26 +54,26 @@ impl TcpConfig {
 #[derive(Debug, Clone)]
 pub struct RawTcpSource {
     pub config: TcpConfig,
+    pub decoder: Decoder,
 }
 
 impl TcpSource for RawTcpSource {
-    type Decoder = BytesDelimitedCodec;
+    type Decoder = Decoder;
 
     fn decoder(&self) -> Self::Decoder {
-        BytesDelimitedCodec::new_with_max_length(b'\n', self.config.max_length)
+        self.decoder.clone()
     }
 
-    fn build_event(&self, frame: Bytes, host: Bytes) -> Option<Event> {
-        let byte_size = frame.len();
-        let mut event = Event::from(frame);
-
+    fn build_event(&self, (mut event, byte_size): (Event, usize), host: Bytes) -> Option<Event> {
         let host_key = if let Some(key) = &self.config.host_key {
             key
         } else {
             &event::log_schema().host_key()
         };
 
-        event.as_mut_log().insert(host_key.clone(), host);
+        if let Event::Log(log) = &mut event {
+            log.insert(host_key.clone(), host);
+        }
 
         trace!(
             message = "Received one event.",
@@ -2,23 +2,27 @@ use crate::{
     event::Event,
     internal_events::{UdpEventReceived, UdpSocketError},
     shutdown::ShutdownSignal,
-    sources::Source,
+    sources::{
+        util::{Decoder, DecodingConfig, Framing},
+        Source,
+    },
     stream::StreamExt,
 };
-use bytes::Bytes;
-use codec::BytesDelimitedCodec;
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use serde::{Deserialize, Serialize};
 use std::{io, net::SocketAddr};
 use string_cache::DefaultAtom as Atom;
 use tokio01::net::udp::{UdpFramed, UdpSocket};
 
-/// UDP processes messages per packet, where messages are separated by newline.
+/// UDP processes messages per packet, where messages are separated by newline
+/// by default.
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct UdpConfig {
     pub address: SocketAddr,
     pub host_key: Option<Atom>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
 }
 
 impl UdpConfig {
@@ -26,13 +30,21 @@ impl UdpConfig {
         Self {
             address,
             host_key: None,
+            decoding: Default::default(),
         }
     }
 }
 
+/// Each packet is split on newlines unless `decoding` says otherwise, with
+/// the last message stretching to the end of the packet.
+pub fn default_framing() -> Framing {
+    Framing::NewlineDelimited { max_length: None }
+}
+
 pub fn udp(
     address: SocketAddr,
     host_key: Atom,
+    decoder: Decoder,
     shutdown: ShutdownSignal,
     out: mpsc::Sender<Event>,
 ) -> Source {
@@ -48,17 +60,13 @@ pub fn udp(
         })
         .and_then(move |socket| {
             let host_key = host_key.clone();
-            // UDP processes messages per packet, where messages are separated by newline.
-            // And stretch to end of packet.
-            UdpFramed::with_decode(socket, BytesDelimitedCodec::new(b'\n'), true)
+            // UDP processes messages per packet, decoding each to its end.
+            UdpFramed::with_decode(socket, decoder, true)
                 .take_until(shutdown)
-                .map(move |(line, addr): (Bytes, _)| {
-                    let byte_size = line.len();
-                    let mut event = Event::from(line);
-
-                    event
-                        .as_mut_log()
-                        .insert(host_key.clone(), addr.to_string());
+                .map(move |((mut event, byte_size), addr): ((Event, usize), _)| {
+                    if let Event::Log(log) = &mut event {
+                        log.insert(host_key.clone(), addr.to_string());
+                    }
 
                     emit!(UdpEventReceived { byte_size });
                     event
@@ -1,7 +1,10 @@
 use crate::{
     event::Event,
     internal_events::UnixSocketEventReceived,
-    sources::{util::build_unix_source, Source},
+    sources::{
+        util::{build_unix_source, Decoder, DecodingConfig, Framing},
+        Source,
+    },
 };
 use bytes::Bytes;
 use futures01::sync::mpsc;
@@ -15,6 +18,8 @@ pub struct UnixConfig {
     #[serde(default = "default_max_length")]
     pub max_length: usize,
     pub host_key: Option<String>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
 }
 
 fn default_max_length() -> usize {
@@ -27,29 +32,34 @@ impl UnixConfig {
             path,
             max_length: default_max_length(),
             host_key: None,
+            decoding: Default::default(),
+        }
+    }
+
+    /// Lines are split on newlines unless `decoding` says otherwise.
+    pub fn default_framing(&self) -> Framing {
+        Framing::NewlineDelimited {
+            max_length: Some(self.max_length),
         }
     }
 }
 
 /**
 * Function to pass to build_unix_source, specific to the basic unix source.
-* Takes a single line of a received message and builds an Event object.
+* Takes a single decoded message and adds the host it was received from.
 **/
-fn build_event(host_key: &str, received_from: Option<Bytes>, line: &str) -> Option<Event> {
-    let byte_size = line.len();
-    let mut event = Event::from(line);
-    if let Some(host) = received_from {
-        event.as_mut_log().insert(host_key, host);
+fn build_event(
+    host_key: &str,
+    received_from: Option<Bytes>,
+    (mut event, byte_size): (Event, usize),
+) -> Option<Event> {
+    if let (Some(host), Event::Log(log)) = (received_from, &mut event) {
+        log.insert(host_key, host);
     }
     emit!(UnixSocketEventReceived { byte_size });
     Some(event)
 }
 
-pub fn unix(
-    path: PathBuf,
-    max_length: usize,
-    host_key: String,
-    out: mpsc::Sender<Event>,
-) -> Source {
-    build_unix_source(path, max_length, host_key, out, build_event)
+pub fn unix(path: PathBuf, decoder: Decoder, host_key: String, out: mpsc::Sender<Event>) -> Source {
+    build_unix_source(path, decoder, host_key, out, build_event)
 }
@@ -1,4 +1,12 @@
-use crate::{shutdown::ShutdownSignal, topology::config::GlobalOptions, Event};
+use crate::{
+    event::{
+        metric::{Metric, MetricValue},
+        sketch::DDSketch,
+    },
+    shutdown::ShutdownSignal,
+    topology::config::GlobalOptions,
+    Event,
+};
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use parser::parse;
 use serde::{Deserialize, Serialize};
@@ -15,6 +23,8 @@ pub mod parser;
 #[derive(Deserialize, Serialize, Debug)]
 struct StatsdConfig {
     address: SocketAddr,
+    #[serde(default)]
+    sketches: bool,
 }
 
 #[typetag::serde(name = "statsd")]
@@ -26,7 +36,7 @@ impl crate::topology::config::SourceConfig for StatsdConfig {
         _shutdown: ShutdownSignal,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
-        Ok(statsd(self.address, out))
+        Ok(statsd(self.address, self.sketches, out))
     }
 
     fn output_type(&self) -> crate::topology::config::DataType {
@@ -38,7 +48,7 @@ impl crate::topology::config::SourceConfig for StatsdConfig {
     }
 }
 
-fn statsd(addr: SocketAddr, out: mpsc::Sender<Event>) -> super::Source {
+fn statsd(addr: SocketAddr, sketches: bool, out: mpsc::Sender<Event>) -> super::Source {
     let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
 
     Box::new(
@@ -61,6 +71,13 @@ fn statsd(addr: SocketAddr, out: mpsc::Sender<Event>) -> super::Source {
                         .lines()
                         .map(parse)
                         .filter_map(|res| res.map_err(|e| error!("{}", e)).ok())
+                        .map(|metric| {
+                            if sketches {
+                                into_sketch(metric)
+                            } else {
+                                metric
+                            }
+                        })
                         .map(Event::Metric)
                         .collect::<Vec<_>>();
                     futures01::stream::iter_ok::<_, std::io::Error>(metrics)
@@ -73,6 +90,18 @@ fn statsd(addr: SocketAddr, out: mpsc::Sender<Event>) -> super::Source {
     )
 }
 
+/// Turns histograms and timers into sketches.
+fn into_sketch(mut metric: Metric) -> Metric {
+    if let MetricValue::Distribution {
+        values,
+        sample_rates,
+    } = &metric.value
+    {
+        metric.value = MetricValue::Sketch(DDSketch::from_distribution(values, sample_rates));
+    }
+    metric
+}
+
 #[cfg(feature = "sinks-prometheus")]
 #[cfg(test)]
 mod test {
@@ -101,7 +130,13 @@ mod test {
         let out_addr = next_addr();
 
         let mut config = config::Config::empty();
-        config.add_source("in", StatsdConfig { address: in_addr });
+        config.add_source(
+            "in",
+            StatsdConfig {
+                address: in_addr,
+                sketches: false,
+            },
+        );
         config.add_sink(
             "out",
             &["in"],
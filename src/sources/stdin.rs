@@ -1,12 +1,14 @@
 use crate::{
-    event::{self, Event},
+    event::{self, Event, Value},
     shutdown::ShutdownSignal,
+    sources::util::{Decoder, DecodingConfig, Framing},
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
 };
-use bytes::Bytes;
+use bytes::BytesMut;
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use serde::{Deserialize, Serialize};
 use std::{io, thread, time::Duration};
+use tokio01::codec::Decoder as _;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields, default)]
@@ -14,6 +16,7 @@ pub struct StdinConfig {
     #[serde(default = "default_max_length")]
     pub max_length: usize,
     pub host_key: Option<String>,
+    pub decoding: DecodingConfig,
 }
 
 impl Default for StdinConfig {
@@ -21,6 +24,16 @@ impl Default for StdinConfig {
         StdinConfig {
             max_length: default_max_length(),
             host_key: None,
+            decoding: Default::default(),
+        }
+    }
+}
+
+impl StdinConfig {
+    /// Lines are split on newlines unless `decoding` says otherwise.
+    fn default_framing(&self) -> Framing {
+        Framing::NewlineDelimited {
+            max_length: Some(self.max_length),
         }
     }
 }
@@ -42,15 +55,17 @@ impl SourceConfig for StdinConfig {
         _shutdown: ShutdownSignal,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
+        let decoder = self.decoding.build(self.default_framing())?;
         Ok(stdin_source(
             io::BufReader::new(io::stdin()),
             self.clone(),
+            decoder,
             out,
         ))
     }
 
     fn output_type(&self) -> DataType {
-        DataType::Log
+        self.decoding.output_type()
     }
 
     fn source_type(&self) -> &'static str {
@@ -58,13 +73,20 @@ impl SourceConfig for StdinConfig {
     }
 }
 
-pub fn stdin_source<R>(stdin: R, config: StdinConfig, out: mpsc::Sender<Event>) -> super::Source
+pub fn stdin_source<R>(
+    mut stdin: R,
+    config: StdinConfig,
+    mut decoder: Decoder,
+    out: mpsc::Sender<Event>,
+) -> super::Source
 where
     R: Send + io::BufRead + 'static,
 {
     Box::new(future::lazy(move || {
         info!("Capturing STDIN");
 
+        // Only the default framing splits lines like `BufRead::lines` would.
+        let trim_cr = config.decoding.framing.is_none();
         let host_key = config
             .host_key
             .clone()
@@ -73,41 +95,83 @@ where
         let (mut tx, rx) = futures01::sync::mpsc::channel(1024);
 
         thread::spawn(move || {
-            for line in stdin.lines() {
-                match line {
+            let mut buf = BytesMut::new();
+            loop {
+                let read = match stdin.fill_buf() {
+                    Ok(chunk) => {
+                        buf.extend_from_slice(chunk);
+                        chunk.len()
+                    }
                     Err(e) => {
                         error!(message = "Unable to read from source.", error = %e);
                         break;
                     }
-                    Ok(string_data) => {
-                        let msg = Bytes::from(string_data);
-                        while let Err(e) = tx.try_send(msg.clone()) {
-                            if e.is_full() {
-                                thread::sleep(Duration::from_millis(10));
-                                continue;
-                            }
-                            error!(message = "Unable to send event.", error = %e);
-                            break;
+                };
+                stdin.consume(read);
+                let eof = read == 0;
+
+                loop {
+                    let decoded = if eof {
+                        decoder.decode_eof(&mut buf)
+                    } else {
+                        decoder.decode(&mut buf)
+                    };
+                    let event = match decoded {
+                        Ok(Some((event, _byte_size))) => event,
+                        Ok(None) => break,
+                        Err(e) => {
+                            error!(message = "Unable to decode input.", error = %e);
+                            return;
                         }
+                    };
+                    while let Err(e) = tx.try_send(event.clone()) {
+                        if e.is_full() {
+                            thread::sleep(Duration::from_millis(10));
+                            continue;
+                        }
+                        error!(message = "Unable to send event.", error = %e);
+                        break;
                     }
                 }
+
+                if eof {
+                    break;
+                }
             }
         });
 
-        rx.map(move |line| create_event(line, &host_key, &hostname))
-            .map_err(|e| error!("error reading line: {:?}", e))
-            .forward(
-                out.sink_map_err(|e| error!(message = "Unable to send event to out.", error = %e)),
-            )
-            .map(|_| info!("finished sending"))
+        rx.map(move |event| {
+            let event = if trim_cr {
+                trim_carriage_return(event)
+            } else {
+                event
+            };
+            add_host(event, &host_key, &hostname)
+        })
+        .map_err(|e| error!("error reading line: {:?}", e))
+        .forward(
+            out.sink_map_err(|e| error!(message = "Unable to send event to out.", error = %e)),
+        )
+        .map(|_| info!("finished sending"))
     }))
 }
 
-fn create_event(line: Bytes, host_key: &str, hostname: &Option<String>) -> Event {
-    let mut event = Event::from(line);
+/// Drops the `\r` of a `\r\n` line ending from the message.
+fn trim_carriage_return(mut event: Event) -> Event {
+    if let Event::Log(log) = &mut event {
+        if let Some(Value::Bytes(message)) = log.get_mut(&event::log_schema().message_key()) {
+            if message.ends_with(b"\r") {
+                message.truncate(message.len() - 1);
+            }
+        }
+    }
 
-    if let Some(hostname) = &hostname {
-        event.as_mut_log().insert(host_key, hostname.clone());
+    event
+}
+
+fn add_host(mut event: Event, host_key: &str, hostname: &Option<String>) -> Event {
+    if let (Some(hostname), Event::Log(log)) = (&hostname, &mut event) {
+        log.insert(host_key, hostname.clone());
     }
 
     event
@@ -123,12 +187,11 @@ mod tests {
     use tokio01::runtime::current_thread::Runtime;
 
     #[test]
-    fn stdin_create_event() {
-        let line = Bytes::from("hello world");
+    fn stdin_add_host() {
         let host_key = "host".to_string();
         let hostname = Some("Some.Machine".to_string());
 
-        let event = create_event(line, &host_key, &hostname);
+        let event = add_host(Event::from("hello world"), &host_key, &hostname);
         let log = event.into_log();
 
         assert_eq!(log[&"host".into()], "Some.Machine".into());
@@ -143,9 +206,10 @@ mod tests {
         let (tx, mut rx) = mpsc::channel(10);
         let config = StdinConfig::default();
         let buf = Cursor::new(String::from("hello world\nhello world again"));
+        let decoder = config.decoding.build(config.default_framing()).unwrap();
 
         let mut rt = Runtime::new().unwrap();
-        let source = stdin_source(buf, config, tx);
+        let source = stdin_source(buf, config, decoder, tx);
 
         rt.block_on(source).unwrap();
 
@@ -170,4 +234,49 @@ mod tests {
         assert!(event.is_ready());
         assert_eq!(Ready(None), event);
     }
+
+    #[test]
+    fn stdin_trims_carriage_returns() {
+        let (tx, rx) = mpsc::channel(10);
+        let config = StdinConfig::default();
+        let buf = Cursor::new(String::from("hello\r\nworld\r\n"));
+        let decoder = config.decoding.build(config.default_framing()).unwrap();
+
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(stdin_source(buf, config, decoder, tx)).unwrap();
+
+        let lines = rx
+            .map(|event| event.as_log()[&event::log_schema().message_key()].to_string_lossy())
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(lines, vec!["hello".to_string(), "world".to_string()]);
+    }
+
+    #[test]
+    fn stdin_decodes_json() {
+        let (tx, rx) = mpsc::channel(10);
+        let config: StdinConfig = toml::from_str(
+            r#"
+            [decoding]
+            codec = "json"
+            [decoding.framing]
+            method = "character_delimited"
+            delimiter = "\u0000"
+            "#,
+        )
+        .unwrap();
+        let buf = Cursor::new(String::from("{\"a\":\n\"b\"}\0{\"a\":\"d\"}"));
+        let decoder = config.decoding.build(config.default_framing()).unwrap();
+
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(stdin_source(buf, config, decoder, tx)).unwrap();
+
+        let values = rx
+            .map(|event| event.as_log()[&"a".into()].to_string_lossy())
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(values, vec!["b".to_string(), "d".to_string()]);
+    }
 }
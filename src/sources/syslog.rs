@@ -2,24 +2,25 @@ use super::util::{SocketListenAddr, TcpSource};
 #[cfg(unix)]
 use crate::sources::util::build_unix_source;
 use crate::{
-    event::{self, Event, Value},
+    event::{self, Event},
     internal_events::{SyslogEventReceived, SyslogUdpReadError},
     shutdown::ShutdownSignal,
+    syslog_message,
     tls::{MaybeTlsSettings, TlsConfig},
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
 };
-use bytes::Bytes;
-use chrono::{Datelike, Utc};
+use bytes::{Bytes, BytesMut};
+use chrono::Utc;
+use codec::{BytesDelimitedCodec, OctetCountingCodec};
 use derive_is_enum_variant::is_enum_variant;
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
 #[cfg(unix)]
 use std::path::PathBuf;
-use syslog_loose::{self, IncompleteDate, Message, ProcId, Protocol};
+use std::{io, net::SocketAddr};
 use tokio01::{
     self,
-    codec::{BytesCodec, LinesCodec},
+    codec::{BytesCodec, Decoder, LinesCodec},
     net::{UdpFramed, UdpSocket},
 };
 use tracing::field;
@@ -97,10 +98,12 @@ impl SourceConfig for SyslogConfig {
             #[cfg(unix)]
             Mode::Unix { path } => Ok(build_unix_source(
                 path,
-                self.max_length,
+                LinesCodec::new_with_max_length(self.max_length),
                 host_key,
                 out,
-                event_from_str,
+                |host_key, default_host, line: String| {
+                    event_from_str(host_key, default_host, &line)
+                },
             )),
         }
     }
@@ -121,14 +124,15 @@ struct SyslogTcpSource {
 }
 
 impl TcpSource for SyslogTcpSource {
-    type Decoder = LinesCodec;
+    type Decoder = SyslogDecoder;
 
     fn decoder(&self) -> Self::Decoder {
-        LinesCodec::new_with_max_length(self.max_length)
+        SyslogDecoder::new(self.max_length)
     }
 
-    fn build_event(&self, frame: String, host: Bytes) -> Option<Event> {
-        event_from_str(&self.host_key, Some(host), &frame).map(|event| {
+    fn build_event(&self, frame: Bytes, host: Bytes) -> Option<Event> {
+        let line = String::from_utf8_lossy(&frame);
+        event_from_str(&self.host_key, Some(host), &line).map(|event| {
             trace!(
                 message = "Received one event.",
                 event = field::debug(&event)
@@ -139,6 +143,92 @@ impl TcpSource for SyslogTcpSource {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum SyslogFraming {
+    OctetCounting,
+    NewlineDelimited,
+}
+
+/// Splits a TCP stream into syslog messages, telling octet-counted frames
+/// from newline-delimited ones by how each frame starts (RFC 6587, section
+/// 3.4), so senders may use either or even mix them.
+#[derive(Debug, Clone)]
+struct SyslogDecoder {
+    octet_counting: OctetCountingCodec,
+    newline_delimited: BytesDelimitedCodec,
+    /// The framing of the frame being decoded, if one has started.
+    framing: Option<SyslogFraming>,
+}
+
+impl SyslogDecoder {
+    fn new(max_length: usize) -> Self {
+        Self {
+            octet_counting: OctetCountingCodec::new_with_max_length(max_length),
+            newline_delimited: BytesDelimitedCodec::new_with_max_length(b'\n', max_length),
+            framing: None,
+        }
+    }
+
+    /// Picks the framing of the next frame, or `None` if too little of it
+    /// has arrived to tell.
+    fn detect_framing(buf: &mut BytesMut) -> Option<SyslogFraming> {
+        // Some senders follow octet-counted frames with a newline anyway.
+        let blank = buf
+            .iter()
+            .take_while(|b| **b == b'\n' || **b == b'\r')
+            .count();
+        buf.advance(blank);
+
+        let digits = buf.iter().take_while(|b| b.is_ascii_digit()).count();
+        if buf.is_empty() || (digits > 0 && digits == buf.len()) {
+            None
+        } else if digits > 0 && buf[digits] == b' ' {
+            Some(SyslogFraming::OctetCounting)
+        } else {
+            Some(SyslogFraming::NewlineDelimited)
+        }
+    }
+
+    fn decode_frame(&mut self, buf: &mut BytesMut, eof: bool) -> Result<Option<Bytes>, io::Error> {
+        let framing = match self.framing.or_else(|| Self::detect_framing(buf)) {
+            Some(framing) => framing,
+            None if eof && !buf.is_empty() => SyslogFraming::NewlineDelimited,
+            None => return Ok(None),
+        };
+        self.framing = Some(framing);
+
+        let frame = match (framing, eof) {
+            (SyslogFraming::OctetCounting, false) => self.octet_counting.decode(buf)?,
+            (SyslogFraming::OctetCounting, true) => self.octet_counting.decode_eof(buf)?,
+            (SyslogFraming::NewlineDelimited, false) => self.newline_delimited.decode(buf)?,
+            (SyslogFraming::NewlineDelimited, true) => self.newline_delimited.decode_eof(buf)?,
+        };
+
+        Ok(frame.map(|frame| {
+            self.framing = None;
+            match framing {
+                SyslogFraming::NewlineDelimited if frame.ends_with(b"\r") => {
+                    frame.slice_to(frame.len() - 1)
+                }
+                _ => frame,
+            }
+        }))
+    }
+}
+
+impl Decoder for SyslogDecoder {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        self.decode_frame(buf, false)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        self.decode_frame(buf, true)
+    }
+}
+
 pub fn udp(
     addr: SocketAddr,
     _max_length: usize,
@@ -178,32 +268,17 @@ pub fn udp(
     )
 }
 
-/// Function used to resolve the year for syslog messages that don't include the year.
-/// If the current month is January, and the syslog message is for December, it will take the previous year.
-/// Otherwise, take the current year.
-fn resolve_year((month, _date, _hour, _min, _sec): IncompleteDate) -> i32 {
-    let now = Utc::now();
-    if now.month() == 1 && month == 12 {
-        now.year() - 1
-    } else {
-        now.year()
-    }
-}
-
 /**
 * Function to pass to build_unix_source, specific to the Unix mode of the syslog source.
 * Handles the logic of parsing and decoding the syslog message format.
 **/
-// TODO: many more cases to handle:
-// octet framing (i.e. num bytes as ascii string prefix) with and without delimiters
-// null byte delimiter in place of newline
+// TODO: null byte delimiter in place of newline
 fn event_from_str(host_key: &str, default_host: Option<Bytes>, line: &str) -> Option<Event> {
     emit!(SyslogEventReceived {
         byte_size: line.len()
     });
 
-    let line = line.trim();
-    let parsed = syslog_loose::parse_message_with_year(line, resolve_year);
+    let parsed = syslog_message::parse(line);
     let mut event = Event::from(&parsed.msg[..]);
 
     if let Some(host) = &parsed.hostname {
@@ -220,7 +295,7 @@ fn event_from_str(host_key: &str, default_host: Option<Bytes>, line: &str) -> Op
         .as_mut_log()
         .insert(event::log_schema().timestamp_key().clone(), timestamp);
 
-    insert_fields_from_syslog(&mut event, parsed);
+    syslog_message::insert_fields(event.as_mut_log(), parsed);
 
     trace!(
         message = "processing one event.",
@@ -230,45 +305,73 @@ fn event_from_str(host_key: &str, default_host: Option<Bytes>, line: &str) -> Op
     Some(event)
 }
 
-fn insert_fields_from_syslog(event: &mut Event, parsed: Message<&str>) {
-    let log = event.as_mut_log();
+#[cfg(test)]
+mod test {
+    use super::{event_from_str, SyslogConfig, SyslogDecoder};
+    use crate::event::{self, Event};
+    use bytes::{Bytes, BytesMut};
+    use chrono::TimeZone;
+    use tokio01::codec::Decoder;
 
-    if let Some(severity) = parsed.severity {
-        log.insert("severity", severity.as_str());
-    }
-    if let Some(facility) = parsed.facility {
-        log.insert("facility", facility.as_str());
-    }
-    if let Protocol::RFC5424(version) = parsed.protocol {
-        log.insert("version", version as i64);
-    }
-    if let Some(app_name) = parsed.appname {
-        log.insert("appname", app_name);
+    fn decode_frames(decoder: &mut SyslogDecoder, input: &[u8]) -> Vec<Bytes> {
+        let mut buf = BytesMut::from(input);
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.decode_eof(&mut buf).unwrap() {
+            frames.push(frame);
+        }
+        frames
     }
-    if let Some(msg_id) = parsed.msgid {
-        log.insert("msgid", msg_id);
+
+    #[test]
+    fn decodes_octet_counted_frames() {
+        let mut decoder = SyslogDecoder::new(1024);
+        let frames = decode_frames(&mut decoder, b"14 <13>first\nline10 <13>second\n");
+
+        assert_eq!(
+            frames,
+            vec![Bytes::from("<13>first\nline"), Bytes::from("<13>second")]
+        );
     }
-    if let Some(procid) = parsed.procid {
-        let value: Value = match procid {
-            ProcId::PID(pid) => pid.into(),
-            ProcId::Name(name) => name.into(),
-        };
-        log.insert("procid", value);
+
+    #[test]
+    fn decodes_mixed_framing() {
+        let mut decoder = SyslogDecoder::new(1024);
+        let frames = decode_frames(&mut decoder, b"<13>newline\r\n9 <13>octet<13>last");
+
+        assert_eq!(
+            frames,
+            vec![
+                Bytes::from("<13>newline"),
+                Bytes::from("<13>octet"),
+                Bytes::from("<13>last"),
+            ]
+        );
     }
 
-    for element in parsed.structured_data.iter() {
-        for (name, value) in element.params.iter() {
-            let key = format!("{}.{}", element.id, name);
-            log.insert(key, value.clone());
-        }
+    #[test]
+    fn waits_for_octet_count() {
+        let mut decoder = SyslogDecoder::new(1024);
+        let mut buf = BytesMut::from(&b"1"[..]);
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(b"0 <13>hello");
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(b"!\n");
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some(Bytes::from("<13>hello!"))
+        );
+        assert_eq!(decoder.decode_eof(&mut buf).unwrap(), None);
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::{event_from_str, SyslogConfig};
-    use crate::event::{self, Event};
-    use chrono::TimeZone;
+    #[test]
+    fn discards_long_octet_counted_frames() {
+        let mut decoder = SyslogDecoder::new(8);
+        let frames = decode_frames(&mut decoder, b"11 <13>toolong8 <13>fine");
+
+        assert_eq!(frames, vec![Bytes::from("<13>fine")]);
+    }
 
     #[test]
     fn config_tcp() {
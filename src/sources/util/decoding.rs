@@ -0,0 +1,530 @@
+//! Framing and parsing shared by the sources that read raw bytes.
+//!
+//! A source's `decoding` option first splits its input into frames, using
+//! the codecs in `lib/codec`, then parses each frame into an event.
+
+use crate::{
+    event::{self, proto, Event},
+    internal_events::DecoderParseFailed,
+    syslog_message,
+    topology::config::DataType,
+};
+use bytes::{Bytes, BytesMut};
+use chrono::Utc;
+use codec::{BytesDelimitedCodec, LengthDelimitedCodec, OctetCountingCodec};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use snafu::{ResultExt, Snafu};
+use std::io;
+use tokio01::codec::{BytesCodec, Decoder as _};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Delimiter {:?} is not a single-byte ASCII character", delimiter))]
+    NonAsciiDelimiter { delimiter: char },
+}
+
+#[derive(Debug, Snafu)]
+pub enum DecodeError {
+    #[snafu(display("Could not split input into frames: {}", source))]
+    InvalidFrame { source: io::Error },
+    #[snafu(display("Invalid JSON: {}", source))]
+    InvalidJson { source: serde_json::Error },
+    #[snafu(display("Expected a JSON object, got {}", kind))]
+    NotAnObject { kind: &'static str },
+    #[snafu(display("Invalid protobuf: {}", source))]
+    InvalidProtobuf { source: prost::DecodeError },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DecodingConfig {
+    /// How the input is split into frames. Each source has its own default.
+    pub framing: Option<Framing>,
+    #[serde(default)]
+    pub codec: Codec,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Framing {
+    NewlineDelimited {
+        max_length: Option<usize>,
+    },
+    CharacterDelimited {
+        delimiter: char,
+        max_length: Option<usize>,
+    },
+    OctetCounting {
+        max_length: Option<usize>,
+    },
+    LengthDelimited {
+        #[serde(default)]
+        prefix: LengthPrefix,
+        max_length: Option<usize>,
+    },
+    /// The whole input, e.g. a datagram or a request body, is one frame.
+    Bytes,
+}
+
+/// How the length of each frame is written ahead of it.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Derivative, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum LengthPrefix {
+    /// A big-endian `u32`, as written by the `vector` sink.
+    #[derivative(Default)]
+    U32,
+    /// A protobuf varint.
+    Varint,
+}
+
+impl From<LengthPrefix> for codec::LengthPrefix {
+    fn from(prefix: LengthPrefix) -> Self {
+        match prefix {
+            LengthPrefix::U32 => codec::LengthPrefix::U32,
+            LengthPrefix::Varint => codec::LengthPrefix::Varint,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Derivative, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum Codec {
+    /// The frame is the message of a new log event.
+    #[derivative(Default)]
+    Bytes,
+    /// The frame is a JSON object whose fields become the event's fields.
+    Json,
+    /// The frame is a line of `key=value` pairs.
+    Logfmt,
+    /// The frame is an RFC 3164 or RFC 5424 syslog message.
+    Syslog,
+    /// The frame is an event encoded as protobuf, as sent by the `vector`
+    /// sink.
+    Native,
+}
+
+impl DecodingConfig {
+    /// Builds the decoder, splitting frames with `default_framing` unless
+    /// the framing is configured.
+    pub fn build(&self, default_framing: Framing) -> crate::Result<Decoder> {
+        let framer = self.framing.as_ref().unwrap_or(&default_framing).build()?;
+        Ok(Decoder::new(framer, self.codec))
+    }
+
+    pub fn output_type(&self) -> DataType {
+        self.codec.output_type()
+    }
+}
+
+impl Framing {
+    pub fn build(&self) -> crate::Result<Framer> {
+        let max_length = |max_length: &Option<usize>| max_length.unwrap_or(std::usize::MAX);
+        Ok(match self {
+            Framing::NewlineDelimited { max_length: max } => Framer::Delimited(
+                BytesDelimitedCodec::new_with_max_length(b'\n', max_length(max)),
+            ),
+            Framing::CharacterDelimited {
+                delimiter,
+                max_length: max,
+            } => {
+                if !delimiter.is_ascii() {
+                    return Err(BuildError::NonAsciiDelimiter {
+                        delimiter: *delimiter,
+                    }
+                    .into());
+                }
+                Framer::Delimited(BytesDelimitedCodec::new_with_max_length(
+                    *delimiter as u8,
+                    max_length(max),
+                ))
+            }
+            Framing::OctetCounting { max_length: max } => {
+                Framer::OctetCounting(OctetCountingCodec::new_with_max_length(max_length(max)))
+            }
+            Framing::LengthDelimited {
+                prefix,
+                max_length: max,
+            } => Framer::LengthDelimited(LengthDelimitedCodec::new_with_prefix(
+                (*prefix).into(),
+                max_length(max),
+            )),
+            Framing::Bytes => Framer::Bytes(BytesCodec::new()),
+        })
+    }
+}
+
+impl Codec {
+    pub fn output_type(self) -> DataType {
+        match self {
+            // Events from another Vector can be logs or metrics.
+            Codec::Native => DataType::Any,
+            _ => DataType::Log,
+        }
+    }
+
+    pub fn parse(self, frame: Bytes) -> Result<Event, DecodeError> {
+        match self {
+            Codec::Bytes => Ok(Event::from(frame)),
+            Codec::Json => {
+                let value: JsonValue = serde_json::from_slice(&frame).context(InvalidJson)?;
+                match value {
+                    JsonValue::Object(fields) => {
+                        let mut event = new_log();
+                        let log = event.as_mut_log();
+                        for (key, value) in fields {
+                            log.insert(key, value);
+                        }
+                        Ok(event)
+                    }
+                    other => Err(DecodeError::NotAnObject {
+                        kind: json_kind(&other),
+                    }),
+                }
+            }
+            Codec::Logfmt => {
+                let mut event = new_log();
+                let log = event.as_mut_log();
+                for logfmt::Pair { key, val } in logfmt::parse(&String::from_utf8_lossy(&frame)) {
+                    // Bare words without a value aren't logfmt.
+                    if let Some(val) = val {
+                        log.insert(key, val);
+                    }
+                }
+                Ok(event)
+            }
+            Codec::Syslog => {
+                let line = String::from_utf8_lossy(&frame);
+                let parsed = syslog_message::parse(&line);
+                let mut event = Event::from(&parsed.msg[..]);
+                let log = event.as_mut_log();
+                if let Some(host) = &parsed.hostname {
+                    log.insert(event::log_schema().host_key().clone(), host.clone());
+                }
+                let timestamp = parsed
+                    .timestamp
+                    .map(|ts| ts.into())
+                    .unwrap_or_else(Utc::now);
+                log.insert(event::log_schema().timestamp_key().clone(), timestamp);
+                syslog_message::insert_fields(log, parsed);
+                Ok(event)
+            }
+            Codec::Native => proto::EventWrapper::decode(frame)
+                .map(Event::from)
+                .context(InvalidProtobuf),
+        }
+    }
+}
+
+fn new_log() -> Event {
+    let mut event = Event::new_empty_log();
+    event
+        .as_mut_log()
+        .insert(event::log_schema().timestamp_key().clone(), Utc::now());
+    event
+}
+
+fn json_kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Object(_) => "an object",
+        JsonValue::Array(_) => "an array",
+        JsonValue::String(_) => "a string",
+        JsonValue::Number(_) => "a number",
+        JsonValue::Bool(_) => "a boolean",
+        JsonValue::Null => "null",
+    }
+}
+
+/// Splits input into frames with one of the codecs in `lib/codec`.
+#[derive(Debug, Clone)]
+pub enum Framer {
+    Delimited(BytesDelimitedCodec),
+    OctetCounting(OctetCountingCodec),
+    LengthDelimited(LengthDelimitedCodec),
+    Bytes(BytesCodec),
+}
+
+impl tokio01::codec::Decoder for Framer {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        match self {
+            Framer::Delimited(codec) => codec.decode(buf),
+            Framer::OctetCounting(codec) => codec.decode(buf),
+            Framer::LengthDelimited(codec) => codec.decode(buf),
+            Framer::Bytes(codec) => codec.decode(buf).map(|frame| frame.map(BytesMut::freeze)),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        match self {
+            Framer::Delimited(codec) => codec.decode_eof(buf),
+            Framer::OctetCounting(codec) => codec.decode_eof(buf),
+            Framer::LengthDelimited(codec) => codec.decode_eof(buf),
+            Framer::Bytes(codec) => codec
+                .decode_eof(buf)
+                .map(|frame| frame.map(BytesMut::freeze)),
+        }
+    }
+}
+
+/// Decodes input into events, along with the size of the frame each came
+/// from. Frames that can't be parsed are skipped, so a single bad message
+/// doesn't end a connection.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    framer: Framer,
+    codec: Codec,
+}
+
+impl Decoder {
+    pub fn new(framer: Framer, codec: Codec) -> Self {
+        Self { framer, codec }
+    }
+
+    /// Decodes a complete input, such as a request body, failing on the
+    /// first frame that can't be parsed.
+    pub fn decode_all(&mut self, mut buf: BytesMut) -> Result<Vec<Event>, DecodeError> {
+        let mut events = Vec::new();
+        while let Some(frame) = self.framer.decode_eof(&mut buf).context(InvalidFrame)? {
+            events.push(self.codec.parse(frame)?);
+        }
+        Ok(events)
+    }
+
+    /// Decodes a complete message, such as a Kafka record, skipping frames
+    /// that can't be parsed. An empty message framed as bytes is still one
+    /// (empty) frame, as it would be if it weren't empty.
+    pub fn decode_message(&mut self, payload: &[u8]) -> Vec<Event> {
+        if payload.is_empty() {
+            if let Framer::Bytes(_) = self.framer {
+                return match self.codec.parse(Bytes::new()) {
+                    Ok(event) => vec![event],
+                    Err(error) => {
+                        emit!(DecoderParseFailed { error });
+                        Vec::new()
+                    }
+                };
+            }
+        }
+
+        let mut buf = BytesMut::from(payload);
+        let mut events = Vec::new();
+        loop {
+            match self.next_event(&mut buf, true) {
+                Ok(Some((event, _byte_size))) => events.push(event),
+                Ok(None) => break,
+                Err(error) => {
+                    error!(message = "Cannot decode message.", %error);
+                    break;
+                }
+            }
+        }
+        events
+    }
+
+    fn next_event(
+        &mut self,
+        buf: &mut BytesMut,
+        eof: bool,
+    ) -> Result<Option<(Event, usize)>, io::Error> {
+        loop {
+            let frame = if eof {
+                self.framer.decode_eof(buf)?
+            } else {
+                self.framer.decode(buf)?
+            };
+            let frame = match frame {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+
+            let byte_size = frame.len();
+            match self.codec.parse(frame) {
+                Ok(event) => return Ok(Some((event, byte_size))),
+                Err(error) => emit!(DecoderParseFailed { error }),
+            }
+        }
+    }
+}
+
+impl tokio01::codec::Decoder for Decoder {
+    type Item = (Event, usize);
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, io::Error> {
+        self.next_event(buf, false)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, io::Error> {
+        self.next_event(buf, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{metric::MetricValue, Metric, Value};
+
+    fn decode(config: &str, input: &[u8]) -> Vec<Event> {
+        let config: DecodingConfig = toml::from_str(config).unwrap();
+        let mut decoder = config.build(Framing::Bytes).unwrap();
+        decoder.decode_all(input.into()).unwrap()
+    }
+
+    #[test]
+    fn defaults_to_bytes() {
+        let events = decode("", b"foo\nbar");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].as_log()[&event::log_schema().message_key()],
+            "foo\nbar".into()
+        );
+    }
+
+    #[test]
+    fn character_delimited_json() {
+        let events = decode(
+            r#"
+            codec = "json"
+            [framing]
+            method = "character_delimited"
+            delimiter = ","
+            "#,
+            br#"{"a":1},{"b":"two"}"#,
+        );
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_log()[&"a".into()], Value::Integer(1));
+        assert_eq!(events[1].as_log()[&"b".into()], "two".into());
+        assert!(events[1]
+            .as_log()
+            .contains(&event::log_schema().timestamp_key()));
+    }
+
+    #[test]
+    fn rejects_non_ascii_delimiter() {
+        let framing = Framing::CharacterDelimited {
+            delimiter: 'é',
+            max_length: None,
+        };
+        assert!(framing.build().is_err());
+    }
+
+    #[test]
+    fn newline_delimited_logfmt() {
+        let events = decode(
+            r#"
+            codec = "logfmt"
+            [framing]
+            method = "newline_delimited"
+            "#,
+            b"level=info msg=\"hello world\"\nlevel=warn\n",
+        );
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_log()[&"msg".into()], "hello world".into());
+        assert_eq!(events[1].as_log()[&"level".into()], "warn".into());
+    }
+
+    #[test]
+    fn octet_counted_syslog() {
+        let message = "<13>1 2019-02-13T19:48:34+00:00 74794bfb6795 root 8449 - - i am\nfoobar";
+        let input = format!("{} {}", message.len(), message);
+        let events = decode(
+            r#"
+            codec = "syslog"
+            [framing]
+            method = "octet_counting"
+            "#,
+            input.as_bytes(),
+        );
+
+        assert_eq!(events.len(), 1);
+        let log = events[0].as_log();
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "i am\nfoobar".into()
+        );
+        assert_eq!(log[&event::log_schema().host_key()], "74794bfb6795".into());
+        assert_eq!(log[&"appname".into()], "root".into());
+    }
+
+    #[test]
+    fn length_delimited_native() {
+        let metric = Event::Metric(Metric {
+            name: "requests".into(),
+            timestamp: None,
+            tags: None,
+            kind: event::metric::MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        });
+        let mut frame = Vec::new();
+        proto::EventWrapper::from(metric.clone())
+            .encode(&mut frame)
+            .unwrap();
+        let mut input = (frame.len() as u32).to_be_bytes().to_vec();
+        input.extend(frame);
+
+        let config = DecodingConfig {
+            framing: Some(Framing::LengthDelimited {
+                prefix: LengthPrefix::U32,
+                max_length: None,
+            }),
+            codec: Codec::Native,
+        };
+        assert_eq!(config.output_type(), DataType::Any);
+        let events = config
+            .build(Framing::Bytes)
+            .unwrap()
+            .decode_all(input.into())
+            .unwrap();
+
+        assert_eq!(events, vec![metric]);
+    }
+
+    #[test]
+    fn varint_length_delimited_native() {
+        let log = Event::from("hello");
+        let mut input = Vec::new();
+        proto::EventWrapper::from(log.clone())
+            .encode_length_delimited(&mut input)
+            .unwrap();
+        proto::EventWrapper::from(log.clone())
+            .encode_length_delimited(&mut input)
+            .unwrap();
+
+        let events = decode(
+            r#"
+            codec = "native"
+            [framing]
+            method = "length_delimited"
+            prefix = "varint"
+            "#,
+            &input,
+        );
+
+        assert_eq!(events, vec![log.clone(), log]);
+    }
+
+    #[test]
+    fn skips_frames_that_fail_to_parse() {
+        let mut decoder = Decoder::new(
+            Framing::NewlineDelimited { max_length: None }
+                .build()
+                .unwrap(),
+            Codec::Json,
+        );
+        let mut buf = BytesMut::from(&b"[1]\n{\"a\":true}\n"[..]);
+
+        let (event, byte_size) = decoder.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(event.as_log()[&"a".into()], Value::Boolean(true));
+        assert_eq!(byte_size, 10);
+        assert!(decoder.decode(&mut buf).unwrap().is_none());
+    }
+}
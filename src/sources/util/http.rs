@@ -1,5 +1,6 @@
 use crate::event::Event;
 use crate::tls::{MaybeTlsSettings, TlsConfig};
+use bytes::Buf;
 use futures01::{sync::mpsc, Future, IntoFuture, Sink};
 use serde::Serialize;
 use std::error::Error;
@@ -38,6 +39,12 @@ pub trait HttpSource: Clone + Send + Sync + 'static {
         header_map: HeaderMap,
     ) -> Result<Vec<Event>, ErrorMessage>;
 
+    /// The largest request body accepted, in bytes. Larger requests are
+    /// rejected with `413 Payload Too Large`.
+    fn max_body_size(&self) -> Option<u64> {
+        None
+    }
+
     fn run(
         self,
         address: SocketAddr,
@@ -54,16 +61,30 @@ pub trait HttpSource: Clone + Send + Sync + 'static {
                 filter = filter.and(warp::path(s)).boxed();
             }
         }
+        let max_body_size = self.max_body_size();
         let svc = filter
             .and(warp::path::end())
-            .and(warp::header::headers_cloned())
+            .and(
+                // Turn away requests announcing a large body before reading it.
+                warp::header::headers_cloned().and_then(move |headers: HeaderMap| {
+                    let length = headers
+                        .get("content-length")
+                        .and_then(|length| length.to_str().ok())
+                        .and_then(|length| length.parse::<u64>().ok());
+                    check_body_size(length, max_body_size)
+                        .map(|_| headers)
+                        .map_err(warp::reject::custom)
+                }),
+            )
             .and(warp::body::concat())
-            .and_then(move |headers: HeaderMap, body| {
+            .and_then(move |headers: HeaderMap, body: FullBody| {
                 let out = out.clone();
                 let trigger = trigger.clone();
                 info!("Handling http request: {:?}", headers);
 
-                self.build_event(body, headers)
+                // Chunked bodies don't announce their length.
+                check_body_size(Some(body.remaining() as u64), max_body_size)
+                    .and_then(|_| self.build_event(body, headers))
                     .map_err(warp::reject::custom)
                     .into_future()
                     .and_then(|events| {
@@ -107,3 +128,16 @@ pub trait HttpSource: Clone + Send + Sync + 'static {
         Ok(Box::new(server))
     }
 }
+
+fn check_body_size(length: Option<u64>, max_body_size: Option<u64>) -> Result<(), ErrorMessage> {
+    match (length, max_body_size) {
+        (Some(length), Some(max_body_size)) if length > max_body_size => Err(ErrorMessage::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Request body of {} bytes is larger than {} bytes",
+                length, max_body_size
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "sources-decoding")]
+mod decoding;
 #[cfg(feature = "sources-http")]
 mod http;
 #[cfg(feature = "sources-socket")]
@@ -5,6 +7,8 @@ mod tcp;
 #[cfg(all(unix, feature = "sources-socket"))]
 mod unix;
 
+#[cfg(feature = "sources-decoding")]
+pub use self::decoding::{Codec, DecodeError, Decoder, DecodingConfig, Framer, Framing};
 #[cfg(feature = "sources-http")]
 pub use self::http::{ErrorMessage, HttpSource};
 #[cfg(feature = "sources-socket")]
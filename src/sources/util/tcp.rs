@@ -1,12 +1,13 @@
 use crate::{
+    event::{metadata, Value},
     internal_events::TcpConnectionError,
     shutdown::ShutdownSignal,
     stream::StreamExt,
-    tls::{MaybeTlsListener, MaybeTlsSettings},
+    tls::{MaybeTlsIncomingStream, MaybeTlsListener, MaybeTlsSettings},
     Event,
 };
 use bytes::Bytes;
-use futures01::{future, sync::mpsc, Future, Sink, Stream};
+use futures01::{future, stream, sync::mpsc, try_ready, Async, Future, Poll, Sink, Stream};
 use listenfd::ListenFd;
 use serde::{de, Deserialize, Deserializer, Serialize};
 use std::{
@@ -17,8 +18,8 @@ use std::{
 use stream_cancel::Tripwire;
 use tokio01::{
     codec::{Decoder, FramedRead},
-    net::TcpListener,
-    prelude::AsyncRead,
+    io::AsyncWrite,
+    net::{TcpListener, TcpStream},
     reactor::Handle,
     timer,
 };
@@ -69,6 +70,15 @@ pub trait TcpSource: Clone + Send + 'static {
         host: Bytes,
     ) -> Option<Event>;
 
+    /// Returns the reply to send to the client once the event built from
+    /// `frame` has been sent on, for protocols that acknowledge delivery.
+    fn build_ack(
+        &self,
+        _frame: &<Self::Decoder as tokio01::codec::Decoder>::Item,
+    ) -> Option<Bytes> {
+        None
+    }
+
     fn run(
         self,
         addr: SocketListenAddr,
@@ -150,27 +160,80 @@ pub trait TcpSource: Clone + Send + 'static {
 
 fn handle_stream(
     span: Span,
-    socket: impl AsyncRead + Send + 'static,
+    socket: MaybeTlsIncomingStream<TcpStream>,
     source: impl TcpSource,
     tripwire: impl Future<Item = (), Error = ()> + Send + 'static,
     host: Bytes,
     out: impl Sink<SinkItem = Event, SinkError = ()> + Send + 'static,
 ) {
-    let handler = FramedRead::new(socket, source.decoder())
+    let mut framed = FramedRead::new(socket, source.decoder());
+    // Acks are queued once their events are sent on, and written back to
+    // the client in between reads.
+    let (ack_tx, mut ack_rx) = mpsc::unbounded();
+    let mut ack = Bytes::new();
+    // The client identity is only known once the TLS handshake, which
+    // happens on the first read, has completed.
+    let mut identity = None;
+    let frames = stream::poll_fn(move || {
+        write_acks(framed.get_mut(), &mut ack_rx, &mut ack)?;
+        let frame = try_ready!(framed.poll());
+        if identity.is_none() {
+            identity = framed.get_ref().peer_identity().map(Value::from);
+        }
+        Ok(Async::Ready(frame.map(|frame| (frame, identity.clone()))))
+    });
+
+    let handler = frames
         .take_until(tripwire)
-        .filter_map(move |frame| {
-            let host = host.clone();
-            source.build_event(frame, host)
-        })
         .map_err(|error| {
             emit!(TcpConnectionError { error });
         })
-        .forward(out)
+        .fold(out, move |out, (frame, identity)| {
+            let ack = source.build_ack(&frame);
+            let event = source.build_event(frame, host.clone()).map(|mut event| {
+                if let (Some(identity), Some(metadata)) = (identity, event.metadata_mut()) {
+                    metadata.insert(metadata::TLS_CLIENT_IDENTITY, identity);
+                }
+                event
+            });
+            let ack_tx = ack_tx.clone();
+            out.send_all(stream::iter_ok::<_, ()>(event))
+                .map(move |(out, _)| {
+                    if let Some(ack) = ack {
+                        // The connection is gone if this fails, so is the
+                        // client waiting for it.
+                        let _ = ack_tx.unbounded_send(ack);
+                    }
+                    out
+                })
+        })
         .map(|_| debug!("connection closed."))
         .map_err(|_| warn!("Error received while processing TCP source"));
     tokio01::spawn(handler.instrument(span));
 }
 
+/// Writes as many of the queued acks to `socket` as it takes without
+/// blocking, keeping the rest of a partly written one in `ack`.
+fn write_acks(
+    socket: &mut impl AsyncWrite,
+    acks: &mut mpsc::UnboundedReceiver<Bytes>,
+    ack: &mut Bytes,
+) -> Poll<(), io::Error> {
+    loop {
+        while !ack.is_empty() {
+            match socket.poll_write(&ack[..])? {
+                Async::Ready(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Async::Ready(written) => ack.advance(written),
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+        match acks.poll() {
+            Ok(Async::Ready(Some(next))) => *ack = next,
+            _ => return socket.poll_flush(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum SocketListenAddr {
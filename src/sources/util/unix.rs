@@ -1,31 +1,35 @@
 use crate::{emit, event::Event, internal_events::UnixSocketError, sources::Source};
 use bytes::Bytes;
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
-use std::path::PathBuf;
+use std::{io, path::PathBuf};
 use tokio01::{
     self,
-    codec::{FramedRead, LinesCodec},
+    codec::{Decoder, FramedRead},
 };
 use tokio_uds::UnixListener;
 use tracing::field;
 use tracing_futures::Instrument;
 
 /**
-* Returns a Source object corresponding to a Unix domain socket.  Passing in different functions
-* for build_event can allow for different source-specific logic (such as decoding syslog messages
-* in the syslog source).
+* Returns a Source object corresponding to a Unix domain socket.  Passing in different decoders
+* and functions for build_event can allow for different source-specific logic (such as decoding
+* syslog messages in the syslog source).
 **/
-pub fn build_unix_source(
+pub fn build_unix_source<D>(
     path: PathBuf,
-    max_length: usize,
+    decoder: D,
     host_key: String,
     out: mpsc::Sender<Event>,
-    build_event: impl Fn(&str, Option<Bytes>, &str) -> Option<Event>
+    build_event: impl Fn(&str, Option<Bytes>, D::Item) -> Option<Event>
         + std::marker::Send
         + std::marker::Sync
         + std::clone::Clone
         + 'static,
-) -> Source {
+) -> Source
+where
+    D: Decoder<Error = io::Error> + Clone + Send + 'static,
+    D::Item: Send,
+{
     let out = out.sink_map_err(|e| error!("error sending line: {:?}", e));
 
     Box::new(future::lazy(move || {
@@ -57,8 +61,8 @@ pub fn build_unix_source(
                 let build_event = build_event.clone();
                 let received_from: Option<Bytes> =
                     path.map(|p| p.to_string_lossy().into_owned().into());
-                let lines_in = FramedRead::new(socket, LinesCodec::new_with_max_length(max_length))
-                    .filter_map(move |line| build_event(&host_key, received_from.clone(), &line))
+                let lines_in = FramedRead::new(socket, decoder.clone())
+                    .filter_map(move |frame| build_event(&host_key, received_from.clone(), frame))
                     .map_err(move |error| {
                         emit!(UnixSocketError {
                             error,
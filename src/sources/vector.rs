@@ -97,7 +97,7 @@ mod test {
     use crate::{
         event::{
             metric::{MetricKind, MetricValue},
-            Metric,
+            Metric, Trace,
         },
         sinks::vector::VectorSinkConfig,
         test_util::{next_addr, wait_for_tcp, CollectCurrent},
@@ -105,6 +105,7 @@ mod test {
         topology::config::{GlobalOptions, SinkConfig, SinkContext, SourceConfig},
         Event,
     };
+    use chrono::Utc;
     use futures01::{stream, sync::mpsc, Future, Sink};
     use std::net::SocketAddr;
 
@@ -142,6 +143,13 @@ mod test {
                 kind: MetricKind::Absolute,
                 value: MetricValue::Counter { value: 1.0 },
             }),
+            Event::from(Trace::new(
+                "5b8aa5a2d2c872e8321cf37308d69df2",
+                "051581bf3cb55c13",
+                "and a trace",
+                Utc::now(),
+                Utc::now(),
+            )),
         ];
 
         let _ = rt
@@ -0,0 +1,58 @@
+//! Syslog message parsing shared by the `syslog` source and the
+//! `syslog_parser` transform.
+
+use crate::event::{LogEvent, Value};
+use chrono::{Datelike, Utc};
+use syslog_loose::{self, IncompleteDate, Message, ProcId, Protocol};
+
+/// Parses a syslog line in either the RFC 3164 or the RFC 5424 format. The
+/// parser is lenient: anything it can't make sense of ends up in `msg`.
+pub fn parse(line: &str) -> Message<&str> {
+    syslog_loose::parse_message_with_year(line.trim(), resolve_year)
+}
+
+/// Function used to resolve the year for syslog messages that don't include the year.
+/// If the current month is January, and the syslog message is for December, it will take the previous year.
+/// Otherwise, take the current year.
+fn resolve_year((month, _date, _hour, _min, _sec): IncompleteDate) -> i32 {
+    let now = Utc::now();
+    if now.month() == 1 && month == 12 {
+        now.year() - 1
+    } else {
+        now.year()
+    }
+}
+
+/// Inserts the header fields and the RFC 5424 structured data of a parsed
+/// message into `log`. The message, host and timestamp are left to the caller.
+pub fn insert_fields(log: &mut LogEvent, parsed: Message<&str>) {
+    if let Some(severity) = parsed.severity {
+        log.insert("severity", severity.as_str());
+    }
+    if let Some(facility) = parsed.facility {
+        log.insert("facility", facility.as_str());
+    }
+    if let Protocol::RFC5424(version) = parsed.protocol {
+        log.insert("version", version as i64);
+    }
+    if let Some(app_name) = parsed.appname {
+        log.insert("appname", app_name);
+    }
+    if let Some(msg_id) = parsed.msgid {
+        log.insert("msgid", msg_id);
+    }
+    if let Some(procid) = parsed.procid {
+        let value: Value = match procid {
+            ProcId::PID(pid) => pid.into(),
+            ProcId::Name(name) => name.into(),
+        };
+        log.insert("procid", value);
+    }
+
+    for element in parsed.structured_data.iter() {
+        for (name, value) in element.params.iter() {
+            let key = format!("{}.{}", element.id, name);
+            log.insert(key, value.clone());
+        }
+    }
+}
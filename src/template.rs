@@ -1,18 +1,21 @@
 use crate::{
     event::{self, Value},
+    types::parse_timestamp,
     Event,
 };
 use bytes::Bytes;
 use chrono::{
     format::{strftime::StrftimeItems, Item},
-    Utc,
+    DateTime, Utc,
 };
 use lazy_static::lazy_static;
-use regex::{Captures, Regex};
+use regex::Regex;
 use serde::{
     de::{self, Deserialize, Deserializer, Visitor},
     ser::{Serialize, Serializer},
 };
+use sha2::{Digest, Sha256};
+use snafu::Snafu;
 use std::fmt;
 use std::path::PathBuf;
 use string_cache::DefaultAtom as Atom;
@@ -21,22 +24,32 @@ lazy_static! {
     static ref RE: Regex = Regex::new(r"\{\{(?P<key>[^\}]+)\}\}").unwrap();
 }
 
+#[derive(Debug, Snafu)]
+pub enum TemplateError {
+    #[snafu(display("Invalid template expression {:?}: {}", expression, reason))]
+    InvalidExpression { expression: String, reason: String },
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Template {
     src: String,
     src_bytes: Bytes,
+    parts: Vec<Part>,
     has_ts: bool,
     has_fields: bool,
 }
 
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Expression(Expression),
+}
+
 impl From<&str> for Template {
     fn from(src: &str) -> Template {
-        Template {
-            src: src.into(),
-            src_bytes: src.into(),
-            has_ts: StrftimeItems::new(src).filter(is_dynamic).count() > 0,
-            has_fields: RE.is_match(src),
-        }
+        // Expressions that don't parse are looked up as field names, as all
+        // of them were before expressions had a syntax.
+        Template::build(src, false).expect("lenient parsing can't fail")
     }
 }
 
@@ -63,14 +76,75 @@ impl From<String> for Template {
 }
 
 impl Template {
+    /// Parses a template, failing on invalid `{{ ... }}` expressions. An
+    /// expression without any fallbacks, filters or strings is taken as a
+    /// field name, even if it contains spaces.
+    pub fn parse(src: &str) -> Result<Template, TemplateError> {
+        Template::build(src, true)
+    }
+
+    fn build(src: &str, strict: bool) -> Result<Template, TemplateError> {
+        let mut parts = Vec::new();
+        let mut last = 0;
+        for captures in RE.captures_iter(src) {
+            let whole = captures.get(0).expect("src should match regex");
+            let key = captures.get(1).expect("src should match regex").as_str();
+            if whole.start() > last {
+                parts.push(Part::Literal(src[last..whole.start()].into()));
+            }
+            let expression = match Expression::parse(key) {
+                Ok(expression) => expression,
+                Err(reason) if strict && uses_expression_syntax(key) => {
+                    return Err(TemplateError::InvalidExpression {
+                        expression: key.trim().into(),
+                        reason,
+                    })
+                }
+                Err(_) => Expression::field(key.trim()),
+            };
+            parts.push(Part::Expression(expression));
+            last = whole.end();
+        }
+        if last < src.len() {
+            parts.push(Part::Literal(src[last..].into()));
+        }
+
+        // Only strftime specifiers outside of expressions format the
+        // timestamp of the event.
+        let has_ts = parts.iter().any(|part| match part {
+            Part::Literal(literal) => StrftimeItems::new(literal).any(|item| is_dynamic(&item)),
+            Part::Expression(_) => false,
+        });
+        Ok(Template {
+            src: src.into(),
+            src_bytes: src.into(),
+            has_ts,
+            has_fields: RE.is_match(src),
+            parts,
+        })
+    }
+
     pub fn render(&self, event: &Event) -> Result<Bytes, Vec<Atom>> {
         match (self.has_fields, self.has_ts) {
             (false, false) => Ok(self.src_bytes.clone()),
-            (true, false) => render_fields(&self.src, event).map(Bytes::from),
+            (true, false) => self.render_fields(event, Escape::Nothing).map(Bytes::from),
             (false, true) => Ok(render_timestamp(&self.src, event).into()),
             (true, true) => {
-                let tmp = render_fields(&self.src, event)?;
-                Ok(render_timestamp(&tmp, event).into())
+                // Plain `{{ field }}` values may hold strftime specifiers of
+                // their own, but `%` in the output of fallbacks and filters
+                // (such as `url_encode`) is taken literally, as are values
+                // that aren't valid specifiers.
+                let tmp = self.render_fields(event, Escape::Filtered)?;
+                if is_valid_format(&tmp) {
+                    return Ok(render_timestamp(&tmp, event).into());
+                }
+                let tmp = self.render_fields(event, Escape::All)?;
+                if is_valid_format(&tmp) {
+                    Ok(render_timestamp(&tmp, event).into())
+                } else {
+                    // The template's own specifiers are invalid.
+                    self.render_fields(event, Escape::Nothing).map(Bytes::from)
+                }
             }
         }
     }
@@ -80,14 +154,18 @@ impl Template {
             .map(|bytes| String::from_utf8(Vec::from(bytes.as_ref())).expect("this is a bug"))
     }
 
+    /// Returns the fields referenced by the template, including those only
+    /// used as fallbacks.
     pub fn get_fields(&self) -> Option<Vec<Atom>> {
         if self.has_fields {
-            RE.captures_iter(&self.src)
-                .map(|c| {
-                    c.get(1)
-                        .map(|s| Atom::from(s.as_str().trim()))
-                        .expect("src should match regex")
+            self.parts
+                .iter()
+                .filter_map(|part| match part {
+                    Part::Expression(expression) => Some(expression.fields()),
+                    Part::Literal(_) => None,
                 })
+                .flatten()
+                .cloned()
                 .collect::<Vec<_>>()
                 .into()
         } else {
@@ -102,38 +180,308 @@ impl Template {
     pub fn get_ref(&self) -> &Bytes {
         &self.src_bytes
     }
+
+    fn render_fields(&self, event: &Event, escape: Escape) -> Result<String, Vec<Atom>> {
+        let mut missing_fields = Vec::new();
+        let mut out = String::with_capacity(self.src.len());
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => out.push_str(literal),
+                Part::Expression(expression) => match expression.render(event) {
+                    Some(value) => match escape {
+                        Escape::Filtered if !expression.is_plain_field() => {
+                            out.push_str(&value.replace('%', "%%"))
+                        }
+                        Escape::All => out.push_str(&value.replace('%', "%%")),
+                        _ => out.push_str(&value),
+                    },
+                    None => missing_fields.extend(expression.fields().cloned()),
+                },
+            }
+        }
+        if missing_fields.is_empty() {
+            Ok(out)
+        } else {
+            Err(missing_fields)
+        }
+    }
+}
+
+/// Which rendered expressions to escape strftime specifiers in.
+#[derive(Clone, Copy)]
+enum Escape {
+    Nothing,
+    /// All but plain `{{ field }}` expressions.
+    Filtered,
+    All,
+}
+
+/// Whether an expression that failed to parse was meant as one, rather than
+/// as a field name with spaces in it.
+fn uses_expression_syntax(src: &str) -> bool {
+    src.contains("??") || src.contains('|') || src.contains('"')
+}
+
+fn is_valid_format(src: &str) -> bool {
+    StrftimeItems::new(src).all(|item| item != Item::Error)
+}
+
+/// A `{{ ... }}` expression: a field, optionally followed by fallbacks
+/// (`?? other_field`, `?? "literal"`), and filters (`| lowercase`).
+#[derive(Debug, Clone, PartialEq)]
+struct Expression {
+    operands: Vec<Operand>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Field(Atom),
+    Literal(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Lowercase,
+    Uppercase,
+    Truncate(usize),
+    Replace(String, String),
+    Hash,
+    UrlEncode,
+    Strftime(String),
 }
 
-fn render_fields(src: &str, event: &Event) -> Result<String, Vec<Atom>> {
-    let mut missing_fields = Vec::new();
-    let out = RE
-        .replace_all(src, |caps: &Captures<'_>| {
-            let key = caps
-                .get(1)
-                .map(|s| Atom::from(s.as_str().trim()))
-                .expect("src should match regex");
-            if let Some(val) = event.as_log().get(&key) {
-                val.to_string_lossy()
-            } else {
-                missing_fields.push(key.clone());
-                String::new()
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    String(String),
+    Fallback,
+    Pipe,
+    Open,
+    Close,
+    Comma,
+}
+
+impl Expression {
+    fn field(name: &str) -> Self {
+        Expression {
+            operands: vec![Operand::Field(name.into())],
+            filters: Vec::new(),
+        }
+    }
+
+    fn parse(src: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(src)?.into_iter().peekable();
+
+        let mut operands = vec![parse_operand(tokens.next())?];
+        while tokens.peek() == Some(&Token::Fallback) {
+            tokens.next();
+            operands.push(parse_operand(tokens.next())?);
+        }
+
+        let mut filters = Vec::new();
+        while let Some(token) = tokens.next() {
+            if token != Token::Pipe {
+                return Err("expected `??` or `|` after a value".into());
             }
+            let name = match tokens.next() {
+                Some(Token::Word(name)) => name,
+                _ => return Err("expected a filter name after `|`".into()),
+            };
+
+            let mut args = Vec::new();
+            if tokens.peek() == Some(&Token::Open) {
+                tokens.next();
+                loop {
+                    match tokens.next() {
+                        Some(Token::Close) if args.is_empty() => break,
+                        Some(Token::Word(arg)) | Some(Token::String(arg)) => args.push(arg),
+                        _ => return Err(format!("invalid arguments to filter `{}`", name)),
+                    }
+                    match tokens.next() {
+                        Some(Token::Comma) => (),
+                        Some(Token::Close) => break,
+                        _ => return Err(format!("invalid arguments to filter `{}`", name)),
+                    }
+                }
+            }
+
+            filters.push(Filter::new(&name, args)?);
+        }
+
+        Ok(Expression { operands, filters })
+    }
+
+    fn is_plain_field(&self) -> bool {
+        match self.operands.as_slice() {
+            [Operand::Field(_)] => self.filters.is_empty(),
+            _ => false,
+        }
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &Atom> {
+        self.operands.iter().filter_map(|operand| match operand {
+            Operand::Field(field) => Some(field),
+            Operand::Literal(_) => None,
         })
-        .into_owned();
-    if missing_fields.is_empty() {
-        Ok(out)
-    } else {
-        Err(missing_fields)
     }
+
+    fn render(&self, event: &Event) -> Option<String> {
+        let value = self.operands.iter().find_map(|operand| match operand {
+            Operand::Field(field) => match event {
+                Event::Log(log) => log.get(field).cloned(),
+                Event::Metric(metric) => metric.lookup(field),
+                Event::Trace(trace) => trace.lookup(field),
+            },
+            Operand::Literal(literal) => Some(literal.as_str().into()),
+        })?;
+
+        self.filters
+            .iter()
+            .try_fold(value, |value, filter| filter.apply(value))
+            .map(|value| value.to_string_lossy())
+    }
+}
+
+fn parse_operand(token: Option<Token>) -> Result<Operand, String> {
+    match token {
+        Some(Token::Word(field)) => Ok(Operand::Field(field.into())),
+        Some(Token::String(literal)) => Ok(Operand::Literal(literal)),
+        _ => Err("expected a field name or a quoted string".into()),
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = src.trim_start();
+    while !rest.is_empty() {
+        let (token, len) = if rest.starts_with("??") {
+            (Token::Fallback, 2)
+        } else if rest.starts_with('|') {
+            (Token::Pipe, 1)
+        } else if rest.starts_with('(') {
+            (Token::Open, 1)
+        } else if rest.starts_with(')') {
+            (Token::Close, 1)
+        } else if rest.starts_with(',') {
+            (Token::Comma, 1)
+        } else if rest.starts_with('"') {
+            let (string, len) = tokenize_string(rest)?;
+            (Token::String(string), len)
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || "|(),\"".contains(c))
+                .unwrap_or_else(|| rest.len());
+            let end = rest[..end].find("??").unwrap_or(end);
+            (Token::Word(rest[..end].into()), end)
+        };
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Reads a double quoted string, in which a backslash escapes the following
+/// character, and returns it along with the length of its source.
+fn tokenize_string(src: &str) -> Result<(String, usize), String> {
+    let mut string = String::new();
+    let mut chars = src.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped)) => string.push(escaped),
+                None => break,
+            },
+            '"' => return Ok((string, index + 1)),
+            c => string.push(c),
+        }
+    }
+    Err("unterminated string".into())
+}
+
+impl Filter {
+    fn new(name: &str, args: Vec<String>) -> Result<Self, String> {
+        let filter = match (name, args.as_slice()) {
+            ("lowercase", []) => Filter::Lowercase,
+            ("uppercase", []) => Filter::Uppercase,
+            ("truncate", [length]) => Filter::Truncate(
+                length
+                    .parse()
+                    .map_err(|_| format!("`truncate` expects a length, got {:?}", length))?,
+            ),
+            ("replace", [from, to]) => Filter::Replace(from.clone(), to.clone()),
+            ("hash", []) => Filter::Hash,
+            ("url_encode", []) => Filter::UrlEncode,
+            ("strftime", [format]) => {
+                if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                    return Err(format!("invalid strftime format {:?}", format));
+                }
+                Filter::Strftime(format.clone())
+            }
+            ("lowercase", _)
+            | ("uppercase", _)
+            | ("truncate", _)
+            | ("replace", _)
+            | ("hash", _)
+            | ("url_encode", _)
+            | ("strftime", _) => {
+                return Err(format!("wrong number of arguments to filter `{}`", name))
+            }
+            _ => return Err(format!("unknown filter `{}`", name)),
+        };
+        Ok(filter)
+    }
+
+    /// Applies the filter, returning `None` if it doesn't apply to the value.
+    fn apply(&self, value: Value) -> Option<Value> {
+        let value = match self {
+            Filter::Lowercase => value.to_string_lossy().to_lowercase(),
+            Filter::Uppercase => value.to_string_lossy().to_uppercase(),
+            Filter::Truncate(length) => value.to_string_lossy().chars().take(*length).collect(),
+            Filter::Replace(from, to) => value.to_string_lossy().replace(from.as_str(), to),
+            Filter::Hash => Sha256::digest(&value.as_bytes())
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            Filter::UrlEncode => url_encode(&value.to_string_lossy()),
+            Filter::Strftime(format) => {
+                let timestamp = match value {
+                    Value::Timestamp(timestamp) => timestamp,
+                    value => parse_timestamp(&value.to_string_lossy()).ok()?,
+                };
+                timestamp.format(format).to_string()
+            }
+        };
+        Some(value.into())
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986.
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
 }
 
 fn render_timestamp(src: &str, event: &Event) -> String {
-    let timestamp = match event {
+    let timestamp: Option<&DateTime<Utc>> = match event {
         Event::Log(log) => log
             .get(&event::log_schema().timestamp_key())
             .and_then(Value::as_timestamp),
-        _ => None,
+        Event::Metric(metric) => metric.timestamp.as_ref(),
+        Event::Trace(trace) => Some(&trace.start_time),
     };
+    // Formatting would fail on invalid specifiers, so leave them be.
+    if !is_valid_format(src) {
+        return src.into();
+    }
     if let Some(ts) = timestamp {
         ts.format(src).to_string()
     } else {
@@ -163,7 +511,7 @@ impl<'de> Visitor<'de> for TemplateVisitor {
     where
         E: de::Error,
     {
-        Ok(Template::from(s))
+        Template::parse(s).map_err(de::Error::custom)
     }
 }
 
@@ -360,4 +708,156 @@ mod tests {
             template.render(&event)
         )
     }
+
+    #[test]
+    fn render_dynamic_with_fallbacks() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("backup", "second");
+        let template = Template::parse(r#"{{ first ?? backup }}-{{ none ?? "unknown" }}"#).unwrap();
+
+        assert_eq!(Ok(Bytes::from("second-unknown")), template.render(&event));
+        assert_eq!(
+            template.get_fields(),
+            Some(vec![
+                Atom::from("first"),
+                Atom::from("backup"),
+                Atom::from("none")
+            ])
+        );
+
+        let template = Template::parse("{{ first ?? second }}").unwrap();
+        assert_eq!(
+            Err(vec![Atom::from("first"), Atom::from("second")]),
+            template.render(&event)
+        );
+    }
+
+    #[test]
+    fn render_dynamic_with_filters() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("app", "My App/Prod");
+        let render = |src: &str| Template::parse(src).unwrap().render_string(&event);
+
+        assert_eq!(Ok("my app/prod".into()), render("{{ app | lowercase }}"));
+        assert_eq!(Ok("MY APP/PROD".into()), render("{{ app | uppercase }}"));
+        assert_eq!(Ok("My A".into()), render("{{ app | truncate(4) }}"));
+        assert_eq!(
+            Ok("My_App-Prod".into()),
+            render(r#"{{ app | replace(" ", "_") | replace("/", "-") }}"#)
+        );
+        assert_eq!(
+            Ok("My%20App%2FProd".into()),
+            render("{{ app | url_encode }}")
+        );
+        assert_eq!(
+            Ok("2cf24dba".into()),
+            render(r#"{{ missing ?? "hello" | hash | truncate(8) }}"#)
+        );
+    }
+
+    #[test]
+    fn render_dynamic_with_field_strftime() {
+        let mut event = Event::from("hello world");
+        event
+            .as_mut_log()
+            .insert("created", Utc.ymd(1999, 12, 31).and_hms(23, 0, 0));
+        event.as_mut_log().insert("updated", "2001-02-03T04:05:06Z");
+        event.as_mut_log().insert("junk", "not a time");
+        event.as_mut_log().insert(
+            crate::event::log_schema().timestamp_key().clone(),
+            Utc.ymd(2020, 1, 2).and_hms(0, 0, 0),
+        );
+
+        let template = Template::parse(
+            r#"{{ created | strftime("%Y") }}/{{ updated | strftime("%m-%d") }}/%F"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(Bytes::from("1999/02-03/2020-01-02")),
+            template.render(&event)
+        );
+
+        let template = Template::parse(r#"{{ junk | strftime("%Y") }}"#).unwrap();
+        assert_eq!(Err(vec![Atom::from("junk")]), template.render(&event));
+    }
+
+    #[test]
+    fn render_metric() {
+        use crate::event::metric::{Metric, MetricKind, MetricValue};
+
+        let event = Event::Metric(Metric {
+            name: "requests".into(),
+            timestamp: Some(Utc.ymd(2001, 2, 3).and_hms(4, 5, 6)),
+            tags: Some(
+                vec![("host".to_owned(), "web-1".to_owned())]
+                    .into_iter()
+                    .collect(),
+            ),
+            kind: MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        });
+        let template =
+            Template::parse("{{ metric.name }}-{{ host }}-{{ region ?? \"none\" }}-%F").unwrap();
+
+        assert_eq!(
+            Ok(Bytes::from("requests-web-1-none-2001-02-03")),
+            template.render(&event)
+        );
+    }
+
+    #[test]
+    fn parse_invalid_expressions() {
+        let error = |src: &str| Template::parse(src).unwrap_err().to_string();
+
+        assert_eq!(
+            error("{{ foo | titlecase }}"),
+            r#"Invalid template expression "foo | titlecase": unknown filter `titlecase`"#
+        );
+        assert_eq!(
+            error("{{ foo | truncate }}"),
+            r#"Invalid template expression "foo | truncate": wrong number of arguments to filter `truncate`"#
+        );
+        assert_eq!(
+            error(r#"{{ foo ?? "open }}"#),
+            r#"Invalid template expression "foo ?? \"open": unterminated string"#
+        );
+        assert_eq!(
+            error("{{ foo bar | lowercase }}"),
+            r#"Invalid template expression "foo bar | lowercase": expected `??` or `|` after a value"#
+        );
+
+        // Without any expression syntax, the whole expression is a field name,
+        // as it was before expressions had a syntax.
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("foo bar", "baz");
+        assert_eq!(
+            Ok(Bytes::from("baz")),
+            Template::from("{{ foo bar }}").render(&event)
+        );
+        assert_eq!(
+            Ok(Bytes::from("baz")),
+            Template::parse("{{ foo bar }}").unwrap().render(&event)
+        );
+        let template: Template = serde_json::from_str(r#""{{ foo bar }}""#).unwrap();
+        assert_eq!(Ok(Bytes::from("baz")), template.render(&event));
+    }
+
+    #[test]
+    fn render_percent_signs_with_strftime() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("path", "a/b c");
+        event.as_mut_log().insert("ratio", "100%");
+        event.as_mut_log().insert(
+            crate::event::log_schema().timestamp_key().clone(),
+            Utc.ymd(2001, 2, 3).and_hms(4, 5, 6),
+        );
+        let render = |src: &str| Template::parse(src).unwrap().render_string(&event);
+
+        assert_eq!(
+            Ok("a%2Fb%20c/2001".into()),
+            render("{{ path | url_encode }}/%Y")
+        );
+        assert_eq!(Ok("100%/2001".into()), render("{{ ratio }}/%Y"));
+        assert_eq!(Ok("100%/%Q".into()), render("{{ ratio }}/%Q"));
+    }
 }
@@ -123,6 +123,27 @@ impl<S> MaybeTlsIncomingStream<S> {
     pub fn peer_addr(&self) -> SocketAddr {
         self.peer_addr
     }
+
+    /// The subject of the certificate presented by the client, formatted as
+    /// `CN=...,O=...`. Only available once the TLS handshake has completed.
+    pub fn peer_identity(&self) -> Option<String> {
+        match &self.state {
+            StreamState::Accepted(MaybeTlsStream::Tls(stream)) => {
+                let certificate = stream.get_ref().ssl().peer_certificate()?;
+                let subject = certificate
+                    .subject_name()
+                    .entries()
+                    .filter_map(|entry| {
+                        let name = entry.object().nid().short_name().ok()?;
+                        let value = entry.data().as_utf8().ok()?;
+                        Some(format!("{}={}", name, value))
+                    })
+                    .collect::<Vec<_>>();
+                Some(subject.join(","))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl MaybeTlsIncomingStream<TcpStream> {
@@ -17,7 +17,7 @@ mod outgoing;
 mod settings;
 
 #[cfg(feature = "sources-tls")]
-pub(crate) use incoming::MaybeTlsListener;
+pub(crate) use incoming::{MaybeTlsIncomingStream, MaybeTlsListener};
 pub(crate) use maybe_tls::MaybeTls;
 pub(crate) use outgoing::MaybeTlsConnector;
 pub use settings::{MaybeTlsSettings, TlsConfig, TlsOptions, TlsSettings};
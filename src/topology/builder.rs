@@ -3,13 +3,26 @@ use super::{
     fanout::{self, Fanout},
     task::Task,
 };
-use crate::{buffers, dns::Resolver, event::Event, runtime, shutdown::SourceShutdownCoordinator};
+use crate::{
+    buffers,
+    dns::Resolver,
+    event::{metadata, Event, Value},
+    runtime,
+    shutdown::SourceShutdownCoordinator,
+    transforms::{util::ready_chunks::ReadyChunks, Transform, BATCH_SIZE},
+};
+use chrono::Utc;
 use futures01::{
     future::{lazy, Either},
-    sync::mpsc,
+    sync::{mpsc, oneshot},
     Future, Stream,
 };
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tokio01::util::FutureExt;
 
 pub struct Pieces {
@@ -76,6 +89,21 @@ pub fn check(config: &super::Config) -> Result<Vec<String>, Vec<String>> {
         }
     }
 
+    for (name, transform) in &config.transforms {
+        if transform.concurrency == 0 {
+            errors.push(format!(
+                "Transform {:?} has a concurrency of 0, it must be at least 1.",
+                name
+            ));
+        } else if transform.concurrency > 1 && !transform.inner.stateless() {
+            errors.push(format!(
+                "Transform {:?} of type {:?} is not stateless and can't have a concurrency above 1.",
+                name,
+                transform.inner.transform_type()
+            ));
+        }
+    }
+
     if let Err(type_errors) = config.typecheck() {
         errors.extend(type_errors);
     }
@@ -128,7 +156,22 @@ pub fn build_pieces(
         };
 
         let (output, control) = Fanout::new();
-        let pump = rx.forward(output).map(|_| ());
+        // Built once, so each event only takes another reference to them.
+        // These keys have slots of their own in the metadata, so setting
+        // them allocates nothing per event.
+        let source_name = Value::from(name.as_str());
+        let source_type = Value::from(typetag);
+        let pump = rx
+            .map(move |mut event| {
+                if let Some(metadata) = event.metadata_mut() {
+                    metadata.insert_if_absent(metadata::SOURCE_NAME, source_name.clone());
+                    metadata.insert_if_absent(metadata::SOURCE_TYPE, source_type.clone());
+                    metadata.insert_if_absent(metadata::INGEST_TIMESTAMP, Utc::now());
+                }
+                event
+            })
+            .forward(output)
+            .map(|_| ());
         let pump = Task::new(&name, &typetag, pump);
 
         // The force_shutdown_tripwire is a Future that when it resolves means that this source
@@ -159,23 +202,34 @@ pub fn build_pieces(
         };
 
         let input_type = transform.inner.input_type();
-        let transform = match transform.inner.build(cx) {
-            Err(error) => {
-                errors.push(format!("Transform \"{}\": {}", name, error));
-                continue;
+        let concurrency = transform.concurrency.max(1);
+        let preserve_order = transform.preserve_order;
+        let mut instances = Vec::with_capacity(concurrency);
+        for _ in 0..concurrency {
+            match transform.inner.build(cx.clone()) {
+                Err(error) => {
+                    errors.push(format!("Transform \"{}\": {}", name, error));
+                    break;
+                }
+                Ok(transform) => instances.push(transform),
             }
-            Ok(transform) => transform,
-        };
+        }
+        if instances.len() < concurrency {
+            continue;
+        }
 
         let (input_tx, input_rx) = futures01::sync::mpsc::channel(100);
         let input_tx = buffers::BufferInputCloner::Memory(input_tx, buffers::WhenFull::Block);
 
         let (output, control) = Fanout::new();
 
-        let transform = transform
-            .transform_stream(filter_event_type(input_rx, input_type))
-            .forward(output)
-            .map(|_| ());
+        let input_rx = filter_event_type(input_rx, input_type);
+        let transform = if concurrency == 1 {
+            instances.pop().unwrap().transform_stream(input_rx)
+        } else {
+            transform_concurrently(instances, input_rx, preserve_order, exec.clone())
+        };
+        let transform = transform.forward(output).map(|_| ());
         let task = Task::new(&name, &typetag, transform);
 
         inputs.insert(name.clone(), (input_tx, trans_inputs.clone()));
@@ -288,5 +342,93 @@ where
             Event::Metric(_) => true,
             _ => false,
         })),
+        DataType::Trace => Box::new(stream.filter(|event| match event {
+            Event::Trace(_) => true,
+            _ => false,
+        })),
+    }
+}
+
+/// Runs several instances of a stateless transform concurrently. Batches of
+/// events drained from the input are each handed to an idle instance on the
+/// executor, with at most one batch in flight per instance.
+fn transform_concurrently(
+    instances: Vec<Box<dyn Transform>>,
+    input_rx: Box<dyn Stream<Item = Event, Error = ()> + Send>,
+    preserve_order: bool,
+    exec: runtime::TaskExecutor,
+) -> Box<dyn Stream<Item = Event, Error = ()> + Send> {
+    let concurrency = instances.len();
+    let idle = Arc::new(Mutex::new(instances));
+
+    let batches = ReadyChunks::new(input_rx, BATCH_SIZE).map(move |events| {
+        let idle = Arc::clone(&idle);
+        let work = lazy(move || {
+            let mut instance = IdleInstance::take(idle);
+            let mut output = Vec::with_capacity(events.len());
+            let count = events.len();
+            // A panic only loses the rest of its batch, rather than the
+            // whole pipeline.
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                instance.transform_batch(events, &mut output)
+            }));
+            if result.is_err() {
+                error!(
+                    message = "Transform panicked; dropping the rest of its batch.",
+                    %count,
+                    rate_limit_secs = 30
+                );
+            }
+            Ok::<_, ()>(output)
+        });
+        oneshot::spawn(work, &exec)
+    });
+
+    let output: Box<dyn Stream<Item = Vec<Event>, Error = ()> + Send> = if preserve_order {
+        Box::new(batches.buffered(concurrency))
+    } else {
+        Box::new(batches.buffer_unordered(concurrency))
+    };
+
+    Box::new(output.map(futures01::stream::iter_ok).flatten())
+}
+
+/// A transform instance taken from the idle pool, which goes back to the
+/// pool when dropped, however its batch went.
+struct IdleInstance {
+    idle: Arc<Mutex<Vec<Box<dyn Transform>>>>,
+    transform: Option<Box<dyn Transform>>,
+}
+
+impl IdleInstance {
+    fn take(idle: Arc<Mutex<Vec<Box<dyn Transform>>>>) -> Self {
+        let transform = idle
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("more batches in flight than transform instances");
+        Self {
+            idle,
+            transform: Some(transform),
+        }
+    }
+
+    fn transform_batch(&mut self, events: Vec<Event>, output: &mut Vec<Event>) {
+        self.transform
+            .as_mut()
+            .expect("instance is only returned when dropped")
+            .transform_batch(events, output)
+    }
+}
+
+impl Drop for IdleInstance {
+    fn drop(&mut self) {
+        if let Some(transform) = self.transform.take() {
+            // The pool's lock is never held while transforming, so it can't
+            // have been poisoned by a panicking transform.
+            if let Ok(mut idle) = self.idle.lock() {
+                idle.push(transform);
+            }
+        }
     }
 }
@@ -123,6 +123,7 @@ pub enum DataType {
     Any,
     Log,
     Metric,
+    Trace,
 }
 
 #[typetag::serde(tag = "type")]
@@ -205,6 +206,13 @@ inventory::collect!(SinkDescription);
 #[derive(Deserialize, Serialize, Debug)]
 pub struct TransformOuter {
     pub inputs: Vec<String>,
+    /// The number of instances of a stateless transform to run concurrently.
+    #[serde(default = "concurrency_default")]
+    pub concurrency: usize,
+    /// Whether a transform running concurrently emits events in the order
+    /// they were received.
+    #[serde(default = "preserve_order_default")]
+    pub preserve_order: bool,
     #[serde(flatten)]
     pub inner: Box<dyn TransformConfig>,
 }
@@ -219,6 +227,12 @@ pub trait TransformConfig: core::fmt::Debug {
 
     fn transform_type(&self) -> &'static str;
 
+    /// Whether the transform keeps no state between events, so that several
+    /// independently built instances of it may process events concurrently.
+    fn stateless(&self) -> bool {
+        false
+    }
+
     /// Allows a transform configuration to expand itself into multiple "child"
     /// transformations to replace it. This allows a transform to act as a macro
     /// for various patterns.
@@ -348,6 +362,8 @@ impl Config {
         let transform = TransformOuter {
             inner: Box::new(transform),
             inputs,
+            concurrency: concurrency_default(),
+            preserve_order: preserve_order_default(),
         };
 
         self.transforms.insert(name.to_string(), transform);
@@ -376,6 +392,8 @@ impl Config {
                         full_name.clone(),
                         TransformOuter {
                             inputs: t.inputs.clone(),
+                            concurrency: t.concurrency,
+                            preserve_order: t.preserve_order,
                             inner: child,
                         },
                     );
@@ -515,6 +533,14 @@ fn healthcheck_default() -> bool {
     true
 }
 
+fn concurrency_default() -> usize {
+    1
+}
+
+fn preserve_order_default() -> bool {
+    true
+}
+
 #[cfg(all(test, feature = "sources-file", feature = "sinks-console"))]
 mod test {
     use super::Config;
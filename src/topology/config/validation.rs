@@ -267,6 +267,26 @@ mod test {
         );
     }
 
+    #[test]
+    fn detects_trace_mismatches() {
+        let mut graph = Graph::default();
+        graph.add_source("trace_source", DataType::Trace);
+        graph.add_source("any_source", DataType::Any);
+        graph.add_sink("log_sink", DataType::Log, vec!["trace_source"]);
+        graph.add_sink(
+            "trace_sink",
+            DataType::Trace,
+            vec!["trace_source", "any_source"],
+        );
+
+        assert_eq!(
+            Err(vec![
+                "Data type mismatch between trace_source (Trace) and log_sink (Log)".into()
+            ]),
+            graph.typecheck()
+        );
+    }
+
     #[test]
     fn allows_log_or_metric_into_any() {
         let mut graph = Graph::default();
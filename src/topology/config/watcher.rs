@@ -100,7 +100,10 @@ fn create_watcher(
 }
 
 #[cfg(unix)]
-fn add_paths(watcher: &mut RecommendedWatcher, config_paths: &Vec<PathBuf>) -> Result<(), Error> {
+pub(crate) fn add_paths(
+    watcher: &mut RecommendedWatcher,
+    config_paths: &Vec<PathBuf>,
+) -> Result<(), Error> {
     for path in config_paths {
         watcher.watch(path, RecursiveMode::NonRecursive)?;
     }
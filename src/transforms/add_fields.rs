@@ -66,6 +66,10 @@ impl TransformConfig for AddFieldsConfig {
     fn transform_type(&self) -> &'static str {
         "add_fields"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 impl AddFields {
@@ -78,13 +82,11 @@ impl AddFields {
 
         AddFields { fields: new_fields }
     }
-}
 
-impl Transform for AddFields {
-    fn transform(&mut self, mut event: Event) -> Option<Event> {
-        for (key, value_or_template) in self.fields.clone() {
+    fn add_fields(&self, event: &mut Event) {
+        for (key, value_or_template) in &self.fields {
             let value = match value_or_template {
-                TemplateOrValue::Template(v) => match v.render_string(&event) {
+                TemplateOrValue::Template(v) => match v.render_string(event) {
                     Ok(v) => v,
                     Err(_) => {
                         warn!(
@@ -95,9 +97,9 @@ impl Transform for AddFields {
                     }
                 }
                 .into(),
-                TemplateOrValue::Value(v) => v,
+                TemplateOrValue::Value(v) => v.clone(),
             };
-            if let Some(_) = event.as_mut_log().insert(&key, value) {
+            if let Some(_) = event.as_mut_log().insert(key, value) {
                 debug!(
                     message = "Field overwritten",
                     field = key.as_ref(),
@@ -105,9 +107,21 @@ impl Transform for AddFields {
                 )
             }
         }
+    }
+}
 
+impl Transform for AddFields {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        self.add_fields(&mut event);
         Some(event)
     }
+
+    fn transform_batch(&mut self, mut events: Vec<Event>, output: &mut Vec<Event>) {
+        for event in &mut events {
+            self.add_fields(event);
+        }
+        output.append(&mut events);
+    }
 }
 
 fn flatten_field(key: Atom, value: TomlValue, new_fields: &mut IndexMap<Atom, TemplateOrValue>) {
@@ -197,6 +211,24 @@ mod tests {
         assert_eq!(kv, Some(&val.into()));
     }
 
+    #[test]
+    fn add_fields_batch() {
+        let events = vec![Event::from("one"), Event::from("two")];
+        let mut fields = IndexMap::new();
+        fields.insert("some_key".into(), "{{message}}".into());
+        let mut augment = AddFields::new(fields);
+
+        let mut output = Vec::new();
+        augment.transform_batch(events, &mut output);
+
+        let key = Atom::from("some_key".to_string());
+        let values = output
+            .iter()
+            .map(|event| event.as_log()[&key].to_string_lossy())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec!["one", "two"]);
+    }
+
     #[test]
     fn add_fields_preserves_types() {
         let event = Event::from("hello world");
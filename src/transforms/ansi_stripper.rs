@@ -41,6 +41,10 @@ impl TransformConfig for AnsiStripperConfig {
     fn transform_type(&self) -> &'static str {
         "ansi_stripper"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 pub struct AnsiStripper {
@@ -0,0 +1,287 @@
+use super::Transform;
+use crate::{
+    event::{self, Event},
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+};
+use serde::{Deserialize, Serialize};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct CefParserConfig {
+    pub field: Option<Atom>,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+    #[derivative(Default(value = "Atom::from(\"cef\")"))]
+    pub target_field: Atom,
+}
+
+inventory::submit! {
+    TransformDescription::new::<CefParserConfig>("cef_parser")
+}
+
+#[typetag::serde(name = "cef_parser")]
+impl TransformConfig for CefParserConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let field = self
+            .field
+            .as_ref()
+            .unwrap_or(&event::log_schema().message_key());
+
+        let header_fields = HEADER_FIELDS
+            .iter()
+            .map(|name| format!("{}.{}", self.target_field, name).into())
+            .collect();
+
+        Ok(Box::new(CefParser {
+            field: field.clone(),
+            drop_field: self.drop_field,
+            target_field: self.target_field.clone(),
+            header_fields,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "cef_parser"
+    }
+
+    fn stateless(&self) -> bool {
+        true
+    }
+}
+
+/// The names of the pipe-separated header fields following the `CEF:` prefix.
+const HEADER_FIELDS: [&str; 7] = [
+    "version",
+    "device_vendor",
+    "device_product",
+    "device_version",
+    "device_event_class_id",
+    "name",
+    "severity",
+];
+
+pub struct CefParser {
+    field: Atom,
+    drop_field: bool,
+    target_field: Atom,
+    header_fields: Vec<Atom>,
+}
+
+impl Transform for CefParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let value = event.as_log().get(&self.field).map(|s| s.to_string_lossy());
+
+        if let Some(value) = value {
+            if let Some((header, extension)) = parse(&value) {
+                let log = event.as_mut_log();
+                if self.drop_field {
+                    log.remove(&self.field);
+                }
+
+                for (name, value) in self.header_fields.iter().zip(header) {
+                    log.insert(name.clone(), value);
+                }
+                for (key, value) in extension {
+                    log.insert(format!("{}.extensions.{}", self.target_field, key), value);
+                }
+            } else {
+                debug!(
+                    message = "Field is not a CEF message.",
+                    field = self.field.as_ref(),
+                    rate_limit_secs = 30
+                );
+            }
+        } else {
+            debug!(
+                message = "Field does not exist.",
+                field = self.field.as_ref(),
+                rate_limit_secs = 30
+            );
+        }
+
+        Some(event)
+    }
+}
+
+/// Parses a CEF message, possibly preceded by a syslog header, into its
+/// header fields and extension key/value pairs.
+fn parse(input: &str) -> Option<(Vec<String>, Vec<(String, String)>)> {
+    let mut rest = &input[input.find("CEF:")? + 4..];
+
+    let mut header = Vec::with_capacity(HEADER_FIELDS.len());
+    while header.len() < HEADER_FIELDS.len() {
+        let end = find_unescaped(rest, '|')?;
+        header.push(unescape_header(&rest[..end]));
+        rest = &rest[end + 1..];
+    }
+
+    Some((header, parse_extension(rest)))
+}
+
+/// Parses the `key=value` pairs of the extension. Values may contain spaces,
+/// so a value runs until the key of the following pair.
+fn parse_extension(input: &str) -> Vec<(String, String)> {
+    let mut keys = Vec::new();
+    let mut search_from = 0;
+    while let Some(offset) = find_unescaped(&input[search_from..], '=') {
+        let equals = search_from + offset;
+        let start = input[..equals]
+            .rfind(char::is_whitespace)
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        // A key can't start inside the previous key.
+        if start >= search_from && start < equals {
+            keys.push((start, equals));
+        }
+        search_from = equals + 1;
+    }
+
+    keys.iter()
+        .enumerate()
+        .map(|(index, &(start, equals))| {
+            let end = keys
+                .get(index + 1)
+                .map(|&(next, _)| next)
+                .unwrap_or_else(|| input.len());
+            let value = input[equals + 1..end].trim_end();
+            (input[start..equals].to_owned(), unescape_value(value))
+        })
+        .collect()
+}
+
+fn find_unescaped(input: &str, needle: char) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == needle => return Some(index),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Replaces the escape sequences of `input`; `escape` maps the character
+/// following a backslash to its unescaped value.
+fn unescape(input: &str, escape: impl Fn(char) -> Option<char>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let rest = chars.as_str();
+            if let Some(unescaped) = rest.chars().next().and_then(&escape) {
+                output.push(unescaped);
+                chars.next();
+                continue;
+            }
+        }
+        output.push(c);
+    }
+    output
+}
+
+fn unescape_header(input: &str) -> String {
+    unescape(input, |c| match c {
+        '|' | '\\' => Some(c),
+        _ => None,
+    })
+}
+
+fn unescape_value(input: &str) -> String {
+    unescape(input, |c| match c {
+        '=' | '\\' => Some(c),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CefParserConfig;
+    use crate::{
+        event::{self, LogEvent},
+        topology::config::{TransformConfig, TransformContext},
+        Event,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn parse_log(text: &str, config: &str) -> LogEvent {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = toml::from_str::<CefParserConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        let mut log = parser.transform(Event::from(text)).unwrap().into_log();
+        log.remove(&event::log_schema().timestamp_key());
+        log
+    }
+
+    #[test]
+    fn cef_parser_parses_header_and_extension() {
+        let log = parse_log(
+            r#"Sep 19 08:26:10 host CEF:0|Security|threatmanager|1.0|100|worm successfully stopped|10|src=10.0.0.1 dst=2.1.2.2 spt=1232 msg=Detected a threat. No action needed"#,
+            "",
+        );
+
+        assert_eq!(
+            serde_json::to_value(log.all_fields()).unwrap(),
+            json!({
+                "cef.version": "0",
+                "cef.device_vendor": "Security",
+                "cef.device_product": "threatmanager",
+                "cef.device_version": "1.0",
+                "cef.device_event_class_id": "100",
+                "cef.name": "worm successfully stopped",
+                "cef.severity": "10",
+                "cef.extensions.src": "10.0.0.1",
+                "cef.extensions.dst": "2.1.2.2",
+                "cef.extensions.spt": "1232",
+                "cef.extensions.msg": "Detected a threat. No action needed",
+            })
+        );
+    }
+
+    #[test]
+    fn cef_parser_handles_escapes() {
+        let log = parse_log(
+            r#"CEF:0|security|threatmanager|1.0|100|detected a \| in message|10|act=blocked a \= sign filePath=C:\\Program Files\\x cs1=line1\nline2"#,
+            r#"target_field = "event""#,
+        );
+
+        assert_eq!(log[&"event.name".into()], "detected a | in message".into());
+        assert_eq!(
+            log[&"event.extensions.act".into()],
+            "blocked a = sign".into()
+        );
+        assert_eq!(
+            log[&"event.extensions.filePath".into()],
+            r#"C:\Program Files\x"#.into()
+        );
+        assert_eq!(log[&"event.extensions.cs1".into()], "line1\nline2".into());
+    }
+
+    #[test]
+    fn cef_parser_ignores_non_cef() {
+        let log = parse_log("CEF:0|only|three", "");
+
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "CEF:0|only|three".into()
+        );
+        assert_eq!(log.keys().count(), 1);
+    }
+}
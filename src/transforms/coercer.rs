@@ -40,6 +40,10 @@ impl TransformConfig for CoercerConfig {
     fn transform_type(&self) -> &'static str {
         "coercer"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 pub struct Coercer {
@@ -47,16 +51,16 @@ pub struct Coercer {
     drop_unspecified: bool,
 }
 
-impl Transform for Coercer {
-    fn transform(&mut self, event: Event) -> Option<Event> {
-        let mut log = event.into_log();
+impl Coercer {
+    /// Coerces the fields of `event` in place.
+    fn coerce(&self, event: &mut Event) {
         if self.drop_unspecified {
             // This uses a different algorithm from the default path
             // below, as it will be fewer steps to fully recreate the
             // event than to scan the event for extraneous fields after
             // conversion.
-            let mut new_event = Event::new_empty_log();
-            let new_log = new_event.as_mut_log();
+            let mut log = std::mem::replace(event, Event::new_empty_log()).into_log();
+            let new_log = event.as_mut_log();
             for (field, conv) in &self.types {
                 if let Some(value) = log.remove(field) {
                     match conv.convert(value) {
@@ -74,8 +78,8 @@ impl Transform for Coercer {
                     }
                 }
             }
-            return Some(new_event);
         } else {
+            let log = event.as_mut_log();
             for (field, conv) in &self.types {
                 if let Some(value) = log.remove(field) {
                     match conv.convert(value) {
@@ -94,7 +98,22 @@ impl Transform for Coercer {
                 }
             }
         }
-        Some(Event::Log(log))
+    }
+}
+
+impl Transform for Coercer {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        self.coerce(&mut event);
+        Some(event)
+    }
+
+    fn transform_batch(&mut self, mut events: Vec<Event>, output: &mut Vec<Event>) {
+        // Coercing never drops events, so the batch is coerced in place and
+        // moved over as a whole.
+        for event in &mut events {
+            self.coerce(event);
+        }
+        output.append(&mut events);
     }
 }
 
@@ -135,6 +154,47 @@ mod tests {
         coercer.transform(event).unwrap().into_log()
     }
 
+    #[test]
+    fn batch_matches_per_event() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let events = vec!["1234", "nope", "5"]
+            .into_iter()
+            .map(|number| {
+                let mut event = Event::from("dummy message");
+                event.as_mut_log().insert("number", number);
+                event
+            })
+            .collect::<Vec<_>>();
+
+        for extra in &["", "drop_unspecified = true"] {
+            let config = toml::from_str::<CoercerConfig>(&format!(
+                r#"{}
+                [types]
+                number = "int"
+                "#,
+                extra
+            ))
+            .unwrap();
+            let mut per_event = config
+                .build(TransformContext::new_test(rt.executor()))
+                .unwrap();
+            let mut batched = config
+                .build(TransformContext::new_test(rt.executor()))
+                .unwrap();
+
+            let expected = events
+                .iter()
+                .cloned()
+                .filter_map(|event| per_event.transform(event))
+                .collect::<Vec<_>>();
+            let mut output = Vec::new();
+            batched.transform_batch(events.clone(), &mut output);
+
+            assert_eq!(output, expected);
+            assert_eq!(output.len(), 3);
+        }
+    }
+
     #[test]
     fn converts_valid_fields() {
         let log = parse_it("");
@@ -0,0 +1,375 @@
+use super::Transform;
+use crate::{
+    event::{self, Event},
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    types::{parse_conversion_map, Conversion},
+};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::collections::HashMap;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("`{}` must be a single ASCII character, got {:?}", option, value))]
+    NonAsciiCharacter { option: &'static str, value: char },
+    #[snafu(display("Either `field_names` must be set or `header` must be enabled"))]
+    NoFieldNames,
+    #[snafu(display("`max_files` must be at least 1"))]
+    ZeroMaxFiles,
+}
+
+#[derive(Deserialize, Serialize, Debug, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct CsvParserConfig {
+    pub field: Option<Atom>,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+    pub field_names: Vec<Atom>,
+    pub header: bool,
+    #[derivative(Default(value = "Atom::from(\"file\")"))]
+    pub file_key: Atom,
+    #[derivative(Default(value = "1000"))]
+    pub max_files: usize,
+    #[derivative(Default(value = "','"))]
+    pub delimiter: char,
+    #[derivative(Default(value = "'\"'"))]
+    pub quote: char,
+    pub escape: Option<char>,
+    pub types: HashMap<Atom, String>,
+}
+
+inventory::submit! {
+    TransformDescription::new::<CsvParserConfig>("csv_parser")
+}
+
+#[typetag::serde(name = "csv_parser")]
+impl TransformConfig for CsvParserConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let field = self
+            .field
+            .as_ref()
+            .unwrap_or(&event::log_schema().message_key());
+        if self.field_names.is_empty() && !self.header {
+            return Err(BuildError::NoFieldNames.into());
+        }
+        if self.header && self.max_files == 0 {
+            return Err(BuildError::ZeroMaxFiles.into());
+        }
+        let conversions = parse_conversion_map(&self.types)?;
+
+        let mut reader = csv::ReaderBuilder::new();
+        reader
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(ascii("delimiter", self.delimiter)?)
+            .quote(ascii("quote", self.quote)?);
+        if let Some(escape) = self.escape {
+            // RFC 4180 escapes quotes by doubling them, an explicit escape
+            // character replaces that.
+            reader
+                .escape(Some(ascii("escape", escape)?))
+                .double_quote(false);
+        }
+
+        let headers = if self.header {
+            Headers::FromFile {
+                file_key: self.file_key.clone(),
+                files: LruCache::new(self.max_files),
+            }
+        } else {
+            Headers::Fixed(self.field_names.clone())
+        };
+
+        Ok(Box::new(CsvParser {
+            field: field.clone(),
+            drop_field: self.drop_field,
+            reader,
+            headers,
+            conversions,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "csv_parser"
+    }
+
+    fn stateless(&self) -> bool {
+        // Headers read from the files have to be seen by every event of
+        // that file.
+        !self.header
+    }
+}
+
+fn ascii(option: &'static str, value: char) -> Result<u8, BuildError> {
+    if value.is_ascii() {
+        Ok(value as u8)
+    } else {
+        Err(BuildError::NonAsciiCharacter { option, value })
+    }
+}
+
+enum Headers {
+    Fixed(Vec<Atom>),
+    /// The first line of every file, keyed by the value of `file_key`. Only
+    /// the most recently seen files are kept, so that a stream of rotated
+    /// files doesn't grow this without bound.
+    FromFile {
+        file_key: Atom,
+        files: LruCache<String, Vec<Atom>>,
+    },
+}
+
+pub struct CsvParser {
+    field: Atom,
+    drop_field: bool,
+    reader: csv::ReaderBuilder,
+    headers: Headers,
+    conversions: HashMap<Atom, Conversion>,
+}
+
+impl CsvParser {
+    fn parse_record(&self, value: &[u8]) -> Option<Vec<String>> {
+        let mut record = csv::StringRecord::new();
+        match self.reader.from_reader(value).read_record(&mut record) {
+            Ok(true) => Some(record.iter().map(Into::into).collect()),
+            Ok(false) => None,
+            Err(error) => {
+                debug!(
+                    message = "Could not parse CSV record.",
+                    field = self.field.as_ref(),
+                    %error,
+                    rate_limit_secs = 30
+                );
+                None
+            }
+        }
+    }
+}
+
+impl Transform for CsvParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let record = match event.as_log().get(&self.field) {
+            Some(value) => self.parse_record(&value.as_bytes()),
+            None => {
+                debug!(
+                    message = "Field does not exist.",
+                    field = self.field.as_ref(),
+                    rate_limit_secs = 30
+                );
+                return Some(event);
+            }
+        };
+        let record = match record {
+            Some(record) => record,
+            None => return Some(event),
+        };
+
+        let names = match &mut self.headers {
+            Headers::Fixed(names) => &*names,
+            Headers::FromFile { file_key, files } => {
+                let file = event
+                    .as_log()
+                    .get(file_key)
+                    .map(|file| file.to_string_lossy())
+                    .unwrap_or_default();
+                match files.get(&file) {
+                    // A file that was truncated or rotated in place starts
+                    // over with its header, which isn't data.
+                    Some(names) if names.iter().map(|name| &name[..]).eq(record.iter()) => {
+                        return None;
+                    }
+                    Some(names) => names,
+                    None => {
+                        let names = record.into_iter().map(Atom::from).collect();
+                        files.put(file, names);
+                        return None;
+                    }
+                }
+            }
+        };
+
+        let log = event.as_mut_log();
+        let mut drop_field = self.drop_field;
+        if record.len() > names.len() {
+            debug!(
+                message = "Record has more columns than field names, ignoring the rest.",
+                columns = record.len(),
+                field_names = names.len(),
+                rate_limit_secs = 30
+            );
+        }
+
+        for (name, value) in names.iter().zip(record) {
+            if *name == self.field {
+                drop_field = false;
+            }
+
+            if let Some(conv) = self.conversions.get(name) {
+                match conv.convert(value.into()) {
+                    Ok(value) => {
+                        log.insert(name.clone(), value);
+                    }
+                    Err(error) => {
+                        debug!(
+                            message = "Could not convert types.",
+                            name = &name[..],
+                            %error,
+                            rate_limit_secs = 30
+                        );
+                    }
+                }
+            } else {
+                log.insert(name.clone(), value);
+            }
+        }
+
+        if drop_field {
+            log.remove(&self.field);
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CsvParserConfig;
+    use crate::{
+        event::{self, LogEvent, Value},
+        topology::config::{TransformConfig, TransformContext},
+        transforms::Transform,
+        Event,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn parser(config: &str) -> Box<dyn Transform> {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        toml::from_str::<CsvParserConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap()
+    }
+
+    fn fields(mut log: LogEvent) -> serde_json::Value {
+        log.remove(&event::log_schema().timestamp_key());
+        serde_json::to_value(log.all_fields()).unwrap()
+    }
+
+    fn file_event(file: &str, line: &str) -> Event {
+        let mut event = Event::from(line);
+        event.as_mut_log().insert("file", file);
+        event
+    }
+
+    #[test]
+    fn csv_parser_handles_rfc4180_quoting() {
+        let mut parser = parser(
+            r#"
+                field_names = ["name", "quote", "count"]
+                types = { count = "int" }
+            "#,
+        );
+
+        let event = Event::from(r#"Jane,"She said ""hi"", then left",3"#);
+        let log = parser.transform(event).unwrap().into_log();
+
+        assert_eq!(
+            fields(log),
+            json!({
+                "name": "Jane",
+                "quote": "She said \"hi\", then left",
+                "count": 3,
+            })
+        );
+    }
+
+    #[test]
+    fn csv_parser_custom_characters() {
+        let mut parser = parser(
+            r#"
+                field_names = ["path", "note"]
+                delimiter = ";"
+                quote = "'"
+                escape = "\\"
+                drop_field = false
+            "#,
+        );
+
+        let event = Event::from(r#"'C:\'s; drive';it\'s fine"#);
+        let log = parser.transform(event).unwrap().into_log();
+
+        assert_eq!(log[&"path".into()], "C:'s; drive".into());
+        assert_eq!(log[&"note".into()], Value::from(r#"it\'s fine"#));
+        assert!(log.get(&event::log_schema().message_key()).is_some());
+    }
+
+    #[test]
+    fn csv_parser_reads_header_per_file() {
+        let mut parser = parser(
+            r#"
+                header = true
+                types = { id = "int" }
+            "#,
+        );
+
+        assert!(parser.transform(file_event("a.csv", "id,name")).is_none());
+        assert!(parser.transform(file_event("b.csv", "name,id")).is_none());
+
+        let a = parser.transform(file_event("a.csv", "1,one")).unwrap();
+        let b = parser.transform(file_event("b.csv", "two,2")).unwrap();
+        assert_eq!(
+            fields(a.into_log()),
+            json!({ "file": "a.csv", "id": 1, "name": "one" })
+        );
+        assert_eq!(
+            fields(b.into_log()),
+            json!({ "file": "b.csv", "id": 2, "name": "two" })
+        );
+
+        // The header of a truncated file is skipped.
+        assert!(parser.transform(file_event("a.csv", "id,name")).is_none());
+    }
+
+    #[test]
+    fn csv_parser_forgets_least_recently_seen_files() {
+        let mut parser = parser(
+            r#"
+                header = true
+                max_files = 1
+            "#,
+        );
+
+        assert!(parser.transform(file_event("a.csv", "id")).is_none());
+        assert!(parser.transform(file_event("b.csv", "name")).is_none());
+
+        let b = parser.transform(file_event("b.csv", "two")).unwrap();
+        assert_eq!(
+            fields(b.into_log()),
+            json!({ "file": "b.csv", "name": "two" })
+        );
+        // The header of `a.csv` was evicted, so its next line is taken as one.
+        assert!(parser.transform(file_event("a.csv", "1")).is_none());
+    }
+
+    #[test]
+    fn csv_parser_requires_field_names() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let config = toml::from_str::<CsvParserConfig>("").unwrap();
+
+        assert!(config
+            .build(TransformContext::new_test(rt.executor()))
+            .is_err());
+    }
+}
@@ -33,6 +33,10 @@ impl TransformConfig for FilterConfig {
     fn transform_type(&self) -> &'static str {
         "filter"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 pub struct Filter {
@@ -52,4 +56,9 @@ impl Transform for Filter {
             false => None,
         }
     }
+
+    fn transform_batch(&mut self, events: Vec<Event>, output: &mut Vec<Event>) {
+        let condition = &self.condition;
+        output.extend(events.into_iter().filter(|event| condition.check(event)));
+    }
 }
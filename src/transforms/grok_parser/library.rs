@@ -0,0 +1,178 @@
+use super::{BuildError, ReadPatternsDir, ReadPatternsFile};
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::Regex;
+use snafu::ResultExt;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Pattern sets shipped with Vector, on top of those bundled with the `grok`
+/// crate. They are always available and can be overridden by user patterns.
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("haproxy", include_str!("patterns/haproxy")),
+    ("java", include_str!("patterns/java")),
+    ("nginx", include_str!("patterns/nginx")),
+    ("postgres", include_str!("patterns/postgres")),
+];
+
+lazy_static! {
+    static ref REFERENCE: Regex = Regex::new(r"%\{(\w+)[^}]*\}").unwrap();
+}
+
+struct Definition {
+    pattern: String,
+    origin: String,
+}
+
+/// A set of named grok pattern definitions, collected from the built-in sets,
+/// a `patterns_dir` and inline `patterns`, in increasing order of precedence.
+#[derive(Default)]
+pub struct Library {
+    definitions: IndexMap<String, Definition>,
+}
+
+impl Library {
+    pub fn load(
+        patterns: &IndexMap<String, String>,
+        patterns_dir: Option<&Path>,
+    ) -> Result<Self, BuildError> {
+        let mut library = Library::default();
+
+        for (name, contents) in BUILTIN_PATTERNS {
+            library.add_file(&format!("built-in {} patterns", name), contents)?;
+        }
+
+        if let Some(dir) = patterns_dir {
+            let mut paths = fs::read_dir(dir)
+                .context(ReadPatternsDir { path: dir })?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+                .context(ReadPatternsDir { path: dir })?;
+            paths.sort();
+
+            for path in paths.into_iter().filter(|path| path.is_file()) {
+                let contents =
+                    fs::read_to_string(&path).context(ReadPatternsFile { path: &path })?;
+                library.add_file(&path.display().to_string(), &contents)?;
+            }
+        }
+
+        for (name, pattern) in patterns {
+            library.insert(name.clone(), pattern.clone(), "patterns".into());
+        }
+
+        library.validate()?;
+        Ok(library)
+    }
+
+    /// Adds the definitions of a file in the Logstash `NAME REGEX` format.
+    fn add_file(&mut self, origin: &str, contents: &str) -> Result<(), BuildError> {
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, char::is_whitespace);
+            let name = parts.next().unwrap_or_default();
+            let pattern = parts.next().unwrap_or_default().trim_start();
+            if pattern.is_empty() {
+                return Err(BuildError::InvalidPatternLine {
+                    origin: origin.into(),
+                    line: index + 1,
+                });
+            }
+
+            self.insert(
+                name.into(),
+                pattern.into(),
+                format!("{}:{}", origin, index + 1),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn insert(&mut self, name: String, pattern: String, origin: String) {
+        self.definitions
+            .insert(name, Definition { pattern, origin });
+    }
+
+    fn validate(&self) -> Result<(), BuildError> {
+        let mut bundled = grok::Grok::with_patterns();
+        let mut known = HashSet::new();
+
+        for (name, definition) in &self.definitions {
+            for reference in references(&definition.pattern) {
+                if self.definitions.contains_key(reference) || known.contains(reference) {
+                    continue;
+                }
+                if bundled
+                    .compile(&format!("%{{{}}}", reference), false)
+                    .is_err()
+                {
+                    return Err(BuildError::MissingPattern {
+                        name: reference.into(),
+                        referenced_by: name.clone(),
+                        origin: definition.origin.clone(),
+                    });
+                }
+                known.insert(reference);
+            }
+        }
+
+        let mut done = HashSet::new();
+        for name in self.definitions.keys() {
+            self.check_recursion(name, &mut Vec::new(), &mut done)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_recursion<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Result<(), BuildError> {
+        if done.contains(name) {
+            return Ok(());
+        }
+
+        if let Some(start) = path.iter().position(|seen| *seen == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name);
+            return Err(BuildError::RecursivePattern {
+                cycle: cycle.join(" -> "),
+            });
+        }
+
+        // References to patterns bundled with the `grok` crate can't lead
+        // back to ours.
+        if let Some(definition) = self.definitions.get(name) {
+            path.push(name);
+            for reference in references(&definition.pattern) {
+                self.check_recursion(reference, path, done)?;
+            }
+            path.pop();
+        }
+
+        done.insert(name);
+        Ok(())
+    }
+
+    pub fn apply(&self, grok: &mut grok::Grok) {
+        for (name, definition) in &self.definitions {
+            grok.insert_definition(name.clone(), definition.pattern.clone());
+        }
+    }
+}
+
+/// Returns the names of the patterns referenced by `pattern`.
+fn references(pattern: &str) -> impl Iterator<Item = &str> {
+    REFERENCE
+        .captures_iter(pattern)
+        .filter_map(|captures| captures.get(1))
+        .map(|name| name.as_str())
+}
@@ -0,0 +1,596 @@
+use super::{MatchPattern, Transform};
+use crate::{
+    event::{self, Event},
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    types::{parse_conversion_map_no_atoms, Conversion},
+};
+use grok::Pattern;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str;
+use string_cache::DefaultAtom as Atom;
+
+mod library;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid grok pattern: {}", source))]
+    InvalidGrok { source: grok::Error },
+    #[snafu(display("Could not read patterns directory {:?}: {}", path, source))]
+    ReadPatternsDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read patterns file {:?}: {}", path, source))]
+    ReadPatternsFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display(
+        "Invalid pattern definition in {} line {}, expected \"NAME REGEX\"",
+        origin,
+        line
+    ))]
+    InvalidPatternLine { origin: String, line: usize },
+    #[snafu(display(
+        "Pattern {:?} referenced by {:?} ({}) is not defined",
+        name,
+        referenced_by,
+        origin
+    ))]
+    MissingPattern {
+        name: String,
+        referenced_by: String,
+        origin: String,
+    },
+    #[snafu(display("Recursive pattern definition: {}", cycle))]
+    RecursivePattern { cycle: String },
+}
+
+#[derive(Deserialize, Serialize, Debug, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct GrokParserConfig {
+    pub pattern: String,
+    pub match_patterns: Vec<MatchPattern>,
+    pub matched_pattern_field: Option<Atom>,
+    pub patterns: IndexMap<String, String>,
+    pub patterns_dir: Option<PathBuf>,
+    pub field: Option<Atom>,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+    pub types: HashMap<String, String>,
+}
+
+inventory::submit! {
+    TransformDescription::new::<GrokParserConfig>("grok_parser")
+}
+
+#[typetag::serde(name = "grok_parser")]
+impl TransformConfig for GrokParserConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let field = self
+            .field
+            .as_ref()
+            .unwrap_or(&event::log_schema().message_key());
+
+        let mut grok = grok::Grok::with_patterns();
+        library::Library::load(&self.patterns, self.patterns_dir.as_deref())?.apply(&mut grok);
+
+        let patterns = match (self.pattern.is_empty(), self.match_patterns.is_empty()) {
+            (_, true) => vec![(self.pattern.clone(), self.types.clone())],
+            (true, false) => self
+                .match_patterns
+                .iter()
+                .map(|pattern| (pattern.pattern().into(), pattern.types(&self.types)))
+                .collect(),
+            (false, false) => {
+                return Err(super::BuildError::ConflictingPatterns {
+                    single: "pattern",
+                    list: "match_patterns",
+                }
+                .into())
+            }
+        };
+        let patterns = patterns
+            .iter()
+            .map(|(pattern, types)| {
+                let pattern = grok.compile(pattern, true).context(InvalidGrok)?;
+                Ok((pattern, parse_conversion_map_no_atoms(types)?))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(Box::new(GrokParser {
+            patterns,
+            field: field.clone(),
+            drop_field: self.drop_field,
+            matched_pattern_field: self.matched_pattern_field.clone(),
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "grok_parser"
+    }
+
+    fn stateless(&self) -> bool {
+        true
+    }
+}
+
+pub struct GrokParser {
+    // Grok patterns compile to Oniguruma expressions, which can't be
+    // combined into a `RegexSet`, so they are simply tried in order.
+    patterns: Vec<(Pattern, HashMap<String, Conversion>)>,
+    field: Atom,
+    drop_field: bool,
+    matched_pattern_field: Option<Atom>,
+}
+
+impl Transform for GrokParser {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        let mut event = event.into_log();
+        let value = event.get(&self.field).map(|s| s.to_string_lossy());
+
+        if let Some(value) = value {
+            let matched = self
+                .patterns
+                .iter()
+                .enumerate()
+                .find_map(|(index, (pattern, types))| {
+                    Some((index, types, pattern.match_against(&value)?))
+                });
+
+            if let Some((index, types, matches)) = matched {
+                let drop_field = self.drop_field && !matches.get(&self.field).is_some();
+                for (name, value) in matches.iter() {
+                    let conv = types.get(name).unwrap_or(&Conversion::Bytes);
+                    match conv.convert(value.into()) {
+                        Ok(value) => {
+                            event.insert(name, value);
+                        }
+                        Err(error) => {
+                            debug!(
+                                message = "Could not convert types.",
+                                %name,
+                                %error,
+                                rate_limit_secs = 30,
+                            );
+                        }
+                    }
+                }
+
+                if drop_field {
+                    event.remove(&self.field);
+                }
+
+                if let Some(matched_pattern_field) = &self.matched_pattern_field {
+                    event.insert(matched_pattern_field, index as i64);
+                }
+            } else {
+                debug!(message = "No fields captured from grok pattern.");
+            }
+        } else {
+            debug!(
+                message = "Field does not exist.",
+                field = self.field.as_ref(),
+                rate_limit_secs = 30,
+            );
+        }
+
+        Some(Event::Log(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GrokParserConfig;
+    use crate::event::LogEvent;
+    use crate::{
+        event,
+        topology::config::{TransformConfig, TransformContext},
+        Event,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn parse_log(
+        event: &str,
+        pattern: &str,
+        field: Option<&str>,
+        drop_field: bool,
+        types: &[(&str, &str)],
+    ) -> LogEvent {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let event = Event::from(event);
+        let mut parser = GrokParserConfig {
+            pattern: pattern.into(),
+            field: field.map(|s| s.into()),
+            drop_field,
+            types: types.iter().map(|&(k, v)| (k.into(), v.into())).collect(),
+            ..Default::default()
+        }
+        .build(TransformContext::new_test(rt.executor()))
+        .unwrap();
+        parser.transform(event).unwrap().into_log()
+    }
+
+    #[test]
+    fn grok_parser_adds_parsed_fields_to_event() {
+        let event = parse_log(
+            r#"109.184.11.34 - - [12/Dec/2015:18:32:56 +0100] "GET /administrator/ HTTP/1.1" 200 4263"#,
+            "%{HTTPD_COMMONLOG}",
+            None,
+            true,
+            &[],
+        );
+
+        let expected = json!({
+            "clientip": "109.184.11.34",
+            "ident": "-",
+            "auth": "-",
+            "timestamp": "12/Dec/2015:18:32:56 +0100",
+            "verb": "GET",
+            "request": "/administrator/",
+            "httpversion": "1.1",
+            "rawrequest": "",
+            "response": "200",
+            "bytes": "4263",
+        });
+
+        assert_eq!(expected, serde_json::to_value(&event.all_fields()).unwrap());
+    }
+
+    #[test]
+    fn grok_parser_does_nothing_on_no_match() {
+        let event = parse_log(
+            r#"help i'm stuck in an http server"#,
+            "%{HTTPD_COMMONLOG}",
+            None,
+            true,
+            &[],
+        );
+
+        assert_eq!(2, event.keys().count());
+        assert_eq!(
+            event::Value::from("help i'm stuck in an http server"),
+            event[&event::log_schema().message_key()]
+        );
+        assert!(
+            event[&event::log_schema().timestamp_key()]
+                .to_string_lossy()
+                .len()
+                > 0
+        );
+    }
+
+    #[test]
+    fn grok_parser_can_not_drop_parsed_field() {
+        let event = parse_log(
+            r#"109.184.11.34 - - [12/Dec/2015:18:32:56 +0100] "GET /administrator/ HTTP/1.1" 200 4263"#,
+            "%{HTTPD_COMMONLOG}",
+            None,
+            false,
+            &[],
+        );
+
+        let expected = json!({
+            "clientip": "109.184.11.34",
+            "ident": "-",
+            "auth": "-",
+            "timestamp": "12/Dec/2015:18:32:56 +0100",
+            "verb": "GET",
+            "request": "/administrator/",
+            "httpversion": "1.1",
+            "rawrequest": "",
+            "response": "200",
+            "bytes": "4263",
+            "message": r#"109.184.11.34 - - [12/Dec/2015:18:32:56 +0100] "GET /administrator/ HTTP/1.1" 200 4263"#,
+        });
+
+        assert_eq!(expected, serde_json::to_value(&event.all_fields()).unwrap());
+    }
+
+    #[test]
+    fn grok_parser_does_nothing_on_missing_field() {
+        let event = parse_log(
+            "i am the only field",
+            "^(?<foo>.*)",
+            Some("bar"),
+            false,
+            &[],
+        );
+
+        assert_eq!(2, event.keys().count());
+        assert_eq!(
+            event::Value::from("i am the only field"),
+            event[&event::log_schema().message_key()]
+        );
+        assert!(
+            event[&event::log_schema().timestamp_key()]
+                .to_string_lossy()
+                .len()
+                > 0
+        );
+    }
+
+    #[test]
+    fn grok_parser_coerces_types() {
+        let event = parse_log(
+            r#"109.184.11.34 - - [12/Dec/2015:18:32:56 +0100] "GET /administrator/ HTTP/1.1" 200 4263"#,
+            "%{HTTPD_COMMONLOG}",
+            None,
+            true,
+            &[("response", "int"), ("bytes", "int")],
+        );
+
+        let expected = json!({
+            "clientip": "109.184.11.34",
+            "ident": "-",
+            "auth": "-",
+            "timestamp": "12/Dec/2015:18:32:56 +0100",
+            "verb": "GET",
+            "request": "/administrator/",
+            "httpversion": "1.1",
+            "rawrequest": "",
+            "response": 200,
+            "bytes": 4263,
+        });
+
+        assert_eq!(expected, serde_json::to_value(&event.all_fields()).unwrap());
+    }
+
+    #[test]
+    fn grok_parser_does_not_drop_parsed_message_field() {
+        let event = parse_log(
+            "12/Dec/2015:18:32:56 +0100 42",
+            "%{HTTPDATE:timestamp} %{NUMBER:message}",
+            None,
+            true,
+            &[],
+        );
+
+        let expected = json!({
+            "timestamp": "12/Dec/2015:18:32:56 +0100",
+            "message": "42",
+        });
+
+        assert_eq!(expected, serde_json::to_value(&event.all_fields()).unwrap());
+    }
+
+    #[test]
+    fn grok_parser_applies_first_matching_pattern() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = GrokParserConfig {
+            match_patterns: vec![
+                "%{HTTPD_COMMONLOG}".into(),
+                "%{LOGLEVEL:level} %{GREEDYDATA:message}".into(),
+                "%{GREEDYDATA:rest}".into(),
+            ],
+            matched_pattern_field: Some("pattern".into()),
+            ..Default::default()
+        }
+        .build(TransformContext::new_test(rt.executor()))
+        .unwrap();
+
+        let event = parser
+            .transform(Event::from("ERROR disk is full"))
+            .unwrap()
+            .into_log();
+
+        assert_eq!(event[&"level".into()], "ERROR".into());
+        assert_eq!(event[&"message".into()], "disk is full".into());
+        assert_eq!(event.get(&"rest".into()), None);
+        assert_eq!(event[&"pattern".into()], event::Value::Integer(1));
+    }
+
+    #[test]
+    fn grok_parser_overrides_types_per_pattern() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = toml::from_str::<GrokParserConfig>(
+            r#"
+            [types]
+            code = "int"
+
+            [[match_patterns]]
+            pattern = "code=%{NUMBER:code}"
+
+            [[match_patterns]]
+            pattern = "id=%{WORD:code}"
+            types = { code = "string" }
+            "#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test(rt.executor()))
+        .unwrap();
+
+        let event = parser.transform(Event::from("code=404")).unwrap().into_log();
+        assert_eq!(event[&"code".into()], event::Value::Integer(404));
+
+        let event = parser.transform(Event::from("id=42")).unwrap().into_log();
+        assert_eq!(event[&"code".into()], "42".into());
+    }
+
+    #[test]
+    fn grok_parser_rejects_pattern_and_match_patterns() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let result = GrokParserConfig {
+            pattern: "%{NUMBER:a}".into(),
+            match_patterns: vec!["%{NUMBER:b}".into()],
+            ..Default::default()
+        }
+        .build(TransformContext::new_test(rt.executor()));
+
+        assert!(result.is_err());
+    }
+
+    fn build_error(config: GrokParserConfig) -> String {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        match config.build(TransformContext::new_test(rt.executor())) {
+            Ok(_) => panic!("build should fail"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn grok_parser_uses_inline_patterns() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = GrokParserConfig {
+            pattern: "%{REQUEST_LINE}".into(),
+            patterns: vec![
+                (
+                    "REQUEST_LINE".into(),
+                    "%{METHOD:method} %{NOTSPACE:path}".into(),
+                ),
+                ("METHOD".into(), "(?:GET|POST)".into()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        }
+        .build(TransformContext::new_test(rt.executor()))
+        .unwrap();
+
+        let event = parser
+            .transform(Event::from("POST /index.html"))
+            .unwrap()
+            .into_log();
+
+        assert_eq!(event[&"method".into()], "POST".into());
+        assert_eq!(event[&"path".into()], "/index.html".into());
+    }
+
+    #[test]
+    fn grok_parser_loads_patterns_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("custom"),
+            "# Custom patterns\n\nTENANT [a-z]+\nTENANT_ID %{TENANT:tenant}-%{INT:id}\n",
+        )
+        .unwrap();
+
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = GrokParserConfig {
+            pattern: "%{TENANT_ID}".into(),
+            patterns_dir: Some(dir.path().into()),
+            types: vec![("id".into(), "int".into())].into_iter().collect(),
+            ..Default::default()
+        }
+        .build(TransformContext::new_test(rt.executor()))
+        .unwrap();
+
+        let event = parser.transform(Event::from("acme-42")).unwrap().into_log();
+
+        assert_eq!(event[&"tenant".into()], "acme".into());
+        assert_eq!(event[&"id".into()], event::Value::Integer(42));
+    }
+
+    #[test]
+    fn grok_parser_rejects_invalid_pattern_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("custom"), "TENANT [a-z]+\nBROKEN\n").unwrap();
+
+        let error = build_error(GrokParserConfig {
+            pattern: "%{TENANT}".into(),
+            patterns_dir: Some(dir.path().into()),
+            ..Default::default()
+        });
+
+        assert!(error.starts_with("Invalid pattern definition in"));
+        assert!(error.contains("line 2"));
+    }
+
+    #[test]
+    fn grok_parser_rejects_missing_pattern() {
+        let error = build_error(GrokParserConfig {
+            pattern: "%{OUTER}".into(),
+            patterns: vec![("OUTER".into(), "%{INNER:inner}".into())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            error,
+            r#"Pattern "INNER" referenced by "OUTER" (patterns) is not defined"#
+        );
+    }
+
+    #[test]
+    fn grok_parser_rejects_recursive_patterns() {
+        let error = build_error(GrokParserConfig {
+            pattern: "%{A}".into(),
+            patterns: vec![
+                ("A".into(), "a%{B}".into()),
+                ("B".into(), "b%{C:c}".into()),
+                ("C".into(), "c|%{A}".into()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+
+        assert_eq!(error, "Recursive pattern definition: A -> B -> C -> A");
+    }
+
+    #[test]
+    fn grok_parser_builtin_nginx_access() {
+        let event = parse_log(
+            r#"93.180.71.3 - - [17/May/2015:08:05:32 +0000] "GET /downloads/product_1 HTTP/1.1" 304 0 "-" "Debian APT-HTTP/1.3 (0.8.16~exp12ubuntu10.21)""#,
+            "%{NGINX_ACCESS}",
+            None,
+            true,
+            &[("status", "int")],
+        );
+
+        assert_eq!(event[&"remote_addr".into()], "93.180.71.3".into());
+        assert_eq!(event[&"method".into()], "GET".into());
+        assert_eq!(event[&"request".into()], "/downloads/product_1".into());
+        assert_eq!(event[&"status".into()], event::Value::Integer(304));
+        assert_eq!(
+            event[&"http_user_agent".into()],
+            "Debian APT-HTTP/1.3 (0.8.16~exp12ubuntu10.21)".into()
+        );
+    }
+
+    #[test]
+    fn grok_parser_builtin_java_stack_frame() {
+        let event = parse_log(
+            "\tat com.example.Service.handle(Service.java:42)",
+            "%{JAVA_STACK_FRAME}",
+            None,
+            true,
+            &[],
+        );
+
+        assert_eq!(event[&"class".into()], "com.example.Service".into());
+        assert_eq!(event[&"method".into()], "handle".into());
+        assert_eq!(event[&"file".into()], "Service.java".into());
+        assert_eq!(event[&"line".into()], "42".into());
+    }
+
+    #[test]
+    fn grok_parser_builtin_postgres() {
+        let event = parse_log(
+            "2020-05-04 12:00:00.123 UTC [1234] LOG:  database system is ready",
+            "%{POSTGRES_LOG}",
+            None,
+            false,
+            &[],
+        );
+
+        assert_eq!(event[&"pid".into()], "1234".into());
+        assert_eq!(event[&"level".into()], "LOG".into());
+        assert_eq!(event[&"message".into()], "database system is ready".into());
+    }
+}
@@ -1,6 +1,6 @@
 use super::Transform;
 use crate::{
-    event::{self, Event},
+    event::{self, Event, LogEvent},
     topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
 };
 use serde::{Deserialize, Serialize};
@@ -40,6 +40,10 @@ impl TransformConfig for JsonParserConfig {
     fn transform_type(&self) -> &'static str {
         "json_parser"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -69,9 +73,10 @@ impl From<JsonParserConfig> for JsonParser {
     }
 }
 
-impl Transform for JsonParser {
-    fn transform(&mut self, mut event: Event) -> Option<Event> {
-        let log = event.as_mut_log();
+impl JsonParser {
+    /// Parses the field of `log` in place, returning whether it held a JSON
+    /// object.
+    fn parse(&self, log: &mut LogEvent) -> bool {
         let to_parse = log.get(&self.field).map(|s| s.as_bytes());
 
         let parsed = to_parse
@@ -120,11 +125,40 @@ impl Transform for JsonParser {
                     }
                 }
             }
-        } else if self.drop_invalid {
-            return None;
+            true
+        } else {
+            false
         }
+    }
+}
 
-        Some(event)
+impl Transform for JsonParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        if self.parse(event.as_mut_log()) || !self.drop_invalid {
+            Some(event)
+        } else {
+            None
+        }
+    }
+
+    fn transform_batch(&mut self, mut events: Vec<Event>, output: &mut Vec<Event>) {
+        if self.drop_invalid {
+            output.reserve(events.len());
+            output.extend(events.into_iter().filter_map(|mut event| {
+                if self.parse(event.as_mut_log()) {
+                    Some(event)
+                } else {
+                    None
+                }
+            }));
+        } else {
+            // Nothing is dropped, so the batch is parsed in place and moved
+            // over as a whole.
+            for event in &mut events {
+                self.parse(event.as_mut_log());
+            }
+            output.append(&mut events);
+        }
     }
 }
 
@@ -135,6 +169,35 @@ mod test {
     use crate::transforms::Transform;
     use string_cache::DefaultAtom as Atom;
 
+    #[test]
+    fn json_parser_batch_matches_per_event() {
+        let events = vec![
+            Event::from(r#"{"greeting": "hello", "name": "bob"}"#),
+            Event::from("not json"),
+            Event::from(r#"{"count": 3}"#),
+        ];
+
+        for &drop_invalid in &[false, true] {
+            let config = JsonParserConfig {
+                drop_invalid,
+                ..Default::default()
+            };
+            let mut per_event = JsonParser::from(config.clone());
+            let mut batched = JsonParser::from(config);
+
+            let expected = events
+                .iter()
+                .cloned()
+                .filter_map(|event| per_event.transform(event))
+                .collect::<Vec<_>>();
+            let mut output = Vec::new();
+            batched.transform_batch(events.clone(), &mut output);
+
+            assert_eq!(output, expected);
+            assert_eq!(output.len(), if drop_invalid { 2 } else { 3 });
+        }
+    }
+
     #[test]
     fn json_parser_drop_field() {
         let mut parser = JsonParser::from(JsonParserConfig::default());
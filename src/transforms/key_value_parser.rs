@@ -0,0 +1,345 @@
+use super::Transform;
+use crate::{
+    event::{self, Event},
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    types::{parse_conversion_map, Conversion},
+};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::collections::HashMap;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("`{}` must not be empty", option))]
+    EmptyDelimiter { option: &'static str },
+}
+
+#[derive(Deserialize, Serialize, Debug, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct KeyValueConfig {
+    pub field: Option<Atom>,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+    #[derivative(Default(value = "\" \".into()"))]
+    pub field_delimiter: String,
+    #[derivative(Default(value = "\"=\".into()"))]
+    pub value_delimiter: String,
+    #[derivative(Default(value = "vec!['\"']"))]
+    pub quote_chars: Vec<char>,
+    pub whitespace: Whitespace,
+    pub types: HashMap<Atom, String>,
+}
+
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Whitespace {
+    /// Whitespace is only skipped where it is the field delimiter.
+    #[derivative(Default)]
+    Strict,
+    /// Whitespace around keys, values and delimiters is ignored.
+    Lenient,
+}
+
+inventory::submit! {
+    TransformDescription::new::<KeyValueConfig>("key_value_parser")
+}
+
+#[typetag::serde(name = "key_value_parser")]
+impl TransformConfig for KeyValueConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let field = self
+            .field
+            .as_ref()
+            .unwrap_or(&event::log_schema().message_key());
+        if self.field_delimiter.is_empty() {
+            return Err(BuildError::EmptyDelimiter {
+                option: "field_delimiter",
+            }
+            .into());
+        }
+        if self.value_delimiter.is_empty() {
+            return Err(BuildError::EmptyDelimiter {
+                option: "value_delimiter",
+            }
+            .into());
+        }
+        let conversions = parse_conversion_map(&self.types)?;
+
+        Ok(Box::new(KeyValue {
+            field: field.clone(),
+            drop_field: self.drop_field,
+            splitter: Splitter {
+                field_delimiter: self.field_delimiter.clone(),
+                value_delimiter: self.value_delimiter.clone(),
+                quote_chars: self.quote_chars.clone(),
+                lenient: self.whitespace == Whitespace::Lenient,
+            },
+            conversions,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "key_value_parser"
+    }
+
+    fn stateless(&self) -> bool {
+        true
+    }
+}
+
+pub struct KeyValue {
+    field: Atom,
+    drop_field: bool,
+    splitter: Splitter,
+    conversions: HashMap<Atom, Conversion>,
+}
+
+impl Transform for KeyValue {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let value = event.as_log().get(&self.field).map(|s| s.to_string_lossy());
+
+        let mut drop_field = self.drop_field;
+        if let Some(value) = &value {
+            for (key, val) in self.splitter.split(value) {
+                let key = Atom::from(key);
+                if key == self.field {
+                    drop_field = false;
+                }
+
+                if let Some(conv) = self.conversions.get(&key) {
+                    match conv.convert(val.into()) {
+                        Ok(value) => {
+                            event.as_mut_log().insert(key, value);
+                        }
+                        Err(error) => {
+                            debug!(
+                                message = "Could not convert types.",
+                                key = &key[..],
+                                %error,
+                                rate_limit_secs = 30
+                            );
+                        }
+                    }
+                } else {
+                    event.as_mut_log().insert(key, val);
+                }
+            }
+
+            if drop_field {
+                event.as_mut_log().remove(&self.field);
+            }
+        } else {
+            debug!(
+                message = "Field does not exist.",
+                field = self.field.as_ref(),
+                rate_limit_secs = 30
+            );
+        };
+
+        Some(event)
+    }
+}
+
+struct Splitter {
+    field_delimiter: String,
+    value_delimiter: String,
+    quote_chars: Vec<char>,
+    lenient: bool,
+}
+
+impl Splitter {
+    /// Splits `input` into key/value pairs. Keys without a value are skipped.
+    fn split(&self, input: &str) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        let mut rest = input;
+
+        loop {
+            rest = self.skip_field_delimiters(rest);
+            if rest.is_empty() {
+                break;
+            }
+
+            let (key, after_key) = self.token(rest, true);
+            rest = self.skip_whitespace(after_key);
+
+            if rest.starts_with(&self.value_delimiter) {
+                rest = self.skip_whitespace(&rest[self.value_delimiter.len()..]);
+                let (value, after_value) = self.token(rest, false);
+                rest = after_value;
+
+                if !key.is_empty() {
+                    pairs.push((key, value));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    /// Reads a key or a value, which is either quoted or runs until the next
+    /// field delimiter (or, for keys, value delimiter).
+    fn token<'a>(&self, input: &'a str, is_key: bool) -> (String, &'a str) {
+        if let Some(quote) = input
+            .chars()
+            .next()
+            .filter(|c| self.quote_chars.contains(c))
+        {
+            let mut token = String::new();
+            let mut chars = input[quote.len_utf8()..].char_indices();
+            while let Some((index, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            token.push(escaped);
+                        }
+                    }
+                    c if c == quote => {
+                        let end = quote.len_utf8() + index + c.len_utf8();
+                        return (token, &input[end..]);
+                    }
+                    c => token.push(c),
+                }
+            }
+            // An unterminated quote runs until the end of the input.
+            return (token, "");
+        }
+
+        let end = input
+            .char_indices()
+            .map(|(index, _)| index)
+            .find(|&index| {
+                let rest = &input[index..];
+                rest.starts_with(&self.field_delimiter)
+                    || (is_key && rest.starts_with(&self.value_delimiter))
+                    || (is_key && self.lenient && rest.starts_with(char::is_whitespace))
+            })
+            .unwrap_or_else(|| input.len());
+
+        let token = &input[..end];
+        let token = if self.lenient { token.trim() } else { token };
+        (token.to_owned(), &input[end..])
+    }
+
+    fn skip_field_delimiters<'a>(&self, mut input: &'a str) -> &'a str {
+        loop {
+            input = self.skip_whitespace(input);
+            if input.starts_with(&self.field_delimiter) {
+                input = &input[self.field_delimiter.len()..];
+            } else {
+                return input;
+            }
+        }
+    }
+
+    fn skip_whitespace<'a>(&self, input: &'a str) -> &'a str {
+        if self.lenient {
+            input.trim_start()
+        } else {
+            input
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyValueConfig;
+    use crate::{
+        event::{self, LogEvent, Value},
+        topology::config::{TransformConfig, TransformContext},
+        Event,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn parse_log(text: &str, config: &str) -> LogEvent {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = toml::from_str::<KeyValueConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        let mut log = parser.transform(Event::from(text)).unwrap().into_log();
+        log.remove(&event::log_schema().timestamp_key());
+        log
+    }
+
+    fn fields(log: LogEvent) -> serde_json::Value {
+        serde_json::to_value(log.all_fields()).unwrap()
+    }
+
+    #[test]
+    fn key_value_parses_logfmt() {
+        let log = parse_log(
+            r#"level=info msg="Stopping all fetchers" tag=stopping_fetchers id=ConsumerFetcherManager-1382721708341 module=kafka.consumer.ConsumerFetcherManager"#,
+            "",
+        );
+
+        assert_eq!(
+            fields(log),
+            json!({
+                "level": "info",
+                "msg": "Stopping all fetchers",
+                "tag": "stopping_fetchers",
+                "id": "ConsumerFetcherManager-1382721708341",
+                "module": "kafka.consumer.ConsumerFetcherManager",
+            })
+        );
+    }
+
+    #[test]
+    fn key_value_custom_delimiters_and_quotes() {
+        let log = parse_log(
+            r#"user:'jane \'jd\' doe'; status:200;; empty:; flag"#,
+            r#"
+                field_delimiter = ";"
+                value_delimiter = ":"
+                quote_chars = ["'"]
+                whitespace = "lenient"
+                types = { status = "int" }
+            "#,
+        );
+
+        assert_eq!(
+            fields(log),
+            json!({
+                "user": "jane 'jd' doe",
+                "status": 200,
+                "empty": "",
+            })
+        );
+    }
+
+    #[test]
+    fn key_value_lenient_whitespace() {
+        let log = parse_log("  a = 1   b= two  c =3 ", r#"whitespace = "lenient""#);
+
+        assert_eq!(
+            fields(log),
+            json!({
+                "a": "1",
+                "b": "two",
+                "c": "3",
+            })
+        );
+    }
+
+    #[test]
+    fn key_value_strict_whitespace() {
+        let log = parse_log("a = 1 b=2", "drop_field = false");
+
+        assert_eq!(log.get(&"a".into()), None);
+        assert_eq!(log[&"b".into()], Value::from("2"));
+        assert!(log.get(&event::log_schema().message_key()).is_some());
+    }
+}
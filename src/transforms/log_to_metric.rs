@@ -1,7 +1,7 @@
 use super::Transform;
 use crate::{
     event::metric::{Metric, MetricKind, MetricValue},
-    event::{self, Value},
+    event::{self, sketch::DDSketch, Value},
     template::Template,
     topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
     Event,
@@ -49,6 +49,8 @@ pub struct HistogramConfig {
     field: Atom,
     name: Option<Atom>,
     tags: Option<IndexMap<Atom, String>>,
+    #[serde(default)]
+    sketch: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -188,9 +190,15 @@ fn to_metric(config: &MetricConfig, event: &Event) -> Result<Metric, TransformEr
                 timestamp,
                 tags,
                 kind: MetricKind::Incremental,
-                value: MetricValue::Distribution {
-                    values: vec![value],
-                    sample_rates: vec![1],
+                value: if hist.sketch {
+                    let mut sketch = DDSketch::default();
+                    sketch.insert(value);
+                    MetricValue::Sketch(sketch)
+                } else {
+                    MetricValue::Distribution {
+                        values: vec![value],
+                        sample_rates: vec![1],
+                    }
                 },
             })
         }
@@ -649,4 +657,28 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn response_time_sketch() {
+        let config = parse_config(
+            r#"
+            [[metrics]]
+            type = "histogram"
+            field = "response_time"
+            sketch = true
+            "#,
+        );
+
+        let event = create_event("response_time", "2.5");
+        let mut transform = LogToMetric::new(config);
+        let metric = transform.transform(event).unwrap().into_metric();
+
+        match metric.value {
+            MetricValue::Sketch(sketch) => {
+                assert_eq!(sketch.count, 1);
+                assert_eq!(sketch.quantile(0.5), Some(2.5));
+            }
+            value => panic!("expected a sketch, got {:?}", value),
+        }
+    }
 }
@@ -0,0 +1,527 @@
+use super::Transform;
+use crate::{
+    event::{Event, Value},
+    internal_events::{
+        LookupEventProcessed, LookupFailedMatch, LookupMissingField, LookupTableLoadFailed,
+        LookupTableLoaded,
+    },
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LookupConfig {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub format: Format,
+    /// Maps table columns to the event fields they are matched against.
+    pub keys: IndexMap<String, Atom>,
+    #[serde(default = "default_target")]
+    pub target: Atom,
+    #[serde(default = "crate::serde::default_true")]
+    pub case_sensitive: bool,
+    #[serde(default = "crate::serde::default_true")]
+    pub watch: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Csv,
+    Ndjson,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Csv
+    }
+}
+
+fn default_target() -> Atom {
+    Atom::from("lookup")
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("At least one key must be specified"))]
+    NoKeys,
+    #[snafu(display("Could not open lookup table {:?}: {}", path, source))]
+    OpenTable {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read lookup table {:?}: {}", path, source))]
+    ReadTable {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Invalid CSV in lookup table {:?}: {}", path, source))]
+    InvalidCsv { path: PathBuf, source: csv::Error },
+    #[snafu(display("Invalid JSON on line {} of lookup table {:?}: {}", line, path, source))]
+    InvalidJson {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Line {} of lookup table {:?} is not a JSON object", line, path))]
+    NotAnObject { path: PathBuf, line: usize },
+    #[snafu(display("Key column {:?} is not present in lookup table {:?}", column, path))]
+    MissingColumn { path: PathBuf, column: String },
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<LookupConfig>("lookup")
+}
+
+#[typetag::serde(name = "lookup")]
+impl TransformConfig for LookupConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        if self.keys.is_empty() {
+            return Err(BuildError::NoKeys.into());
+        }
+
+        let loader = Loader {
+            path: self.path.clone(),
+            format: self.format,
+            columns: self.keys.keys().cloned().collect(),
+            case_sensitive: self.case_sensitive,
+        };
+        let table = loader.load()?;
+        emit!(LookupTableLoaded {
+            path: &self.path,
+            rows: table.rows.len(),
+        });
+
+        let table = Arc::new(RwLock::new(table));
+        if self.watch {
+            watch_table(loader, Arc::downgrade(&table))?;
+        }
+
+        Ok(Box::new(Lookup {
+            table,
+            fields: self.keys.values().cloned().collect(),
+            target: self.target.clone(),
+            case_sensitive: self.case_sensitive,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "lookup"
+    }
+}
+
+/// An in-memory copy of the lookup file, indexed on the configured key
+/// columns.
+#[derive(Debug, Default)]
+struct Table {
+    rows: Vec<BTreeMap<String, Value>>,
+    index: HashMap<Vec<String>, usize>,
+}
+
+impl Table {
+    fn get(&self, key: &[String]) -> Option<&BTreeMap<String, Value>> {
+        self.index.get(key).map(|&idx| &self.rows[idx])
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Loader {
+    path: PathBuf,
+    format: Format,
+    columns: Vec<String>,
+    case_sensitive: bool,
+}
+
+impl Loader {
+    fn load(&self) -> crate::Result<Table> {
+        let rows = match self.format {
+            Format::Csv => self.read_csv()?,
+            Format::Ndjson => self.read_ndjson()?,
+        };
+
+        let mut index = HashMap::with_capacity(rows.len());
+        for (idx, row) in rows.iter().enumerate() {
+            let key = self
+                .columns
+                .iter()
+                .map(|column| {
+                    row.get(column)
+                        .map(|value| normalize(value, self.case_sensitive))
+                        .ok_or_else(|| BuildError::MissingColumn {
+                            path: self.path.clone(),
+                            column: column.clone(),
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // The first row with a given key wins, mirroring how most
+            // lookup tools treat duplicates.
+            if index.contains_key(&key) {
+                warn!(
+                    message = "duplicate key in lookup table; ignoring row.",
+                    path = ?self.path,
+                    row = idx + 1,
+                    rate_limit_secs = 30
+                );
+            } else {
+                index.insert(key, idx);
+            }
+        }
+
+        Ok(Table { rows, index })
+    }
+
+    fn read_csv(&self) -> crate::Result<Vec<BTreeMap<String, Value>>> {
+        let file = File::open(&self.path).context(OpenTable { path: &self.path })?;
+        let mut reader = csv::Reader::from_reader(file);
+
+        let headers = reader
+            .headers()
+            .context(InvalidCsv { path: &self.path })?
+            .clone();
+
+        reader
+            .records()
+            .map(|record| -> crate::Result<BTreeMap<String, Value>> {
+                let record = record.context(InvalidCsv { path: &self.path })?;
+                Ok(headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(name, value)| (name.to_owned(), Value::from(value)))
+                    .collect())
+            })
+            .collect()
+    }
+
+    fn read_ndjson(&self) -> crate::Result<Vec<BTreeMap<String, Value>>> {
+        let file = File::open(&self.path).context(OpenTable { path: &self.path })?;
+
+        let mut rows = Vec::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.context(ReadTable { path: &self.path })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let json = serde_json::from_str(&line).context(InvalidJson {
+                path: &self.path,
+                line: idx + 1,
+            })?;
+            match json {
+                serde_json::Value::Object(object) => rows.push(
+                    object
+                        .into_iter()
+                        .map(|(name, value)| (name, Value::from(value)))
+                        .collect(),
+                ),
+                _ => {
+                    return Err(BuildError::NotAnObject {
+                        path: self.path.clone(),
+                        line: idx + 1,
+                    }
+                    .into())
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+}
+
+fn normalize(value: &Value, case_sensitive: bool) -> String {
+    let value = value.to_string_lossy();
+    if case_sensitive {
+        value
+    } else {
+        value.to_lowercase()
+    }
+}
+
+/// Reloads the table whenever the file changes on disk. The watcher thread
+/// exits once the transform, and with it the last strong reference to the
+/// table, is dropped.
+#[cfg(unix)]
+fn watch_table(loader: Loader, table: std::sync::Weak<RwLock<Table>>) -> crate::Result<()> {
+    use crate::topology::config::watcher::{add_paths, CONFIG_WATCH_DELAY};
+    use notify::{raw_watcher, Op, RawEvent};
+    use std::sync::mpsc::{channel, RecvTimeoutError};
+
+    info!(message = "Watching lookup table.", path = ?loader.path);
+    let paths = vec![loader.path.clone()];
+    let (sender, receiver) = channel();
+    let mut watcher = raw_watcher(sender)?;
+    add_paths(&mut watcher, &paths)?;
+
+    std::thread::spawn(move || loop {
+        match receiver.recv_timeout(CONFIG_WATCH_DELAY) {
+            Ok(RawEvent { op: Ok(op), .. })
+                if op.intersects(Op::CREATE | Op::REMOVE | Op::WRITE | Op::CLOSE_WRITE) =>
+            {
+                // Consume events until the file has settled.
+                while let Ok(..) = receiver.recv_timeout(CONFIG_WATCH_DELAY) {}
+
+                let table = match table.upgrade() {
+                    Some(table) => table,
+                    None => break,
+                };
+
+                // Editors frequently replace the file, so the path has to be
+                // re-added to follow the new inode.
+                if let Err(error) = add_paths(&mut watcher, &paths) {
+                    error!(message = "Failed to readd lookup table to watch.", %error);
+                }
+
+                match loader.load() {
+                    Ok(new) => {
+                        emit!(LookupTableLoaded {
+                            path: &loader.path,
+                            rows: new.rows.len(),
+                        });
+                        *table.write().unwrap() = new;
+                    }
+                    Err(error) => {
+                        emit!(LookupTableLoadFailed {
+                            path: &loader.path,
+                            error,
+                        });
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => {
+                if table.upgrade().is_none() {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn watch_table(loader: Loader, _table: std::sync::Weak<RwLock<Table>>) -> crate::Result<()> {
+    warn!(
+        message = "Reloading lookup tables on Windows isn't currently supported.",
+        path = ?loader.path
+    );
+    Ok(())
+}
+
+pub struct Lookup {
+    table: Arc<RwLock<Table>>,
+    fields: Vec<Atom>,
+    target: Atom,
+    case_sensitive: bool,
+}
+
+impl Lookup {
+    fn key(&self, event: &Event) -> Option<Vec<String>> {
+        let log = event.as_log();
+        self.fields
+            .iter()
+            .map(|field| match log.get(field) {
+                Some(value) => Some(normalize(value, self.case_sensitive)),
+                None => {
+                    emit!(LookupMissingField { field });
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl Transform for Lookup {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        emit!(LookupEventProcessed);
+
+        let key = match self.key(&event) {
+            Some(key) => key,
+            None => return Some(event),
+        };
+
+        let table = self.table.read().unwrap();
+        match table.get(&key) {
+            Some(row) => {
+                let log = event.as_mut_log();
+                for (column, value) in row {
+                    log.insert(format!("{}.{}", self.target, column), value.clone());
+                }
+            }
+            None => {
+                emit!(LookupFailedMatch);
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LookupConfig;
+    use crate::{
+        event::{Event, LogEvent, Value},
+        test_util::temp_file,
+        topology::config::{TransformConfig, TransformContext},
+        transforms::Transform,
+    };
+    use std::{fs, path::Path, thread, time::Duration};
+
+    const OWNERS_CSV: &str = "\
+service,team,pager
+billing,payments,payments-oncall
+search,discovery,discovery-oncall
+";
+
+    fn build(path: &Path, config: &str) -> Box<dyn Transform> {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        toml::from_str::<LookupConfig>(&format!("path = {:?}\n{}", path, config))
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap()
+    }
+
+    fn event(fields: &[(&str, &str)]) -> Event {
+        let mut event = Event::new_empty_log();
+        for (k, v) in fields {
+            event.as_mut_log().insert(*k, *v);
+        }
+        event
+    }
+
+    fn transform(transform: &mut Box<dyn Transform>, event: Event) -> LogEvent {
+        transform.transform(event).unwrap().into_log()
+    }
+
+    #[test]
+    fn lookup_csv_exact_match() {
+        let path = temp_file();
+        fs::write(&path, OWNERS_CSV).unwrap();
+        let mut lookup = build(&path, r#"keys = { service = "app" }"#);
+
+        let log = transform(&mut lookup, event(&[("app", "billing")]));
+        assert_eq!(log[&"lookup.team".into()], "payments".into());
+        assert_eq!(log[&"lookup.pager".into()], "payments-oncall".into());
+        assert_eq!(log[&"lookup.service".into()], "billing".into());
+
+        let log = transform(&mut lookup, event(&[("app", "Billing")]));
+        assert!(log.get(&"lookup.team".into()).is_none());
+    }
+
+    #[test]
+    fn lookup_case_insensitive_match() {
+        let path = temp_file();
+        fs::write(&path, OWNERS_CSV).unwrap();
+        let mut lookup = build(
+            &path,
+            r#"
+            keys = { service = "app" }
+            case_sensitive = false
+            target = "owner"
+            "#,
+        );
+
+        let log = transform(&mut lookup, event(&[("app", "SEARCH")]));
+        assert_eq!(log[&"owner.team".into()], "discovery".into());
+    }
+
+    #[test]
+    fn lookup_multiple_keys_ndjson() {
+        let path = temp_file();
+        fs::write(
+            &path,
+            r#"{"host": "web-1", "dc": "us-east", "rack": 12, "tags": {"tier": "frontend"}}
+{"host": "web-1", "dc": "eu-west", "rack": 3}
+
+{"host": "db-1", "dc": "us-east", "rack": 7}
+"#,
+        )
+        .unwrap();
+        let mut lookup = build(
+            &path,
+            r#"
+            format = "ndjson"
+            keys = { host = "hostname", dc = "region" }
+            target = "inventory"
+            "#,
+        );
+
+        let log = transform(
+            &mut lookup,
+            event(&[("hostname", "web-1"), ("region", "us-east")]),
+        );
+        assert_eq!(log[&"inventory.rack".into()], Value::Integer(12));
+        assert_eq!(log[&"inventory.tags.tier".into()], "frontend".into());
+
+        let log = transform(
+            &mut lookup,
+            event(&[("hostname", "web-1"), ("region", "eu-west")]),
+        );
+        assert_eq!(log[&"inventory.rack".into()], Value::Integer(3));
+    }
+
+    #[test]
+    fn lookup_passes_events_missing_key_fields() {
+        let path = temp_file();
+        fs::write(&path, OWNERS_CSV).unwrap();
+        let mut lookup = build(&path, r#"keys = { service = "app" }"#);
+
+        let log = transform(&mut lookup, event(&[("other", "billing")]));
+        assert_eq!(log.keys().collect::<Vec<_>>(), vec!["other".to_string()]);
+    }
+
+    #[test]
+    fn lookup_rejects_missing_key_column() {
+        let path = temp_file();
+        fs::write(&path, OWNERS_CSV).unwrap();
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let error = toml::from_str::<LookupConfig>(&format!(
+            "path = {:?}\nkeys = {{ owner = \"app\" }}",
+            path
+        ))
+        .unwrap()
+        .build(TransformContext::new_test(rt.executor()))
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("\"owner\""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lookup_reloads_on_change() {
+        crate::test_util::trace_init();
+        let path = temp_file();
+        fs::write(&path, OWNERS_CSV).unwrap();
+        let mut lookup = build(&path, r#"keys = { service = "app" }"#);
+
+        fs::write(&path, "service,team\nbilling,finance\n").unwrap();
+
+        for _ in 0..50 {
+            let log = transform(&mut lookup, event(&[("app", "billing")]));
+            if log[&"lookup.team".into()] == "finance".into() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+        panic!("Lookup table was not reloaded");
+    }
+}
@@ -7,6 +7,13 @@ impl<'a> ToLua<'a> for Event {
         match self {
             Event::Log(log) => table.set("log", log.to_lua(ctx)?)?,
             Event::Metric(metric) => table.set("metric", metric.to_lua(ctx)?)?,
+            Event::Trace(_) => {
+                return Err(LuaError::ToLuaConversionError {
+                    from: "Event",
+                    to: "table",
+                    message: Some("Traces are not supported by the Lua transform".to_string()),
+                })
+            }
         }
         Ok(LuaValue::Table(table))
     }
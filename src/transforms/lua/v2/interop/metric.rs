@@ -1,5 +1,8 @@
 use super::util::{table_to_set, table_to_timestamp, timestamp_to_table};
-use crate::event::metric::{Metric, MetricKind, MetricValue};
+use crate::event::{
+    metric::{Metric, MetricKind, MetricValue},
+    sketch::DDSketch,
+};
 use rlua::prelude::*;
 use std::collections::BTreeMap;
 
@@ -93,6 +96,17 @@ impl<'a> ToLua<'a> for Metric {
                 aggregated_summary.set("sum", sum)?;
                 tbl.set("aggregated_summary", aggregated_summary)?;
             }
+            MetricValue::Sketch(sketch) => {
+                let sketch_tbl = ctx.create_table()?;
+                sketch_tbl.set("alpha", sketch.alpha)?;
+                sketch_tbl.set("keys", sketch.keys)?;
+                sketch_tbl.set("counts", sketch.counts)?;
+                sketch_tbl.set("count", sketch.count)?;
+                sketch_tbl.set("sum", sketch.sum)?;
+                sketch_tbl.set("min", sketch.min)?;
+                sketch_tbl.set("max", sketch.max)?;
+                tbl.set("sketch", sketch_tbl)?;
+            }
         }
 
         Ok(LuaValue::Table(tbl))
@@ -159,11 +173,21 @@ impl<'a> FromLua<'a> for Metric {
                 count: aggregated_summary.get("count")?,
                 sum: aggregated_summary.get("sum")?,
             }
+        } else if let Some(sketch) = table.get::<_, Option<LuaTable>>("sketch")? {
+            MetricValue::Sketch(DDSketch {
+                alpha: sketch.get("alpha")?,
+                keys: sketch.get("keys")?,
+                counts: sketch.get("counts")?,
+                count: sketch.get("count")?,
+                sum: sketch.get("sum")?,
+                min: sketch.get("min")?,
+                max: sketch.get("max")?,
+            })
         } else {
             return Err(LuaError::FromLuaConversionError {
                 from: value.type_name(),
                 to: "Metric",
-                message: Some("Cannot find metric value, expected presence one of \"counter\", \"gauge\", \"set\", \"distribution\", \"aggregated_histogram\", \"aggregated_summary\"".to_string()),
+                message: Some("Cannot find metric value, expected presence one of \"counter\", \"gauge\", \"set\", \"distribution\", \"aggregated_histogram\", \"aggregated_summary\", \"sketch\"".to_string()),
             });
         };
 
@@ -358,6 +382,28 @@ mod test {
         assert_metric(metric, assertions)
     }
 
+    #[test]
+    fn to_lua_sketch() {
+        let metric = Metric {
+            name: "example sketch".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Sketch(DDSketch::from_distribution(&[1.0, 3.0], &[2, 1])),
+        };
+        let assertions = vec![
+            "type(metric.sketch) == 'table'",
+            "metric.sketch.alpha == 0.01",
+            "#metric.sketch.keys == 2",
+            "metric.sketch.counts[1] == 2",
+            "metric.sketch.count == 3",
+            "metric.sketch.sum == 5",
+            "metric.sketch.min == 1",
+            "metric.sketch.max == 3",
+        ];
+        assert_metric(metric, assertions)
+    }
+
     #[test]
     fn from_lua_counter_minimal() {
         let value = r#"{
@@ -1,7 +1,9 @@
 use crate::Event;
+use serde::{Deserialize, Serialize};
 use snafu::Snafu;
+use std::{collections::HashMap, hash::Hash};
 
-mod util;
+pub(crate) mod util;
 
 #[cfg(feature = "transforms-add_fields")]
 pub mod add_fields;
@@ -11,10 +13,14 @@ pub mod add_tags;
 pub mod ansi_stripper;
 #[cfg(feature = "transforms-aws_ec2_metadata")]
 pub mod aws_ec2_metadata;
+#[cfg(feature = "transforms-cef_parser")]
+pub mod cef_parser;
 #[cfg(feature = "transforms-coercer")]
 pub mod coercer;
 #[cfg(feature = "transforms-concat")]
 pub mod concat;
+#[cfg(feature = "transforms-csv_parser")]
+pub mod csv_parser;
 #[cfg(feature = "transforms-dedupe")]
 pub mod dedupe;
 #[cfg(feature = "transforms-field_filter")]
@@ -27,16 +33,22 @@ pub mod geoip;
 pub mod grok_parser;
 #[cfg(feature = "transforms-json_parser")]
 pub mod json_parser;
+#[cfg(feature = "transforms-key_value_parser")]
+pub mod key_value_parser;
 #[cfg(feature = "transforms-kubernetes")]
 pub mod kubernetes;
 #[cfg(feature = "transforms-log_to_metric")]
 pub mod log_to_metric;
 #[cfg(feature = "transforms-logfmt_parser")]
 pub mod logfmt_parser;
+#[cfg(feature = "transforms-lookup")]
+pub mod lookup;
 #[cfg(feature = "transforms-lua")]
 pub mod lua;
 #[cfg(feature = "transforms-merge")]
 pub mod merge;
+#[cfg(feature = "transforms-redact")]
+pub mod redact;
 #[cfg(feature = "transforms-regex_parser")]
 pub mod regex_parser;
 #[cfg(feature = "transforms-remove_fields")]
@@ -51,12 +63,20 @@ pub mod sampler;
 pub mod split;
 #[cfg(feature = "transforms-swimlanes")]
 pub mod swimlanes;
+#[cfg(feature = "transforms-syslog_parser")]
+pub mod syslog_parser;
 #[cfg(feature = "transforms-tag_cardinality_limit")]
 pub mod tag_cardinality_limit;
+#[cfg(feature = "transforms-timestamp_parser")]
+pub mod timestamp_parser;
 #[cfg(feature = "transforms-tokenizer")]
 pub mod tokenizer;
 
 use futures01::Stream;
+use util::ready_chunks::ReadyChunks;
+
+/// The maximum number of events handed to `Transform::transform_batch` at once.
+pub(crate) const BATCH_SIZE: usize = 128;
 
 pub trait Transform: Send {
     fn transform(&mut self, event: Event) -> Option<Event>;
@@ -67,6 +87,20 @@ pub trait Transform: Send {
         }
     }
 
+    /// Transforms a batch of events, pushing the results onto `output`.
+    ///
+    /// The default implementation calls `transform_into` for each event.
+    /// Transforms that can amortize work across events, or that simply want
+    /// to avoid a dynamic call per event, should override it.
+    fn transform_batch(&mut self, events: Vec<Event>, output: &mut Vec<Event>) {
+        for event in events {
+            self.transform_into(output, event);
+        }
+    }
+
+    /// Drives the transform from the topology. The default implementation
+    /// drains whatever events are ready on the input channel, up to
+    /// `BATCH_SIZE`, and hands them to `transform_batch` in one go.
     fn transform_stream(
         self: Box<Self>,
         input_rx: Box<dyn Stream<Item = Event, Error = ()> + Send>,
@@ -76,10 +110,10 @@ pub trait Transform: Send {
     {
         let mut me = self;
         Box::new(
-            input_rx
-                .map(move |event| {
-                    let mut output = Vec::with_capacity(1);
-                    me.transform_into(&mut output, event);
+            ReadyChunks::new(input_rx, BATCH_SIZE)
+                .map(move |events| {
+                    let mut output = Vec::with_capacity(events.len());
+                    me.transform_batch(events, &mut output);
                     futures01::stream::iter_ok(output.into_iter())
                 })
                 .flatten(),
@@ -94,4 +128,53 @@ enum BuildError {
 
     #[snafu(display("Invalid substring expression: {}", name))]
     InvalidSubstring { name: String },
+
+    #[snafu(display("Only one of `{}` and `{}` may be set", single, list))]
+    ConflictingPatterns {
+        single: &'static str,
+        list: &'static str,
+    },
+}
+
+/// An entry of a parser's `match_patterns`: either just the pattern, or a
+/// table of the pattern and `types` for its captures, which take precedence
+/// over the parser's own `types`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum MatchPattern {
+    Pattern(String),
+    WithTypes {
+        pattern: String,
+        #[serde(default)]
+        types: HashMap<String, String>,
+    },
+}
+
+impl MatchPattern {
+    pub fn pattern(&self) -> &str {
+        match self {
+            MatchPattern::Pattern(pattern) | MatchPattern::WithTypes { pattern, .. } => pattern,
+        }
+    }
+
+    /// The types to coerce this pattern's captures to, given the parser's.
+    pub fn types<K>(&self, defaults: &HashMap<K, String>) -> HashMap<K, String>
+    where
+        K: Clone + Eq + Hash + From<String>,
+    {
+        let mut types = defaults.clone();
+        if let MatchPattern::WithTypes { types: own, .. } = self {
+            types.extend(
+                own.iter()
+                    .map(|(name, conversion)| (K::from(name.clone()), conversion.clone())),
+            );
+        }
+        types
+    }
+}
+
+impl From<&str> for MatchPattern {
+    fn from(pattern: &str) -> Self {
+        MatchPattern::Pattern(pattern.into())
+    }
 }
@@ -0,0 +1,500 @@
+use super::Transform;
+use crate::{
+    event::{Event, Value},
+    internal_events::{RedactEventProcessed, RedactPerformed},
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+};
+use hmac::{Hmac, Mac};
+use indexmap::IndexMap;
+use regex::{Match, Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use snafu::ResultExt;
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::net::Ipv6Addr;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RedactConfig {
+    #[serde(default)]
+    pub fields: Vec<Atom>,
+    #[serde(default = "default_detectors")]
+    pub detectors: Vec<Detector>,
+    #[serde(default)]
+    pub patterns: IndexMap<String, String>,
+    #[serde(default)]
+    pub replacement: Replacement,
+}
+
+/// The built-in detectors.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Detector {
+    CreditCard,
+    Email,
+    Ipv4,
+    Ipv6,
+    UsSsn,
+    Jwt,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum Replacement {
+    Fixed {
+        #[serde(default = "default_fixed_value")]
+        value: String,
+    },
+    Mask {
+        #[serde(default = "default_mask_char")]
+        char: char,
+    },
+    Hmac {
+        key: String,
+    },
+}
+
+impl Default for Replacement {
+    fn default() -> Self {
+        Replacement::Fixed {
+            value: default_fixed_value(),
+        }
+    }
+}
+
+fn default_detectors() -> Vec<Detector> {
+    vec![
+        Detector::CreditCard,
+        Detector::Email,
+        Detector::Ipv4,
+        Detector::Ipv6,
+        Detector::UsSsn,
+        Detector::Jwt,
+    ]
+}
+
+fn default_fixed_value() -> String {
+    "[REDACTED]".into()
+}
+
+fn default_mask_char() -> char {
+    '*'
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<RedactConfig>("redact")
+}
+
+#[typetag::serde(name = "redact")]
+impl TransformConfig for RedactConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let mut patterns = Vec::new();
+
+        for detector in &self.detectors {
+            let (regex, validate) = detector.pattern();
+            patterns.push(Pattern {
+                name: detector.name().into(),
+                regex: Regex::new(regex).expect("built-in pattern is valid"),
+                validate,
+            });
+        }
+
+        for (name, regex) in &self.patterns {
+            patterns.push(Pattern {
+                name: name.clone(),
+                regex: Regex::new(regex).context(super::InvalidRegex)?,
+                validate: None,
+            });
+        }
+
+        Ok(Box::new(Redact::new(
+            self.fields.clone(),
+            patterns,
+            self.replacement.clone(),
+        )?))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "redact"
+    }
+
+    fn stateless(&self) -> bool {
+        true
+    }
+}
+
+/// Checks a candidate match against the text it was found in, for
+/// detectors that can't be expressed precisely as a regular expression.
+type Validator = fn(&str, &Match) -> bool;
+
+impl Detector {
+    fn name(&self) -> &'static str {
+        match self {
+            Detector::CreditCard => "credit_card",
+            Detector::Email => "email",
+            Detector::Ipv4 => "ipv4",
+            Detector::Ipv6 => "ipv6",
+            Detector::UsSsn => "us_ssn",
+            Detector::Jwt => "jwt",
+        }
+    }
+
+    fn pattern(&self) -> (&'static str, Option<Validator>) {
+        match self {
+            Detector::CreditCard => (r"\b(?:\d[ -]?){12,18}\d\b", Some(is_luhn_valid)),
+            Detector::Email => (r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b", None),
+            Detector::Ipv4 => (
+                r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+                None,
+            ),
+            Detector::Ipv6 => (
+                r"(?i)(?:[0-9a-f]{1,4}:(?:[0-9a-f]{0,4}:){1,6}|::(?:[0-9a-f]{0,4}:){0,5})(?:[0-9a-f]{1,4}|(?:\d{1,3}\.){3}\d{1,3})?",
+                Some(is_ipv6),
+            ),
+            Detector::UsSsn => (r"\b\d{3}-\d{2}-\d{4}\b", Some(is_us_ssn)),
+            Detector::Jwt => (
+                r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+                None,
+            ),
+        }
+    }
+}
+
+fn is_luhn_valid(_text: &str, candidate: &Match) -> bool {
+    let digits = candidate
+        .as_str()
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .collect::<Vec<_>>();
+    if digits.len() < 13 || digits.len() > 19 {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(idx, &digit)| match (idx % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+fn is_ipv6(text: &str, candidate: &Match) -> bool {
+    // The candidate pattern can't use word boundaries since addresses may
+    // begin or end with `:`, so check the surrounding characters here
+    // instead. This keeps things like `Foo::bar` intact.
+    let is_part_of_word = |c: char| c.is_ascii_alphanumeric() || c == ':' || c == '.';
+    let mut preceding = text[..candidate.start()].chars().rev();
+    let before = preceding.next();
+    let after = text[candidate.end()..].chars().next();
+
+    // A single `:` separates an address from a preceding key, as in
+    // `addr:fe80::1`, unless it continues an address-like run of text.
+    let is_key_separator = before == Some(':')
+        && !candidate.as_str().starts_with(':')
+        && !preceding
+            .next()
+            .map_or(false, |c| c.is_ascii_hexdigit() || c == ':' || c == '.');
+
+    (is_key_separator || !before.map_or(false, is_part_of_word))
+        && !after.map_or(false, is_part_of_word)
+        && candidate.as_str().chars().any(|c| c.is_ascii_hexdigit())
+        && candidate.as_str().parse::<Ipv6Addr>().is_ok()
+}
+
+fn is_us_ssn(_text: &str, candidate: &Match) -> bool {
+    let mut parts = candidate.as_str().split('-');
+    let area = parts.next().unwrap_or_default();
+    let group = parts.next().unwrap_or_default();
+    let serial = parts.next().unwrap_or_default();
+
+    area != "000" && area != "666" && !area.starts_with('9') && group != "00" && serial != "0000"
+}
+
+struct Pattern {
+    name: String,
+    regex: Regex,
+    validate: Option<Validator>,
+}
+
+impl Pattern {
+    /// Returns the redacted text and the number of redactions made, or
+    /// `None` if nothing was redacted.
+    fn redact(&self, text: &str, replacer: &Replacer) -> Option<(String, usize)> {
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        let mut count = 0;
+
+        for candidate in self.regex.find_iter(text) {
+            if let Some(validate) = self.validate {
+                if !validate(text, &candidate) {
+                    continue;
+                }
+            }
+
+            redacted.push_str(&text[last..candidate.start()]);
+            replacer.replace(candidate.as_str(), &mut redacted);
+            last = candidate.end();
+            count += 1;
+        }
+
+        if count == 0 {
+            None
+        } else {
+            redacted.push_str(&text[last..]);
+            Some((redacted, count))
+        }
+    }
+}
+
+enum Replacer {
+    Fixed(String),
+    Mask(char),
+    Hmac(Hmac<Sha256>),
+}
+
+impl Replacer {
+    fn replace(&self, value: &str, output: &mut String) {
+        match self {
+            Replacer::Fixed(fixed) => output.push_str(fixed),
+            Replacer::Mask(mask) => output.extend(value.chars().map(|_| *mask)),
+            Replacer::Hmac(mac) => {
+                let mut mac = mac.clone();
+                mac.input(value.as_bytes());
+                for byte in mac.result().code() {
+                    write!(output, "{:02x}", byte).expect("writing to a String never fails");
+                }
+            }
+        }
+    }
+}
+
+pub struct Redact {
+    fields: Vec<Atom>,
+    patterns: Vec<Pattern>,
+    pattern_set: RegexSet,
+    replacer: Replacer,
+}
+
+impl Redact {
+    fn new(
+        fields: Vec<Atom>,
+        patterns: Vec<Pattern>,
+        replacement: Replacement,
+    ) -> crate::Result<Self> {
+        // All patterns are checked in a single pass, so only the patterns
+        // that are known to match need to run individually.
+        let pattern_set = RegexSet::new(patterns.iter().map(|pattern| pattern.regex.as_str()))
+            .context(super::InvalidRegex)?;
+
+        let replacer = match replacement {
+            Replacement::Fixed { value } => Replacer::Fixed(value),
+            Replacement::Mask { char } => Replacer::Mask(char),
+            Replacement::Hmac { key } => Replacer::Hmac(
+                Hmac::new_varkey(key.as_bytes()).map_err(|_| "Invalid HMAC key length")?,
+            ),
+        };
+
+        Ok(Self {
+            fields,
+            patterns,
+            pattern_set,
+            replacer,
+        })
+    }
+
+    fn redact_str<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+
+        for idx in self.pattern_set.matches(&text).iter() {
+            let pattern = &self.patterns[idx];
+            if let Some((redacted, count)) = pattern.redact(&text, &self.replacer) {
+                emit!(RedactPerformed {
+                    detector: &pattern.name,
+                    count,
+                });
+                text = Cow::Owned(redacted);
+            }
+        }
+
+        text
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Bytes(bytes) => {
+                let redacted = match self.redact_str(&String::from_utf8_lossy(bytes)) {
+                    Cow::Owned(redacted) => Some(redacted),
+                    Cow::Borrowed(_) => None,
+                };
+                if let Some(redacted) = redacted {
+                    *bytes = redacted.into();
+                }
+            }
+            Value::Map(map) => map.values_mut().for_each(|value| self.redact_value(value)),
+            Value::Array(array) => array.iter_mut().for_each(|value| self.redact_value(value)),
+            _ => {}
+        }
+    }
+}
+
+impl Transform for Redact {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        emit!(RedactEventProcessed);
+
+        let log = event.as_mut_log();
+        if self.fields.is_empty() {
+            let keys = log.keys().map(Atom::from).collect::<Vec<_>>();
+            for key in keys {
+                if let Some(value) = log.get_mut(&key) {
+                    self.redact_value(value);
+                }
+            }
+        } else {
+            for field in &self.fields {
+                if let Some(value) = log.get_mut(field) {
+                    self.redact_value(value);
+                }
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RedactConfig;
+    use crate::{
+        event::{Event, LogEvent, Value},
+        topology::config::{TransformConfig, TransformContext},
+    };
+
+    fn redact(config: &str, event: Event) -> LogEvent {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut transform = toml::from_str::<RedactConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+        transform.transform(event).unwrap().into_log()
+    }
+
+    fn redact_message(config: &str, message: &str) -> String {
+        let log = redact(config, Event::from(message));
+        log[&"message".into()].to_string_lossy()
+    }
+
+    #[test]
+    fn redacts_builtin_detectors() {
+        assert_eq!(
+            redact_message("", "card 4111 1111 1111 1111 from jane.doe@example.com"),
+            "card [REDACTED] from [REDACTED]"
+        );
+        assert_eq!(
+            redact_message(
+                "",
+                "client 192.168.0.1 and 2001:db8::ff00:42:8329 connected"
+            ),
+            "client [REDACTED] and [REDACTED] connected"
+        );
+        assert_eq!(
+            redact_message(
+                "",
+                "ssn=123-45-6789 token=eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.abc-_"
+            ),
+            "ssn=[REDACTED] token=[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn redacts_ipv6_after_key() {
+        assert_eq!(
+            redact_message("", "addr:fe80::1 peer=::1"),
+            "addr:[REDACTED] peer=[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn skips_invalid_candidates() {
+        let message = "order 4111 1111 1111 1112 at 12:30:45 in Foo::bar, ref 000-12-3456";
+        assert_eq!(redact_message("", message), message);
+    }
+
+    #[test]
+    fn redacts_custom_patterns_only() {
+        let config = r#"
+            detectors = []
+            [patterns]
+            api_key = "key-[0-9a-f]{8}"
+        "#;
+        assert_eq!(
+            redact_message(config, "auth key-deadbeef for admin@example.com"),
+            "auth [REDACTED] for admin@example.com"
+        );
+    }
+
+    #[test]
+    fn masks_values() {
+        let config = r##"
+            detectors = ["email"]
+            replacement = { strategy = "mask", char = "#" }
+        "##;
+        assert_eq!(redact_message(config, "to a@b.io"), "to ######");
+    }
+
+    #[test]
+    fn hashes_values_consistently() {
+        let config = r#"
+            detectors = ["email"]
+            replacement = { strategy = "hmac", key = "secret" }
+        "#;
+        let first = redact_message(config, "a@example.com");
+        let second = redact_message(config, "a@example.com");
+        let other = redact_message(config, "b@example.com");
+
+        assert_eq!(first.len(), 64);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn redacts_selected_fields() {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert("user.email", "a@example.com");
+        event.as_mut_log().insert("user.ip", "10.0.0.1");
+        event.as_mut_log().insert("other", "b@example.com");
+        event.as_mut_log().insert("count", 42);
+
+        let log = redact(r#"fields = ["user"]"#, event);
+        assert_eq!(log[&"user.email".into()], "[REDACTED]".into());
+        assert_eq!(log[&"user.ip".into()], "[REDACTED]".into());
+        assert_eq!(log[&"other".into()], "b@example.com".into());
+        assert_eq!(log[&"count".into()], Value::Integer(42));
+    }
+
+    #[test]
+    fn redacts_all_fields_by_default() {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert("a.b[0]", "a@example.com");
+        event.as_mut_log().insert("c", "10.0.0.1");
+
+        let log = redact("", event);
+        assert_eq!(log[&"a.b[0]".into()], "[REDACTED]".into());
+        assert_eq!(log[&"c".into()], "[REDACTED]".into());
+    }
+}
@@ -1,11 +1,11 @@
-use super::Transform;
+use super::{MatchPattern, Transform};
 use crate::{
-    event::{self, Event, Value},
+    event::{self, Event, LogEvent, Value},
     internal_events::{RegexEventProcessed, RegexFailedMatch, RegexMissingField},
     topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
-    types::{parse_check_conversion_map, Conversion},
+    types::{parse_check_conversion_map, parse_conversion_map, Conversion},
 };
-use regex::bytes::{CaptureLocations, Regex};
+use regex::bytes::{CaptureLocations, Regex, RegexSet};
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 use std::collections::HashMap;
@@ -17,6 +17,8 @@ use string_cache::DefaultAtom as Atom;
 #[serde(default, deny_unknown_fields)]
 pub struct RegexParserConfig {
     pub regex: String,
+    pub match_patterns: Vec<MatchPattern>,
+    pub matched_pattern_field: Option<Atom>,
     pub field: Option<Atom>,
     #[derivative(Default(value = "true"))]
     pub drop_field: bool,
@@ -48,19 +50,76 @@ impl TransformConfig for RegexParserConfig {
     fn transform_type(&self) -> &'static str {
         "regex"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 pub struct RegexParser {
-    regex: Regex,
+    regexset: RegexSet,
+    patterns: Vec<CompiledRegex>,
     field: Atom,
-    drop_field: bool,
     drop_failed: bool,
     target_field: Option<Atom>,
     overwrite_target: bool,
+    matched_pattern_field: Option<Atom>,
+}
+
+struct CompiledRegex {
+    regex: Regex,
+    drop_field: bool,
     capture_names: Vec<(usize, Atom, Conversion)>,
     capture_locs: CaptureLocations,
 }
 
+impl CompiledRegex {
+    fn new(
+        regex: Regex,
+        field: &Atom,
+        drop_field: bool,
+        target_field: Option<&Atom>,
+        types: &HashMap<Atom, Conversion>,
+    ) -> Self {
+        // Build a buffer of the regex capture locations to avoid
+        // repeated allocations.
+        let capture_locs = regex.capture_locations();
+
+        // Calculate the location (index into the capture locations) of
+        // each named capture, and the required type coercion.
+        let capture_names: Vec<(usize, Atom, Conversion)> = regex
+            .capture_names()
+            .enumerate()
+            .filter_map(|(idx, cn)| {
+                cn.map(|cn| {
+                    let cn: Atom = cn.into();
+                    let conv = types.get(&cn).unwrap_or(&Conversion::Bytes);
+                    (idx, cn, conv.clone())
+                })
+            })
+            .collect();
+
+        // Pre-calculate if the source field name should be dropped.
+        let drop_field = drop_field && !capture_names.iter().any(|(_, f, _)| f == field);
+
+        // Pre-calculate the names the captures are inserted under.
+        let capture_names = match target_field {
+            Some(target) => capture_names
+                .into_iter()
+                .map(|(idx, name, conv)| (idx, Atom::from(format!("{}.{}", target, name)), conv))
+                .collect(),
+            None => capture_names,
+        };
+
+        Self {
+            regex,
+            drop_field,
+            capture_names,
+            capture_locs,
+        }
+    }
+}
+
 impl RegexParser {
     pub fn build(config: &RegexParserConfig) -> crate::Result<Box<dyn Transform>> {
         let field = config
@@ -68,80 +127,109 @@ impl RegexParser {
             .as_ref()
             .unwrap_or(&event::log_schema().message_key());
 
-        let regex = Regex::new(&config.regex).context(super::InvalidRegex)?;
-
-        let names = &regex
-            .capture_names()
+        let patterns = match (config.regex.is_empty(), config.match_patterns.is_empty()) {
+            (_, true) => vec![(config.regex.clone(), config.types.clone())],
+            (true, false) => config
+                .match_patterns
+                .iter()
+                .map(|pattern| (pattern.pattern().to_owned(), pattern.types(&config.types)))
+                .collect(),
+            (false, false) => {
+                return Err(super::BuildError::ConflictingPatterns {
+                    single: "regex",
+                    list: "match_patterns",
+                }
+                .into())
+            }
+        };
+
+        let regexset = RegexSet::new(patterns.iter().map(|(pattern, _)| pattern))
+            .context(super::InvalidRegex)?;
+        let regexes = patterns
+            .iter()
+            .map(|(pattern, _)| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()
+            .context(super::InvalidRegex)?;
+
+        let names = &regexes
+            .iter()
+            .flat_map(|regex| regex.capture_names())
             .filter_map(|s| s.map(Into::into))
             .collect::<Vec<_>>();
-        let types = parse_check_conversion_map(&config.types, names)?;
+        parse_check_conversion_map(&config.types, names)?;
+        let patterns = regexes
+            .into_iter()
+            .zip(patterns)
+            .map(|(regex, (_, types))| Ok((regex, parse_conversion_map(&types)?)))
+            .collect::<crate::Result<Vec<_>>>()?;
 
         Ok(Box::new(RegexParser::new(
-            regex,
+            regexset,
+            patterns,
             field.clone(),
             config.drop_field,
             config.drop_failed,
             config.target_field.clone(),
             config.overwrite_target,
-            types,
+            config.matched_pattern_field.clone(),
         )))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        regex: Regex,
+        regexset: RegexSet,
+        patterns: Vec<(Regex, HashMap<Atom, Conversion>)>,
         field: Atom,
-        mut drop_field: bool,
+        drop_field: bool,
         drop_failed: bool,
         target_field: Option<Atom>,
         overwrite_target: bool,
-        types: HashMap<Atom, Conversion>,
+        matched_pattern_field: Option<Atom>,
     ) -> Self {
-        // Build a buffer of the regex capture locations to avoid
-        // repeated allocations.
-        let capture_locs = regex.capture_locations();
-
-        // Calculate the location (index into the capture locations) of
-        // each named capture, and the required type coercion.
-        let capture_names: Vec<(usize, Atom, Conversion)> = regex
-            .capture_names()
-            .enumerate()
-            .filter_map(|(idx, cn)| {
-                cn.map(|cn| {
-                    let cn: Atom = cn.into();
-                    let conv = types.get(&cn).unwrap_or(&Conversion::Bytes);
-                    (idx, cn, conv.clone())
-                })
+        let patterns = patterns
+            .into_iter()
+            .map(|(regex, types)| {
+                CompiledRegex::new(regex, &field, drop_field, target_field.as_ref(), &types)
             })
             .collect();
 
-        // Pre-calculate if the source field name should be dropped.
-        drop_field = drop_field && !capture_names.iter().any(|(_, f, _)| *f == field);
-
         Self {
-            regex,
+            regexset,
+            patterns,
             field,
-            drop_field,
             drop_failed,
             target_field,
             overwrite_target,
-            capture_names,
-            capture_locs,
+            matched_pattern_field,
         }
     }
+
+    /// Finds the first pattern matching `value` and fills in its capture
+    /// locations. With several patterns, the `RegexSet` narrows the search
+    /// down so that only the matching pattern is run for its captures.
+    fn find_match(&mut self, value: &[u8]) -> Option<usize> {
+        let index = if self.patterns.len() == 1 {
+            0
+        } else {
+            self.regexset.matches(value).iter().next()?
+        };
+
+        let pattern = &mut self.patterns[index];
+        pattern
+            .regex
+            .captures_read(&mut pattern.capture_locs, value)
+            .map(|_| index)
+    }
 }
 
-impl Transform for RegexParser {
-    fn transform(&mut self, mut event: Event) -> Option<Event> {
-        let log = event.as_mut_log();
+impl RegexParser {
+    /// Parses the field of `log` in place, returning whether the event
+    /// should be kept.
+    fn parse(&mut self, log: &mut LogEvent) -> bool {
         let value = log.get(&self.field).map(|s| s.as_bytes());
-        emit!(RegexEventProcessed);
 
         if let Some(value) = &value {
-            if self
-                .regex
-                .captures_read(&mut self.capture_locs, &value)
-                .is_some()
-            {
+            if let Some(index) = self.find_match(&value) {
                 // Handle optional overwriting of the target field
                 if let Some(target_field) = &self.target_field {
                     if log.contains(target_field) {
@@ -149,20 +237,17 @@ impl Transform for RegexParser {
                             log.remove(target_field);
                         } else {
                             error!(message = "target field already exists", %target_field, rate_limit_secs = 30);
-                            return Some(event);
+                            return true;
                         }
                     }
                 }
 
-                for (idx, name, conversion) in &self.capture_names {
-                    if let Some((start, end)) = self.capture_locs.get(*idx) {
+                let pattern = &self.patterns[index];
+                for (idx, name, conversion) in &pattern.capture_names {
+                    if let Some((start, end)) = pattern.capture_locs.get(*idx) {
                         let capture: Value = value[start..end].into();
                         match conversion.convert(capture) {
                             Ok(value) => {
-                                let name = match &self.target_field {
-                                    Some(target) => Atom::from(format!("{}.{}", target, name)),
-                                    None => name.clone(),
-                                };
                                 log.insert(name, value);
                             }
                             Err(error) => {
@@ -176,10 +261,13 @@ impl Transform for RegexParser {
                         }
                     }
                 }
-                if self.drop_field {
+                if pattern.drop_field {
                     log.remove(&self.field);
                 }
-                return Some(event);
+                if let Some(matched_pattern_field) = &self.matched_pattern_field {
+                    log.insert(matched_pattern_field, index as i64);
+                }
+                return true;
             } else {
                 emit!(RegexFailedMatch { value });
             }
@@ -187,10 +275,35 @@ impl Transform for RegexParser {
             emit!(RegexMissingField { field: &self.field });
         }
 
-        if self.drop_failed {
+        !self.drop_failed
+    }
+}
+
+impl Transform for RegexParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        emit!(RegexEventProcessed { count: 1 });
+        if self.parse(event.as_mut_log()) {
+            Some(event)
+        } else {
             None
+        }
+    }
+
+    fn transform_batch(&mut self, mut events: Vec<Event>, output: &mut Vec<Event>) {
+        // The processed events are counted once for the whole batch.
+        emit!(RegexEventProcessed { count: events.len() });
+        if self.drop_failed {
+            output.reserve(events.len());
+            for mut event in events {
+                if self.parse(event.as_mut_log()) {
+                    output.push(event);
+                }
+            }
         } else {
-            Some(event)
+            for event in &mut events {
+                self.parse(event.as_mut_log());
+            }
+            output.append(&mut events);
         }
     }
 }
@@ -350,6 +463,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn batch_matches_per_event() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let events = vec!["status=1234 time=5678", "garbage", "status=5 time=6"]
+            .into_iter()
+            .map(Event::from)
+            .collect::<Vec<_>>();
+
+        for extra in &["", "drop_failed = true", "target_field = \"parsed\""] {
+            let config = toml::from_str::<RegexParserConfig>(&format!(
+                r#"
+                regex = 'status=(?P<status>\d+) time=(?P<time>\d+)'
+                types = {{ status = "int" }}
+                {}
+                "#,
+                extra
+            ))
+            .unwrap();
+            let mut per_event = config
+                .build(TransformContext::new_test(rt.executor()))
+                .unwrap();
+            let mut batched = config
+                .build(TransformContext::new_test(rt.executor()))
+                .unwrap();
+
+            let expected = events
+                .iter()
+                .cloned()
+                .filter_map(|event| per_event.transform(event))
+                .collect::<Vec<_>>();
+            let mut output = Vec::new();
+            batched.transform_batch(events.clone(), &mut output);
+
+            assert_eq!(output, expected);
+        }
+    }
+
     #[test]
     fn does_not_drop_event_if_match() {
         let log = do_transform("asdf1234", r"asdf", "drop_failed = true");
@@ -391,4 +541,111 @@ mod tests {
         assert_eq!(log[&"status".into()], Value::Integer(1234));
         assert_eq!(log[&"time".into()], Value::Float(6789.01));
     }
+
+    fn do_transform_patterns(event: &str, config: &str) -> Option<LogEvent> {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let event = Event::from(event);
+        let mut parser = toml::from_str::<RegexParserConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        parser.transform(event).map(|event| event.into_log())
+    }
+
+    #[test]
+    fn applies_first_matching_pattern() {
+        let config = r#"
+            match_patterns = [
+                '^status=(?P<status>\d+)$',
+                '^(?P<level>\w+): (?P<message>.*)$',
+                '^(?P<word>\w+).*$',
+            ]
+            matched_pattern_field = "pattern"
+        "#;
+
+        let log = do_transform_patterns("error: disk full", config).unwrap();
+        assert_eq!(log[&"level".into()], "error".into());
+        assert_eq!(log[&"message".into()], "disk full".into());
+        assert_eq!(log.get(&"word".into()), None);
+        assert_eq!(log[&"pattern".into()], Value::Integer(1));
+
+        let log = do_transform_patterns("status=200", config).unwrap();
+        assert_eq!(log[&"status".into()], "200".into());
+        assert_eq!(log.get(&"message".into()), None);
+        assert_eq!(log[&"pattern".into()], Value::Integer(0));
+    }
+
+    #[test]
+    fn coerces_types_for_every_pattern() {
+        let config = r#"
+            match_patterns = [
+                '^code=(?P<code>\d+)$',
+                '^code=(?P<code>\w+) took=(?P<took>[\d.]+)$',
+            ]
+            [types]
+            code = "int"
+            took = "float"
+        "#;
+
+        let log = do_transform_patterns("code=404", config).unwrap();
+        assert_eq!(log[&"code".into()], Value::Integer(404));
+
+        let log = do_transform_patterns("code=42 took=1.5", config).unwrap();
+        assert_eq!(log[&"code".into()], Value::Integer(42));
+        assert_eq!(log[&"took".into()], Value::Float(1.5));
+    }
+
+    #[test]
+    fn overrides_types_per_pattern() {
+        let config = r#"
+            [types]
+            code = "int"
+            took = "float"
+
+            [[match_patterns]]
+            pattern = '^code=(?P<code>\d+)$'
+
+            [[match_patterns]]
+            pattern = '^code=(?P<code>\w+) took=(?P<took>[\d.]+)$'
+            types = { code = "string" }
+        "#;
+
+        let log = do_transform_patterns("code=404", config).unwrap();
+        assert_eq!(log[&"code".into()], Value::Integer(404));
+
+        let log = do_transform_patterns("code=42 took=1.5", config).unwrap();
+        assert_eq!(log[&"code".into()], "42".into());
+        assert_eq!(log[&"took".into()], Value::Float(1.5));
+    }
+
+    #[test]
+    fn does_nothing_if_no_pattern_matches() {
+        let log = do_transform_patterns(
+            "asdf1234",
+            r#"
+                match_patterns = ['^a=(?P<a>\d+)$', '^b=(?P<b>\d+)$']
+                matched_pattern_field = "pattern"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(log[&"message".into()], "asdf1234".into());
+        assert_eq!(log.get(&"pattern".into()), None);
+    }
+
+    #[test]
+    fn rejects_regex_and_match_patterns() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let result = toml::from_str::<RegexParserConfig>(
+            r#"
+                regex = "(?P<a>.*)"
+                match_patterns = ["(?P<b>.*)"]
+            "#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test(rt.executor()));
+
+        assert!(result.is_err());
+    }
 }
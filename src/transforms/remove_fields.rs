@@ -42,6 +42,10 @@ impl TransformConfig for RemoveFieldsConfig {
     fn transform_type(&self) -> &'static str {
         "remove_fields"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 impl RemoveFields {
@@ -48,6 +48,10 @@ impl TransformConfig for RenameFieldsConfig {
     fn transform_type(&self) -> &'static str {
         "rename_fields"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 impl RenameFields {
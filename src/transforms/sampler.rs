@@ -1,13 +1,19 @@
 use super::Transform;
 use crate::{
-    event::{self, Event},
+    event::{self, Event, LogEvent, Trace},
     topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
 };
 use regex::RegexSet; // TODO: use regex::bytes
 use serde::{Deserialize, Serialize};
-use snafu::ResultExt;
+use snafu::{ResultExt, Snafu};
 use string_cache::DefaultAtom as Atom;
 
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("`rate` must be at least 1"))]
+    ZeroRate,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct SamplerConfig {
@@ -24,6 +30,10 @@ inventory::submit! {
 #[typetag::serde(name = "sampler")]
 impl TransformConfig for SamplerConfig {
     fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        if self.rate == 0 {
+            return Err(BuildError::ZeroRate.into());
+        }
+
         Ok(RegexSet::new(&self.pass_list)
             .map::<Box<dyn Transform>, _>(|regex_set| {
                 Box::new(Sampler::new(self.rate, self.key_field.clone(), regex_set))
@@ -32,11 +42,11 @@ impl TransformConfig for SamplerConfig {
     }
 
     fn input_type(&self) -> DataType {
-        DataType::Log
+        DataType::Any
     }
 
     fn output_type(&self) -> DataType {
-        DataType::Log
+        DataType::Any
     }
 
     fn transform_type(&self) -> &'static str {
@@ -61,39 +71,79 @@ impl Sampler {
     }
 }
 
-impl Transform for Sampler {
-    fn transform(&mut self, mut event: Event) -> Option<Event> {
-        let message = event
-            .as_log()
+impl Sampler {
+    fn sample_log(&self, mut log: LogEvent) -> Option<LogEvent> {
+        let message = log
             .get(&self.key_field)
             .map(|v| v.to_string_lossy())
             .unwrap_or_else(|| "".into());
 
         if self.pass_list.is_match(&message) {
-            return Some(event);
+            return Some(log);
+        }
+
+        if self.is_sampled(&message) {
+            log.insert(Atom::from("sample_rate"), self.rate.to_string());
+            Some(log)
+        } else {
+            None
         }
+    }
 
-        if seahash::hash(message.as_bytes()) % self.rate == 0 {
-            event
-                .as_mut_log()
-                .insert(Atom::from("sample_rate"), self.rate.to_string());
+    /// Spans are sampled by their trace ID, so a trace is either kept or
+    /// dropped as a whole. The pass list is matched against the span name.
+    fn sample_trace(&self, mut trace: Trace) -> Option<Trace> {
+        if self.pass_list.is_match(&trace.name) {
+            return Some(trace);
+        }
 
-            Some(event)
+        if self.is_sampled(&trace.trace_id) {
+            trace
+                .attributes
+                .insert("sample_rate".into(), self.rate.to_string().into());
+            Some(trace)
         } else {
             None
         }
     }
+
+    fn is_sampled(&self, key: &str) -> bool {
+        seahash::hash(key.as_bytes()) % self.rate == 0
+    }
+}
+
+impl Transform for Sampler {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::Log(log) => self.sample_log(log).map(Event::Log),
+            Event::Trace(trace) => self.sample_trace(trace).map(Event::Trace),
+            // Metrics aren't sampled.
+            Event::Metric(_) => Some(event),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Sampler;
-    use crate::event::{self, Event};
+    use super::{Sampler, SamplerConfig};
+    use crate::event::{self, Event, Trace};
+    use crate::topology::config::{TransformConfig, TransformContext};
     use crate::transforms::Transform;
     use approx::assert_relative_eq;
+    use chrono::Utc;
     use regex::RegexSet;
     use string_cache::DefaultAtom as Atom;
 
+    #[test]
+    fn rejects_zero_rate() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let config = toml::from_str::<SamplerConfig>("rate = 0").unwrap();
+
+        assert!(config
+            .build(TransformContext::new_test(rt.executor()))
+            .is_err());
+    }
+
     #[test]
     fn samples_at_roughly_the_configured_rate() {
         let num_events = 10000;
@@ -159,6 +209,32 @@ mod tests {
         assert_eq!(total_passed, 1000);
     }
 
+    #[test]
+    fn samples_spans_by_trace_id() {
+        let mut sampler = Sampler::new(4, None, RegexSet::new(&["important"]).unwrap());
+        let now = Utc::now();
+
+        for trace in 0..100 {
+            let trace_id = format!("{:032x}", trace);
+            let spans = (0..5)
+                .map(|span| Trace::new(&trace_id[..], format!("{:016x}", span), "span", now, now))
+                .collect::<Vec<_>>();
+            let passed = spans
+                .into_iter()
+                .filter_map(|span| sampler.transform(span.into()))
+                .collect::<Vec<_>>();
+
+            assert!(passed.is_empty() || passed.len() == 5);
+            for span in passed {
+                assert_eq!(span.as_trace().attributes["sample_rate"], "4".into());
+            }
+        }
+
+        let important = Trace::new("00", "01", "important span", now, now);
+        let mut sampler = Sampler::new(0, None, RegexSet::new(&["important"]).unwrap());
+        assert!(sampler.transform(important.into()).is_some());
+    }
+
     #[test]
     fn sampler_adds_sampling_rate_to_event() {
         let events = random_events(10000);
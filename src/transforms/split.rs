@@ -57,6 +57,10 @@ impl TransformConfig for SplitConfig {
     fn transform_type(&self) -> &'static str {
         "split"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 pub struct Split {
@@ -0,0 +1,187 @@
+use super::Transform;
+use crate::{
+    event::{self, Event},
+    syslog_message,
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct SyslogParserConfig {
+    pub field: Option<Atom>,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+}
+
+inventory::submit! {
+    TransformDescription::new::<SyslogParserConfig>("syslog_parser")
+}
+
+#[typetag::serde(name = "syslog_parser")]
+impl TransformConfig for SyslogParserConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let field = self
+            .field
+            .as_ref()
+            .unwrap_or(&event::log_schema().message_key());
+
+        Ok(Box::new(SyslogParser {
+            field: field.clone(),
+            drop_field: self.drop_field,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "syslog_parser"
+    }
+
+    fn stateless(&self) -> bool {
+        true
+    }
+}
+
+pub struct SyslogParser {
+    field: Atom,
+    drop_field: bool,
+}
+
+impl Transform for SyslogParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let value = event.as_log().get(&self.field).map(|s| s.to_string_lossy());
+
+        if let Some(value) = value {
+            let parsed = syslog_message::parse(&value);
+
+            // The parser never fails, it puts whatever it doesn't understand
+            // into the message. A line without a priority or a timestamp
+            // is not syslog.
+            if parsed.facility.is_none() && parsed.timestamp.is_none() {
+                debug!(
+                    message = "Field is not a syslog message.",
+                    field = self.field.as_ref(),
+                    rate_limit_secs = 30
+                );
+                return Some(event);
+            }
+
+            let log = event.as_mut_log();
+            let message_key = event::log_schema().message_key();
+            if self.drop_field && self.field != *message_key {
+                log.remove(&self.field);
+            }
+
+            log.insert(message_key.clone(), parsed.msg);
+            if let Some(host) = parsed.hostname {
+                log.insert(event::log_schema().host_key().clone(), host);
+            }
+            if let Some(timestamp) = parsed.timestamp {
+                let timestamp: DateTime<Utc> = timestamp.into();
+                log.insert(event::log_schema().timestamp_key().clone(), timestamp);
+            }
+
+            syslog_message::insert_fields(log, parsed);
+        } else {
+            debug!(
+                message = "Field does not exist.",
+                field = self.field.as_ref(),
+                rate_limit_secs = 30
+            );
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyslogParserConfig;
+    use crate::{
+        event::{self, LogEvent, Value},
+        topology::config::{TransformConfig, TransformContext},
+        Event,
+    };
+    use chrono::{TimeZone, Utc};
+
+    fn parse_log(text: &str, config: &str) -> LogEvent {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = toml::from_str::<SyslogParserConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        parser.transform(Event::from(text)).unwrap().into_log()
+    }
+
+    #[test]
+    fn syslog_parser_parses_rfc5424() {
+        let log = parse_log(
+            r#"<13>1 2019-02-13T19:48:34+00:00 74794bfb6795 root 8449 - [meta sequenceId="1"] i am foobar"#,
+            "",
+        );
+
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "i am foobar".into()
+        );
+        assert_eq!(log[&event::log_schema().host_key()], "74794bfb6795".into());
+        assert_eq!(
+            log[&event::log_schema().timestamp_key()],
+            Value::Timestamp(Utc.ymd(2019, 2, 13).and_hms(19, 48, 34))
+        );
+        assert_eq!(log[&"meta.sequenceId".into()], "1".into());
+        assert_eq!(log[&"severity".into()], "notice".into());
+        assert_eq!(log[&"facility".into()], "user".into());
+        assert_eq!(log[&"appname".into()], "root".into());
+        assert_eq!(log[&"procid".into()], Value::Integer(8449));
+        assert_eq!(log[&"version".into()], Value::Integer(1));
+    }
+
+    #[test]
+    fn syslog_parser_parses_rfc3164_from_other_field() {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert(
+            "raw",
+            "<34>Feb 13 20:07:26 mymachine su: 'su root' failed for lonvick on /dev/pts/8",
+        );
+
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = toml::from_str::<SyslogParserConfig>(r#"field = "raw""#)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+        let log = parser.transform(event).unwrap().into_log();
+
+        assert_eq!(log.get(&"raw".into()), None);
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "'su root' failed for lonvick on /dev/pts/8".into()
+        );
+        assert_eq!(log[&event::log_schema().host_key()], "mymachine".into());
+        assert_eq!(log[&"appname".into()], "su".into());
+        assert_eq!(log[&"severity".into()], "crit".into());
+        assert_eq!(log[&"facility".into()], "auth".into());
+    }
+
+    #[test]
+    fn syslog_parser_ignores_non_syslog() {
+        let log = parse_log("just some text", "");
+
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "just some text".into()
+        );
+        assert_eq!(log.get(&"severity".into()), None);
+    }
+}
@@ -0,0 +1,513 @@
+use super::Transform;
+use crate::{
+    event::{self, Event, Value},
+    topology::config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    types::format_has_zone,
+};
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDateTime, TimeZone as _, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid timezone {:?}: {}", name, message))]
+    InvalidTimezone { name: String, message: String },
+    #[snafu(display("At least one timestamp format is required"))]
+    NoFormats,
+    #[snafu(display("{} must be at most {}, got {}", option, max, secs))]
+    RangeTooLarge {
+        option: &'static str,
+        secs: u64,
+        max: i64,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct TimestampParserConfig {
+    pub field: Option<Atom>,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+    pub target_field: Option<Atom>,
+    #[derivative(Default(value = "vec![\"rfc3339\".into(), \"unix\".into()]"))]
+    pub formats: Vec<String>,
+    #[derivative(Default(value = "\"local\".into()"))]
+    pub timezone: String,
+    pub max_past_secs: Option<u64>,
+    pub max_future_secs: Option<u64>,
+    pub out_of_range: OutOfRange,
+    #[derivative(Default(value = "Atom::from(\"timestamp_out_of_range\")"))]
+    pub out_of_range_field: Atom,
+}
+
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutOfRange {
+    /// Keep the parsed timestamp and set `out_of_range_field` to `true`.
+    #[derivative(Default)]
+    Flag,
+    /// Replace the parsed timestamp with the nearest allowed one.
+    Clamp,
+}
+
+inventory::submit! {
+    TransformDescription::new::<TimestampParserConfig>("timestamp_parser")
+}
+
+#[typetag::serde(name = "timestamp_parser")]
+impl TransformConfig for TimestampParserConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let field = self
+            .field
+            .as_ref()
+            .unwrap_or(&event::log_schema().message_key());
+        let target_field = self
+            .target_field
+            .as_ref()
+            .unwrap_or(&event::log_schema().timestamp_key());
+        if self.formats.is_empty() {
+            return Err(BuildError::NoFormats.into());
+        }
+
+        let timezone = match self.timezone.as_str() {
+            "local" => TimeZone::Local,
+            name => TimeZone::Named(name.parse::<Tz>().map_err(|message| {
+                BuildError::InvalidTimezone {
+                    name: name.into(),
+                    message,
+                }
+            })?),
+        };
+
+        Ok(Box::new(TimestampParser {
+            field: field.clone(),
+            drop_field: self.drop_field && field != target_field,
+            target_field: target_field.clone(),
+            formats: self
+                .formats
+                .iter()
+                .map(|format| Format::new(format))
+                .collect(),
+            timezone,
+            max_past: range_duration("max_past_secs", self.max_past_secs)?,
+            max_future: range_duration("max_future_secs", self.max_future_secs)?,
+            out_of_range: self.out_of_range,
+            out_of_range_field: self.out_of_range_field.clone(),
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "timestamp_parser"
+    }
+
+    fn stateless(&self) -> bool {
+        true
+    }
+}
+
+fn range_duration(
+    option: &'static str,
+    secs: Option<u64>,
+) -> Result<Option<Duration>, BuildError> {
+    let max = Duration::max_value().num_seconds();
+    match secs {
+        Some(secs) if secs > max as u64 => Err(BuildError::RangeTooLarge { option, secs, max }),
+        secs => Ok(secs.map(|secs| Duration::seconds(secs as i64))),
+    }
+}
+
+/// The timezone of timestamps that don't specify one.
+#[derive(Clone, Copy, Debug)]
+enum TimeZone {
+    Local,
+    Named(Tz),
+}
+
+impl TimeZone {
+    fn localize(self, naive: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        // Ambiguous times, during DST transitions, resolve to the earlier one.
+        match self {
+            TimeZone::Local => earliest(Local.from_local_datetime(naive)),
+            TimeZone::Named(tz) => earliest(tz.from_local_datetime(naive)),
+        }
+    }
+}
+
+fn earliest<T: chrono::TimeZone>(result: LocalResult<DateTime<T>>) -> Option<DateTime<Utc>> {
+    match result {
+        LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => {
+            Some(datetime.with_timezone(&Utc))
+        }
+        LocalResult::None => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Format {
+    Rfc3339,
+    /// Seconds, or fractions of a second, since the Unix epoch. The value is
+    /// the number of units in a second.
+    Unix(i64),
+    Strftime {
+        format: String,
+        has_zone: bool,
+    },
+}
+
+impl Format {
+    fn new(format: &str) -> Self {
+        match format {
+            "rfc3339" => Format::Rfc3339,
+            "unix" => Format::Unix(1),
+            "unix_ms" => Format::Unix(1_000),
+            "unix_us" => Format::Unix(1_000_000),
+            "unix_ns" => Format::Unix(1_000_000_000),
+            format => Format::Strftime {
+                format: format.into(),
+                has_zone: format_has_zone(format),
+            },
+        }
+    }
+
+    fn parse(&self, value: &str, timezone: TimeZone) -> Option<DateTime<Utc>> {
+        match self {
+            Format::Rfc3339 => DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|datetime| datetime.with_timezone(&Utc)),
+            Format::Unix(units) => parse_unix(value, *units),
+            Format::Strftime {
+                format,
+                has_zone: true,
+            } => DateTime::parse_from_str(value, format)
+                .ok()
+                .map(|datetime| datetime.with_timezone(&Utc)),
+            Format::Strftime {
+                format,
+                has_zone: false,
+            } => NaiveDateTime::parse_from_str(value, format)
+                .ok()
+                .and_then(|naive| timezone.localize(&naive)),
+        }
+    }
+}
+
+fn parse_unix(value: &str, units: i64) -> Option<DateTime<Utc>> {
+    const NANOS: i64 = 1_000_000_000;
+
+    // Fractions are parsed by hand, going through `f64` loses precision.
+    let (whole, fraction) = match value.find('.') {
+        Some(index) => (&value[..index], &value[index + 1..]),
+        None => (value, ""),
+    };
+    let whole = whole.parse::<i64>().ok()?;
+    let mut fraction_nanos = if fraction.is_empty() {
+        0
+    } else {
+        if !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        // Digits beyond nanosecond precision are ignored.
+        let digits = &fraction[..fraction.len().min(9)];
+        digits.parse::<i64>().ok()? * 10i64.pow(9 - digits.len() as u32) / units
+    };
+    if value.starts_with('-') {
+        fraction_nanos = -fraction_nanos;
+    }
+
+    let nanos = whole.rem_euclid(units) * (NANOS / units) + fraction_nanos;
+    let secs = whole
+        .div_euclid(units)
+        .checked_add(nanos.div_euclid(NANOS))?;
+    earliest(Utc.timestamp_opt(secs, nanos.rem_euclid(NANOS) as u32))
+}
+
+pub struct TimestampParser {
+    field: Atom,
+    drop_field: bool,
+    target_field: Atom,
+    formats: Vec<Format>,
+    timezone: TimeZone,
+    max_past: Option<Duration>,
+    max_future: Option<Duration>,
+    out_of_range: OutOfRange,
+    out_of_range_field: Atom,
+}
+
+impl TimestampParser {
+    fn parse(&self, value: &str) -> Option<DateTime<Utc>> {
+        let value = value.trim();
+        self.formats
+            .iter()
+            .find_map(|format| format.parse(value, self.timezone))
+    }
+
+    /// Returns the allowed timestamp nearest to `timestamp`, if it is out of
+    /// range.
+    fn check_range(&self, timestamp: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let now = Utc::now();
+        // A bound that can't be represented is no bound at all.
+        let earliest = self
+            .max_past
+            .and_then(|max_past| now.checked_sub_signed(max_past));
+        let latest = self
+            .max_future
+            .and_then(|max_future| now.checked_add_signed(max_future));
+        match (earliest, latest) {
+            (Some(earliest), _) if timestamp < earliest => Some(earliest),
+            (_, Some(latest)) if timestamp > latest => Some(latest),
+            _ => None,
+        }
+    }
+}
+
+impl Transform for TimestampParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let value = match event.as_log().get(&self.field) {
+            Some(Value::Timestamp(_)) => return Some(event),
+            Some(value) => value.to_string_lossy(),
+            None => {
+                debug!(
+                    message = "Field does not exist.",
+                    field = self.field.as_ref(),
+                    rate_limit_secs = 30
+                );
+                return Some(event);
+            }
+        };
+
+        let mut timestamp = match self.parse(&value) {
+            Some(timestamp) => timestamp,
+            None => {
+                debug!(
+                    message = "Could not parse timestamp.",
+                    field = self.field.as_ref(),
+                    value = value.as_str(),
+                    rate_limit_secs = 30
+                );
+                return Some(event);
+            }
+        };
+
+        let log = event.as_mut_log();
+        if let Some(nearest) = self.check_range(timestamp) {
+            debug!(
+                message = "Timestamp is out of range.",
+                %timestamp,
+                rate_limit_secs = 30
+            );
+            match self.out_of_range {
+                OutOfRange::Flag => {
+                    log.insert(self.out_of_range_field.clone(), true);
+                }
+                OutOfRange::Clamp => timestamp = nearest,
+            }
+        }
+
+        if self.drop_field {
+            log.remove(&self.field);
+        }
+        log.insert(self.target_field.clone(), timestamp);
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimestampParserConfig;
+    use crate::{
+        event::{self, LogEvent, Value},
+        topology::config::{TransformConfig, TransformContext},
+        Event,
+    };
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn parse_log(text: &str, config: &str) -> LogEvent {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let mut parser = toml::from_str::<TimestampParserConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        parser.transform(Event::from(text)).unwrap().into_log()
+    }
+
+    fn timestamp(log: &LogEvent) -> Value {
+        log[&event::log_schema().timestamp_key()].clone()
+    }
+
+    #[test]
+    fn timestamp_parser_tries_formats_in_order() {
+        let config = r#"
+            formats = ["%d/%b/%Y:%H:%M:%S %z", "%Y-%m-%d %H:%M:%S", "rfc3339"]
+            timezone = "UTC"
+        "#;
+
+        let log = parse_log("10/Oct/2000:13:55:36 -0700", config);
+        assert_eq!(
+            timestamp(&log),
+            Value::Timestamp(Utc.ymd(2000, 10, 10).and_hms(20, 55, 36))
+        );
+        assert_eq!(log.get(&event::log_schema().message_key()), None);
+
+        let log = parse_log("2000-10-10 13:55:36", config);
+        assert_eq!(
+            timestamp(&log),
+            Value::Timestamp(Utc.ymd(2000, 10, 10).and_hms(13, 55, 36))
+        );
+
+        let log = parse_log("2000-10-10T13:55:36.5+02:00", config);
+        assert_eq!(
+            timestamp(&log),
+            Value::Timestamp(Utc.ymd(2000, 10, 10).and_hms_milli(11, 55, 36, 500))
+        );
+
+        let log = parse_log("not a timestamp", config);
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "not a timestamp".into()
+        );
+    }
+
+    #[test]
+    fn timestamp_parser_parses_unix_timestamps() {
+        let expected = Value::Timestamp(Utc.ymd(2020, 3, 1).and_hms_micro(12, 0, 0, 123_456));
+
+        let log = parse_log("1583064000.123456", "");
+        assert_eq!(timestamp(&log), expected);
+        let log = parse_log("1583064000123456", r#"formats = ["unix_us"]"#);
+        assert_eq!(timestamp(&log), expected);
+        let log = parse_log("1583064000123456000", r#"formats = ["unix_ns"]"#);
+        assert_eq!(timestamp(&log), expected);
+        let log = parse_log("1583064000123", r#"formats = ["unix_ms"]"#);
+        assert_eq!(
+            timestamp(&log),
+            Value::Timestamp(Utc.ymd(2020, 3, 1).and_hms_milli(12, 0, 0, 123))
+        );
+    }
+
+    #[test]
+    fn timestamp_parser_rejects_overflowing_unix_timestamps() {
+        for text in &[
+            format!("{}.5", std::i64::MAX),
+            format!("{}.5", std::i64::MIN),
+        ] {
+            let log = parse_log(text, "");
+            assert_eq!(log[&event::log_schema().message_key()], text.into());
+        }
+    }
+
+    #[test]
+    fn timestamp_parser_uses_iana_timezone() {
+        let config = r#"
+            formats = ["%Y-%m-%d %H:%M:%S"]
+            timezone = "America/New_York"
+            target_field = "parsed"
+        "#;
+
+        let log = parse_log("2020-01-15 08:00:00", config);
+        assert_eq!(
+            log[&"parsed".into()],
+            Value::Timestamp(Utc.ymd(2020, 1, 15).and_hms(13, 0, 0))
+        );
+        let log = parse_log("2020-07-15 08:00:00", config);
+        assert_eq!(
+            log[&"parsed".into()],
+            Value::Timestamp(Utc.ymd(2020, 7, 15).and_hms(12, 0, 0))
+        );
+    }
+
+    #[test]
+    fn timestamp_parser_rejects_unknown_timezone() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let config =
+            toml::from_str::<TimestampParserConfig>(r#"timezone = "Mars/Olympus""#).unwrap();
+
+        assert!(config
+            .build(TransformContext::new_test(rt.executor()))
+            .is_err());
+    }
+
+    #[test]
+    fn timestamp_parser_rejects_excessive_range() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        for option in &["max_past_secs", "max_future_secs"] {
+            let config = toml::from_str::<TimestampParserConfig>(&format!(
+                "{} = 18446744073709551615",
+                option
+            ))
+            .unwrap();
+
+            assert!(config
+                .build(TransformContext::new_test(rt.executor()))
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn timestamp_parser_ignores_unrepresentable_range() {
+        let log = parse_log(
+            "2000-01-01T00:00:00Z",
+            "max_past_secs = 9223372036854775\nmax_future_secs = 9223372036854775",
+        );
+
+        assert_eq!(
+            timestamp(&log),
+            Value::Timestamp(Utc.ymd(2000, 1, 1).and_hms(0, 0, 0))
+        );
+        assert_eq!(log.get(&"timestamp_out_of_range".into()), None);
+    }
+
+    #[test]
+    fn timestamp_parser_flags_out_of_range() {
+        let log = parse_log(
+            "2000-01-01T00:00:00Z",
+            "max_past_secs = 86400\nmax_future_secs = 86400",
+        );
+
+        assert_eq!(
+            timestamp(&log),
+            Value::Timestamp(Utc.ymd(2000, 1, 1).and_hms(0, 0, 0))
+        );
+        assert_eq!(log[&"timestamp_out_of_range".into()], Value::Boolean(true));
+    }
+
+    #[test]
+    fn timestamp_parser_clamps_out_of_range() {
+        let config = r#"
+            max_past_secs = 86400
+            max_future_secs = 3600
+            out_of_range = "clamp"
+        "#;
+
+        let before = Utc::now();
+        let log = parse_log("2999-01-01T00:00:00Z", config);
+        let after = Utc::now();
+        match timestamp(&log) {
+            Value::Timestamp(clamped) => {
+                assert!(clamped >= before + Duration::hours(1));
+                assert!(clamped <= after + Duration::hours(1));
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+        assert_eq!(log.get(&"timestamp_out_of_range".into()), None);
+
+        let log = parse_log("2020-01-01T00:00:00Z", config);
+        match timestamp(&log) {
+            Value::Timestamp(clamped) => assert!(clamped >= before - Duration::days(1)),
+            value => panic!("Unexpected value {:?}", value),
+        }
+    }
+}
@@ -62,6 +62,10 @@ impl TransformConfig for TokenizerConfig {
     fn transform_type(&self) -> &'static str {
         "tokenizer"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 pub struct Tokenizer {
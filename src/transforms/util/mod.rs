@@ -1,2 +1,3 @@
+pub mod ready_chunks;
 #[cfg(any(feature = "transforms-lua"))]
 pub mod runtime_transform;
@@ -0,0 +1,101 @@
+use futures01::{stream::Fuse, Async, Poll, Stream};
+
+/// A stream combinator which groups the items that are immediately available
+/// from the inner stream into vectors of at most `capacity` items.
+///
+/// Unlike `Stream::chunks`, this never waits for a chunk to fill up: as soon
+/// as the inner stream returns `NotReady`, whatever has been buffered so far
+/// is yielded.
+pub struct ReadyChunks<S: Stream> {
+    inner: Fuse<S>,
+    items: Vec<S::Item>,
+    capacity: usize,
+}
+
+impl<S: Stream> ReadyChunks<S> {
+    pub fn new(inner: S, capacity: usize) -> Self {
+        assert!(capacity > 0);
+
+        Self {
+            inner: inner.fuse(),
+            items: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn take(&mut self) -> Vec<S::Item> {
+        std::mem::replace(&mut self.items, Vec::with_capacity(self.capacity))
+    }
+}
+
+impl<S: Stream> Stream for ReadyChunks<S> {
+    type Item = Vec<S::Item>;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        loop {
+            match self.inner.poll() {
+                Ok(Async::Ready(Some(item))) => {
+                    self.items.push(item);
+                    if self.items.len() >= self.capacity {
+                        return Ok(Async::Ready(Some(self.take())));
+                    }
+                }
+                Ok(Async::Ready(None)) => {
+                    return if self.items.is_empty() {
+                        Ok(Async::Ready(None))
+                    } else {
+                        Ok(Async::Ready(Some(self.take())))
+                    };
+                }
+                Ok(Async::NotReady) => {
+                    return if self.items.is_empty() {
+                        Ok(Async::NotReady)
+                    } else {
+                        Ok(Async::Ready(Some(self.take())))
+                    };
+                }
+                Err(error) => {
+                    // Errors are passed through immediately; any buffered
+                    // items are yielded on the next poll.
+                    return Err(error);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadyChunks;
+    use futures01::{sync::mpsc, Async, Future, Sink, Stream};
+
+    #[test]
+    fn ready_chunks_splits_at_capacity() {
+        let stream = futures01::stream::iter_ok::<_, ()>(0..5);
+        let chunks = ReadyChunks::new(stream, 2).collect().wait().unwrap();
+
+        assert_eq!(chunks, vec![vec![0, 1], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn ready_chunks_yields_without_waiting() {
+        let (tx, rx) = mpsc::channel(10);
+        let tx = tx.send(1).wait().unwrap();
+        let tx = tx.send(2).wait().unwrap();
+
+        futures01::future::lazy(move || {
+            let mut chunks = ReadyChunks::new(rx, 10);
+
+            assert_eq!(chunks.poll(), Ok(Async::Ready(Some(vec![1, 2]))));
+            assert_eq!(chunks.poll(), Ok(Async::NotReady));
+
+            drop(tx);
+            assert_eq!(chunks.poll(), Ok(Async::Ready(None)));
+
+            Ok::<_, ()>(())
+        })
+        .wait()
+        .unwrap();
+    }
+}
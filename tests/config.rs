@@ -667,3 +667,41 @@ fn parses_sink_full_es_aws() {
     )
     .unwrap();
 }
+
+#[cfg(all(
+    feature = "sources-socket",
+    feature = "transforms-sampler",
+    feature = "sinks-socket"
+))]
+#[test]
+fn concurrency_requires_stateless_transform() {
+    let err = load(
+        r#"
+        [sources.in]
+        type = "socket"
+        mode = "tcp"
+        address = "127.0.0.1:1235"
+
+        [transforms.sampler]
+        type = "sampler"
+        inputs = ["in"]
+        rate = 10
+        concurrency = 2
+
+        [sinks.out]
+        type = "socket"
+        mode = "tcp"
+        inputs = ["sampler"]
+        encoding = "text"
+        address = "127.0.0.1:9999"
+      "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        vec![
+            "Transform \"sampler\" of type \"sampler\" is not stateless and can't have a concurrency above 1."
+        ]
+    );
+}
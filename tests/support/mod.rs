@@ -149,6 +149,9 @@ impl Transform for MockTransform {
                     .get(&event::log_schema().message_key())
                     .unwrap()
                     .to_string_lossy();
+                if v == "panic" {
+                    panic!("mock transform asked to panic");
+                }
                 v.push_str(&self.suffix);
                 log.insert(event::log_schema().message_key().clone(), Value::from(v));
             }
@@ -179,6 +182,9 @@ impl Transform for MockTransform {
                     *count += 1;
                     *sum += self.increase;
                 }
+                MetricValue::Sketch(ref mut sketch) => {
+                    sketch.insert(self.increase);
+                }
                 MetricValue::Gauge { ref mut value, .. } => {
                     *value += self.increase;
                 }
@@ -186,6 +192,9 @@ impl Transform for MockTransform {
                     values.insert(self.suffix.clone());
                 }
             },
+            Event::Trace(trace) => {
+                trace.name.push_str(&self.suffix);
+            }
         };
         Some(event)
     }
@@ -223,6 +232,10 @@ impl TransformConfig for MockTransformConfig {
     fn transform_type(&self) -> &'static str {
         "mock"
     }
+
+    fn stateless(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
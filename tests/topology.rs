@@ -207,6 +207,70 @@ fn topology_transform_chain() {
     assert_eq!(vec!["this first second"], res);
 }
 
+#[test]
+fn topology_concurrent_transform_preserves_order() {
+    let mut rt = runtime();
+    let (in1, source1) = source();
+    let (out1, sink1) = sink(10);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_transform("t1", &["in1"], transform(" transformed", 0.0));
+    config.add_sink("out1", &["t1"], sink1);
+    config.transforms["t1"].concurrency = 4;
+
+    let (topology, _crash) = topology::start(config, &mut rt, false).unwrap();
+
+    let messages = (0..1000).map(|i| i.to_string()).collect::<Vec<_>>();
+    let events = messages.iter().map(|message| Event::from(&message[..]));
+    let h_out1 = oneshot::spawn(out1.map(into_message).collect(), &rt.executor());
+    rt.block_on(in1.send_all(iter_ok(events))).unwrap();
+    rt.block_on(topology.stop()).unwrap();
+    let res = rt.block_on(h_out1).unwrap();
+    shutdown_on_idle(rt);
+
+    let expected = messages
+        .into_iter()
+        .map(|message| message + " transformed")
+        .collect::<Vec<_>>();
+    assert_eq!(expected, res);
+}
+
+#[test]
+fn topology_concurrent_transform_survives_panics() {
+    let mut rt = runtime();
+    let (mut in1, source1) = source();
+    let (out1, sink1) = sink(10);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_transform("t1", &["in1"], transform(" transformed", 0.0));
+    config.add_sink("out1", &["t1"], sink1);
+    config.transforms["t1"].concurrency = 2;
+
+    let (topology, _crash) = topology::start(config, &mut rt, false).unwrap();
+
+    let h_out1 = oneshot::spawn(out1.map(into_message).collect(), &rt.executor());
+    // Each panic would take an instance out of the pool for good unless
+    // it's returned.
+    for _ in 0..3 {
+        in1 = rt.block_on(in1.send(Event::from("panic"))).unwrap();
+        thread::sleep(Duration::from_millis(50));
+    }
+    let messages = (0..10).map(|i| i.to_string()).collect::<Vec<_>>();
+    let events = messages.iter().map(|message| Event::from(&message[..]));
+    rt.block_on(in1.send_all(iter_ok(events))).unwrap();
+    rt.block_on(topology.stop()).unwrap();
+    let res = rt.block_on(h_out1).unwrap();
+    shutdown_on_idle(rt);
+
+    let expected = messages
+        .into_iter()
+        .map(|message| message + " transformed")
+        .collect::<Vec<_>>();
+    assert_eq!(expected, res);
+}
+
 #[test]
 fn topology_remove_one_source() {
     let mut rt = runtime();